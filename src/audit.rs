@@ -0,0 +1,358 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/audit.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::Result;
+use crate::rules::PasswordRules;
+use crate::strength::{
+    estimate_entropy_bits, evaluate_password_strength, get_improvement_suggestions,
+    get_strength_code, get_strength_feedback, pwquality_score, ScoreScale,
+};
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use zeroize::Zeroize;
+
+const HIBP_RANGE_URL: &str = "https://api.pwnedpasswords.com/range/";
+const SHARED_PREFIX_LENGTH: usize = 4;
+
+pub struct AuditEntry {
+    pub line: usize,
+    pub username: Option<String>,
+    pub password: String,
+}
+
+impl Drop for AuditEntry {
+    fn drop(&mut self) {
+        self.password.zeroize();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Finding {
+    Weak,
+    Duplicate(Vec<usize>),
+    SharedPrefix(Vec<usize>),
+    Breached(u64),
+    /// The rules the password breaks.
+    Policy(Vec<&'static str>),
+}
+
+impl std::fmt::Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines = |lines: &[usize]| {
+            lines
+                .iter()
+                .map(|l| l.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            Finding::Weak => write!(f, "weak password"),
+            Finding::Duplicate(others) => write!(f, "duplicate of line(s) {}", lines(others)),
+            Finding::SharedPrefix(others) => {
+                write!(f, "shares its prefix with line(s) {}", lines(others))
+            }
+            Finding::Breached(count) => write!(f, "found {} times in known breaches", count),
+            Finding::Policy(violations) => write!(f, "breaks the policy ({})", violations.join(", ")),
+        }
+    }
+}
+
+pub struct EntryReport {
+    pub line: usize,
+    pub username: Option<String>,
+    pub score: f64,
+    pub feedback: String,
+    pub findings: Vec<Finding>,
+}
+
+pub struct AuditReport {
+    pub entries: Vec<EntryReport>,
+}
+
+impl AuditReport {
+    pub fn count(&self, matcher: fn(&Finding) -> bool) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.findings.iter().any(matcher))
+            .count()
+    }
+
+    /// Adds a [`Finding::Policy`] to the report of every entry breaking `rules`.
+    pub fn check_policy(&mut self, entries: &[AuditEntry], rules: &PasswordRules) {
+        for (entry, report) in entries.iter().zip(self.entries.iter_mut()) {
+            let violations = rules.violations(&entry.password);
+            if !violations.is_empty() {
+                report.findings.push(Finding::Policy(violations));
+            }
+        }
+    }
+
+    /// The entries that are weak or break the policy, which `--fail-on-weak`
+    /// rejects.
+    pub fn rejected(&self) -> impl Iterator<Item = &EntryReport> {
+        self.entries.iter().filter(|e| {
+            e.findings
+                .iter()
+                .any(|f| matches!(f, Finding::Weak | Finding::Policy(_)))
+        })
+    }
+}
+
+pub fn parse_audit_input(contents: &str, csv: bool) -> Vec<AuditEntry> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .filter_map(|(i, line)| {
+            if !csv {
+                return Some(AuditEntry {
+                    line: i + 1,
+                    username: None,
+                    password: line.to_string(),
+                });
+            }
+            let (username, password) = line.split_once(',')?;
+            if i == 0 && username.trim().eq_ignore_ascii_case("username") {
+                return None;
+            }
+            Some(AuditEntry {
+                line: i + 1,
+                username: Some(username.trim().to_string()),
+                password: password.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn group_lines<'a>(
+    entries: &'a [AuditEntry],
+    key: impl Fn(&'a AuditEntry) -> Option<&'a str>,
+) -> HashMap<&'a str, Vec<usize>> {
+    let mut groups: HashMap<&str, Vec<usize>> = HashMap::new();
+    for entry in entries {
+        if let Some(k) = key(entry) {
+            groups.entry(k).or_default().push(entry.line);
+        }
+    }
+    groups
+}
+
+fn others(group: &[usize], line: usize) -> Vec<usize> {
+    group.iter().copied().filter(|&l| l != line).collect()
+}
+
+pub fn audit(entries: &[AuditEntry], weak_threshold: f64) -> AuditReport {
+    let duplicates = group_lines(entries, |e| Some(e.password.as_str()));
+    let prefixes = group_lines(entries, |e| {
+        e.password
+            .char_indices()
+            .nth(SHARED_PREFIX_LENGTH)
+            .map(|(end, _)| &e.password[..end])
+    });
+
+    let reports = entries
+        .iter()
+        .map(|entry| {
+            let score = evaluate_password_strength(&entry.password);
+            let mut findings = Vec::new();
+            if score < weak_threshold {
+                findings.push(Finding::Weak);
+            }
+            let duplicate_lines = others(&duplicates[entry.password.as_str()], entry.line);
+            if !duplicate_lines.is_empty() {
+                findings.push(Finding::Duplicate(duplicate_lines.clone()));
+            }
+            if let Some((end, _)) = entry.password.char_indices().nth(SHARED_PREFIX_LENGTH) {
+                let prefix_lines: Vec<usize> =
+                    others(&prefixes[&entry.password[..end]], entry.line)
+                        .into_iter()
+                        .filter(|l| !duplicate_lines.contains(l))
+                        .collect();
+                if !prefix_lines.is_empty() {
+                    findings.push(Finding::SharedPrefix(prefix_lines));
+                }
+            }
+            EntryReport {
+                line: entry.line,
+                username: entry.username.clone(),
+                score,
+                feedback: get_strength_feedback(score),
+                findings,
+            }
+        })
+        .collect();
+
+    AuditReport { entries: reports }
+}
+
+/// The JSON report printed by `check --json`.
+pub fn check_report(
+    password: &str,
+    policy: Option<&PasswordRules>,
+    scale: ScoreScale,
+) -> serde_json::Value {
+    let score = evaluate_password_strength(password);
+    let mut report = serde_json::json!({
+        "scale": scale.to_string(),
+        "score": score,
+        "entropy_bits": estimate_entropy_bits(password),
+        "strength": {
+            "code": get_strength_code(score),
+            "text": get_strength_feedback(score),
+        },
+        "suggestions": get_improvement_suggestions(password)
+            .into_iter()
+            .map(|s| serde_json::json!({ "code": s.code, "message": s.message }))
+            .collect::<Vec<_>>(),
+    });
+    if scale == ScoreScale::Pwquality {
+        match pwquality_score(password) {
+            Ok(score) => report["score"] = score.into(),
+            Err(failure) => {
+                report["score"] = serde_json::Value::Null;
+                report["failure"] = failure.into();
+            }
+        }
+    }
+    if let Some(policy) = policy {
+        let violations = policy.violations(password);
+        report["policy"] = serde_json::json!({
+            "compliant": violations.is_empty(),
+            "violations": violations,
+        });
+    }
+    report
+}
+
+/// One CSV row per password for `check --report`: score, entropy, the codes of
+/// the improvement suggestions and, when a policy is given, its compliance.
+pub fn check_report_csv(
+    entries: &[AuditEntry],
+    rules: Option<&PasswordRules>,
+    scale: ScoreScale,
+) -> String {
+    let mut report = String::from("line,score,entropy_bits,strength,issues,compliant,violations\n");
+    for entry in entries {
+        let score = evaluate_password_strength(&entry.password);
+        let scaled = match scale {
+            ScoreScale::Npwg => format!("{:.4}", score),
+            ScoreScale::Pwquality => pwquality_score(&entry.password)
+                .map(|score| score.to_string())
+                .unwrap_or_else(|_| "failed".to_string()),
+        };
+        let issues: Vec<&str> = get_improvement_suggestions(&entry.password)
+            .iter()
+            .map(|s| s.code)
+            .collect();
+        let (compliant, violations) = match rules {
+            Some(rules) => {
+                let violations = rules.violations(&entry.password);
+                let compliant = if violations.is_empty() { "yes" } else { "no" };
+                (compliant, violations.join(";"))
+            }
+            None => ("", String::new()),
+        };
+        report.push_str(&format!(
+            "{},{},{:.1},{},{},{},{}\n",
+            entry.line,
+            scaled,
+            estimate_entropy_bits(&entry.password),
+            get_strength_code(score),
+            issues.join(";"),
+            compliant,
+            violations
+        ));
+    }
+    report
+}
+
+pub async fn pwned_count(password: &str) -> Result<u64> {
+    let digest = Sha1::digest(password.as_bytes());
+    let hash: String = digest.iter().map(|b| format!("{:02X}", b)).collect();
+    let (prefix, suffix) = hash.split_at(5);
+
+    let body = crate::net::fetch_text(&format!("{}{}", HIBP_RANGE_URL, prefix)).await?;
+
+    Ok(body
+        .lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .find(|(candidate, _)| candidate.eq_ignore_ascii_case(suffix))
+        .and_then(|(_, count)| count.trim().parse().ok())
+        .unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_audit_input() {
+        let entries = parse_audit_input("username,password\nalice,pa,ss\nbob,x\n", true);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].username.as_deref(), Some("alice"));
+        assert_eq!(entries[0].password, "pa,ss");
+        assert_eq!(entries[0].line, 2);
+
+        let entries = parse_audit_input("one\n\ntwo,three\n", false);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].password, "two,three");
+        assert_eq!(entries[1].line, 3);
+    }
+
+    #[test]
+    fn test_audit_findings() {
+        let entries = parse_audit_input("Summer2024!\nSummer2025!\nqwerty\nqwerty\nx\n", false);
+        let report = audit(&entries, 0.3);
+
+        assert!(report.entries[0]
+            .findings
+            .contains(&Finding::SharedPrefix(vec![2])));
+        assert!(report.entries[2]
+            .findings
+            .contains(&Finding::Duplicate(vec![4])));
+        assert!(!report.entries[2]
+            .findings
+            .iter()
+            .any(|f| matches!(f, Finding::SharedPrefix(_))));
+        assert!(report.entries[4].findings.contains(&Finding::Weak));
+        assert_eq!(report.count(|f| matches!(f, Finding::Duplicate(_))), 2);
+    }
+
+    #[test]
+    fn test_rejected() {
+        let entries = parse_audit_input("k7#Vq!pX2m@Lr9&w
+x
+k7#Vq!pX2m
+", false);
+        let mut report = audit(&entries, 0.3);
+        let rules = crate::rules::parse_password_rules("minlength: 12").unwrap();
+        report.check_policy(&entries, &rules);
+
+        assert_eq!(report.entries[2].findings.last(), Some(&Finding::Policy(vec!["minlength"])));
+        let rejected: Vec<usize> = report.rejected().map(|e| e.line).collect();
+        assert_eq!(rejected, [2, 3]);
+        assert_eq!(Finding::Policy(vec!["minlength", "required"]).to_string(), "breaks the policy (minlength, required)");
+    }
+
+    #[test]
+    fn test_check_report_csv() {
+        let entries = parse_audit_input("xqz\nk7#Vq!pX2m@Lr9&w\n", false);
+        let rules = crate::rules::parse_password_rules("minlength: 8").unwrap();
+        let report = check_report_csv(&entries, Some(&rules), ScoreScale::Npwg);
+        let rows: Vec<&str> = report.lines().collect();
+        assert_eq!(rows[0], "line,score,entropy_bits,strength,issues,compliant,violations");
+        assert!(rows[1].starts_with("1,"));
+        assert!(rows[1].ends_with(",weak,too_short;few_character_classes,no,minlength"));
+        assert!(rows[2].ends_with(",,yes,"));
+        assert!(check_report_csv(&entries, None, ScoreScale::Npwg)
+            .lines()
+            .nth(1)
+            .unwrap()
+            .ends_with(",,"));
+        let report = check_report_csv(&entries, None, ScoreScale::Pwquality);
+        assert!(report.lines().nth(1).unwrap().starts_with("1,failed,"));
+    }
+}
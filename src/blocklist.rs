@@ -0,0 +1,89 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/blocklist.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::Result;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Words that are mildly offensive or awkward to hand to a customer. Strong
+/// profanity is absent from the EFF lists already; this covers what is left
+/// and what a custom list is likely to bring in.
+const BUILTIN: &[&str] = &[
+    "abuse", "abusive", "bastard", "bloody", "boob", "booze", "crap", "crappy", "damn", "dammit",
+    "drunk", "dumb", "fart", "hell", "hooker", "idiot", "jerk", "kill", "killer", "moron",
+    "murder", "naked", "nazi", "nude", "piss", "poop", "porn", "racist", "rape", "sexy", "slut",
+    "stupid", "suicide", "turd", "whore",
+];
+
+/// Words to drop from a wordlist before any are chosen. Matching ignores case
+/// and covers whole words only.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Blocklist {
+    words: HashSet<String>,
+}
+
+impl Blocklist {
+    pub fn builtin() -> Self {
+        Blocklist {
+            words: BUILTIN.iter().map(|word| word.to_string()).collect(),
+        }
+    }
+
+    /// Adds the words of a file with one word per line; blank lines and lines
+    /// starting with '#' are skipped.
+    pub fn extend_from_file(&mut self, path: &Path) -> Result<()> {
+        let text = std::fs::read_to_string(path)?;
+        self.extend(
+            text.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#')),
+        );
+        Ok(())
+    }
+
+    pub fn extend<'a>(&mut self, words: impl IntoIterator<Item = &'a str>) {
+        self.words.extend(words.into_iter().map(str::to_lowercase));
+    }
+
+    pub fn blocks(&self, word: &str) -> bool {
+        self.words.contains(&word.to_lowercase())
+    }
+
+    /// Removes blocked words and returns how many were removed.
+    pub fn filter(&self, words: &mut Vec<String>) -> usize {
+        let before = words.len();
+        words.retain(|word| !self.blocks(word));
+        before - words.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_filters_case_insensitively() {
+        let mut words: Vec<String> = ["abacus", "Stupid", "hello", "hell"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        assert_eq!(Blocklist::builtin().filter(&mut words), 2);
+        assert_eq!(words, vec!["abacus", "hello"]);
+    }
+
+    #[test]
+    fn test_extend_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blocklist.txt");
+        std::fs::write(&path, "# customer terms\nAcme\n\n  rival \n").unwrap();
+        let mut blocklist = Blocklist::default();
+        blocklist.extend_from_file(&path).unwrap();
+        assert_eq!(blocklist.words.len(), 2);
+        assert!(blocklist.blocks("acme"));
+        assert!(blocklist.blocks("RIVAL"));
+        assert!(!blocklist.blocks("customer"));
+    }
+}
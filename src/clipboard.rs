@@ -0,0 +1,180 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/clipboard.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+#![cfg_attr(not(feature = "clipboard"), allow(dead_code))]
+
+use crate::encrypt::write_output;
+use crate::error::{ClipboardErrorKind, PasswordGeneratorError, Result};
+use crate::salt::{encode, random_bytes, Encoding};
+use sha1::{Digest, Sha1};
+use std::path::{Path, PathBuf};
+
+const FINGERPRINT_FILENAME: &str = "npwg-clipboard";
+const SALT_LENGTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Backend {
+    #[default]
+    Auto,
+    Wayland,
+    X11,
+}
+
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backend::Auto => write!(f, "auto"),
+            Backend::Wayland => write!(f, "wayland"),
+            Backend::X11 => write!(f, "x11"),
+        }
+    }
+}
+
+impl std::str::FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Backend::Auto),
+            "wayland" => Ok(Backend::Wayland),
+            "x11" => Ok(Backend::X11),
+            _ => Err(format!(
+                "Invalid clipboard backend: {} (use auto, wayland or x11)",
+                s
+            )),
+        }
+    }
+}
+
+impl Backend {
+    /// Picks the concrete backend from the session environment, the same way
+    /// arboard does: Wayland whenever `WAYLAND_DISPLAY` is set, X11 otherwise.
+    pub fn resolve(self, wayland_display: bool, x11_display: bool) -> Result<Backend> {
+        let resolved = match self {
+            Backend::Auto if wayland_display => Backend::Wayland,
+            Backend::Auto => Backend::X11,
+            backend => backend,
+        };
+        match resolved {
+            Backend::Wayland if !wayland_display => Err(PasswordGeneratorError::clipboard(
+                ClipboardErrorKind::Unavailable,
+                "Wayland clipboard requested but WAYLAND_DISPLAY is not set",
+            )),
+            Backend::X11 if !x11_display => Err(PasswordGeneratorError::clipboard(
+                ClipboardErrorKind::Unavailable,
+                "X11 clipboard requested but DISPLAY is not set (no X server or XWayland)",
+            )),
+            backend => Ok(backend),
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self {
+            Backend::Wayland => {
+                "the compositor must support the wlr/ext data-control protocol; try --clipboard-backend x11 under XWayland"
+            }
+            Backend::X11 => "check that DISPLAY points to a running X server",
+            Backend::Auto => "set WAYLAND_DISPLAY or DISPLAY",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CopyOptions {
+    pub clear_after: Option<u64>,
+    pub backend: Backend,
+}
+
+/// Where the fingerprint of a secret waiting for `--clear-after` lives: the
+/// per-login runtime directory, or the per-user temporary directory on macOS
+/// and Windows. None where neither exists, and then nothing is recorded.
+pub fn fingerprint_path() -> Option<PathBuf> {
+    if let Some(runtime) = std::env::var_os("XDG_RUNTIME_DIR") {
+        return Some(PathBuf::from(runtime).join(FINGERPRINT_FILENAME));
+    }
+    cfg!(any(target_os = "macos", windows)).then(|| std::env::temp_dir().join(FINGERPRINT_FILENAME))
+}
+
+fn fingerprint(salt: &str, text: &str) -> String {
+    let digest = Sha1::digest(format!("{}{}", salt, text).as_bytes());
+    encode(&digest, Encoding::Hex)
+}
+
+/// Records a salted fingerprint of a secret placed on the clipboard until it is
+/// cleared, so a copy made meanwhile does not mistake it for the user's own
+/// clipboard contents.
+pub fn remember_secret(path: &Path, text: &str) -> Result<()> {
+    let salt = encode(&random_bytes(SALT_LENGTH)?, Encoding::Hex);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_output(
+        path,
+        format!("{}:{}", salt, fingerprint(&salt, text)).as_bytes(),
+        None,
+    )
+}
+
+pub fn is_own_secret(path: &Path, text: &str) -> bool {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| {
+            let (salt, hash) = contents.trim().split_once(':')?;
+            Some(fingerprint(salt, text) == hash)
+        })
+        .unwrap_or(false)
+}
+
+/// Removes the fingerprint of `text`, leaving one that a later copy recorded.
+pub fn forget_secret(path: &Path, text: &str) {
+    if is_own_secret(path, text) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// The clipboard contents to put back once the secret is cleared: whatever was
+/// there before the copy, unless it was empty or another npwg secret still
+/// waiting to be cleared. Call it before recording the new secret.
+pub fn restorable(path: Option<&Path>, previous: Option<String>) -> Option<String> {
+    previous.filter(|text| !text.is_empty() && !path.is_some_and(|path| is_own_secret(path, text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_backend() {
+        assert_eq!("X11".parse::<Backend>(), Ok(Backend::X11));
+        assert!("quartz".parse::<Backend>().is_err());
+        assert_eq!(Backend::Auto.resolve(true, true).unwrap(), Backend::Wayland);
+        assert_eq!(Backend::Auto.resolve(false, true).unwrap(), Backend::X11);
+        assert_eq!(Backend::X11.resolve(true, true).unwrap(), Backend::X11);
+        assert!(Backend::Wayland.resolve(false, true).is_err());
+        assert!(Backend::X11.resolve(true, false).is_err());
+    }
+
+    #[test]
+    fn test_restorable_skips_own_secrets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(FINGERPRINT_FILENAME);
+        remember_secret(&path, "s3cr3t").unwrap();
+
+        assert!(is_own_secret(&path, "s3cr3t"));
+        assert_eq!(restorable(Some(&path), Some("s3cr3t".to_string())), None);
+        assert_eq!(restorable(None, Some("s3cr3t".to_string())), Some("s3cr3t".to_string()));
+        assert_eq!(restorable(Some(&path), Some(String::new())), None);
+        assert_eq!(
+            restorable(Some(&path), Some("shopping list".to_string())),
+            Some("shopping list".to_string())
+        );
+
+        forget_secret(&path, "other");
+        assert!(is_own_secret(&path, "s3cr3t"));
+        forget_secret(&path, "s3cr3t");
+        assert!(!path.exists());
+    }
+}
@@ -5,7 +5,13 @@
 // Copyright (c) 2022 Volker Schwaberow
 
 use crate::error::{PasswordGeneratorError, Result};
+use crate::grammar::{Lexicon, Role};
+use crate::language::Language;
+use crate::unicode::Normalization;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashSet;
 
 pub const DEFINE: &[(&str, &str)] = &[
@@ -24,6 +30,7 @@ pub const DEFINE: &[(&str, &str)] = &[
     ("homoglyph6", "!|"),
     ("homoglyph7", "<({[]})>"),
     ("homoglyph8", "~-"),
+    ("printsafe", "ACDEFHJKLMNPRTWXY34679"),
     ("slashes", "/\\"),
     ("brackets", "[]{}()"),
     ("punctuation", "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~"),
@@ -44,23 +51,609 @@ pub enum PasswordGeneratorMode {
     Diceware,
 }
 
+/// Punctuation that needs no quoting anywhere in a POSIX shell word, a YAML
+/// plain scalar or an .env value.
+pub const SHELL_SAFE_SYMBOLS: &str = "+-./=_";
+
+pub fn is_shell_safe(c: char) -> bool {
+    c.is_ascii_alphanumeric() || SHELL_SAFE_SYMBOLS.contains(c)
+}
+
+/// Where a generated secret gets embedded, leaving out what would have to be
+/// escaped there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EscapeProfile {
+    /// String literals and connection strings.
+    Sql,
+    /// Distinguished names (RFC 4514) and search filters (RFC 4515).
+    Ldap,
+    /// JSON strings.
+    Json,
+}
+
+impl std::fmt::Display for EscapeProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EscapeProfile::Sql => write!(f, "sql"),
+            EscapeProfile::Ldap => write!(f, "ldap"),
+            EscapeProfile::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl std::str::FromStr for EscapeProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sql" => Ok(EscapeProfile::Sql),
+            "ldap" => Ok(EscapeProfile::Ldap),
+            "json" => Ok(EscapeProfile::Json),
+            _ => Err(format!("Invalid escape profile: {} (use sql, ldap or json)", s)),
+        }
+    }
+}
+
+impl EscapeProfile {
+    /// Characters with a special meaning in this context.
+    pub fn special_chars(&self) -> &'static str {
+        match self {
+            EscapeProfile::Sql => "'\"`\\;",
+            EscapeProfile::Ldap => "\"\\,+;<>=#*()",
+            EscapeProfile::Json => "\"\\",
+        }
+    }
+
+    /// Whitespace and control characters are left out everywhere, so a
+    /// secret never starts or ends with a space or holds a NUL.
+    pub fn allows(&self, c: char) -> bool {
+        !c.is_whitespace() && !c.is_control() && !self.special_chars().contains(c)
+    }
+
+    /// Sequences made of allowed characters that still start a comment.
+    pub fn forbidden_sequences(&self) -> &'static [&'static str] {
+        match self {
+            EscapeProfile::Sql => &["--", "/*", "*/"],
+            EscapeProfile::Ldap | EscapeProfile::Json => &[],
+        }
+    }
+}
+
+#[derive(Clone)]
 pub enum Separator {
-    Fixed(char),
+    Fixed(String),
     Random(Vec<char>),
+    /// The characters in turn, starting over after the last.
+    Cycle(Vec<char>),
+    /// One random character at a random gap; the other words are joined
+    /// directly, as in `word1word2-word3`.
+    Positional(Vec<char>),
+}
+
+impl Separator {
+    /// The entropy the separators between `gaps` pairs of words add.
+    pub fn entropy_bits(&self, gaps: usize) -> f64 {
+        match self {
+            Separator::Fixed(_) | Separator::Cycle(_) => 0.0,
+            Separator::Random(chars) => gaps as f64 * (chars.len() as f64).log2(),
+            Separator::Positional(chars) if gaps > 0 => {
+                (gaps as f64).log2() + (chars.len() as f64).log2()
+            }
+            Separator::Positional(_) => 0.0,
+        }
+    }
+
+    fn all_chars(&self, allowed: impl Fn(char) -> bool) -> bool {
+        match self {
+            Separator::Fixed(separator) => separator.chars().all(allowed),
+            Separator::Random(chars) | Separator::Cycle(chars) | Separator::Positional(chars) => {
+                chars.iter().copied().all(allowed)
+            }
+        }
+    }
+}
+
+/// Word casing and separator for passphrases, chosen together.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JoinStyle {
+    Camel,
+    Pascal,
+    Kebab,
+    Snake,
+    None,
+}
+
+impl std::fmt::Display for JoinStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JoinStyle::Camel => write!(f, "camel"),
+            JoinStyle::Pascal => write!(f, "pascal"),
+            JoinStyle::Kebab => write!(f, "kebab"),
+            JoinStyle::Snake => write!(f, "snake"),
+            JoinStyle::None => write!(f, "none"),
+        }
+    }
+}
+
+impl std::str::FromStr for JoinStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "camel" => Ok(JoinStyle::Camel),
+            "pascal" => Ok(JoinStyle::Pascal),
+            "kebab" => Ok(JoinStyle::Kebab),
+            "snake" => Ok(JoinStyle::Snake),
+            "none" => Ok(JoinStyle::None),
+            _ => Err(format!(
+                "Invalid join style: {} (use camel, pascal, kebab, snake or none)",
+                s
+            )),
+        }
+    }
+}
+
+impl JoinStyle {
+    pub fn separator(&self) -> &'static str {
+        match self {
+            JoinStyle::Kebab => "-",
+            JoinStyle::Snake => "_",
+            JoinStyle::Camel | JoinStyle::Pascal | JoinStyle::None => "",
+        }
+    }
+
+    /// The `index`-th word of the passphrase, cased for this style.
+    pub fn apply(&self, word: &str, index: usize) -> String {
+        let capitalize = match self {
+            JoinStyle::Camel => index > 0,
+            JoinStyle::Pascal => true,
+            JoinStyle::Kebab | JoinStyle::Snake => false,
+            JoinStyle::None => return word.to_string(),
+        };
+        let lower = word.to_lowercase();
+        let mut chars = lower.chars();
+        match chars.next() {
+            Some(first) if capitalize => first.to_uppercase().chain(chars).collect(),
+            _ => lower,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LengthRange {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl std::fmt::Display for LengthRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.min == self.max {
+            write!(f, "{}", self.min)
+        } else {
+            write!(f, "{}-{}", self.min, self.max)
+        }
+    }
+}
+
+impl std::str::FromStr for LengthRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parse = |value: &str| {
+            value
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid range: {} (use N or MIN-MAX)", s))
+        };
+        let (min, max) = match s.split_once('-') {
+            Some((min, max)) => (parse(min)?, parse(max)?),
+            None => {
+                let value = parse(s)?;
+                (value, value)
+            }
+        };
+        if min == 0 || min > max {
+            return Err(format!(
+                "Invalid range: {} (bounds must be positive and MIN <= MAX)",
+                s
+            ));
+        }
+        Ok(LengthRange { min, max })
+    }
+}
+
+/// Character class a password must start or end with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CharClass {
+    Letter,
+    Upper,
+    Lower,
+    Digit,
+    Alnum,
+    Symbol,
+}
+
+impl std::fmt::Display for CharClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CharClass::Letter => write!(f, "letter"),
+            CharClass::Upper => write!(f, "upper"),
+            CharClass::Lower => write!(f, "lower"),
+            CharClass::Digit => write!(f, "digit"),
+            CharClass::Alnum => write!(f, "alnum"),
+            CharClass::Symbol => write!(f, "symbol"),
+        }
+    }
+}
+
+impl std::str::FromStr for CharClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "letter" => Ok(CharClass::Letter),
+            "upper" => Ok(CharClass::Upper),
+            "lower" => Ok(CharClass::Lower),
+            "digit" => Ok(CharClass::Digit),
+            "alnum" => Ok(CharClass::Alnum),
+            "symbol" => Ok(CharClass::Symbol),
+            _ => Err(format!(
+                "Invalid character class: {} (use letter, upper, lower, digit, alnum or symbol)",
+                s
+            )),
+        }
+    }
 }
 
+impl CharClass {
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            CharClass::Letter => c.is_alphabetic(),
+            CharClass::Upper => c.is_uppercase(),
+            CharClass::Lower => c.is_lowercase(),
+            CharClass::Digit => c.is_ascii_digit(),
+            CharClass::Alnum => c.is_alphanumeric(),
+            CharClass::Symbol => !c.is_alphanumeric() && !c.is_whitespace(),
+        }
+    }
+}
+
+/// Seed for reproducible output. Plain numbers keep the historical `u64`
+/// seeding; `0x` hex strings and any other text are turned into a full 32-byte
+/// ChaCha seed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Seed {
+    Number(u64),
+    Bytes([u8; 32]),
+}
+
+impl std::str::FromStr for Seed {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if let Ok(number) = s.parse::<u64>() {
+            return Ok(Seed::Number(number));
+        }
+        if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(format!("Invalid hex seed: {}", s));
+            }
+            if hex.is_empty() || hex.len() % 2 != 0 {
+                return Err(format!("Invalid hex seed: {} (use an even number of hex digits)", s));
+            }
+            let bytes: Vec<u8> = hex
+                .as_bytes()
+                .chunks(2)
+                .map(|pair| {
+                    let digit = |b: u8| (b as char).to_digit(16).unwrap_or(0) as u8;
+                    digit(pair[0]) << 4 | digit(pair[1])
+                })
+                .collect();
+            return Ok(Seed::Bytes(match <[u8; 32]>::try_from(bytes.as_slice()) {
+                Ok(seed) => seed,
+                Err(_) => Sha256::digest(&bytes).into(),
+            }));
+        }
+        if s.is_empty() {
+            return Err("Seed must not be empty".to_string());
+        }
+        Ok(Seed::Bytes(Sha256::digest(s.as_bytes()).into()))
+    }
+}
+
+impl Seed {
+    pub fn rng(&self) -> StdRng {
+        match self {
+            Seed::Number(number) => StdRng::seed_from_u64(*number),
+            Seed::Bytes(bytes) => StdRng::from_seed(*bytes),
+        }
+    }
+
+    /// A distinct, still reproducible seed for the n-th derived stream: the
+    /// seed itself for 0, otherwise SHA-256 over the seed and `n`, so the
+    /// streams of neighbouring seeds do not overlap.
+    pub fn offset(&self, n: u64) -> Seed {
+        if n == 0 {
+            return *self;
+        }
+        let mut hasher = Sha256::new();
+        match self {
+            Seed::Number(number) => hasher.update(number.to_le_bytes()),
+            Seed::Bytes(bytes) => hasher.update(bytes),
+        }
+        hasher.update(n.to_le_bytes());
+        Seed::Bytes(hasher.finalize().into())
+    }
+}
+
+/// The characters of a charset: a name from [`DEFINE`] or a Unicode
+/// specification such as `unicode:Greek`, `unicode:U+0370-03FF` or
+/// `category:Lu`.
+pub fn charset_chars(name: &str) -> Result<Vec<char>> {
+    if crate::unicode::is_spec(name) {
+        return crate::unicode::expand(name);
+    }
+    DEFINE
+        .iter()
+        .find(|(key, _)| *key == name)
+        .map(|(_, chars)| chars.chars().collect())
+        .ok_or_else(|| {
+            PasswordGeneratorError::InvalidConfig(format!(
+                "Unknown characterset '{}'. Use one of: {}, or unicode:<block>, unicode:U+XXXX-YYYY or category:<Xx>",
+                name,
+                DEFINE.iter().map(|&(key, _)| key).collect::<Vec<_>>().join(", ")
+            ))
+        })
+}
+
+/// Splits an `--allowed` entry into the charset and an optional `:<weight>`.
+/// Unicode specifications contain a colon themselves, so only a second one
+/// starts their weight.
+pub fn split_charset_weight(entry: &str) -> (&str, Option<&str>) {
+    match entry.rsplit_once(':') {
+        Some((charset, _)) if crate::unicode::is_spec(entry) && !charset.contains(':') => (entry, None),
+        Some((charset, weight)) => (charset.trim(), Some(weight.trim())),
+        None => (entry, None),
+    }
+}
+
+fn separator_charset(charset_name: &str) -> Result<Vec<char>> {
+    charset_chars(charset_name).map_err(|_| {
+        PasswordGeneratorError::InvalidConfig(format!(
+            "Unknown characterset '{}' for separator",
+            charset_name
+        ))
+    })
+}
+
+pub fn parse_separator(value: &str) -> Result<Separator> {
+    match value {
+        "random" => return Ok(Separator::Random(('a'..='z').chain('0'..='9').collect())),
+        "cycle" => return Ok(Separator::Cycle(vec!['-', '.', '_'])),
+        "positional" => return Ok(Separator::Positional(('0'..='9').collect())),
+        _ => {}
+    }
+    if let Some(charset_name) = value.strip_prefix("set:") {
+        return Ok(Separator::Random(separator_charset(charset_name)?));
+    }
+    if let Some(charset_name) = value.strip_prefix("positional:") {
+        return Ok(Separator::Positional(separator_charset(charset_name)?));
+    }
+    if let Some(chars) = value.strip_prefix("cycle:").filter(|chars| !chars.is_empty()) {
+        return Ok(Separator::Cycle(chars.chars().collect()));
+    }
+    if value.is_empty() || value == "cycle:" {
+        return Err(PasswordGeneratorError::InvalidConfig(
+            "Separator must be a non-empty string, 'random', 'set:<charset>', 'cycle[:<chars>]' or 'positional[:<charset>]'"
+                .to_string(),
+        ));
+    }
+    Ok(Separator::Fixed(value.to_string()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternEntry {
+    pub pattern: String,
+    pub count: Option<usize>,
+}
+
+pub const PATTERN_HELP: &str = "\
+Pattern grammar (one symbol per output character):
+  L or l   a letter from the allowed characters
+  D or d   a digit from the allowed characters
+  S or s   a symbol (non-alphanumeric) from the allowed characters
+  W or w   a word from the diceware wordlist
+  {word} {letter} {digit} {symbol}
+           the same as W, L, D and S, for templates that read better
+  {adjective} {noun} {nouns} {verb} {adverb}
+           a word of that kind from the grammar word lists
+  \\c       the character c itself, e.g. \\X, \\7 or \\{
+  other    punctuation and spaces are copied as they are
+
+Any other letter or digit is rejected. When the pattern is shorter than
+--length, the rest is filled with random allowed characters, except in
+templates with words.
+
+Examples: npwg -p 'LLLL-DDDD-SS'
+          npwg -p 'W-W-DDDD'
+          npwg -p '{word}{symbol}{word}{digit}{digit}'";
+
+/// One element of a parsed pattern.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PatternToken {
+    Letter,
+    Digit,
+    Symbol,
+    Word,
+    /// A word from the grammar lists, see [`crate::grammar`].
+    Role(Role),
+    Literal(char),
+}
+
+fn pattern_error(pattern: &str, column: usize, reason: String, hint: &str) -> PasswordGeneratorError {
+    PasswordGeneratorError::PatternError {
+        pattern: pattern.to_string(),
+        column,
+        reason,
+        hint: hint.to_string(),
+    }
+}
+
+/// Splits a pattern into tokens, reporting the first offending symbol with
+/// its 1-based column.
+pub fn parse_pattern(pattern: &str) -> Result<Vec<PatternToken>> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars().enumerate();
+    while let Some((index, symbol)) = chars.next() {
+        let token = match symbol {
+            'L' | 'l' => PatternToken::Letter,
+            'D' | 'd' => PatternToken::Digit,
+            'S' | 's' => PatternToken::Symbol,
+            'W' | 'w' => PatternToken::Word,
+            '\\' => match chars.next() {
+                Some((_, literal)) => PatternToken::Literal(literal),
+                None => {
+                    return Err(pattern_error(
+                        pattern,
+                        index + 1,
+                        "dangling escape".to_string(),
+                        "follow the backslash with the character to copy, or remove it",
+                    ))
+                }
+            },
+            '{' => {
+                let mut name = String::new();
+                let mut closed = false;
+                for (_, c) in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    name.push(c);
+                }
+                match name.as_str() {
+                    _ if !closed => {
+                        return Err(pattern_error(
+                            pattern,
+                            index + 1,
+                            "unclosed '{'".to_string(),
+                            "close the placeholder with '}', or write \\{ for a literal '{'",
+                        ))
+                    }
+                    "word" => PatternToken::Word,
+                    "letter" => PatternToken::Letter,
+                    "digit" => PatternToken::Digit,
+                    "symbol" => PatternToken::Symbol,
+                    _ => match name.parse::<Role>() {
+                        Ok(role) => PatternToken::Role(role),
+                        Err(_) => {
+                            return Err(pattern_error(
+                                pattern,
+                                index + 1,
+                                format!("unknown placeholder '{{{}}}'", name),
+                                "use {word}, {letter}, {digit}, {symbol}, {adjective}, {noun}, {nouns}, {verb} or {adverb}",
+                            ))
+                        }
+                    },
+                }
+            }
+            c if c.is_alphanumeric() => {
+                return Err(pattern_error(
+                    pattern,
+                    index + 1,
+                    format!("unknown symbol '{}'", c),
+                    &format!("use L, D, S or W, or write \\{} for a literal '{}'", c, c),
+                ));
+            }
+            c => PatternToken::Literal(c),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Whether a valid pattern draws from the diceware wordlist.
+pub fn pattern_uses_words(pattern: &str) -> bool {
+    parse_pattern(pattern).is_ok_and(|tokens| tokens.contains(&PatternToken::Word))
+}
+
+/// Whether a valid pattern draws from the grammar word lists.
+pub fn pattern_uses_roles(pattern: &str) -> bool {
+    parse_pattern(pattern)
+        .is_ok_and(|tokens| tokens.iter().any(|token| matches!(token, PatternToken::Role(_))))
+}
+
+/// Checks a pattern against [`PATTERN_HELP`], reporting the first offending
+/// symbol with its 1-based column.
+pub fn validate_pattern(pattern: &str) -> Result<()> {
+    parse_pattern(pattern).map(|_| ())
+}
+
+pub fn parse_pattern_list(contents: &str) -> Vec<PatternEntry> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.rsplit_once(char::is_whitespace) {
+            Some((pattern, count)) if count.parse::<usize>().is_ok() => PatternEntry {
+                pattern: pattern.trim_end().to_string(),
+                count: count.parse().ok(),
+            },
+            _ => PatternEntry {
+                pattern: line.to_string(),
+                count: None,
+            },
+        })
+        .collect()
+}
+
+pub const MIN_DICTIONARY_WORD_LEN: usize = 4;
+
+#[derive(Clone)]
 pub struct PasswordGeneratorConfig {
     pub length: usize,
     pub pattern: Option<String>,
     pub allowed_chars: Vec<char>,
     pub excluded_chars: HashSet<char>,
     pub included_chars: HashSet<char>,
+    /// Drop characters that look like another one in the pool (UTS #39).
+    pub exclude_confusables: bool,
+    /// Keep only characters that [`is_shell_safe`].
+    pub shell_safe: bool,
+    pub escape_profile: Option<EscapeProfile>,
+    /// Applied to every generated password and passphrase.
+    pub normalization: Normalization,
     pub avoid_repetition: bool,
     pub mode: PasswordGeneratorMode,
     pub num_passwords: usize,
     pub separator: Option<Separator>,
     pub pronounceable: bool,
-    pub seed: Option<u64>,
+    pub language: Option<Language>,
+    pub seed: Option<Seed>,
+    pub required_sets: Vec<Vec<char>>,
+    /// The longest password a site or `--rules` policy accepts.
+    pub max_length: Option<usize>,
+    pub max_consecutive: Option<usize>,
+    pub min_entropy_bits: Option<f64>,
+    pub min_strength: Option<f64>,
+    pub word_range: Option<LengthRange>,
+    pub length_range: Option<LengthRange>,
+    pub join_style: Option<JoinStyle>,
+    pub append_digits: usize,
+    pub fips: bool,
+    pub jitter: Option<[u8; 32]>,
+    pub dictionary: Vec<String>,
+    /// Words for `W` and `{word}` in patterns; see [`Self::load_pattern_words`].
+    pub pattern_words: Vec<String>,
+    /// Word lists for `{adjective}`, `{noun}` and the other role placeholders;
+    /// the built-in lists are filled in by [`Self::load_pattern_words`].
+    pub lexicon: Option<Lexicon>,
+    pub forbidden: Vec<String>,
+    pub reject_keyboard_runs: bool,
+    pub avoid_sequential: bool,
+    pub start_with: Option<CharClass>,
+    pub end_with: Option<CharClass>,
+    pub charset_weights: Vec<(String, u32)>,
 }
 
 impl Default for PasswordGeneratorConfig {
@@ -77,12 +670,37 @@ impl PasswordGeneratorConfig {
             excluded_chars: HashSet::new(),
             included_chars: HashSet::new(),
             num_passwords: 1,
+            exclude_confusables: false,
+            shell_safe: false,
+            escape_profile: None,
+            normalization: Normalization::None,
             avoid_repetition: false,
             mode: PasswordGeneratorMode::Password,
             separator: None,
             pronounceable: false,
+            language: None,
             pattern: None,
             seed: None,
+            required_sets: Vec::new(),
+            max_length: None,
+            max_consecutive: None,
+            min_entropy_bits: None,
+            min_strength: None,
+            word_range: None,
+            length_range: None,
+            join_style: None,
+            append_digits: 0,
+            fips: false,
+            jitter: None,
+            dictionary: Vec::new(),
+            pattern_words: Vec::new(),
+            lexicon: None,
+            forbidden: Vec::new(),
+            reject_keyboard_runs: false,
+            avoid_sequential: false,
+            start_with: None,
+            end_with: None,
+            charset_weights: Vec::new(),
         };
         config.set_allowed_chars("allprint");
         config
@@ -99,8 +717,55 @@ impl PasswordGeneratorConfig {
     }
 
     pub fn add_allowed_chars(&mut self, charset_name: &str) {
-        if let Some((_, chars)) = DEFINE.iter().find(|(name, _)| *name == charset_name) {
-            self.allowed_chars.extend(chars.chars());
+        let chars: Vec<char> = if crate::unicode::is_spec(charset_name) {
+            crate::unicode::expand(charset_name).unwrap_or_default()
+        } else if let Some((_, chars)) = DEFINE.iter().find(|(name, _)| *name == charset_name) {
+            chars.chars().collect()
+        } else {
+            return;
+        };
+        // Sets overlap each other, and a character listed twice would be
+        // drawn twice as often, whichever order the sets come in.
+        let mut present: HashSet<char> = self.allowed_chars.iter().copied().collect();
+        self.allowed_chars
+            .extend(chars.into_iter().filter(|&c| present.insert(c)));
+    }
+
+    /// Per-character sampling weights derived from `charset_weights`: each set
+    /// shares its weight among its available characters, and characters
+    /// outside every weighted set share a weight of 1. `None` means uniform.
+    pub fn char_weights(&self) -> Option<Vec<(char, f64)>> {
+        if self.charset_weights.is_empty() {
+            return None;
+        }
+        let available: HashSet<char> = self.available_chars().into_iter().collect();
+        let mut weights: std::collections::BTreeMap<char, f64> = std::collections::BTreeMap::new();
+        for (name, weight) in &self.charset_weights {
+            let Ok(chars) = charset_chars(name) else {
+                continue;
+            };
+            let chars: HashSet<char> = chars.into_iter().filter(|c| available.contains(c)).collect();
+            for &c in &chars {
+                *weights.entry(c).or_default() += *weight as f64 / chars.len() as f64;
+            }
+        }
+        let uncovered: Vec<char> = available
+            .iter()
+            .copied()
+            .filter(|c| !weights.contains_key(c))
+            .collect();
+        for &c in &uncovered {
+            weights.insert(c, 1.0 / uncovered.len() as f64);
+        }
+        Some(weights.into_iter().collect())
+    }
+
+    /// The length of the next password: uniform within `length_range` when
+    /// set, otherwise `length`.
+    pub fn draw_length(&self, rng: &mut impl rand::Rng) -> usize {
+        match self.length_range {
+            Some(range) => rng.random_range(range.min..=range.max),
+            None => self.length,
         }
     }
 
@@ -112,6 +777,185 @@ impl PasswordGeneratorConfig {
         self.avoid_repetition = avoid;
     }
 
+    /// Loads the diceware wordlist when the pattern asks for words and none
+    /// are loaded yet, and the built-in grammar lists for role placeholders.
+    pub async fn load_pattern_words(&mut self) -> Result<()> {
+        let uses_words = self.pattern.as_deref().is_some_and(pattern_uses_words);
+        if uses_words && self.pattern_words.is_empty() {
+            self.pattern_words = crate::diceware::get_wordlist().await?;
+        }
+        if self.pattern.as_deref().is_some_and(pattern_uses_roles) && self.lexicon.is_none() {
+            self.lexicon = Some(Lexicon::builtin());
+        }
+        Ok(())
+    }
+
+    /// Words that generated passwords must not contain. Words shorter than
+    /// [`MIN_DICTIONARY_WORD_LEN`] are dropped because short words turn up in
+    /// almost every random string.
+    pub fn set_dictionary<I: IntoIterator<Item = String>>(&mut self, words: I) {
+        let words: HashSet<String> = words
+            .into_iter()
+            .map(|word| word.trim().to_lowercase())
+            .filter(|word| word.chars().count() >= MIN_DICTIONARY_WORD_LEN)
+            .collect();
+        self.dictionary = words.into_iter().collect();
+        self.dictionary.sort();
+    }
+
+    /// Substrings generated output must never contain, compared
+    /// case-insensitively and after undoing common leetspeak.
+    pub fn set_forbidden<I: IntoIterator<Item = String>>(&mut self, entries: I) {
+        let entries: HashSet<String> = entries
+            .into_iter()
+            .map(|entry| crate::generator::leet_normalize(entry.trim()))
+            .filter(|entry| !entry.is_empty())
+            .collect();
+        self.forbidden = entries.into_iter().collect();
+        self.forbidden.sort();
+    }
+
+    /// The characters passwords are drawn from, each listed once.
+    pub fn available_chars(&self) -> Vec<char> {
+        // Each character is drawn in the requested normalization form, so
+        // output is never lengthened or merged after the checks have run.
+        let mut available_chars: Vec<char> = self
+            .allowed_chars
+            .iter()
+            .chain(self.included_chars.iter())
+            .filter_map(|&c| self.normalization.single_char(c))
+            .collect();
+        let mut seen = HashSet::new();
+        available_chars.retain(|&c| !self.excluded_chars.contains(&c) && seen.insert(c));
+        if self.exclude_confusables {
+            available_chars = crate::unicode::without_confusables(&available_chars);
+        }
+        if self.shell_safe {
+            available_chars.retain(|&c| is_shell_safe(c));
+        }
+        if let Some(profile) = self.escape_profile {
+            available_chars.retain(|&c| profile.allows(c));
+        }
+        available_chars
+    }
+
+    pub fn pool_size(&self) -> usize {
+        self.available_chars().iter().collect::<HashSet<_>>().len()
+    }
+
+    pub fn entropy_bits(&self) -> f64 {
+        if let (true, Some(language)) = (self.pronounceable, self.language) {
+            return self.length as f64 * language.table().bits_per_char();
+        }
+        if self.pronounceable {
+            let consonant_slots = self.length.div_ceil(2) as f64;
+            let vowel_slots = (self.length / 2) as f64;
+            return consonant_slots * 21f64.log2() + vowel_slots * 5f64.log2();
+        }
+        if let Some(pattern) = &self.pattern {
+            return self.pattern_entropy_bits(pattern);
+        }
+        if let Some(weights) = self.char_weights() {
+            let total: f64 = weights.iter().map(|(_, weight)| weight).sum();
+            let per_char: f64 = weights
+                .iter()
+                .map(|(_, weight)| weight / total)
+                .filter(|&p| p > 0.0)
+                .map(|p| -p * p.log2())
+                .sum();
+            return self.length as f64 * per_char;
+        }
+        let pool: HashSet<char> = self.available_chars().into_iter().collect();
+        if pool.is_empty() {
+            return 0.0;
+        }
+        // Bits for one position restricted to every given class.
+        let position_bits = |classes: &[Option<CharClass>]| {
+            let matching = pool
+                .iter()
+                .filter(|&&c| classes.iter().flatten().all(|class| class.contains(c)))
+                .count();
+            (matching.max(1) as f64).log2()
+        };
+        match self.length {
+            0 => 0.0,
+            1 => position_bits(&[self.start_with, self.end_with]),
+            length => {
+                (length - 2) as f64 * position_bits(&[])
+                    + position_bits(&[self.start_with])
+                    + position_bits(&[self.end_with])
+            }
+        }
+    }
+
+    /// Entropy of a password drawn for `pattern`, token by token: a class
+    /// token adds the bits of the pool characters in that class, `W` and the
+    /// word roles the bits of their word lists, and literals nothing. Without
+    /// word slots, the positions padded up to the length draw from the whole
+    /// pool.
+    fn pattern_entropy_bits(&self, pattern: &str) -> f64 {
+        let pool = self.available_chars();
+        let bits = |choices: usize| (choices.max(1) as f64).log2();
+        let class_bits = |class: fn(&char) -> bool| bits(pool.iter().filter(|c| class(c)).count());
+        let tokens = parse_pattern(pattern).unwrap_or_default();
+        let mut pad = true;
+        let mut total = 0.0;
+        for token in &tokens {
+            total += match token {
+                PatternToken::Letter => class_bits(|c| c.is_ascii_alphabetic()),
+                PatternToken::Digit => class_bits(|c| c.is_ascii_digit()),
+                PatternToken::Symbol => class_bits(|c| !c.is_ascii_alphanumeric()),
+                PatternToken::Word => {
+                    pad = false;
+                    bits(self.pattern_words.len())
+                }
+                PatternToken::Role(role) => {
+                    pad = false;
+                    self.lexicon.as_ref().map_or(0.0, |lexicon| bits(lexicon.words(*role).len()))
+                }
+                PatternToken::Literal(_) => 0.0,
+            };
+        }
+        if pad {
+            total += self.length.saturating_sub(tokens.len()) as f64 * bits(pool.len());
+        }
+        total
+    }
+
+    /// Entropy of a passphrase with the configured (shortest) word count drawn
+    /// from a wordlist of `wordlist_len` words, plus any appended digits.
+    pub fn passphrase_entropy_bits(&self, wordlist_len: usize) -> f64 {
+        let separator_bits = match (&self.separator, self.join_style) {
+            (Some(separator), None) => separator.entropy_bits(self.length.saturating_sub(1)),
+            _ => 0.0,
+        };
+        self.length as f64 * (wordlist_len as f64).log2()
+            + self.append_digits as f64 * 10f64.log2()
+            + separator_bits
+    }
+
+    pub fn check_entropy_floor(&self, bits: f64) -> Result<()> {
+        match self.min_entropy_bits {
+            Some(min) if bits < min => {
+                let source = if self.mode == PasswordGeneratorMode::Diceware {
+                    format!("{} words", self.length)
+                } else {
+                    format!(
+                        "length {} from a pool of {} characters",
+                        self.length,
+                        self.pool_size()
+                    )
+                };
+                Err(PasswordGeneratorError::PolicyViolation(format!(
+                    "Estimated entropy of {:.1} bits ({}) is below the minimum of {} bits; \
+                     increase the length, widen the character set or pass --force",
+                    bits, source, min
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub fn validate(&self) -> Result<()> {
         if self.allowed_chars.is_empty() {
             return Err(PasswordGeneratorError::InvalidConfig(
@@ -123,6 +967,64 @@ impl PasswordGeneratorConfig {
                 "Password length must be greater than 0".to_string(),
             ));
         }
+        let available_chars = self.available_chars();
+        if self.shell_safe {
+            self.validate_char_filter(&available_chars, "--shell-safe", is_shell_safe)?;
+        }
+        if let Some(profile) = self.escape_profile {
+            let option = format!("--escape-profile {}", profile);
+            self.validate_char_filter(&available_chars, &option, |c| profile.allows(c))?;
+        }
+        if self
+            .required_sets
+            .iter()
+            .any(|set| !set.iter().any(|c| available_chars.contains(c)))
+        {
+            return Err(PasswordGeneratorError::InvalidConfig(
+                "A required character class has no characters in the allowed set".to_string(),
+            ));
+        }
+        if self.required_sets.len() > self.length {
+            return Err(PasswordGeneratorError::InvalidConfig(
+                "Password length is shorter than the number of required character classes"
+                    .to_string(),
+            ));
+        }
+        if let Some(pattern) = &self.pattern {
+            validate_pattern(pattern)?;
+        }
+        for (edge, class) in [("start", self.start_with), ("end", self.end_with)] {
+            if let Some(class) = class {
+                if !available_chars.iter().any(|&c| class.contains(c)) {
+                    return Err(PasswordGeneratorError::InvalidConfig(format!(
+                        "Passwords must {} with a {} character, but the allowed set has none",
+                        edge, class
+                    )));
+                }
+            }
+        }
+        let shortest = self.length_range.map_or(self.length, |range| range.min);
+        if let (1, Some(start), Some(end)) = (shortest, self.start_with, self.end_with) {
+            if !available_chars.iter().any(|&c| start.contains(c) && end.contains(c)) {
+                return Err(PasswordGeneratorError::InvalidConfig(format!(
+                    "A one-character password must both start with a {} and end with a {} character, but the allowed set has none that is both",
+                    start, end
+                )));
+            }
+        }
+        if self.max_consecutive == Some(0) {
+            return Err(PasswordGeneratorError::InvalidConfig(
+                "Maximum consecutive characters must be greater than 0".to_string(),
+            ));
+        }
+        if self
+            .min_strength
+            .is_some_and(|strength| !(0.0..=1.0).contains(&strength))
+        {
+            return Err(PasswordGeneratorError::InvalidConfig(
+                "Minimum strength must be between 0.0 and 1.0".to_string(),
+            ));
+        }
         if self.num_passwords == 0 {
             return Err(PasswordGeneratorError::InvalidConfig(
                 "Number of passwords must be greater than 0".to_string(),
@@ -130,6 +1032,45 @@ impl PasswordGeneratorConfig {
         }
         Ok(())
     }
+
+    /// Checks that what `available_chars` cannot filter, the literals of a
+    /// pattern and the passphrase separator, only uses characters `allowed`
+    /// by `option`.
+    fn validate_char_filter(
+        &self,
+        available_chars: &[char],
+        option: &str,
+        allowed: impl Fn(char) -> bool,
+    ) -> Result<()> {
+        if available_chars.is_empty() {
+            return Err(PasswordGeneratorError::InvalidConfig(format!(
+                "{} leaves no characters in the allowed set; add letters or digits",
+                option
+            )));
+        }
+        if let Some(pattern) = &self.pattern {
+            let literal = parse_pattern(pattern)?.into_iter().find_map(|token| match token {
+                PatternToken::Literal(c) if !allowed(c) => Some(c),
+                _ => None,
+            });
+            if let Some(c) = literal {
+                return Err(PasswordGeneratorError::InvalidConfig(format!(
+                    "The pattern puts '{}' into every password, which {} does not allow",
+                    c, option
+                )));
+            }
+        }
+        let separator_rejected = self.join_style.is_none()
+            && self.separator.as_ref().is_some_and(|separator| !separator.all_chars(&allowed));
+        if separator_rejected {
+            return Err(PasswordGeneratorError::InvalidConfig(format!(
+                "The separator has characters {} does not allow",
+                option
+            )));
+        }
+        Ok(())
+    }
+
     pub fn set_use_words(&mut self, use_words: bool) {
         self.mode = if use_words {
             PasswordGeneratorMode::Diceware
@@ -177,6 +1118,31 @@ mod tests {
         assert_eq!(config.allowed_chars, allprint_chars);
     }
 
+    #[test]
+    fn test_unicode_charsets() {
+        assert_eq!(split_charset_weight("lowerletter:4"), ("lowerletter", Some("4")));
+        assert_eq!(split_charset_weight("unicode:Greek"), ("unicode:Greek", None));
+        assert_eq!(split_charset_weight("category:Lu:3"), ("category:Lu", Some("3")));
+
+        let mut config = PasswordGeneratorConfig::new();
+        config.clear_allowed_chars();
+        config.add_allowed_chars("upperletter");
+        config.add_allowed_chars("category:Lu");
+        let upper = crate::unicode::expand("category:Lu").unwrap();
+        assert_eq!(config.allowed_chars.len(), upper.len());
+        assert!((config.entropy_bits() - 8.0 * (upper.len() as f64).log2()).abs() < 1e-9);
+
+        // The order of overlapping sets does not change the pool.
+        let mut reversed = PasswordGeneratorConfig::new();
+        reversed.clear_allowed_chars();
+        reversed.add_allowed_chars("category:Lu");
+        reversed.add_allowed_chars("upperletter");
+        reversed.included_chars.insert('A');
+        assert_eq!(reversed.available_chars().len(), upper.len());
+        assert_eq!(reversed.entropy_bits(), config.entropy_bits());
+        assert!(charset_chars("unicode:Nowhere").is_err());
+    }
+
     #[test]
     fn test_add_allowed_chars() {
         let mut config = PasswordGeneratorConfig::new();
@@ -193,7 +1159,7 @@ mod tests {
         config.add_allowed_chars("upperletter");
         assert_eq!(
             {
-                let mut chars: Vec<char> = config.allowed_chars.iter().cloned().collect();
+                let mut chars: Vec<char> = config.allowed_chars.to_vec();
                 chars.sort_unstable();
                 chars.into_iter().collect::<String>()
             },
@@ -213,4 +1179,298 @@ mod tests {
         config.add_allowed_chars("");
         assert_eq!(config.allowed_chars, before_invalid);
     }
+
+    #[test]
+    fn test_entropy_bits() {
+        let mut config = PasswordGeneratorConfig::new();
+        config.set_allowed_chars("digit");
+        config.length = 10;
+        assert_eq!(config.pool_size(), 10);
+        assert!((config.entropy_bits() - 10.0 * 10f64.log2()).abs() < 1e-9);
+
+        config.excluded_chars.insert('0');
+        assert_eq!(config.pool_size(), 9);
+
+        let mut weighted = PasswordGeneratorConfig::new();
+        weighted.set_allowed_chars("lowerletter");
+        weighted.add_allowed_chars("digit");
+        weighted.length = 10;
+        weighted.charset_weights = vec![("lowerletter".to_string(), 1), ("digit".to_string(), 1)];
+        assert!((weighted.entropy_bits() - 10.0 * (1.0 + 0.5 * 26f64.log2() + 0.5 * 10f64.log2())).abs() < 1e-9);
+        weighted.charset_weights[0].1 = 4;
+        let weights = weighted.char_weights().unwrap();
+        assert_eq!(weights.len(), 36);
+        assert!(weighted.entropy_bits() < 10.0 * 36f64.log2());
+
+        let mut edges = PasswordGeneratorConfig::new();
+        edges.set_allowed_chars("lowerletter");
+        edges.add_allowed_chars("digit");
+        edges.length = 8;
+        edges.start_with = Some("letter".parse().unwrap());
+        assert!((edges.entropy_bits() - (7.0 * 36f64.log2() + 26f64.log2())).abs() < 1e-9);
+        edges.end_with = Some(CharClass::Symbol);
+        assert!(edges.validate().is_err());
+
+        let mut pattern = PasswordGeneratorConfig::new();
+        pattern.set_allowed_chars("lowerletter");
+        pattern.add_allowed_chars("digit");
+        pattern.length = 6;
+        pattern.pattern = Some("LLD-".to_string());
+        let expected = 2.0 * 26f64.log2() + 10f64.log2() + 2.0 * 36f64.log2();
+        assert!((pattern.entropy_bits() - expected).abs() < 1e-9);
+        pattern.pattern = Some("WD{noun}".to_string());
+        pattern.pattern_words = (0..1024).map(|i| i.to_string()).collect();
+        pattern.lexicon = Some(Lexicon::builtin());
+        let nouns = Lexicon::builtin().words(Role::Noun).len() as f64;
+        assert!((pattern.entropy_bits() - (10.0 + 10f64.log2() + nouns.log2())).abs() < 1e-9);
+
+        config.pronounceable = true;
+        config.length = 3;
+        assert!((config.entropy_bits() - (2.0 * 21f64.log2() + 5f64.log2())).abs() < 1e-9);
+
+        config.length = 4;
+        config.append_digits = 2;
+        assert!(
+            (config.passphrase_entropy_bits(7776) - (4.0 * 7776f64.log2() + 2.0 * 10f64.log2()))
+                .abs()
+                < 1e-9
+        );
+
+        config.language = Some(Language::De);
+        assert!(config.entropy_bits() > 0.0);
+    }
+
+    #[test]
+    fn test_check_entropy_floor() {
+        let mut config = PasswordGeneratorConfig::new();
+        config.set_allowed_chars("digit");
+        config.length = 6;
+        assert!(config.check_entropy_floor(config.entropy_bits()).is_ok());
+
+        config.min_entropy_bits = Some(40.0);
+        let err = config
+            .check_entropy_floor(config.entropy_bits())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("19.9 bits"));
+        assert!(err.contains("pool of 10 characters"));
+
+        config.length = 13;
+        assert!(config.check_entropy_floor(config.entropy_bits()).is_ok());
+    }
+
+    #[test]
+    fn test_parse_pattern_list() {
+        let entries = parse_pattern_list("# comment\nLLDDS 3\n\n  DDDD-LLLL\nLL 2 S\n");
+        assert_eq!(
+            entries,
+            vec![
+                PatternEntry {
+                    pattern: "LLDDS".to_string(),
+                    count: Some(3),
+                },
+                PatternEntry {
+                    pattern: "DDDD-LLLL".to_string(),
+                    count: None,
+                },
+                PatternEntry {
+                    pattern: "LL 2 S".to_string(),
+                    count: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_pattern() {
+        assert!(validate_pattern("LLDD-ss ll").is_ok());
+        assert!(validate_pattern("\\XLL\\7").is_ok());
+        let error = validate_pattern("LDXS").unwrap_err().to_string();
+        assert!(error.contains("unknown symbol 'X' at column 3"), "{}", error);
+        let error = validate_pattern("LL\\").unwrap_err().to_string();
+        assert!(error.contains("dangling escape at column 3"), "{}", error);
+        assert!(validate_pattern("W-w-DDDD").is_ok());
+        let error = validate_pattern("W{pronoun}").unwrap_err().to_string();
+        assert!(error.contains("unknown placeholder '{pronoun}' at column 2"), "{}", error);
+        let error = validate_pattern("D{word").unwrap_err().to_string();
+        assert!(error.contains("unclosed '{' at column 2"), "{}", error);
+    }
+
+    #[test]
+    fn test_parse_pattern_placeholders() {
+        use PatternToken::*;
+        assert_eq!(
+            parse_pattern("{word}{symbol}W-\\{d").unwrap(),
+            [Word, Symbol, Word, Literal('-'), Literal('{'), Digit]
+        );
+        assert_eq!(parse_pattern("{letter}{digit}").unwrap(), [Letter, Digit]);
+        assert!(pattern_uses_words("LL{word}"));
+        assert!(!pattern_uses_words("LLDD"));
+        assert!(!pattern_uses_words("W{bad}"));
+        assert_eq!(
+            parse_pattern("{adj}-{noun}-D").unwrap(),
+            [Role(crate::grammar::Role::Adjective), Literal('-'), Role(crate::grammar::Role::Noun), Literal('-'), Digit]
+        );
+        assert!(pattern_uses_roles("{verb}DD"));
+        assert!(!pattern_uses_roles("W-DD"));
+    }
+
+    #[test]
+    fn test_parse_length_range() {
+        assert_eq!(
+            "4-6".parse::<LengthRange>(),
+            Ok(LengthRange { min: 4, max: 6 })
+        );
+        assert_eq!("5".parse::<LengthRange>(), Ok(LengthRange { min: 5, max: 5 }));
+        assert_eq!(LengthRange { min: 4, max: 6 }.to_string(), "4-6");
+        assert!("6-4".parse::<LengthRange>().is_err());
+        assert!("0-3".parse::<LengthRange>().is_err());
+        assert!("four".parse::<LengthRange>().is_err());
+    }
+
+    #[test]
+    fn test_parse_seed() {
+        use rand::RngCore;
+
+        assert_eq!("42".parse::<Seed>(), Ok(Seed::Number(42)));
+        let hex = format!("0x{}", "01".repeat(32));
+        assert_eq!(hex.parse::<Seed>(), Ok(Seed::Bytes([1; 32])));
+        assert!("0xabc".parse::<Seed>().is_err());
+        assert!("0xzz".parse::<Seed>().is_err());
+        assert!("0xa€".parse::<Seed>().is_err());
+        assert!("0x+f".parse::<Seed>().is_err());
+        assert!("".parse::<Seed>().is_err());
+
+        let phrase = "correct horse battery staple".parse::<Seed>().unwrap();
+        assert_eq!(phrase, "correct horse battery staple".parse().unwrap());
+        assert_ne!(phrase, "correct horse battery stapler".parse().unwrap());
+        assert_eq!(phrase.rng().next_u64(), phrase.rng().next_u64());
+        assert_ne!(phrase.offset(1), phrase);
+        assert_eq!(Seed::Number(5).offset(0), Seed::Number(5));
+        assert_ne!(Seed::Number(5).offset(1000), Seed::Number(1005).offset(0));
+        assert_ne!(Seed::Number(5).offset(1001), Seed::Number(1005).offset(1));
+    }
+
+    #[test]
+    fn test_set_dictionary() {
+        let mut config = PasswordGeneratorConfig::new();
+        config.set_dictionary(
+            ["Tree", "cat", " tree ", "house"]
+                .iter()
+                .map(|w| w.to_string()),
+        );
+        assert_eq!(config.dictionary, vec!["house", "tree"]);
+        assert_eq!(
+            crate::generator::find_dictionary_word("x9TREEq", &config.dictionary),
+            Some("tree")
+        );
+        assert_eq!(
+            crate::generator::find_dictionary_word("x9catq", &config.dictionary),
+            None
+        );
+    }
+
+    #[test]
+    fn test_set_forbidden() {
+        let mut config = PasswordGeneratorConfig::new();
+        config.set_forbidden(["Password", "", " ACME "].iter().map(|w| w.to_string()));
+        assert_eq!(config.forbidden, vec!["acme", "password"]);
+        assert!(crate::generator::find_forbidden("xP@ssw0rdx", &config.forbidden).is_some());
+        assert!(crate::generator::find_forbidden("4cm3-9", &config.forbidden).is_some());
+        assert!(crate::generator::find_forbidden("passw-ord", &config.forbidden).is_none());
+    }
+
+    #[test]
+    fn test_join_style() {
+        let join = |style: JoinStyle| {
+            ["correct", "Horse", "battery"]
+                .iter()
+                .enumerate()
+                .map(|(i, word)| style.apply(word, i))
+                .collect::<Vec<_>>()
+                .join(style.separator())
+        };
+        assert_eq!(join("camel".parse().unwrap()), "correctHorseBattery");
+        assert_eq!(join(JoinStyle::Pascal), "CorrectHorseBattery");
+        assert_eq!(join(JoinStyle::Kebab), "correct-horse-battery");
+        assert_eq!(join(JoinStyle::Snake), "correct_horse_battery");
+        assert_eq!(join(JoinStyle::None), "correctHorsebattery");
+        assert!("title".parse::<JoinStyle>().is_err());
+    }
+
+    #[test]
+    fn test_shell_safe() {
+        let mut config = PasswordGeneratorConfig::new();
+        config.set_allowed_chars("allprint");
+        config.shell_safe = true;
+        let pool: String = config.available_chars().into_iter().collect();
+        assert!(pool.contains('a') && pool.contains('Z') && pool.contains('0'));
+        assert!(pool.chars().all(is_shell_safe));
+        assert!(pool.contains('_') && !pool.contains('$') && !pool.contains('\'') && !pool.contains(' '));
+        assert!(config.validate().is_ok());
+
+        config.pattern = Some("LLLL-DDDD".to_string());
+        assert!(config.validate().is_ok());
+        config.pattern = Some("LLLL\\$DDDD".to_string());
+        assert!(config.validate().is_err());
+        config.pattern = None;
+
+        config.separator = Some(Separator::Fixed(" ".to_string()));
+        assert!(config.validate().is_err());
+        config.join_style = Some(JoinStyle::Kebab);
+        assert!(config.validate().is_ok());
+
+        config.clear_allowed_chars();
+        config.set_allowed_chars("symbol1");
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_escape_profile() {
+        let mut config = PasswordGeneratorConfig::new();
+        config.set_allowed_chars("allprint");
+        config.escape_profile = Some(EscapeProfile::Ldap);
+        let pool: String = config.available_chars().into_iter().collect();
+        assert!(pool.contains('a') && pool.contains('-') && pool.contains('\''));
+        assert!(!pool.contains(['"', '\\', ',', '=', '#', '*', '(']));
+        assert!(config.validate().is_ok());
+
+        config.escape_profile = Some(EscapeProfile::Sql);
+        assert!(!config.available_chars().contains(&'\''));
+        config.pattern = Some("LLLL;DDDD".to_string());
+        assert!(config.validate().unwrap_err().to_string().contains("--escape-profile sql"));
+
+        assert!(!EscapeProfile::Json.allows('\0') && !EscapeProfile::Json.allows(' '));
+        assert!(EscapeProfile::Json.allows('\''));
+        assert_eq!("JSON".parse::<EscapeProfile>(), Ok(EscapeProfile::Json));
+    }
+
+    #[test]
+    fn test_parse_separator() {
+        assert!(matches!(parse_separator("-"), Ok(Separator::Fixed(s)) if s == "-"));
+        assert!(matches!(parse_separator(", "), Ok(Separator::Fixed(s)) if s == ", "));
+        assert!(matches!(
+            parse_separator("random"),
+            Ok(Separator::Random(chars)) if chars.len() == 36
+        ));
+        assert!(matches!(
+            parse_separator("set:symbol1"),
+            Ok(Separator::Random(chars)) if chars == "#%&?@".chars().collect::<Vec<char>>()
+        ));
+        assert!(parse_separator("set:invalid_charset").is_err());
+        assert!(parse_separator("").is_err());
+        assert!(matches!(parse_separator("cycle"), Ok(Separator::Cycle(chars)) if chars == ['-', '.', '_']));
+        assert!(matches!(parse_separator("cycle:+="), Ok(Separator::Cycle(chars)) if chars == ['+', '=']));
+        assert!(parse_separator("cycle:").is_err());
+        assert!(matches!(
+            parse_separator("positional"),
+            Ok(Separator::Positional(chars)) if chars.len() == 10
+        ));
+        assert!(parse_separator("positional:nope").is_err());
+
+        assert_eq!(Separator::Fixed("-".to_string()).entropy_bits(3), 0.0);
+        assert_eq!(Separator::Random(vec!['a', 'b']).entropy_bits(3), 3.0);
+        assert_eq!(Separator::Positional(vec!['a', 'b']).entropy_bits(4), 3.0);
+        assert_eq!(Separator::Positional(vec!['a', 'b']).entropy_bits(0), 0.0);
+    }
 }
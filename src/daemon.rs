@@ -0,0 +1,165 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/daemon.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::{PasswordGeneratorError, Result};
+use dirs::home_dir;
+use std::future::Future;
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::signal::unix::{signal, SignalKind};
+use tracing::{debug, warn};
+use zeroize::Zeroize;
+
+pub fn default_socket() -> Result<PathBuf> {
+    let home = home_dir().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "Home directory not found")
+    })?;
+    Ok(home.join(".npwg").join("npwg.sock"))
+}
+
+/// Listens on `path`, readable and writable by its owner only. A socket left
+/// behind by a daemon that died is replaced; one that still answers is not,
+/// and neither is anything at `path` that is not a socket.
+///
+/// The socket is bound in a private directory next to `path`, given its mode
+/// and only then renamed into place, so it is never reachable with the
+/// default mode.
+pub async fn bind(path: &Path) -> Result<UnixListener> {
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(PasswordGeneratorError::InvalidConfig(format!(
+                "{} exists and is not a socket; refusing to replace it",
+                path.display()
+            )));
+        }
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(PasswordGeneratorError::InvalidConfig(format!(
+                "A daemon is already listening on {}",
+                path.display()
+            )));
+        }
+        std::fs::remove_file(path)?;
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(parent)?;
+    let nonce = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.subsec_nanos());
+    let staging = parent.join(format!(".npwg-bind-{}-{}", std::process::id(), nonce));
+    std::fs::DirBuilder::new().mode(0o700).create(&staging)?;
+    let staged = staging.join("npwg.sock");
+    let bound = (|| -> std::io::Result<UnixListener> {
+        let listener = UnixListener::bind(&staged)?;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+        std::fs::rename(&staged, path)?;
+        Ok(listener)
+    })();
+    let _ = std::fs::remove_file(&staged);
+    let _ = std::fs::remove_dir(&staging);
+    Ok(bound?)
+}
+
+/// Answers request lines from every connection with `answer` until Ctrl-C
+/// or SIGTERM, then removes the socket. Connections from another user than
+/// the one owning the socket are closed unanswered, whatever the file mode
+/// says.
+pub async fn serve<F, Fut>(listener: UnixListener, path: &Path, answer: F) -> Result<()>
+where
+    F: Fn(String) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = String> + Send,
+{
+    let owner = std::fs::metadata(path)?.uid();
+    let mut terminate = signal(SignalKind::terminate())?;
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => accepted?.0,
+            _ = tokio::signal::ctrl_c() => break,
+            _ = terminate.recv() => break,
+        };
+        match stream.peer_cred() {
+            Ok(peer) if peer.uid() == owner => {}
+            Ok(peer) => {
+                warn!(uid = peer.uid(), "refused connection from another user");
+                continue;
+            }
+            Err(error) => {
+                warn!(%error, "cannot read peer credentials, refusing connection");
+                continue;
+            }
+        }
+        let answer = answer.clone();
+        tokio::spawn(async move {
+            if let Err(error) = handle_connection(stream, answer).await {
+                debug!(%error, "connection closed");
+            }
+        });
+    }
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+async fn handle_connection<F, Fut>(stream: UnixStream, answer: F) -> std::io::Result<()>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = String>,
+{
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut response = answer(line).await;
+        response.push('\n');
+        let written = writer.write_all(response.as_bytes()).await;
+        response.zeroize();
+        written?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bind_and_serve() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("npwg.sock");
+        let listener = bind(&path).await.unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert!(bind(&path).await.is_err());
+        assert!(std::fs::read_dir(dir).unwrap().all(|entry| entry.unwrap().path() == path));
+
+        let notes = dir.join("notes.txt");
+        std::fs::write(&notes, "keep me").unwrap();
+        assert!(bind(&notes).await.unwrap_err().to_string().contains("not a socket"));
+        assert_eq!(std::fs::read_to_string(&notes).unwrap(), "keep me");
+        std::fs::remove_file(&notes).unwrap();
+
+        let server_path = path.clone();
+        let server = tokio::spawn(async move {
+            let _ = serve(listener, &server_path, |line: String| async move { line.to_uppercase() }).await;
+        });
+        let stream = UnixStream::connect(&path).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        writer.write_all(b"one\n\ntwo\n").await.unwrap();
+        let mut lines = BufReader::new(reader).lines();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "ONE");
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "TWO");
+        server.abort();
+
+        // The aborted server leaves its socket behind, which a new daemon replaces.
+        let _ = server.await;
+        assert!(bind(&path).await.is_ok());
+    }
+}
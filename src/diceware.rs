@@ -4,47 +4,413 @@
 // Author: Volker Schwaberow <volker@schwaberow.de>
 // Copyright (c) 2022 Volker Schwaberow
 
+use crate::blocklist::Blocklist;
 use crate::error::PasswordGeneratorError;
-use crate::error::Result;
+use crate::error::{Result, WordlistErrorKind};
+use crate::minisign::PublicKey;
 use dirs::home_dir;
-use reqwest;
-use std::fs::{self, File};
-use std::io::Write;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::{debug, info, warn};
 
 const DICEWARE_FILENAME: &str = "diceware_wordlist.txt";
 const DICEWARE_URL: &str = "https://www.eff.org/files/2016/07/18/eff_large_wordlist.txt";
 
-pub async fn get_wordlist() -> Result<Vec<String>> {
+/// Files of the wordlist cache: the list, its signature and the state of an
+/// interrupted download.
+const CACHE_SUFFIXES: [&str; 4] = ["", ".minisig", ".part", ".part.validator"];
+
+static TRUSTED_KEY: OnceLock<PublicKey> = OnceLock::new();
+static BLOCKLIST: OnceLock<Blocklist> = OnceLock::new();
+static CACHE_DIR: OnceLock<PathBuf> = OnceLock::new();
+static MIGRATED: std::sync::Once = std::sync::Once::new();
+
+/// Requires every wordlist loaded from now on to carry a minisign signature
+/// by `key` next to it.
+pub fn set_trusted_key(key: PublicKey) {
+    let _ = TRUSTED_KEY.set(key);
+}
+
+/// Drops the words of `blocklist` from every wordlist loaded from now on.
+pub fn set_blocklist(blocklist: Blocklist) {
+    let _ = BLOCKLIST.set(blocklist);
+}
+
+/// Keeps the wordlist in `dir` instead of the data directory.
+pub fn set_cache_dir(dir: PathBuf) {
+    let _ = CACHE_DIR.set(dir);
+}
+
+/// Where the wordlist is kept: `--cache-dir`, or `npwg` in the platform data
+/// directory (`~/.local/share/npwg` on Linux). Files that earlier versions
+/// kept in `~/.npwg` are moved to the data directory on first use.
+pub fn cache_dir() -> Result<PathBuf> {
+    if let Some(dir) = CACHE_DIR.get() {
+        return Ok(dir.clone());
+    }
     let home = home_dir().ok_or_else(|| {
         std::io::Error::new(std::io::ErrorKind::NotFound, "Home directory not found")
     })?;
-    let wordlist_path = home.join(".npwg").join(DICEWARE_FILENAME);
+    let legacy = home.join(".npwg");
+    let Some(dir) = dirs::data_dir().map(|data| data.join("npwg")) else {
+        return Ok(legacy);
+    };
+    MIGRATED.call_once(|| match migrate_cache(&legacy, &dir) {
+        Ok(moved) if !moved.is_empty() => {
+            info!(from = %legacy.display(), to = %dir.display(), files = moved.len(), "moved the wordlist cache")
+        }
+        Ok(_) => {}
+        Err(error) => warn!(%error, "could not move the wordlist cache from {}", legacy.display()),
+    });
+    Ok(dir)
+}
+
+/// Moves the cache files from `legacy` to `dir`, leaving alone any that
+/// already exist in `dir`, and returns the new paths.
+fn migrate_cache(legacy: &Path, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut moved = Vec::new();
+    for suffix in CACHE_SUFFIXES {
+        let name = format!("{}{}", DICEWARE_FILENAME, suffix);
+        let (from, to) = (legacy.join(&name), dir.join(&name));
+        if !from.is_file() || to.exists() {
+            continue;
+        }
+        fs::create_dir_all(dir)?;
+        // A rename fails across file systems, e.g. a separately mounted home.
+        if fs::rename(&from, &to).is_err() {
+            fs::copy(&from, &to)?;
+            fs::remove_file(&from)?;
+        }
+        moved.push(to);
+    }
+    Ok(moved)
+}
+
+/// Where the minisign signature of a wordlist is expected: the same path
+/// with `.minisig` appended.
+pub fn signature_path(wordlist_path: &Path) -> PathBuf {
+    let mut path = wordlist_path.as_os_str().to_owned();
+    path.push(".minisig");
+    PathBuf::from(path)
+}
+
+/// Verifies the wordlist at `path` against its `.minisig` file and returns
+/// the signature's trusted comment.
+pub fn verify_wordlist(path: &Path, signature: Option<&Path>, key: &PublicKey) -> Result<String> {
+    verify_contents(path, &fs::read(path)?, signature, key)
+}
+
+/// Like [`verify_wordlist`], for the `contents` already read from `path`, so
+/// the bytes that are checked are the ones that get used.
+fn verify_contents(path: &Path, contents: &[u8], signature: Option<&Path>, key: &PublicKey) -> Result<String> {
+    let signature = signature.map_or_else(|| signature_path(path), Path::to_path_buf);
+    let signature_text = fs::read_to_string(&signature).map_err(|e| {
+        PasswordGeneratorError::wordlist(WordlistErrorKind::Signature, format!(
+            "signature check failed: cannot read {}: {}",
+            signature.display(),
+            e
+        ))
+    })?;
+    key.verify(contents, &signature_text)
+}
+
+pub fn wordlist_path() -> Result<PathBuf> {
+    Ok(cache_dir()?.join(DICEWARE_FILENAME))
+}
+
+pub async fn get_wordlist() -> Result<Vec<String>> {
+    let wordlist_path = wordlist_path()?;
 
     if wordlist_path.exists() {
-        let wordlist = std::fs::read_to_string(&wordlist_path)?;
-        Ok(wordlist
+        info!(path = %wordlist_path.display(), "loading cached wordlist");
+        let contents = fs::read(&wordlist_path)?;
+        if let Some(key) = TRUSTED_KEY.get() {
+            let comment = verify_contents(&wordlist_path, &contents, None, key)?;
+            info!(key = %key.key_id(), comment, "wordlist signature verified");
+        }
+        let wordlist = String::from_utf8(contents)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let mut words: Vec<String> = wordlist
             .lines()
             .filter_map(|line| line.split_once('\t'))
             .map(|(_, word)| word.to_string())
-            .collect())
+            .collect();
+        if let Some(blocklist) = BLOCKLIST.get() {
+            let removed = blocklist.filter(&mut words);
+            info!(removed, "blocked words removed from wordlist");
+        }
+        debug!(words = words.len(), "wordlist loaded");
+        Ok(words)
     } else {
+        if TRUSTED_KEY.get().is_some() {
+            return Err(PasswordGeneratorError::wordlist(WordlistErrorKind::Signature, format!(
+                "signature check failed: a wordlist key is set but no wordlist is installed at {}; the EFF download is unsigned, so install a signed list and its .minisig there",
+                wordlist_path.display()
+            )));
+        }
+        crate::net::ensure_download(&format!(
+            "downloading the diceware wordlist (none is cached at {})",
+            wordlist_path.display()
+        ))?;
         download_wordlist(&wordlist_path).await?;
         Err(PasswordGeneratorError::WordlistDownloaded)
     }
 }
 
-async fn download_wordlist(wordlist_path: &PathBuf) -> Result<()> {
-    println!("Downloading wordlist from {}", DICEWARE_URL);
+/// The dice index of every word in the installed wordlist, as printed in
+/// its first column.
+pub fn dice_indices() -> Result<HashMap<String, String>> {
+    Ok(parse_dice_indices(&fs::read_to_string(wordlist_path()?)?))
+}
+
+fn parse_dice_indices(wordlist: &str) -> HashMap<String, String> {
+    wordlist
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(index, word)| (word.to_string(), index.trim().to_string()))
+        .collect()
+}
+
+pub fn bits_per_word(wordlist_len: usize) -> f64 {
+    (wordlist_len as f64).log2()
+}
+
+pub fn words_for_entropy(wordlist_len: usize, bits: f64) -> Result<usize> {
+    if wordlist_len < 2 {
+        return Err(PasswordGeneratorError::wordlist(
+            WordlistErrorKind::TooFewWords,
+            "Wordlist must contain at least two words to provide entropy",
+        ));
+    }
+    if !bits.is_finite() || bits <= 0.0 {
+        return Err(PasswordGeneratorError::InvalidConfig(
+            "Target entropy must be a positive number of bits".to_string(),
+        ));
+    }
+    Ok(((bits / bits_per_word(wordlist_len)).ceil() as usize).max(1))
+}
+
+fn dice_digits(size: usize) -> Option<u32> {
+    let mut digits = 0;
+    let mut n = size;
+    while n > 1 && n.is_multiple_of(6) {
+        n /= 6;
+        digits += 1;
+    }
+    (n == 1 && digits > 0).then_some(digits)
+}
 
-    let response = reqwest::get(DICEWARE_URL).await?.text().await?;
-    fs::create_dir_all(wordlist_path.parent().ok_or_else(|| {
-        std::io::Error::new(std::io::ErrorKind::NotFound, "Parent directory not found")
-    })?)?;
+pub fn dice_index(index: usize, digits: u32) -> String {
+    (0..digits)
+        .rev()
+        .map(|position| {
+            let roll = (index / 6usize.pow(position)) % 6 + 1;
+            char::from_digit(roll as u32, 10).unwrap()
+        })
+        .collect()
+}
 
-    let mut file = File::create(wordlist_path)?;
-    file.write_all(response.as_bytes())?;
+pub fn build_wordlist(
+    corpus: &str,
+    size: usize,
+    min_len: usize,
+    max_len: usize,
+) -> Result<Vec<String>> {
+    if dice_digits(size).is_none() {
+        return Err(PasswordGeneratorError::wordlist(WordlistErrorKind::InvalidSize, format!(
+            "Wordlist size {} is not a power of 6 (e.g. 1296 or 7776)",
+            size
+        )));
+    }
 
-    println!("Wordlist downloaded to {:?}", wordlist_path);
+    let mut frequencies: HashMap<String, usize> = HashMap::new();
+    for token in corpus.split(|c: char| !c.is_alphabetic()) {
+        let word = token.to_lowercase();
+        let length = word.chars().count();
+        if length >= min_len && length <= max_len {
+            *frequencies.entry(word).or_insert(0) += 1;
+        }
+    }
+
+    if frequencies.len() < size {
+        return Err(PasswordGeneratorError::wordlist(WordlistErrorKind::CorpusTooSmall, format!(
+            "Corpus only contains {} distinct words between {} and {} characters, {} required",
+            frequencies.len(),
+            min_len,
+            max_len,
+            size
+        )));
+    }
+
+    let mut ranked: Vec<(String, usize)> = frequencies.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let mut words: Vec<String> = ranked.into_iter().take(size).map(|(w, _)| w).collect();
+    words.sort();
+    Ok(words)
+}
+
+pub fn format_wordlist(words: &[String]) -> String {
+    let digits = dice_digits(words.len()).unwrap_or(1);
+    words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| format!("{}\t{}\n", dice_index(i, digits), word))
+        .collect()
+}
+
+/// Where an unfinished download of the wordlist is kept until it is complete
+/// and has been checked.
+fn partial_path(wordlist_path: &Path) -> PathBuf {
+    let mut path = wordlist_path.as_os_str().to_owned();
+    path.push(".part");
+    PathBuf::from(path)
+}
+
+/// Whether `bytes` could be the start of a wordlist: UTF-8 apart from a
+/// character cut off at the end, and every complete line `index<TAB>word`.
+fn is_wordlist_prefix(bytes: &[u8]) -> bool {
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(error) if error.error_len().is_none() => {
+            std::str::from_utf8(&bytes[..error.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return false,
+    };
+    let complete = text.rfind('\n').map_or("", |end| &text[..end]);
+    complete.lines().all(|line| line.trim().is_empty() || is_wordlist_line(line))
+}
+
+fn is_wordlist_line(line: &str) -> bool {
+    line.split_once('\t')
+        .is_some_and(|(index, word)| !index.trim().is_empty() && !word.trim().is_empty())
+}
+
+/// Checks a finished download before it replaces the installed wordlist.
+fn validate_wordlist(text: &str) -> Result<()> {
+    let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+    if let Some(line) = lines.iter().find(|line| !is_wordlist_line(line)) {
+        return Err(PasswordGeneratorError::wordlist(WordlistErrorKind::Malformed, format!(
+            "downloaded wordlist has a line that is not 'index<TAB>word': {:?}",
+            line
+        )));
+    }
+    if lines.len() < 2 {
+        return Err(PasswordGeneratorError::wordlist(
+            WordlistErrorKind::TooFewWords,
+            "downloaded wordlist contains fewer than two words",
+        ));
+    }
     Ok(())
 }
+
+async fn download_wordlist(wordlist_path: &Path) -> Result<()> {
+    eprintln!("Downloading wordlist from {}", DICEWARE_URL);
+
+    info!(url = DICEWARE_URL, path = %wordlist_path.display(), "wordlist not cached, downloading");
+    let partial = partial_path(wordlist_path);
+    crate::net::fetch_to_file(DICEWARE_URL, &partial, is_wordlist_prefix).await?;
+    let contents = fs::read(&partial)?;
+    debug!(bytes = contents.len(), "wordlist download complete");
+    let checked = String::from_utf8(contents)
+        .map_err(|_| {
+            PasswordGeneratorError::wordlist(
+                WordlistErrorKind::Malformed,
+                "downloaded wordlist is not valid UTF-8",
+            )
+        })
+        .and_then(|text| validate_wordlist(&text));
+    if let Err(error) = checked {
+        let _ = fs::remove_file(&partial);
+        return Err(error);
+    }
+    fs::rename(&partial, wordlist_path)?;
+
+    eprintln!("Wordlist downloaded to {:?}", wordlist_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_cache() {
+        let root = tempfile::tempdir().unwrap();
+        let (legacy, dir) = (root.path().join(".npwg"), root.path().join("data").join("npwg"));
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join(DICEWARE_FILENAME), "11111\tabacus\n").unwrap();
+        fs::write(legacy.join("diceware_wordlist.txt.minisig"), "old").unwrap();
+        fs::write(legacy.join("config.toml"), "").unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("diceware_wordlist.txt.minisig"), "new").unwrap();
+
+        let moved = migrate_cache(&legacy, &dir).unwrap();
+        let kept = fs::read_to_string(dir.join("diceware_wordlist.txt.minisig")).unwrap();
+        let left = (legacy.join("diceware_wordlist.txt.minisig").exists(), legacy.join("config.toml").exists());
+        let again = migrate_cache(&legacy, &dir).unwrap();
+
+        assert_eq!(moved, [dir.join(DICEWARE_FILENAME)]);
+        assert_eq!(kept, "new");
+        assert_eq!(left, (true, true));
+        assert!(again.is_empty());
+    }
+
+    #[test]
+    fn test_dice_index() {
+        assert_eq!(dice_index(0, 5), "11111");
+        assert_eq!(dice_index(7775, 5), "66666");
+        assert_eq!(dice_index(6, 2), "21");
+        assert_eq!(dice_digits(7776), Some(5));
+        assert_eq!(dice_digits(1296), Some(4));
+        assert_eq!(dice_digits(1000), None);
+        assert_eq!(dice_digits(1), None);
+    }
+
+    #[test]
+    fn test_wordlist_prefix_and_validation() {
+        assert!(is_wordlist_prefix(b"11111\tabacus\n11112\tabdo"));
+        assert!(is_wordlist_prefix("11111\tabacus\n11112\tcaf\u{e9}".as_bytes().split_last().unwrap().1));
+        assert!(!is_wordlist_prefix(b"<html>\n<body>"));
+        assert!(!is_wordlist_prefix(b"11111\tabacus\n\xff\xfe\n"));
+
+        assert!(validate_wordlist("11111\tabacus\n11112\tabdomen\n").is_ok());
+        assert!(validate_wordlist("11111\tabacus\n").is_err());
+        assert!(matches!(
+            validate_wordlist("11111\tabacus\n<html>\n"),
+            Err(PasswordGeneratorError::WordlistError {
+                kind: WordlistErrorKind::Malformed,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_dice_indices() {
+        let indices = parse_dice_indices("11111\tabacus\n11112\tabdomen\nno index\n");
+        assert_eq!(indices.len(), 2);
+        assert_eq!(indices["abdomen"], "11112");
+    }
+
+    #[test]
+    fn test_build_wordlist() {
+        let corpus = "The quick brown fox. The lazy dog! Quick, quick: brown? a an jumps over";
+        let words = build_wordlist(corpus, 6, 3, 5).unwrap();
+        assert_eq!(words, vec!["brown", "dog", "fox", "jumps", "quick", "the"]);
+        assert_eq!(format_wordlist(&words).lines().next(), Some("1\tbrown"));
+
+        assert!(build_wordlist(corpus, 36, 3, 5).is_err());
+        assert!(build_wordlist(corpus, 5, 3, 5).is_err());
+    }
+
+    #[test]
+    fn test_words_for_entropy() {
+        assert_eq!(words_for_entropy(7776, 90.0).unwrap(), 7);
+        assert_eq!(words_for_entropy(7776, 77.0).unwrap(), 6);
+        assert_eq!(words_for_entropy(1024, 40.0).unwrap(), 4);
+        assert_eq!(words_for_entropy(1024, 0.5).unwrap(), 1);
+        assert!(words_for_entropy(1, 40.0).is_err());
+        assert!(words_for_entropy(7776, 0.0).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/encrypt.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::{PasswordGeneratorError, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recipient {
+    Age(String),
+    Gpg(String),
+}
+
+impl std::fmt::Display for Recipient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recipient::Age(key) => write!(f, "age recipient {}", key),
+            Recipient::Gpg(key) => write!(f, "GPG key {}", key),
+        }
+    }
+}
+
+impl std::str::FromStr for Recipient {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if s.starts_with("age1") {
+            s.parse::<age::x25519::Recipient>()
+                .map_err(|e| format!("Invalid age recipient: {}", e))?;
+            Ok(Recipient::Age(s.to_string()))
+        } else if let Some(key) = s.strip_prefix("gpg:") {
+            Ok(Recipient::Gpg(key.to_string()))
+        } else if !s.is_empty() && !s.starts_with('-') {
+            Ok(Recipient::Gpg(s.to_string()))
+        } else {
+            Err(format!("Invalid recipient: {}", s))
+        }
+    }
+}
+
+/// Opens `path` for writing, readable only by the owner. A file that already
+/// existed with wider permissions is tightened through the open handle before
+/// it is emptied, so no secret is ever written to it with the old mode, and a
+/// symlink at `path` is refused rather than followed.
+#[cfg(unix)]
+fn create_private(path: &Path) -> Result<File> {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .mode(0o600)
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(path)
+        .map_err(|e| match e.raw_os_error() {
+            Some(libc::ELOOP) => PasswordGeneratorError::Export(format!(
+                "Refusing to write a secret through the symlink {}",
+                path.display()
+            )),
+            _ => e.into(),
+        })?;
+    if file.metadata()?.is_file() {
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        file.set_len(0)?;
+    }
+    Ok(file)
+}
+
+#[cfg(not(unix))]
+fn create_private(path: &Path) -> Result<File> {
+    Ok(OpenOptions::new().write(true).create(true).truncate(true).open(path)?)
+}
+
+/// Writes `bytes` readable only by the owner, also tightening the mode of a
+/// file that already existed with wider permissions.
+pub fn write_private(path: &Path, bytes: &[u8]) -> Result<()> {
+    create_private(path)?.write_all(bytes)?;
+    Ok(())
+}
+
+pub fn write_output(path: &Path, plaintext: &[u8], recipient: Option<&Recipient>) -> Result<()> {
+    match recipient {
+        None => create_private(path)?.write_all(plaintext)?,
+        Some(Recipient::Age(key)) => {
+            let recipient = key
+                .parse::<age::x25519::Recipient>()
+                .map_err(|e| PasswordGeneratorError::Export(e.to_string()))?;
+            let ciphertext = age::encrypt(&recipient, plaintext).map_err(|e| {
+                PasswordGeneratorError::Export(format!("age encryption failed: {}", e))
+            })?;
+            create_private(path)?.write_all(&ciphertext)?;
+        }
+        Some(Recipient::Gpg(key)) => {
+            let mut child = Command::new("gpg")
+                .args([
+                    "--batch",
+                    "--yes",
+                    "--encrypt",
+                    "--recipient",
+                    key,
+                    "--output",
+                ])
+                .arg(path)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .spawn()
+                .map_err(|e| {
+                    PasswordGeneratorError::Export(format!("Failed to run 'gpg': {}", e))
+                })?;
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(plaintext)?;
+            }
+            let status = child.wait()?;
+            if !status.success() {
+                return Err(PasswordGeneratorError::Export(format!(
+                    "gpg encryption for '{}' failed ({})",
+                    key, status
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use age::secrecy::ExposeSecret;
+    use std::io::Read;
+
+    #[test]
+    fn test_parse_recipient() {
+        let identity = age::x25519::Identity::generate();
+        let public = identity.to_public().to_string();
+        assert_eq!(
+            public.parse::<Recipient>(),
+            Ok(Recipient::Age(public.clone()))
+        );
+        assert!("age1invalid".parse::<Recipient>().is_err());
+        assert_eq!(
+            "gpg:alice@example.com".parse::<Recipient>(),
+            Ok(Recipient::Gpg("alice@example.com".to_string()))
+        );
+        assert_eq!(
+            "0xDEADBEEF".parse::<Recipient>(),
+            Ok(Recipient::Gpg("0xDEADBEEF".to_string()))
+        );
+        assert!("--armor".parse::<Recipient>().is_err());
+    }
+
+    #[test]
+    fn test_write_output_age_roundtrip() {
+        let identity = age::x25519::Identity::generate();
+        let recipient = Recipient::Age(identity.to_public().to_string());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("passwords.age");
+
+        write_output(&path, b"secret\n", Some(&recipient)).unwrap();
+        let mut ciphertext = Vec::new();
+        File::open(&path)
+            .unwrap()
+            .read_to_end(&mut ciphertext)
+            .unwrap();
+
+        assert!(!ciphertext.windows(6).any(|w| w == b"secret"));
+        let identity: age::x25519::Identity = identity.to_string().expose_secret().parse().unwrap();
+        assert_eq!(age::decrypt(&identity, &ciphertext).unwrap(), b"secret\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_private_tightens_existing_file() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keyfile");
+        std::fs::write(&path, b"old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private(&path, &[0, 1, 2]).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        let contents = std::fs::read(&path).unwrap();
+
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(contents, [0, 1, 2]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_private_refuses_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let (target, link) = (dir.path().join("target"), dir.path().join("link"));
+        std::fs::write(&target, b"keep").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let written = write_private(&link, b"secret");
+        let contents = std::fs::read(&target).unwrap();
+
+        assert!(written.unwrap_err().to_string().contains("symlink"));
+        assert_eq!(contents, b"keep");
+    }
+}
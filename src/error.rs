@@ -4,23 +4,105 @@
 // Author: Volker Schwaberow <volker@schwaberow.de>
 // Copyright (c) 2022 Volker Schwaberow
 
+use crate::i18n::{tr, trf};
+use crate::net::DownloadFailure;
 use dialoguer::Error as DialoguerError;
 use thiserror::Error;
 
+/// What went wrong with a wordlist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordlistErrorKind {
+    /// Fewer than two words, so no entropy per word.
+    TooFewWords,
+    /// A built list whose size is not a power of 6.
+    InvalidSize,
+    /// The corpus has too few usable words for the requested size.
+    CorpusTooSmall,
+    /// The minisign signature is missing, malformed or does not match.
+    Signature,
+    /// A downloaded list that is not in the `index<TAB>word` format.
+    Malformed,
+}
+
+/// What went wrong with the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(feature = "clipboard"), allow(dead_code))]
+pub enum ClipboardErrorKind {
+    /// No clipboard could be reached, e.g. no display server.
+    Unavailable,
+    /// The request is not supported by this platform or build.
+    Unsupported,
+    /// The clipboard was reached but refused the copy.
+    CopyFailed,
+    /// The background process that owns the clipboard could not run.
+    Daemon,
+}
+
 #[derive(Error, Debug)]
 pub enum PasswordGeneratorError {
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
+    #[error("Invalid pattern '{pattern}': {reason} at column {column}; run --pattern-help for the grammar")]
+    PatternError {
+        pattern: String,
+        column: usize,
+        reason: String,
+        hint: String,
+    },
+    #[error("Invalid password rules: {reason} at column {column}")]
+    RulesError {
+        rules: String,
+        column: usize,
+        width: usize,
+        reason: String,
+        hint: String,
+    },
+    #[error("Policy violation: {0}")]
+    PolicyViolation(String),
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+    #[cfg(feature = "download")]
     #[error("Network error: {0}")]
     Network(#[from] reqwest::Error),
+    #[error("Download of {url} failed after {attempts} attempt(s): {failure}")]
+    #[cfg_attr(not(feature = "download"), allow(dead_code))]
+    Download {
+        url: String,
+        attempts: u32,
+        failure: DownloadFailure,
+    },
+    #[error("Offline mode: {0} requires network access")]
+    Offline(String),
+    #[error("{0} is not possible: npwg was built without the download feature")]
+    DownloadUnavailable(String),
+    #[error("Wordlist error: {message}")]
+    WordlistError {
+        kind: WordlistErrorKind,
+        message: String,
+    },
     #[error("Worldlist downloaded, restart the program to use it.")]
     WordlistDownloaded,
     #[error("Dialoguer error: {0}")]
     DialoguerError(DialoguerError),
-    #[error("{0}")]
-    ClipboardError(String),
+    #[error("{message}")]
+    ClipboardError {
+        kind: ClipboardErrorKind,
+        message: String,
+    },
+    #[error("Export error: {0}")]
+    Export(String),
+    #[error("Store error: {0}")]
+    Store(String),
+    #[error("External scorer error: {0}")]
+    ExternalScorer(String),
+    #[error("Plugin error: {0}")]
+    Plugin(String),
+    #[error("Password quality check failed:\n {0}")]
+    QualityCheckFailed(String),
+    #[error("Below the strength threshold: {0}")]
+    BelowThreshold(String),
+    #[error("Not compliant with the policy: {0}")]
+    NonCompliant(String),
 }
 
 impl From<DialoguerError> for PasswordGeneratorError {
@@ -29,4 +111,227 @@ impl From<DialoguerError> for PasswordGeneratorError {
     }
 }
 
+impl PasswordGeneratorError {
+    pub fn wordlist(kind: WordlistErrorKind, message: impl Into<String>) -> Self {
+        PasswordGeneratorError::WordlistError {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    pub fn clipboard(kind: ClipboardErrorKind, message: impl Into<String>) -> Self {
+        PasswordGeneratorError::ClipboardError {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PasswordGeneratorError::InvalidConfig(_) => "invalid_config",
+            PasswordGeneratorError::PatternError { .. } => "pattern",
+            PasswordGeneratorError::RulesError { .. } => "rules",
+            PasswordGeneratorError::PolicyViolation(_) => "policy_violation",
+            PasswordGeneratorError::Io(_) => "io",
+            #[cfg(feature = "download")]
+            PasswordGeneratorError::Network(_) => "network",
+            PasswordGeneratorError::Download { .. } => "network",
+            PasswordGeneratorError::Offline(_) => "offline",
+            PasswordGeneratorError::DownloadUnavailable(_) => "download_unavailable",
+            PasswordGeneratorError::WordlistError {
+                kind: WordlistErrorKind::Signature,
+                ..
+            } => "wordlist_signature",
+            PasswordGeneratorError::WordlistError { .. } => "wordlist",
+            PasswordGeneratorError::WordlistDownloaded => "wordlist_downloaded",
+            PasswordGeneratorError::DialoguerError(_) => "interactive",
+            PasswordGeneratorError::ClipboardError { .. } => "clipboard",
+            PasswordGeneratorError::Export(_) => "export",
+            PasswordGeneratorError::Store(_) => "store",
+            PasswordGeneratorError::ExternalScorer(_) => "external_scorer",
+            PasswordGeneratorError::Plugin(_) => "plugin",
+            PasswordGeneratorError::QualityCheckFailed(_) => "quality_check_failed",
+            PasswordGeneratorError::BelowThreshold(_) => "below_threshold",
+            PasswordGeneratorError::NonCompliant(_) => "non_compliant",
+        }
+    }
+
+    /// The message in the active locale. Details that come from other
+    /// libraries or from validation are passed through as they are.
+    pub fn localized(&self) -> String {
+        match self {
+            PasswordGeneratorError::InvalidConfig(message) => {
+                trf("Invalid configuration: {}", &[message])
+            }
+            PasswordGeneratorError::PatternError {
+                pattern,
+                column,
+                reason,
+                ..
+            } => trf(
+                "Invalid pattern '{}': {} at column {}; run --pattern-help for the grammar",
+                &[pattern, reason, column],
+            ),
+            PasswordGeneratorError::RulesError { column, reason, .. } => trf(
+                "Invalid password rules: {} at column {}",
+                &[reason, column],
+            ),
+            PasswordGeneratorError::PolicyViolation(message) => {
+                trf("Policy violation: {}", &[message])
+            }
+            PasswordGeneratorError::Io(error) => trf("IO error: {}", &[error]),
+            #[cfg(feature = "download")]
+            PasswordGeneratorError::Network(error) => trf("Network error: {}", &[error]),
+            PasswordGeneratorError::Download {
+                url,
+                attempts,
+                failure,
+            } => trf(
+                "Download of {} failed after {} attempt(s): {}",
+                &[url, attempts, failure],
+            ),
+            PasswordGeneratorError::Offline(action) => {
+                trf("Offline mode: {} requires network access", &[action])
+            }
+            PasswordGeneratorError::DownloadUnavailable(action) => trf(
+                "{} is not possible: npwg was built without the download feature",
+                &[action],
+            ),
+            PasswordGeneratorError::WordlistError { message, .. } => {
+                trf("Wordlist error: {}", &[message])
+            }
+            PasswordGeneratorError::WordlistDownloaded => {
+                tr("Worldlist downloaded, restart the program to use it.").to_string()
+            }
+            PasswordGeneratorError::DialoguerError(error) => trf("Dialoguer error: {}", &[error]),
+            PasswordGeneratorError::ClipboardError { message, .. } => message.clone(),
+            PasswordGeneratorError::Export(message) => trf("Export error: {}", &[message]),
+            PasswordGeneratorError::Store(message) => trf("Store error: {}", &[message]),
+            PasswordGeneratorError::ExternalScorer(message) => {
+                trf("External scorer error: {}", &[message])
+            }
+            PasswordGeneratorError::Plugin(message) => trf("Plugin error: {}", &[message]),
+            PasswordGeneratorError::QualityCheckFailed(message) => {
+                trf("Password quality check failed:\n {}", &[&tr(message)])
+            }
+            PasswordGeneratorError::BelowThreshold(message) => {
+                trf("Below the strength threshold: {}", &[message])
+            }
+            PasswordGeneratorError::NonCompliant(message) => {
+                trf("Not compliant with the policy: {}", &[message])
+            }
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PasswordGeneratorError::InvalidConfig(_)
+            | PasswordGeneratorError::PatternError { .. }
+            | PasswordGeneratorError::RulesError { .. }
+            | PasswordGeneratorError::PolicyViolation(_) => 2,
+            PasswordGeneratorError::ClipboardError { .. } => 3,
+            #[cfg(feature = "download")]
+            PasswordGeneratorError::Network(_) => 4,
+            PasswordGeneratorError::Download { .. }
+            | PasswordGeneratorError::Offline(_)
+            | PasswordGeneratorError::DownloadUnavailable(_) => 4,
+            PasswordGeneratorError::Io(_) | PasswordGeneratorError::Export(_) => 5,
+            PasswordGeneratorError::Store(_) => 6,
+            PasswordGeneratorError::WordlistError {
+                kind: WordlistErrorKind::Signature,
+                ..
+            } => 7,
+            PasswordGeneratorError::WordlistError { .. } => 2,
+            PasswordGeneratorError::WordlistDownloaded
+            | PasswordGeneratorError::DialoguerError(_)
+            | PasswordGeneratorError::ExternalScorer(_)
+            | PasswordGeneratorError::Plugin(_)
+            | PasswordGeneratorError::QualityCheckFailed(_) => 1,
+            PasswordGeneratorError::BelowThreshold(_) => 8,
+            PasswordGeneratorError::NonCompliant(_) => 9,
+        }
+    }
+}
+
+impl PasswordGeneratorError {
+    /// For errors in user-written strings, the input with a caret line under
+    /// the offending characters and a hint, ready to print below the message.
+    pub fn snippet(&self) -> Option<String> {
+        let (input, column, width, hint) = match self {
+            PasswordGeneratorError::PatternError {
+                pattern,
+                column,
+                hint,
+                ..
+            } => (pattern, *column, 1, hint),
+            PasswordGeneratorError::RulesError {
+                rules,
+                column,
+                width,
+                hint,
+                ..
+            } => (rules, *column, *width, hint),
+            _ => return None,
+        };
+        let padding: String = input
+            .chars()
+            .take(column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        Some(format!(
+            "  |\n  | {}\n  | {}{}\n  = {} {}",
+            input,
+            padding,
+            "^".repeat(width.max(1)),
+            tr("hint:"),
+            tr(hint)
+        ))
+    }
+}
+
 pub type Result<T> = std::result::Result<T, PasswordGeneratorError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let errors = [
+            PasswordGeneratorError::InvalidConfig("bad".to_string()),
+            PasswordGeneratorError::clipboard(ClipboardErrorKind::Unavailable, "bad"),
+            PasswordGeneratorError::Io(std::io::Error::other("bad")),
+            PasswordGeneratorError::WordlistDownloaded,
+        ];
+        let codes: Vec<i32> = errors.iter().map(|e| e.exit_code()).collect();
+        assert_eq!(codes, vec![2, 3, 5, 1]);
+        assert_eq!(errors[0].kind(), "invalid_config");
+        assert_eq!(errors[1].kind(), "clipboard");
+        assert_eq!(errors[0].localized(), errors[0].to_string());
+    }
+
+    #[test]
+    fn test_typed_variants() {
+        let pattern = PasswordGeneratorError::PatternError {
+            pattern: "LDX".to_string(),
+            column: 3,
+            reason: "unknown symbol 'X'".to_string(),
+            hint: "escape it".to_string(),
+        };
+        assert_eq!(pattern.kind(), "pattern");
+        assert!(pattern.to_string().contains("unknown symbol 'X' at column 3"));
+        assert_eq!(
+            pattern.snippet().unwrap(),
+            "  |\n  | LDX\n  |   ^\n  = hint: escape it"
+        );
+        assert!(PasswordGeneratorError::PolicyViolation("x".to_string()).snippet().is_none());
+
+        let signature = PasswordGeneratorError::wordlist(WordlistErrorKind::Signature, "bad");
+        let size = PasswordGeneratorError::wordlist(WordlistErrorKind::InvalidSize, "bad");
+        assert_eq!((signature.exit_code(), signature.kind()), (7, "wordlist_signature"));
+        assert_eq!((size.exit_code(), size.kind()), (2, "wordlist"));
+        assert_eq!(PasswordGeneratorError::PolicyViolation("x".to_string()).exit_code(), 2);
+        assert_eq!(PasswordGeneratorError::BelowThreshold("x".to_string()).exit_code(), 8);
+        assert_eq!(PasswordGeneratorError::NonCompliant("x".to_string()).exit_code(), 9);
+    }
+}
@@ -0,0 +1,282 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/export.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::{PasswordGeneratorError, Result};
+use keepass::db::fields;
+use keepass::{Database, DatabaseKey};
+use std::fs::File;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Account {
+    pub title: String,
+    pub username: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeepassFormat {
+    Xml,
+    Kdbx,
+}
+
+impl KeepassFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_lowercase)
+            .as_deref()
+        {
+            Some("xml") => Ok(KeepassFormat::Xml),
+            Some("kdbx") => Ok(KeepassFormat::Kdbx),
+            _ => Err(PasswordGeneratorError::InvalidConfig(format!(
+                "Cannot determine KeePass format of '{}' (use a .xml or .kdbx extension)",
+                path.display()
+            ))),
+        }
+    }
+}
+
+pub fn parse_accounts_csv(contents: &str) -> Result<Vec<Account>> {
+    let mut accounts = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let columns: Vec<&str> = line.split(',').map(str::trim).collect();
+        if number == 0 && columns[0].eq_ignore_ascii_case("title") {
+            continue;
+        }
+        if columns.len() > 3 || columns[0].is_empty() {
+            return Err(PasswordGeneratorError::InvalidConfig(format!(
+                "Invalid account on line {}: expected 'title[,username[,url]]'",
+                number + 1
+            )));
+        }
+        accounts.push(Account {
+            title: columns[0].to_string(),
+            username: columns.get(1).unwrap_or(&"").to_string(),
+            url: columns.get(2).unwrap_or(&"").to_string(),
+        });
+    }
+    Ok(accounts)
+}
+
+pub fn default_accounts(count: usize) -> Vec<Account> {
+    (1..=count)
+        .map(|i| Account {
+            title: format!("npwg {}", i),
+            username: String::new(),
+            url: String::new(),
+        })
+        .collect()
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+pub fn keepass_xml(accounts: &[Account], passwords: &[String]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\" standalone=\"yes\"?>\n<KeePassFile>\n\t<Meta>\n\t\t<Generator>npwg</Generator>\n\t</Meta>\n\t<Root>\n\t\t<Group>\n\t\t\t<Name>npwg</Name>\n",
+    );
+    for (account, password) in accounts.iter().zip(passwords) {
+        xml.push_str("\t\t\t<Entry>\n");
+        for (key, value, protect) in [
+            (fields::TITLE, account.title.as_str(), false),
+            (fields::USERNAME, account.username.as_str(), false),
+            (fields::PASSWORD, password.as_str(), true),
+            (fields::URL, account.url.as_str(), false),
+        ] {
+            let value_tag = if protect {
+                "<Value ProtectInMemory=\"True\">"
+            } else {
+                "<Value>"
+            };
+            xml.push_str(&format!(
+                "\t\t\t\t<String><Key>{}</Key>{}{}</Value></String>\n",
+                key,
+                value_tag,
+                escape_xml(value)
+            ));
+        }
+        xml.push_str("\t\t\t</Entry>\n");
+    }
+    xml.push_str("\t\t</Group>\n\t</Root>\n</KeePassFile>\n");
+    xml
+}
+
+pub fn write_kdbx(
+    path: &Path,
+    accounts: &[Account],
+    passwords: &[String],
+    master_password: &str,
+) -> Result<()> {
+    let mut db = Database::new();
+    db.root_mut().edit(|group| group.name = "npwg".into());
+    for (account, password) in accounts.iter().zip(passwords) {
+        db.root_mut().add_entry().edit(|entry| {
+            entry.set_unprotected(fields::TITLE, account.title.as_str());
+            entry.set_unprotected(fields::USERNAME, account.username.as_str());
+            entry.set_protected(fields::PASSWORD, password.as_str());
+            entry.set_unprotected(fields::URL, account.url.as_str());
+        });
+    }
+
+    let mut file = File::create(path)?;
+    db.save(&mut file, DatabaseKey::new().with_password(master_password))
+        .map_err(|e| PasswordGeneratorError::Export(e.to_string()))
+}
+
+/// The note type of an Anki export.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnkiCards {
+    /// One card per passphrase, asking for it by its number.
+    Basic,
+    /// One cloze per word, so each review asks for the words in turn.
+    Cloze,
+}
+
+impl std::fmt::Display for AnkiCards {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnkiCards::Basic => write!(f, "basic"),
+            AnkiCards::Cloze => write!(f, "cloze"),
+        }
+    }
+}
+
+impl std::str::FromStr for AnkiCards {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "basic" => Ok(AnkiCards::Basic),
+            "cloze" => Ok(AnkiCards::Cloze),
+            _ => Err(format!("Invalid Anki card type: {} (use basic or cloze)", s)),
+        }
+    }
+}
+
+/// Turns every run of letters and digits into its own cloze deletion and
+/// keeps the separators between them visible.
+fn cloze_words(passphrase: &str) -> String {
+    let mut text = String::with_capacity(passphrase.len() * 2);
+    let mut number = 0;
+    let mut in_word = false;
+    for c in passphrase.chars() {
+        if c.is_alphanumeric() != in_word {
+            if in_word {
+                text.push_str("}}");
+            } else {
+                number += 1;
+                text.push_str(&format!("{{{{c{}::", number));
+            }
+            in_word = !in_word;
+        }
+        text.push(c);
+    }
+    if in_word {
+        text.push_str("}}");
+    }
+    text
+}
+
+/// A tab separated file Anki imports with File > Import. The header lines
+/// pick the note type and deck, so no field mapping has to be done by hand.
+/// Each note is named by `label` and its number, such as "Passphrase 2".
+pub fn anki_tsv(secrets: &[String], cards: AnkiCards, deck: &str, label: &str) -> Result<String> {
+    if deck.trim().is_empty() || deck.chars().any(char::is_control) {
+        return Err(PasswordGeneratorError::InvalidConfig(format!(
+            "Invalid Anki deck name: '{}'",
+            deck
+        )));
+    }
+    if secrets.iter().any(|s| s.contains(['\t', '\n', '\r'])) {
+        return Err(PasswordGeneratorError::Export(
+            "Secrets with tabs or line breaks cannot be written to an Anki TSV file".to_string(),
+        ));
+    }
+    let notetype = match cards {
+        AnkiCards::Basic => "Basic",
+        AnkiCards::Cloze => "Cloze",
+    };
+    let mut tsv = format!(
+        "#separator:tab\n#html:false\n#notetype:{}\n#deck:{}\n#tags:npwg\n",
+        notetype, deck
+    );
+    for (i, secret) in secrets.iter().enumerate() {
+        match cards {
+            AnkiCards::Basic => tsv.push_str(&format!("{} {}\t{}\n", label, i + 1, secret)),
+            AnkiCards::Cloze => tsv.push_str(&format!("{}\t{} {}\n", cloze_words(secret), label, i + 1)),
+        }
+    }
+    Ok(tsv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_accounts_csv() {
+        let accounts =
+            parse_accounts_csv("title,username,url\nmail,alice,https://mail\n\ndb\n").unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].username, "alice");
+        assert_eq!(accounts[0].url, "https://mail");
+        assert_eq!(accounts[1].title, "db");
+        assert!(accounts[1].username.is_empty());
+
+        assert!(parse_accounts_csv("a,b,c,d").is_err());
+        assert!(parse_accounts_csv(",bob").is_err());
+    }
+
+    #[test]
+    fn test_keepass_xml_escapes_values() {
+        let accounts = default_accounts(1);
+        let xml = keepass_xml(&accounts, &["a<b&\"c".to_string()]);
+        assert!(xml.contains("<Key>Title</Key><Value>npwg 1</Value>"));
+        assert!(xml.contains("<Value ProtectInMemory=\"True\">a&lt;b&amp;&quot;c</Value>"));
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            KeepassFormat::from_path(Path::new("vault.KDBX")).unwrap(),
+            KeepassFormat::Kdbx
+        );
+        assert_eq!(
+            KeepassFormat::from_path(Path::new("export.xml")).unwrap(),
+            KeepassFormat::Xml
+        );
+        assert!(KeepassFormat::from_path(Path::new("vault")).is_err());
+    }
+
+    #[test]
+    fn test_anki_tsv() {
+        assert_eq!(cloze_words("correct-horse battery9"), "{{c1::correct}}-{{c2::horse}} {{c3::battery9}}");
+        assert_eq!(cloze_words("!ab"), "!{{c1::ab}}");
+
+        let secrets = vec!["tick-tock".to_string()];
+        let cloze = anki_tsv(&secrets, AnkiCards::Cloze, "Master", "Passphrase").unwrap();
+        assert!(cloze.starts_with("#separator:tab\n#html:false\n#notetype:Cloze\n#deck:Master\n"));
+        assert!(cloze.ends_with("\n{{c1::tick}}-{{c2::tock}}\tPassphrase 1\n"));
+        let basic = anki_tsv(&secrets, AnkiCards::Basic, "npwg", "Password").unwrap();
+        assert!(basic.ends_with("#notetype:Basic\n#deck:npwg\n#tags:npwg\nPassword 1\ttick-tock\n"));
+
+        assert!(anki_tsv(&secrets, AnkiCards::Basic, "a\tb", "Password").is_err());
+        assert!(anki_tsv(&["a\tb".to_string()], AnkiCards::Basic, "npwg", "Password").is_err());
+        assert_eq!("Cloze".parse::<AnkiCards>(), Ok(AnkiCards::Cloze));
+    }
+}
@@ -0,0 +1,276 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/fips.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::config::{PasswordGeneratorConfig, PasswordGeneratorMode};
+use crate::error::{PasswordGeneratorError, Result};
+use crate::salt::random_bytes;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use std::collections::HashSet;
+use zeroize::Zeroize;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const DRBG_NAME: &str = "HMAC_DRBG(SHA-256), NIST SP 800-90A";
+const PERSONALIZATION: &[u8] = b"npwg fips";
+const ENTROPY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 16;
+const MAX_REQUEST_BYTES: usize = 1 << 16;
+const RESEED_INTERVAL: u64 = 1 << 20;
+
+/// HMAC_DRBG with SHA-256, instantiated and periodically reseeded from the
+/// operating system entropy source.
+pub struct HmacDrbg {
+    key: [u8; 32],
+    value: [u8; 32],
+    reseed_counter: u64,
+}
+
+fn hmac(key: &[u8; 32], parts: &[&[u8]]) -> [u8; 32] {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+impl HmacDrbg {
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Self {
+        let mut drbg = HmacDrbg {
+            key: [0; 32],
+            value: [1; 32],
+            reseed_counter: 1,
+        };
+        drbg.update(&[entropy, nonce, personalization]);
+        drbg
+    }
+
+    pub fn from_os() -> Result<Self> {
+        let mut seed = random_bytes(ENTROPY_LENGTH + NONCE_LENGTH)?;
+        let drbg = Self::new(
+            &seed[..ENTROPY_LENGTH],
+            &seed[ENTROPY_LENGTH..],
+            PERSONALIZATION,
+        );
+        seed.zeroize();
+        Ok(drbg)
+    }
+
+    fn update(&mut self, provided: &[&[u8]]) {
+        for round in [0x00u8, 0x01] {
+            let mut parts: Vec<&[u8]> = vec![&self.value, std::slice::from_ref(&round)];
+            parts.extend_from_slice(provided);
+            self.key = hmac(&self.key, &parts);
+            self.value = hmac(&self.key, &[&self.value]);
+            if provided.iter().all(|part| part.is_empty()) {
+                break;
+            }
+        }
+    }
+
+    pub fn reseed(&mut self, entropy: &[u8]) {
+        self.update(&[entropy]);
+        self.reseed_counter = 1;
+    }
+
+    /// One generate request; requests must not exceed 64 KiB.
+    pub fn generate(&mut self, output: &mut [u8]) -> Result<()> {
+        if self.reseed_counter > RESEED_INTERVAL {
+            let mut entropy = random_bytes(ENTROPY_LENGTH)?;
+            self.reseed(&entropy);
+            entropy.zeroize();
+        }
+        for chunk in output.chunks_mut(self.value.len()) {
+            self.value = hmac(&self.key, &[&self.value]);
+            chunk.copy_from_slice(&self.value[..chunk.len()]);
+        }
+        self.update(&[]);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+}
+
+impl Drop for HmacDrbg {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.value.zeroize();
+    }
+}
+
+impl RngCore for HmacDrbg {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(MAX_REQUEST_BYTES) {
+            self.generate(chunk)
+                .expect("operating system entropy source unavailable for reseeding");
+        }
+    }
+}
+
+/// Rejects settings whose output could not be justified as uniform draws from
+/// the approved DRBG.
+pub fn validate(config: &PasswordGeneratorConfig) -> Result<()> {
+    let refuse = |reason: &str| {
+        Err(PasswordGeneratorError::PolicyViolation(format!(
+            "FIPS mode: {}",
+            reason
+        )))
+    };
+    if config.seed.is_some() {
+        return refuse("a user-supplied seed replaces the approved entropy source");
+    }
+    if config.pronounceable {
+        return refuse("pronounceable passwords do not draw uniformly from the character set");
+    }
+    if config.avoid_repetition {
+        return refuse("avoiding repeated characters skews the character distribution");
+    }
+    if !config.charset_weights.is_empty() {
+        return refuse("weighted character sets do not draw uniformly from the character set");
+    }
+    if config.max_consecutive.is_some() {
+        return refuse("limiting repeated characters skews the character distribution");
+    }
+    if !config.required_sets.is_empty() {
+        return refuse("placing required character classes (--rules, --site) skews the character distribution");
+    }
+    if config.pattern.is_some() {
+        return refuse("patterns fall back to literal characters and fixed word slots instead of uniform draws");
+    }
+    if config.min_strength.is_some() {
+        return refuse("filtering by heuristic strength score skews the output distribution");
+    }
+    if !config.dictionary.is_empty()
+        || !config.forbidden.is_empty()
+        || config.reject_keyboard_runs
+        || config.avoid_sequential
+    {
+        return refuse("rejecting candidates that contain dictionary words, forbidden substrings, keyboard runs or sequential characters skews the output distribution");
+    }
+    Ok(())
+}
+
+pub fn validate_wordlist(wordlist: &[String]) -> Result<()> {
+    if wordlist.iter().collect::<HashSet<_>>().len() != wordlist.len() {
+        return Err(PasswordGeneratorError::PolicyViolation(
+            "FIPS mode: the wordlist contains duplicate words, so draws are not uniform"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// The line printed to stderr after generating in FIPS mode, recording the
+/// construction and parameters used.
+pub fn attestation(
+    config: &PasswordGeneratorConfig,
+    wordlist_len: Option<usize>,
+) -> String {
+    let (mode, space, bits) = match (&config.mode, wordlist_len) {
+        (PasswordGeneratorMode::Diceware, Some(words)) => (
+            "diceware",
+            format!("wordlist={}", words),
+            config.passphrase_entropy_bits(words),
+        ),
+        _ => (
+            "password",
+            format!("pool={}", config.pool_size()),
+            config.entropy_bits(),
+        ),
+    };
+    format!(
+        "FIPS attestation: drbg={}; entropy=os; seed=none; mode={}; length={}; count={}; {}; entropy_bits={:.1}",
+        DRBG_NAME,
+        mode,
+        config
+            .length_range
+            .or(config.word_range)
+            .map_or(config.length.to_string(), |range| range.to_string()),
+        config.num_passwords,
+        space,
+        bits
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_hmac_drbg_cavp_vector() {
+        // NIST CAVP HMAC_DRBG SHA-256, no prediction resistance, COUNT = 0.
+        let mut drbg = HmacDrbg::new(
+            &hex("ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488"),
+            &hex("659ba96c601dc69fc902940805ec0ca8"),
+            &[],
+        );
+        let mut output = [0u8; 128];
+        drbg.generate(&mut output).unwrap();
+        drbg.generate(&mut output).unwrap();
+        assert_eq!(
+            output.to_vec(),
+            hex("e528e9abf2dece54d47c7e75e5fe302149f817ea9fb4bee6f4199697d04d5b89d54fbb978a15b5c443c9ec21036d2460b6f73ebad0dc2aba6e624abf07745bc107694bb7547bb0995f70de25d6b29e2d3011bb19d27676c07162c8b5ccde0668961df86803482cb37ed6d5c0bb8d50cf1f50d476aa0458bdaba806f48be9dcb8")
+        );
+    }
+
+    #[test]
+    fn test_validate_refuses_unjustified_settings() {
+        let mut config = PasswordGeneratorConfig::new();
+        assert!(validate(&config).is_ok());
+
+        // Overlapping sets add each character once, so draws stay uniform.
+        config.add_allowed_chars("digit");
+        assert!(validate(&config).is_ok());
+        assert_eq!(config.available_chars().len(), config.pool_size());
+
+        let mut config = PasswordGeneratorConfig::new();
+        config.seed = Some("1".parse().unwrap());
+        assert!(validate(&config).is_err());
+
+        let mut config = PasswordGeneratorConfig::new();
+        config.pronounceable = true;
+        assert!(validate(&config).is_err());
+
+        let mut config = PasswordGeneratorConfig::new();
+        config.required_sets = vec![vec!['0', '1']];
+        assert!(validate(&config).is_err());
+
+        let mut config = PasswordGeneratorConfig::new();
+        config.pattern = Some("LLDD".to_string());
+        assert!(validate(&config).is_err());
+
+        assert!(validate_wordlist(&["a".to_string(), "b".to_string()]).is_ok());
+        assert!(validate_wordlist(&["a".to_string(), "a".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_attestation_lists_parameters() {
+        let mut config = PasswordGeneratorConfig::new();
+        config.length = 20;
+        let line = attestation(&config, None);
+        assert!(line.contains("HMAC_DRBG(SHA-256)"));
+        assert!(line.contains("mode=password; length=20; count=1; pool=94"));
+    }
+}
@@ -4,13 +4,23 @@
 // Author: Volker Schwaberow <volker@schwaberow.de>
 // Copyright (c) 2022 Volker Schwaberow
 
-use crate::config::PasswordGeneratorConfig;
-use crate::config::Separator;
+use crate::config::{CharClass, PasswordGeneratorConfig, PatternToken};
+use crate::config::{Seed, Separator};
+use crate::grammar::{pluralize, Lexicon, Role, Template};
+use crate::error::PasswordGeneratorError;
+use crate::fips::HmacDrbg;
+use crate::progress::{Progress, Unit};
+use crate::strength::{
+    evaluate_generated_strength, find_keyboard_sequence, find_sequential_run, KEYBOARD_RUN_LENGTH,
+    SEQUENTIAL_RUN_LENGTH,
+};
 use clap::ValueEnum;
 use rand::seq::IndexedRandom;
 use rand::seq::IteratorRandom;
-use rand::{Rng, SeedableRng};
+use rand::{Rng, RngCore, SeedableRng};
 use rand::rngs::StdRng;
+use tracing::trace;
+use zeroize::Zeroize;
 
 const DEFAULT_SEPARATORS: &[char] = &[
     'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's',
@@ -24,6 +34,8 @@ pub enum MutationType {
     Remove,
     Swap,
     Shift,
+    Append,
+    Prepend,
 }
 
 impl std::fmt::Display for MutationType {
@@ -34,6 +46,8 @@ impl std::fmt::Display for MutationType {
             MutationType::Remove => write!(f, "remove"),
             MutationType::Swap => write!(f, "swap"),
             MutationType::Shift => write!(f, "shift"),
+            MutationType::Append => write!(f, "append"),
+            MutationType::Prepend => write!(f, "prepend"),
         }
     }
 }
@@ -48,124 +62,713 @@ impl std::str::FromStr for MutationType {
             "remove" => Ok(MutationType::Remove),
             "swap" => Ok(MutationType::Swap),
             "shift" => Ok(MutationType::Shift),
+            "append" => Ok(MutationType::Append),
+            "prepend" => Ok(MutationType::Prepend),
             _ => Err(format!("Invalid mutation type: {}", s)),
         }
     }
 }
 
-pub async fn generate_password(config: &PasswordGeneratorConfig) -> String {
-    let mut rng =  match config.seed {
-        Some(seed) => StdRng::seed_from_u64(seed),
-        None => StdRng::from_rng(&mut rand::rng()),
-    };
-    let mut password = String::with_capacity(config.length);
+fn config_rng(config: &PasswordGeneratorConfig) -> crate::error::Result<Box<dyn RngCore + Send>> {
+    if config.fips {
+        return Ok(Box::new(HmacDrbg::from_os()?));
+    }
+    Ok(Box::new(match &config.seed {
+        Some(seed) => seed.rng(),
+        None => match &config.jitter {
+            Some(jitter) => crate::jitter::mixed_rng(jitter),
+            None => StdRng::from_rng(&mut rand::rng()),
+        },
+    }))
+}
+
+/// One password from `config`. Fails with a policy violation instead of
+/// returning a password that misses a required character class.
+pub async fn generate_password(config: &PasswordGeneratorConfig) -> crate::error::Result<String> {
+    let mut rng = config_rng(config)?;
+    let mut password = String::new();
 
-    let mut available_chars: Vec<char> = config.allowed_chars.clone();
-    available_chars.extend(config.included_chars.iter());
-    available_chars.retain(|c| !config.excluded_chars.contains(c));
+    let length = config.draw_length(&mut rng);
+    let available_chars = config.available_chars();
+    let weights = config.char_weights();
+    trace!(pool_size = available_chars.len(), weighted = weights.is_some(), "character pool prepared");
+
+    for _ in 0..MAX_REQUIRED_ATTEMPTS {
+        password = match &config.pattern {
+            Some(pattern) => generate_with_pattern(
+                pattern,
+                &available_chars,
+                &config.pattern_words,
+                config.lexicon.as_ref(),
+                length,
+                config.max_consecutive,
+                &mut rng,
+            ),
+            None => {
+                let sampled = match &weights {
+                    Some(weights) => sample_weighted(weights, length, config.max_consecutive, &mut rng),
+                    None => sample_password(&available_chars, length, config.max_consecutive, &mut rng),
+                };
+                constrain_edges(sampled, &available_chars, config, &mut rng)
+            }
+        };
+        if contains_required_sets(&password, &config.required_sets)
+            && edges_fit(&password, config)
+            && config.normalization.is_normalized(&password)
+        {
+            return Ok(password);
+        }
+    }
 
     if let Some(pattern) = &config.pattern {
-        return generate_with_pattern(pattern, &available_chars, config.length, config.seed);
+        password.zeroize();
+        return Err(PasswordGeneratorError::PolicyViolation(format!(
+            "No password from the pattern '{}' contained every required character class after {} attempts; add the missing classes to the pattern",
+            pattern, MAX_REQUIRED_ATTEMPTS
+        )));
     }
 
-    for _ in 0..config.length {
-        if let Some(&c) = available_chars.choose(&mut rng) {
+    for _ in 0..MAX_REQUIRED_ATTEMPTS {
+        let placed = place_required_sets(&password, &available_chars, config, &mut rng);
+        password.zeroize();
+        password = constrain_edges(placed, &available_chars, config, &mut rng);
+        // Redrawing the edges may have replaced a placed character, so
+        // everything is checked again.
+        if contains_required_sets(&password, &config.required_sets)
+            && edges_fit(&password, config)
+            && within_run_limit(&password, config.max_consecutive)
+            && config.normalization.is_normalized(&password)
+        {
+            return Ok(password);
+        }
+        password.zeroize();
+        password = match &weights {
+            Some(weights) => sample_weighted(weights, length, config.max_consecutive, &mut rng),
+            None => sample_password(&available_chars, length, config.max_consecutive, &mut rng),
+        };
+    }
+    password.zeroize();
+    Err(PasswordGeneratorError::PolicyViolation(format!(
+        "No password of {} characters fits the required character classes, --start-with, --end-with and --max-repeat after {} attempts; increase the length or relax the rules",
+        length, MAX_REQUIRED_ATTEMPTS
+    )))
+}
+
+fn edges_fit(password: &str, config: &PasswordGeneratorConfig) -> bool {
+    let fits = |c: Option<char>, class: Option<CharClass>| match (c, class) {
+        (Some(c), Some(class)) => class.contains(c),
+        _ => true,
+    };
+    fits(password.chars().next(), config.start_with) && fits(password.chars().last(), config.end_with)
+}
+
+fn within_run_limit(password: &str, max_consecutive: Option<usize>) -> bool {
+    let Some(max) = max_consecutive else {
+        return true;
+    };
+    let chars: Vec<char> = password.chars().collect();
+    chars.chunk_by(|a, b| a == b).all(|run| run.len() <= max)
+}
+
+const MAX_REQUIRED_ATTEMPTS: usize = 1000;
+
+fn sample_password(
+    available_chars: &[char],
+    length: usize,
+    max_consecutive: Option<usize>,
+    rng: &mut impl Rng,
+) -> String {
+    let mut password: Vec<char> = Vec::with_capacity(length);
+
+    for _ in 0..length {
+        if let Some(c) = choose_limited(available_chars.iter(), &password, max_consecutive, rng) {
             password.push(c);
         }
     }
 
-    password
+    password.into_iter().collect()
+}
+
+/// Like [`sample_password`], but draws each character with its weight.
+fn sample_weighted(
+    weights: &[(char, f64)],
+    length: usize,
+    max_consecutive: Option<usize>,
+    rng: &mut impl Rng,
+) -> String {
+    let mut password: Vec<char> = Vec::with_capacity(length);
+
+    for _ in 0..length {
+        let choice = match (trailing_run(&password), max_consecutive) {
+            (Some((prev, run)), Some(max)) if run >= max => weights
+                .iter()
+                .filter(|(c, _)| *c != prev)
+                .copied()
+                .collect::<Vec<_>>()
+                .choose_weighted(rng, |(_, weight)| *weight)
+                .ok()
+                .map(|(c, _)| *c),
+            _ => weights
+                .choose_weighted(rng, |(_, weight)| *weight)
+                .ok()
+                .map(|(c, _)| *c),
+        };
+        if let Some(c) = choice {
+            password.push(c);
+        }
+    }
+
+    password.into_iter().collect()
 }
 
-fn generate_with_pattern(pattern: &str, available_chars: &[char], length: usize, seed: Option<u64>) -> String {
-    let mut rng = match seed {
-        Some(seed) => StdRng::seed_from_u64(seed),
-        None => StdRng::from_rng(&mut rand::rng()),
+/// Redraws the first and last character from the classes required by
+/// `--start-with` and `--end-with`, keeping run limits intact. An edge that
+/// no character fits is left as it is, for the caller to reject.
+fn constrain_edges(
+    password: String,
+    available_chars: &[char],
+    config: &PasswordGeneratorConfig,
+    rng: &mut impl Rng,
+) -> String {
+    if config.start_with.is_none() && config.end_with.is_none() {
+        return password;
+    }
+    let mut chars: Vec<char> = password.chars().collect();
+    if chars.is_empty() {
+        return password;
+    }
+    let last = chars.len() - 1;
+    let mut positions = vec![0, last];
+    positions.dedup();
+    for position in positions {
+        let classes: Vec<_> = [
+            config.start_with.filter(|_| position == 0),
+            config.end_with.filter(|_| position == last),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let fits = |c: char| classes.iter().all(|class| class.contains(c));
+        if fits(chars[position]) {
+            continue;
+        }
+        let neighbours = [position.checked_sub(1), Some(position + 1)]
+            .into_iter()
+            .flatten()
+            .filter_map(|i| chars.get(i).copied())
+            .collect::<Vec<_>>();
+        let candidates = available_chars.iter().filter(|&&c| {
+            fits(c) && (config.max_consecutive.is_none() || !neighbours.contains(&c))
+        });
+        if let Some(&c) = candidates.choose(rng) {
+            chars[position] = c;
+        }
+    }
+    chars.into_iter().collect()
+}
+
+/// The last character and how many times it repeats at the end.
+fn trailing_run(chars: &[char]) -> Option<(char, usize)> {
+    let last = *chars.last()?;
+    Some((last, chars.iter().rev().take_while(|&&c| c == last).count()))
+}
+
+/// Picks a candidate, skipping the previous character once it already
+/// repeats `max_consecutive` times.
+fn choose_limited<'a>(
+    candidates: impl Iterator<Item = &'a char>,
+    so_far: &[char],
+    max_consecutive: Option<usize>,
+    rng: &mut impl Rng,
+) -> Option<char> {
+    match (trailing_run(so_far), max_consecutive) {
+        (Some((prev, run)), Some(max)) if run >= max => {
+            candidates.filter(|&&c| c != prev).choose(rng).copied()
+        }
+        _ => candidates.choose(rng).copied(),
+    }
+}
+
+/// Replaces characters that would extend a run beyond `max_consecutive`,
+/// for output that was not sampled with the limit in place.
+fn limit_runs(text: &str, available_chars: &[char], max_consecutive: Option<usize>, rng: &mut impl Rng) -> String {
+    let Some(max) = max_consecutive else {
+        return text.to_string();
     };
-    let mut password = String::with_capacity(length);
+    let mut limited: Vec<char> = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        match trailing_run(&limited) {
+            Some((prev, run)) if prev == c && run >= max => {
+                limited.push(choose_limited(available_chars.iter(), &limited, Some(max), rng).unwrap_or(c));
+            }
+            _ => limited.push(c),
+        }
+    }
+    limited.into_iter().collect()
+}
+
+fn contains_required_sets(password: &str, required_sets: &[Vec<char>]) -> bool {
+    required_sets
+        .iter()
+        .all(|set| password.chars().any(|c| set.contains(&c)))
+}
+
+/// Overwrites one random position per required set with a character of the
+/// set, different from its neighbours when runs are limited.
+fn place_required_sets(
+    password: &str,
+    available_chars: &[char],
+    config: &PasswordGeneratorConfig,
+    rng: &mut impl Rng,
+) -> String {
+    let mut chars: Vec<char> = password.chars().collect();
+    let required_sets = &config.required_sets;
+    let positions = rand::seq::index::sample(rng, chars.len(), required_sets.len());
+    for (position, set) in positions.iter().zip(required_sets) {
+        let neighbours = [position.checked_sub(1), Some(position + 1)]
+            .into_iter()
+            .flatten()
+            .filter_map(|i| chars.get(i).copied())
+            .collect::<Vec<_>>();
+        if let Some(&c) = set
+            .iter()
+            .filter(|c| available_chars.contains(c))
+            .filter(|c| config.max_consecutive.is_none() || !neighbours.contains(c))
+            .choose(rng)
+        {
+            chars[position] = c;
+        }
+    }
+    chars.into_iter().collect()
+}
+
+/// Fills a pattern from [`crate::config::parse_pattern`]; words for `W` and
+/// `{word}` come from `words`, which is empty unless the pattern needs them,
+/// and role placeholders draw from `lexicon`. Templates with words are used
+/// as they are, without padding to `length`.
+fn generate_with_pattern(
+    pattern: &str,
+    available_chars: &[char],
+    words: &[String],
+    lexicon: Option<&Lexicon>,
+    length: usize,
+    max_consecutive: Option<usize>,
+    rng: &mut impl Rng,
+) -> String {
+    let mut password: Vec<char> = Vec::with_capacity(length);
+    let tokens = crate::config::parse_pattern(pattern).unwrap_or_default();
+    let pad = !tokens
+        .iter()
+        .any(|token| matches!(token, PatternToken::Word | PatternToken::Role(_)));
 
-    for symbol in pattern.chars() {
-        let char_opt = match symbol {
-            'L' | 'l' => available_chars.iter().filter(|c| c.is_ascii_alphabetic()).choose(&mut rng),
-            'D' | 'd' => available_chars.iter().filter(|c| c.is_ascii_digit()).choose(&mut rng),
-            'S' | 's' => available_chars.iter().filter(|c| !c.is_ascii_alphanumeric()).choose(&mut rng),
-            _ => None,
+    for token in tokens {
+        let (class, fallback): (fn(&char) -> bool, char) = match token {
+            PatternToken::Letter => (|c| c.is_ascii_alphabetic(), 'L'),
+            PatternToken::Digit => (|c| c.is_ascii_digit(), 'D'),
+            PatternToken::Symbol => (|c| !c.is_ascii_alphanumeric(), 'S'),
+            PatternToken::Word => {
+                if let Some(word) = words.choose(rng) {
+                    password.extend(word.chars());
+                }
+                continue;
+            }
+            PatternToken::Role(role) => {
+                if let Some(word) = lexicon.and_then(|lexicon| lexicon.words(role).choose(rng)) {
+                    match role {
+                        Role::Plural => password.extend(pluralize(word).chars()),
+                        _ => password.extend(word.chars()),
+                    }
+                }
+                continue;
+            }
+            PatternToken::Literal(literal) => {
+                password.push(literal);
+                continue;
+            }
         };
+        let candidates = available_chars.iter().filter(|c| class(c));
+        password.push(choose_limited(candidates, &password, max_consecutive, rng).unwrap_or(fallback));
+    }
 
-        if let Some(&c) = char_opt {
+    while pad && password.len() < length {
+        if let Some(c) = choose_limited(available_chars.iter(), &password, max_consecutive, rng) {
             password.push(c);
-        } else {
-            password.push(symbol);
         }
     }
 
-    while password.len() < length {
-        if let Some(&c) = available_chars.choose(&mut rng) {
-            password.push(c);
+    password.into_iter().collect()
+}
+
+pub async fn generate_passwords(config: &PasswordGeneratorConfig) -> crate::error::Result<Vec<String>> {
+    let mut candidate_config = config.clone();
+    let mut passwords = Vec::with_capacity(config.num_passwords);
+    let mut progress = Progress::new("Generating", Some(config.num_passwords as u64), Unit::Items);
+    for index in 0..config.num_passwords as u64 {
+        candidate_config.seed = attempt_seed(config, index, 0);
+        match generate_password(&candidate_config).await {
+            Ok(password) => passwords.push(password),
+            Err(error) => {
+                passwords.iter_mut().for_each(|p| p.zeroize());
+                return Err(error);
+            }
         }
+        progress.inc(1);
     }
+    Ok(passwords)
+}
 
-    password
+const MAX_STRENGTH_ATTEMPTS: u64 = 1000;
+
+/// The seed of the `attempt`th draw for the `index`th password of a seeded
+/// batch. Every batch path derives seeds this way, so a seeded password does
+/// not depend on which filters happen to be active.
+fn attempt_seed(config: &PasswordGeneratorConfig, index: u64, attempt: u64) -> Option<Seed> {
+    config
+        .seed
+        .map(|seed| seed.offset(index * MAX_STRENGTH_ATTEMPTS + attempt))
+}
+
+/// Restarts `rng` from the seed of the `index`th item when the batch is
+/// seeded, for the paths that draw a whole batch from one generator.
+fn reseed_for(rng: &mut Box<dyn RngCore + Send>, config: &PasswordGeneratorConfig, index: u64) {
+    if let (false, Some(seed)) = (config.fips, attempt_seed(config, index, 0)) {
+        *rng = Box::new(seed.rng());
+    }
+}
+
+/// The first dictionary word embedded in the password, ignoring case.
+pub fn find_dictionary_word<'a>(password: &str, dictionary: &'a [String]) -> Option<&'a str> {
+    let lowered = password.to_lowercase();
+    dictionary
+        .iter()
+        .find(|word| lowered.contains(word.as_str()))
+        .map(String::as_str)
+}
+
+/// Folds case and common leetspeak substitutions so that "P@ssw0rd" and
+/// "password" compare equal.
+pub fn leet_normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            '4' | '@' => 'a',
+            '8' => 'b',
+            '3' => 'e',
+            '9' => 'g',
+            '1' | '!' | '|' | 'l' => 'i',
+            '0' => 'o',
+            '5' | '$' => 's',
+            '7' | '+' => 't',
+            c => c,
+        })
+        .collect()
 }
 
-pub async fn generate_passwords(config: &PasswordGeneratorConfig) -> Vec<String> {
+/// The first blocklisted substring found in the candidate. `forbidden` holds
+/// entries already passed through [`leet_normalize`].
+pub fn find_forbidden<'a>(candidate: &str, forbidden: &'a [String]) -> Option<&'a str> {
+    if forbidden.is_empty() {
+        return None;
+    }
+    let normalized = leet_normalize(candidate);
+    forbidden
+        .iter()
+        .find(|entry| normalized.contains(entry.as_str()))
+        .map(String::as_str)
+}
+
+/// Like [`generate_passwords`] (or the pronounceable variant), but redraws each
+/// candidate until it reaches `config.min_strength` and contains no word from
+/// `config.dictionary`, no blocklisted substring and, if requested, no run of
+/// adjacent keyboard keys or sequential characters. Seeded runs stay
+/// reproducible by deriving one seed per attempt.
+pub async fn generate_strong_passwords(
+    config: &PasswordGeneratorConfig,
+) -> crate::error::Result<Vec<String>> {
+    if config.min_strength.is_none()
+        && config.dictionary.is_empty()
+        && config.forbidden.is_empty()
+        && !config.reject_keyboard_runs
+        && !config.avoid_sequential
+    {
+        return Ok(if config.pronounceable {
+            generate_pronounceable_passwords(config).await?
+        } else {
+            generate_passwords(config).await?
+        });
+    }
+
+    let mut candidate_config = config.clone();
     let mut passwords = Vec::with_capacity(config.num_passwords);
-    for _ in 0..config.num_passwords {
-        passwords.push(generate_password(config).await);
+    let mut progress = Progress::new("Generating", Some(config.num_passwords as u64), Unit::Items);
+    for index in 0..config.num_passwords as u64 {
+        match generate_strong_password(config, &mut candidate_config, index).await {
+            Ok(password) => {
+                passwords.push(password);
+                progress.inc(1);
+            }
+            Err(error) => {
+                passwords.iter_mut().for_each(|p| p.zeroize());
+                return Err(error);
+            }
+        }
+    }
+    Ok(passwords)
+}
+
+/// The `index`th password of [`generate_strong_passwords`], drawn with
+/// `candidate_config`, a clone of `config` whose seed is replaced per attempt.
+pub(crate) async fn generate_strong_password(
+    config: &PasswordGeneratorConfig,
+    candidate_config: &mut PasswordGeneratorConfig,
+    index: u64,
+) -> crate::error::Result<String> {
+    for attempt in 0..MAX_STRENGTH_ATTEMPTS {
+        candidate_config.seed = attempt_seed(config, index, attempt);
+        let mut candidate = if config.pronounceable {
+            generate_pronounceable_password(candidate_config).await?
+        } else {
+            generate_password(candidate_config).await?
+        };
+        let strong_enough = config
+            .min_strength
+            .is_none_or(|min_strength| evaluate_generated_strength(&candidate) >= min_strength);
+        if !strong_enough {
+            trace!(attempt, "candidate is below the minimum strength");
+        } else if let Some(word) = find_dictionary_word(&candidate, &config.dictionary) {
+            trace!(attempt, word, "candidate contains a dictionary word");
+        } else if find_forbidden(&candidate, &config.forbidden).is_some() {
+            trace!(attempt, "candidate contains a forbidden substring");
+        } else if config.reject_keyboard_runs
+            && find_keyboard_sequence(&candidate, KEYBOARD_RUN_LENGTH).is_some()
+        {
+            trace!(attempt, "candidate contains a keyboard sequence");
+        } else if config.avoid_sequential
+            && find_sequential_run(&candidate, SEQUENTIAL_RUN_LENGTH).is_some()
+        {
+            trace!(attempt, "candidate contains sequential characters");
+        } else {
+            trace!(attempt, "candidate passed the filters");
+            return Ok(candidate);
+        }
+        candidate.zeroize();
     }
-    passwords
+    Err(PasswordGeneratorError::PolicyViolation(match config.min_strength {
+        Some(min_strength) => format!(
+            "No candidate reached strength {} and passed the word filters after {} attempts; lower --min-strength or increase the length",
+            min_strength, MAX_STRENGTH_ATTEMPTS
+        ),
+        None => format!(
+            "No candidate passed the word and keyboard filters after {} attempts; shorten the password or relax --reject-words/--forbid/--reject-keyboard-runs/--avoid-sequential",
+            MAX_STRENGTH_ATTEMPTS
+        ),
+    }))
+}
+
+/// Like [`generate_diceware_passphrase`], but redraws each passphrase that
+/// contains a blocklisted substring, giving up after a fixed number of
+/// attempts.
+pub async fn generate_allowed_passphrases(
+    wordlist: &[String],
+    config: &PasswordGeneratorConfig,
+) -> crate::error::Result<Vec<String>> {
+    Ok(generate_allowed_passphrase_words(wordlist, config)
+        .await?
+        .into_iter()
+        .map(|(passphrase, _)| passphrase)
+        .collect())
+}
+
+/// Like [`generate_allowed_passphrases`], but also returns the wordlist
+/// positions of the words in each passphrase.
+pub async fn generate_allowed_passphrase_words(
+    wordlist: &[String],
+    config: &PasswordGeneratorConfig,
+) -> crate::error::Result<Vec<(String, Vec<usize>)>> {
+    if config.forbidden.is_empty() {
+        return diceware_passphrases(wordlist, config);
+    }
+
+    let mut candidate_config = config.clone();
+    candidate_config.num_passwords = 1;
+    let mut passphrases = Vec::with_capacity(config.num_passwords);
+    let mut progress = Progress::new("Generating", Some(config.num_passwords as u64), Unit::Items);
+    for index in 0..config.num_passwords as u64 {
+        let mut accepted = None;
+        for attempt in 0..MAX_STRENGTH_ATTEMPTS {
+            candidate_config.seed = attempt_seed(config, index, attempt);
+            let mut candidate = diceware_passphrases(wordlist, &candidate_config)?.remove(0);
+            if find_forbidden(&candidate.0, &config.forbidden).is_none() {
+                accepted = Some(candidate);
+                break;
+            }
+            trace!(attempt, "passphrase contains a forbidden substring");
+            candidate.0.zeroize();
+        }
+        match accepted {
+            Some(passphrase) => {
+                passphrases.push(passphrase);
+                progress.inc(1);
+            }
+            None => {
+                passphrases.iter_mut().for_each(|(p, _)| p.zeroize());
+                return Err(PasswordGeneratorError::PolicyViolation(format!(
+                    "No passphrase was free of forbidden substrings after {} attempts; relax --forbid",
+                    MAX_STRENGTH_ATTEMPTS
+                )));
+            }
+        }
+    }
+    Ok(passphrases)
 }
 
 pub async fn generate_diceware_passphrase(
     wordlist: &[String],
     config: &PasswordGeneratorConfig,
-) -> Vec<String> {
-    let mut rng = match config.seed {
-        Some(seed) => StdRng::seed_from_u64(seed),
-        None => StdRng::from_rng(&mut rand::rng()),
-    };
+) -> crate::error::Result<Vec<String>> {
+    Ok(diceware_passphrases(wordlist, config)?
+        .into_iter()
+        .map(|(passphrase, _)| passphrase)
+        .collect())
+}
+
+fn diceware_passphrases(
+    wordlist: &[String],
+    config: &PasswordGeneratorConfig,
+) -> crate::error::Result<Vec<(String, Vec<usize>)>> {
+    let mut rng = config_rng(config)?;
+    // Drawing positions through `choose` keeps the random stream, and with it
+    // seeded output, the same as drawing the words directly.
+    let positions: Vec<usize> = (0..wordlist.len()).collect();
     let num_passphrases = config.num_passwords;
     let num_words = config.length;
     let mut passphrases = Vec::with_capacity(num_passphrases);
-
-    for _ in 0..num_passphrases {
+    let mut progress = Progress::new("Generating", Some(num_passphrases as u64), Unit::Items);
+    for index in 0..num_passphrases as u64 {
+        reseed_for(&mut rng, config, index);
+        let num_words = match config.word_range {
+            Some(range) => rng.random_range(range.min..=range.max),
+            None => num_words,
+        };
         let mut passphrase = String::with_capacity(num_words * 5 + (num_words - 1));
+        let mut chosen = Vec::with_capacity(num_words);
+        let mut separators = Separators::new(config, DEFAULT_SEPARATORS, num_words);
         for i in 0..num_words {
-            if i > 0 {
-                passphrase.push_str(&get_separator(config, DEFAULT_SEPARATORS, &mut rng));
+            let position = *positions.choose(&mut rng).unwrap();
+            chosen.push(position);
+            let word = &wordlist[position];
+            match config.join_style {
+                Some(style) => {
+                    if i > 0 {
+                        passphrase.push_str(style.separator());
+                    }
+                    passphrase.push_str(&style.apply(word, i));
+                }
+                None => {
+                    if i > 0 {
+                        passphrase.push_str(&separators.next(&mut rng));
+                    }
+                    passphrase.push_str(word);
+                }
             }
-            passphrase.push_str(wordlist.choose(&mut rng).unwrap());
         }
-        passphrases.push(passphrase);
+        for _ in 0..config.append_digits {
+            passphrase.push(char::from(b'0' + rng.random_range(0..10u8)));
+        }
+        passphrases.push((config.normalization.apply(passphrase), chosen));
+        progress.inc(1);
     }
 
-    passphrases
+    Ok(passphrases)
 }
 
-fn get_separator(
+/// Sentence-like passphrases, one word per template slot, joined with the
+/// configured separator or `-`.
+pub fn generate_grammar_passphrases(
+    template: &Template,
+    lexicon: &Lexicon,
     config: &PasswordGeneratorConfig,
-    default_separators: &[char],
-    rng: &mut impl rand::Rng,
-) -> String {
-    match &config.separator {
-        Some(Separator::Fixed(c)) => c.to_string(),
-        Some(Separator::Random(chars)) => chars.choose(rng).unwrap().to_string(),
-        None => default_separators.choose(rng).unwrap().to_string(),
+) -> crate::error::Result<Vec<String>> {
+    let mut rng = config_rng(config)?;
+    let mut passphrases = Vec::with_capacity(config.num_passwords);
+    let mut progress = Progress::new("Generating", Some(config.num_passwords as u64), Unit::Items);
+    for index in 0..config.num_passwords as u64 {
+        reseed_for(&mut rng, config, index);
+        let mut passphrase = String::new();
+        let mut separators = Separators::new(config, &['-'], template.0.len());
+        for (i, &role) in template.0.iter().enumerate() {
+            if i > 0 {
+                passphrase.push_str(&separators.next(&mut rng));
+            }
+            let word = lexicon.words(role).choose(&mut rng).map_or("", String::as_str);
+            match role {
+                Role::Plural => passphrase.push_str(&pluralize(word)),
+                _ => passphrase.push_str(word),
+            }
+        }
+        passphrases.push(config.normalization.apply(passphrase));
+        progress.inc(1);
     }
+    Ok(passphrases)
 }
 
-pub async fn generate_pronounceable_password(config: &PasswordGeneratorConfig) -> String {
-    let mut rng = match config.seed {
-        Some(seed) => StdRng::seed_from_u64(seed),
-        None => StdRng::from_rng(&mut rand::rng()),
-    };
-    let mut password = String::with_capacity(config.length);
+/// The separators between the words of one passphrase, gap by gap.
+struct Separators<'a> {
+    config: &'a PasswordGeneratorConfig,
+    defaults: &'a [char],
+    gaps: usize,
+    gap: usize,
+    position: Option<usize>,
+}
+
+impl<'a> Separators<'a> {
+    fn new(config: &'a PasswordGeneratorConfig, defaults: &'a [char], words: usize) -> Self {
+        Separators {
+            config,
+            defaults,
+            gaps: words.saturating_sub(1),
+            gap: 0,
+            position: None,
+        }
+    }
+
+    fn next(&mut self, rng: &mut impl rand::Rng) -> String {
+        let gap = self.gap;
+        self.gap += 1;
+        match &self.config.separator {
+            Some(Separator::Fixed(separator)) => separator.clone(),
+            Some(Separator::Random(chars)) => chars.choose(rng).unwrap().to_string(),
+            Some(Separator::Cycle(chars)) => chars[gap % chars.len()].to_string(),
+            Some(Separator::Positional(chars)) => {
+                let gaps = self.gaps.max(1);
+                let position = *self.position.get_or_insert_with(|| rng.random_range(0..gaps));
+                if gap == position {
+                    chars.choose(rng).unwrap().to_string()
+                } else {
+                    String::new()
+                }
+            }
+            None => self.defaults.choose(rng).unwrap().to_string(),
+        }
+    }
+}
+
+pub async fn generate_pronounceable_password(
+    config: &PasswordGeneratorConfig,
+) -> crate::error::Result<String> {
+    let mut rng = config_rng(config)?;
+    let length = config.draw_length(&mut rng);
+    if let Some(language) = config.language {
+        return Ok(config.normalization.apply(language.table().generate(length, &mut rng)));
+    }
+
+    let mut password = String::with_capacity(length);
 
     let consonants = "bcdfghjklmnpqrstvwxyz";
     let vowels = "aeiou";
 
-    while password.len() < config.length {
-        if password.len() % 2 == 0 {
+    while password.len() < length {
+        if password.len().is_multiple_of(2) {
             password.push(
                 *consonants
                     .chars()
@@ -184,15 +787,21 @@ pub async fn generate_pronounceable_password(config: &PasswordGeneratorConfig) -
         }
     }
 
-    password
+    Ok(password)
 }
 
-pub async fn generate_pronounceable_passwords(config: &PasswordGeneratorConfig) -> Vec<String> {
+pub async fn generate_pronounceable_passwords(
+    config: &PasswordGeneratorConfig,
+) -> crate::error::Result<Vec<String>> {
+    let mut candidate_config = config.clone();
     let mut passwords = Vec::with_capacity(config.num_passwords);
-    for _ in 0..config.num_passwords {
-        passwords.push(generate_pronounceable_password(config).await);
+    let mut progress = Progress::new("Generating", Some(config.num_passwords as u64), Unit::Items);
+    for index in 0..config.num_passwords as u64 {
+        candidate_config.seed = attempt_seed(config, index, 0);
+        passwords.push(generate_pronounceable_password(&candidate_config).await?);
+        progress.inc(1);
     }
-    passwords
+    Ok(passwords)
 }
 
 pub fn mutate_password(
@@ -200,14 +809,13 @@ pub fn mutate_password(
     config: &PasswordGeneratorConfig,
     lengthen: usize,
     mutation_strength: u32,
-) -> String {
-    let mut rng = match config.seed {
-        Some(seed) => StdRng::seed_from_u64(seed),
-        None => StdRng::from_rng(&mut rand::rng()),
-    };
-    let mut mutated = password.to_string();
+) -> crate::error::Result<String> {
+    let mut rng = config_rng(config)?;
+    let available_chars = config.available_chars();
+    // Positions count characters, not bytes, so pools outside ASCII work too.
+    let mut mutated: Vec<char> = password.chars().collect();
     let mutation_count =
-        (password.len() as f64 * (mutation_strength as f64 / 10.0)).ceil() as usize;
+        (mutated.len() as f64 * (mutation_strength as f64 / 10.0)).ceil() as usize;
 
     for _ in 0..mutation_count {
         let index = rng.random_range(0..mutated.len());
@@ -221,13 +829,13 @@ pub fn mutate_password(
 
         match mutation_type {
             MutationType::Replace => {
-                if let Some(new_char) = config.allowed_chars.choose(&mut rng) {
-                    mutated.replace_range(index..index + 1, &new_char.to_string());
+                if let Some(&new_char) = available_chars.choose(&mut rng) {
+                    mutated[index] = new_char;
                 }
             }
             MutationType::Insert => {
-                if let Some(new_char) = config.allowed_chars.choose(&mut rng) {
-                    mutated.insert(index, *new_char);
+                if let Some(&new_char) = available_chars.choose(&mut rng) {
+                    mutated.insert(index, new_char);
                 }
             }
             MutationType::Remove => {
@@ -237,23 +845,77 @@ pub fn mutate_password(
             }
             MutationType::Swap => {
                 if index < mutated.len() - 1 {
-                    let mut chars: Vec<char> = mutated.chars().collect();
-                    chars.swap(index, index + 1);
-                    mutated = chars.into_iter().collect();
+                    mutated.swap(index, index + 1);
                 }
             }
             MutationType::Shift => {
                 let shift_factor = rng.random_range(1..50);
-                mutated = shift_and_encode(&mutated, shift_factor);
+                let text: String = mutated.iter().collect();
+                mutated = shift_and_encode(&text, shift_factor).chars().collect();
             }
+            MutationType::Append | MutationType::Prepend => unreachable!(),
         }
     }
 
+    let mut mutated: String = mutated.into_iter().collect();
     if lengthen > 0 {
         mutated = lengthen_password(&mutated, lengthen);
     }
 
-    mutated
+    Ok(limit_runs(&mutated, &available_chars, config.max_consecutive, &mut rng))
+}
+
+/// Attaches `count` random characters of `class` to the end (`Append`) or
+/// the start (`Prepend`) of the password, the way people usually make a
+/// password "stronger". Characters come from the configured pool, or from
+/// printable ASCII when the pool has none of that class.
+pub fn affix_password(
+    password: &str,
+    config: &PasswordGeneratorConfig,
+    mutation_type: &MutationType,
+    class: CharClass,
+    count: usize,
+) -> crate::error::Result<String> {
+    let mut rng = config_rng(config)?;
+    let mut pool: Vec<char> = config
+        .available_chars()
+        .into_iter()
+        .filter(|&c| class.contains(c))
+        .collect();
+    if pool.is_empty() {
+        pool = ('!'..='~').filter(|&c| class.contains(c)).collect();
+    }
+    let affix: String = (0..count).filter_map(|_| pool.choose(&mut rng).copied()).collect();
+    let mutated = match mutation_type {
+        MutationType::Prepend => format!("{}{}", affix, password),
+        _ => format!("{}{}", password, affix),
+    };
+    Ok(limit_runs(&mutated, &pool, config.max_consecutive, &mut rng))
+}
+
+/// Applies the chosen mutation: `Append` and `Prepend` attach
+/// `mutation_strength` characters of `class`, every other type goes through
+/// [`mutate_password`].
+pub fn apply_mutation(
+    password: &str,
+    config: &PasswordGeneratorConfig,
+    mutation_type: &MutationType,
+    class: CharClass,
+    lengthen: usize,
+    mutation_strength: u32,
+) -> crate::error::Result<String> {
+    match mutation_type {
+        MutationType::Append | MutationType::Prepend => {
+            let affixed =
+                affix_password(password, config, mutation_type, class, mutation_strength as usize)?;
+            Ok(if lengthen > 0 {
+                lengthen_password(&affixed, lengthen)
+            } else {
+                affixed
+            })
+        }
+        _ => mutate_password(password, config, lengthen, mutation_strength),
+    }
 }
 
 fn shift_and_encode(password: &str, shift: u8) -> String {
@@ -282,3 +944,206 @@ fn random_char() -> char {
         .copied()
         .unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn longest_run(text: &str) -> usize {
+        let chars: Vec<char> = text.chars().collect();
+        chars.chunk_by(|a, b| a == b).map(<[char]>::len).max().unwrap_or(0)
+    }
+
+    #[test]
+    fn test_max_consecutive_is_respected() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let chars = ['a', 'b'];
+        for _ in 0..50 {
+            assert!(longest_run(&sample_password(&chars, 40, Some(1), &mut rng)) <= 1);
+            assert!(longest_run(&generate_with_pattern("LLLL", &chars, &[], None, 40, Some(2), &mut rng)) <= 2);
+        }
+        assert_eq!(limit_runs("aaaab", &chars, Some(2), &mut rng), "aabab");
+        assert_eq!(limit_runs("aaaa", &chars, None, &mut rng), "aaaa");
+    }
+
+    #[tokio::test]
+    async fn test_required_sets_are_met_or_refused() {
+        let mut config = PasswordGeneratorConfig::new();
+        config.set_allowed_chars("lowerletter");
+        config.add_allowed_chars("digit");
+        config.required_sets = vec![vec!['7']];
+        config.length = 4;
+        config.max_consecutive = Some(1);
+        for _ in 0..50 {
+            let password = generate_password(&config).await.unwrap();
+            assert!(password.contains('7') && longest_run(&password) <= 1, "{}", password);
+        }
+
+        config.pattern = Some("LLLL".to_string());
+        let refused = generate_password(&config).await.unwrap_err();
+        assert!(matches!(refused, PasswordGeneratorError::PolicyViolation(_)));
+        assert!(generate_passwords(&config).await.is_err());
+    }
+
+    #[test]
+    fn test_mutate_with_non_ascii_pool() {
+        let mut config = PasswordGeneratorConfig::new();
+        config.clear_allowed_chars();
+        config.add_allowed_chars("unicode:Greek");
+        config.excluded_chars = std::collections::HashSet::from(['\u{3b1}']);
+        let pool = config.available_chars();
+        for seed in 0..20 {
+            config.seed = Some(Seed::Number(seed));
+            let mutated = mutate_password("abcdefgh", &config, 0, 10).unwrap();
+            assert!(mutated.chars().all(|c| c.is_ascii_lowercase() || pool.contains(&c)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_normalized_output_keeps_length() {
+        use crate::unicode::Normalization;
+        let mut config = PasswordGeneratorConfig::new();
+        config.clear_allowed_chars();
+        config.add_allowed_chars("unicode:Latin1");
+        crate::rules::parse_password_rules("minlength: 16; maxlength: 16")
+            .unwrap()
+            .apply(&mut config);
+        config.normalization = Normalization::Nfd;
+        config.seed = Some(Seed::Number(1));
+        config.num_passwords = 20;
+        for password in generate_passwords(&config).await.unwrap() {
+            assert_eq!(password.chars().count(), 16);
+            assert!(Normalization::Nfd.is_normalized(&password));
+        }
+
+        config.allowed_chars = vec!['\u{2126}', '\u{3a9}'];
+        config.normalization = Normalization::Nfc;
+        assert_eq!(config.pool_size(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_seeded_batches_match_across_paths() {
+        let mut config = PasswordGeneratorConfig::new();
+        config.num_passwords = 3;
+        config.seed = Some(Seed::Number(5));
+        let fast = generate_passwords(&config).await.unwrap();
+        assert_ne!(fast[0], fast[1]);
+        assert_ne!(fast[1], fast[2]);
+
+        // Any filter moves the batch onto the per-attempt path.
+        config.min_strength = Some(0.0);
+        assert_eq!(generate_strong_passwords(&config).await.unwrap(), fast);
+
+        let wordlist: Vec<String> = ["apple", "banana", "cherry", "date"].map(String::from).to_vec();
+        let passphrases = generate_allowed_passphrases(&wordlist, &config).await.unwrap();
+        config.forbidden = vec!["zzzz".to_string()];
+        assert_eq!(generate_allowed_passphrases(&wordlist, &config).await.unwrap(), passphrases);
+    }
+
+    #[test]
+    fn test_constrain_edges() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut config = PasswordGeneratorConfig::new();
+        config.start_with = Some(crate::config::CharClass::Letter);
+        config.end_with = Some(crate::config::CharClass::Alnum);
+        let chars = config.available_chars();
+        for _ in 0..50 {
+            let password = constrain_edges(sample_password(&chars, 12, None, &mut rng), &chars, &config, &mut rng);
+            assert!(password.starts_with(|c: char| c.is_alphabetic()));
+            assert!(password.ends_with(|c: char| c.is_alphanumeric()));
+        }
+        config.start_with = Some(crate::config::CharClass::Digit);
+        config.end_with = Some(crate::config::CharClass::Alnum);
+        let single = constrain_edges("!".to_string(), &chars, &config, &mut rng);
+        assert!(single.chars().all(|c| c.is_ascii_digit()));
+
+        config.length = 1;
+        config.end_with = Some(crate::config::CharClass::Letter);
+        assert!(config.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_edges_keep_required_sets() {
+        let mut config = PasswordGeneratorConfig::new();
+        config.set_allowed_chars("lowerletter");
+        config.add_allowed_chars("digit");
+        config.length = 2;
+        config.required_sets = vec![vec!['7']];
+        config.start_with = Some(crate::config::CharClass::Letter);
+        for _ in 0..50 {
+            let password = generate_password(&config).await.unwrap();
+            assert!(password.starts_with(|c: char| c.is_ascii_lowercase()) && password.ends_with('7'), "{}", password);
+        }
+
+        // Both positions are taken by edges that exclude the required digit.
+        config.end_with = Some(crate::config::CharClass::Letter);
+        assert!(generate_password(&config).await.is_err());
+    }
+
+    #[test]
+    fn test_pattern_with_words() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let chars: Vec<char> = "abc123!".chars().collect();
+        let words = ["apple".to_string(), "pear".to_string()];
+        for _ in 0..20 {
+            let password = generate_with_pattern("W-{word}-DDDD", &chars, &words, None, 8, None, &mut rng);
+            let parts: Vec<&str> = password.split('-').collect();
+            assert_eq!(parts.len(), 3, "{}", password);
+            assert!(words.iter().any(|w| w == parts[0]) && words.iter().any(|w| w == parts[1]));
+            assert!(parts[2].len() == 4 && parts[2].chars().all(|c| c.is_ascii_digit()));
+        }
+        let templated = generate_with_pattern("{word}{symbol}{digit}", &chars, &words, None, 20, None, &mut rng);
+        assert!(templated.ends_with(|c: char| c.is_ascii_digit()));
+        assert!(templated.contains('!'));
+        assert!(templated.len() <= 7, "{}", templated);
+
+        let lexicon = Lexicon::builtin();
+        let sentence =
+            generate_with_pattern("{adj}-{nouns}-DD", &chars, &[], Some(&lexicon), 30, None, &mut rng);
+        let parts: Vec<&str> = sentence.split('-').collect();
+        assert!(lexicon.adjectives.iter().any(|w| w == parts[0]), "{}", sentence);
+        assert!(lexicon.nouns.iter().any(|w| pluralize(w) == parts[1]), "{}", sentence);
+        assert_eq!(parts[2].len(), 2);
+    }
+
+    #[test]
+    fn test_grammar_passphrases() {
+        let mut config = PasswordGeneratorConfig::new();
+        config.num_passwords = 5;
+        config.seed = Some(crate::config::Seed::Number(9));
+        let lexicon = Lexicon::builtin();
+        let template: Template = crate::grammar::DEFAULT_TEMPLATE.parse().unwrap();
+        let passphrases = generate_grammar_passphrases(&template, &lexicon, &config).unwrap();
+        assert_eq!(passphrases, generate_grammar_passphrases(&template, &lexicon, &config).unwrap());
+        for passphrase in &passphrases {
+            let words: Vec<&str> = passphrase.split('-').collect();
+            assert_eq!(words.len(), 4, "{}", passphrase);
+            assert!(lexicon.adjectives.iter().any(|w| w == words[0]));
+            assert!(lexicon.nouns.iter().any(|w| w == words[1]));
+            assert!(lexicon.verbs.iter().any(|w| w == words[2]));
+            assert!(lexicon.nouns.iter().any(|w| pluralize(w) == words[3]));
+        }
+        config.separator = Some(Separator::Fixed(" ".to_string()));
+        let spaced = generate_grammar_passphrases(&"noun verb".parse().unwrap(), &lexicon, &config).unwrap();
+        assert!(spaced.iter().all(|p| p.split(' ').count() == 2));
+    }
+
+    #[test]
+    fn test_affix_password() {
+        let mut config = PasswordGeneratorConfig::new();
+        config.seed = Some(crate::config::Seed::Number(5));
+        let appended = affix_password("secret", &config, &MutationType::Append, CharClass::Digit, 3).unwrap();
+        assert_eq!(appended.len(), 9);
+        assert!(appended.starts_with("secret"));
+        assert!(appended[6..].chars().all(|c| c.is_ascii_digit()));
+
+        let prepended = affix_password("secret", &config, &MutationType::Prepend, CharClass::Symbol, 2).unwrap();
+        assert!(prepended.ends_with("secret"));
+        assert!(prepended[..2].chars().all(|c| c.is_ascii_punctuation()));
+
+        let lengthened =
+            apply_mutation("secret", &config, &MutationType::Append, CharClass::Upper, 2, 1).unwrap();
+        assert_eq!(lengthened.len(), 9);
+        assert!(lengthened[6..7].chars().all(|c| c.is_ascii_uppercase()));
+    }
+}
@@ -0,0 +1,350 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/grammar.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+mod words;
+
+use crate::error::{PasswordGeneratorError, Result};
+
+/// The template used by a bare `--grammar`, e.g. "purple-otter-juggles-anvils".
+pub const DEFAULT_TEMPLATE: &str = "adjective-noun-verb-nouns";
+
+/// The kind of word filling one slot of a sentence template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Adjective,
+    Noun,
+    /// A noun in its plural form, drawn from the same list as [`Role::Noun`].
+    Plural,
+    Verb,
+    Adverb,
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Role::Adjective => write!(f, "adjective"),
+            Role::Noun => write!(f, "noun"),
+            Role::Plural => write!(f, "nouns"),
+            Role::Verb => write!(f, "verb"),
+            Role::Adverb => write!(f, "adverb"),
+        }
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "adjective" | "adj" => Ok(Role::Adjective),
+            "noun" => Ok(Role::Noun),
+            "nouns" | "plural" => Ok(Role::Plural),
+            "verb" => Ok(Role::Verb),
+            "adverb" | "adv" => Ok(Role::Adverb),
+            _ => Err(format!(
+                "Invalid word role: {} (use adjective, noun, nouns, verb or adverb)",
+                s
+            )),
+        }
+    }
+}
+
+/// A sentence template such as `adjective-noun-verb-nouns`. Roles may be
+/// separated by hyphens, commas or spaces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template(pub Vec<Role>);
+
+impl std::str::FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let roles = s
+            .split(|c: char| c == '-' || c == ',' || c.is_whitespace())
+            .filter(|role| !role.is_empty())
+            .map(str::parse)
+            .collect::<std::result::Result<Vec<Role>, String>>()?;
+        if roles.is_empty() {
+            return Err("A grammar template needs at least one word role".to_string());
+        }
+        Ok(Template(roles))
+    }
+}
+
+impl std::fmt::Display for Template {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let roles: Vec<String> = self.0.iter().map(Role::to_string).collect();
+        write!(f, "{}", roles.join("-"))
+    }
+}
+
+/// Parses `--role-words ROLE=PATH`.
+pub fn parse_role_list(value: &str) -> std::result::Result<(Role, std::path::PathBuf), String> {
+    match value.split_once('=') {
+        Some((role, path)) if !path.is_empty() => Ok((role.parse()?, path.into())),
+        _ => Err(format!("expected ROLE=PATH, got '{}'", value)),
+    }
+}
+
+/// Adds the regular English `-s` ending, which plural nouns and verbs in
+/// the third person singular share.
+fn add_s(word: &str) -> String {
+    let consonant_y = word
+        .strip_suffix('y')
+        .filter(|stem| stem.ends_with(|c: char| !"aeiou".contains(c)));
+    if let Some(stem) = consonant_y {
+        format!("{}ies", stem)
+    } else if ["s", "x", "z", "ch", "sh"].iter().any(|end| word.ends_with(end)) {
+        format!("{}es", word)
+    } else {
+        format!("{}s", word)
+    }
+}
+
+/// Plural of a regular English noun.
+pub fn pluralize(noun: &str) -> String {
+    add_s(noun)
+}
+
+/// Third person singular of a regular English verb, so "juggle" becomes
+/// "juggles".
+pub fn conjugate(verb: &str) -> String {
+    add_s(verb)
+}
+
+/// The role for a part-of-speech tag: the role names themselves and the
+/// usual Universal Dependencies and Penn Treebank tags.
+fn role_for_tag(tag: &str) -> Option<Role> {
+    match tag.to_lowercase().as_str() {
+        "adjective" | "adj" | "jj" | "a" => Some(Role::Adjective),
+        "noun" | "nn" | "n" => Some(Role::Noun),
+        "verb" | "vb" | "v" => Some(Role::Verb),
+        "adverb" | "adv" | "rb" | "r" => Some(Role::Adverb),
+        _ => None,
+    }
+}
+
+/// Word lists read from files, to take the place of the built-in ones.
+/// Verbs are given in their base form and conjugated when loaded.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CustomLists {
+    lists: Vec<(Role, Vec<String>)>,
+}
+
+impl CustomLists {
+    /// Lines of a wordlist: one word each, optionally after a dice index;
+    /// blank lines and `#` comments are skipped.
+    fn words(contents: &str) -> impl Iterator<Item = &str> {
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    }
+
+    fn push(&mut self, role: Role, word: &str) {
+        let word = word.to_lowercase();
+        let word = match role {
+            Role::Verb => conjugate(&word),
+            _ => word,
+        };
+        match self.lists.iter_mut().find(|(listed, _)| *listed == role) {
+            Some((_, words)) => words.push(word),
+            None => self.lists.push((role, vec![word])),
+        }
+    }
+
+    /// Adds a list tagged with parts of speech, one `word TAG` per line
+    /// (`otter NOUN`, `purple\tadj`). Words with other tags are skipped.
+    pub fn add_tagged(&mut self, contents: &str) -> Result<()> {
+        let before = self.lists.iter().map(|(_, words)| words.len()).sum::<usize>();
+        for line in Self::words(contents) {
+            let mut fields = line.split_whitespace().rev();
+            if let (Some(tag), Some(word)) = (fields.next(), fields.next()) {
+                if let Some(role) = role_for_tag(tag) {
+                    self.push(role, word);
+                }
+            }
+        }
+        if self.lists.iter().map(|(_, words)| words.len()).sum::<usize>() == before {
+            return Err(PasswordGeneratorError::InvalidConfig(
+                "The tagged wordlist has no adjectives, nouns, verbs or adverbs; write one 'word TAG' per line"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Adds a plain list whose words all fill `role`.
+    pub fn add_list(&mut self, role: Role, contents: &str) -> Result<()> {
+        if role == Role::Plural {
+            return Err(PasswordGeneratorError::InvalidConfig(
+                "Plurals are made from the noun list; give a list for 'noun' instead".to_string(),
+            ));
+        }
+        let mut added = false;
+        for line in Self::words(contents) {
+            if let Some(word) = line.split_whitespace().last() {
+                self.push(role, word);
+                added = true;
+            }
+        }
+        if !added {
+            return Err(PasswordGeneratorError::InvalidConfig(format!(
+                "The word list for '{}' is empty",
+                role
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Word lists for each role. Verbs are kept in the third person singular so
+/// that "otter juggles anvils" reads as a sentence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lexicon {
+    pub adjectives: Vec<String>,
+    pub nouns: Vec<String>,
+    pub verbs: Vec<String>,
+    pub adverbs: Vec<String>,
+}
+
+impl Lexicon {
+    pub fn builtin() -> Self {
+        let owned = |list: &[&str]| list.iter().map(|word| word.to_string()).collect();
+        Lexicon {
+            adjectives: owned(words::ADJECTIVES),
+            nouns: owned(words::NOUNS),
+            verbs: owned(words::VERBS),
+            adverbs: owned(words::ADVERBS),
+        }
+    }
+
+    /// The built-in lists, with every role found in `custom` replaced by the
+    /// words given for it. Duplicates are dropped.
+    pub fn with_custom(custom: &CustomLists) -> Self {
+        let mut lexicon = Self::builtin();
+        for (role, words) in &custom.lists {
+            let mut words = words.clone();
+            words.sort();
+            words.dedup();
+            match role {
+                Role::Adjective => lexicon.adjectives = words,
+                Role::Noun | Role::Plural => lexicon.nouns = words,
+                Role::Verb => lexicon.verbs = words,
+                Role::Adverb => lexicon.adverbs = words,
+            }
+        }
+        lexicon
+    }
+
+    /// The list a role draws from; plurals share the noun list.
+    pub fn words(&self, role: Role) -> &[String] {
+        match role {
+            Role::Adjective => &self.adjectives,
+            Role::Noun | Role::Plural => &self.nouns,
+            Role::Verb => &self.verbs,
+            Role::Adverb => &self.adverbs,
+        }
+    }
+
+    /// Every role used by the template must have words to draw from.
+    pub fn check(&self, template: &Template) -> Result<()> {
+        match template.0.iter().find(|&&role| self.words(role).is_empty()) {
+            Some(role) => Err(PasswordGeneratorError::InvalidConfig(format!(
+                "No words are available for the '{}' slot",
+                role
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Bits contributed by each slot: log2 of the size of its list.
+    pub fn slot_bits(&self, template: &Template) -> Vec<(Role, f64)> {
+        template
+            .0
+            .iter()
+            .map(|&role| (role, (self.words(role).len().max(1) as f64).log2()))
+            .collect()
+    }
+
+    pub fn entropy_bits(&self, template: &Template) -> f64 {
+        self.slot_bits(template).iter().map(|(_, bits)| bits).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_template() {
+        let template: Template = "adj noun, verb-nouns".parse().unwrap();
+        assert_eq!(template.0, [Role::Adjective, Role::Noun, Role::Verb, Role::Plural]);
+        assert_eq!(template.to_string(), DEFAULT_TEMPLATE);
+        assert!("adjective-pronoun".parse::<Template>().is_err());
+        assert!(" - ".parse::<Template>().is_err());
+    }
+
+    #[test]
+    fn test_pluralize() {
+        assert_eq!(pluralize("anvil"), "anvils");
+        assert_eq!(pluralize("box"), "boxes");
+        assert_eq!(pluralize("bench"), "benches");
+        assert_eq!(pluralize("cherry"), "cherries");
+        assert_eq!(pluralize("monkey"), "monkeys");
+        assert_eq!(conjugate("juggle"), "juggles");
+        assert_eq!(conjugate("carry"), "carries");
+        assert_eq!(conjugate("wash"), "washes");
+    }
+
+    #[test]
+    fn test_custom_lists() {
+        let mut custom = CustomLists::default();
+        custom
+            .add_tagged("# word tag\notter NOUN\n11111\tpurple\tadj\njuggle VB\nthe DET\nOtter noun\n")
+            .unwrap();
+        custom.add_list(Role::Adverb, "gladly\n\nsadly\n").unwrap();
+        let lexicon = Lexicon::with_custom(&custom);
+        assert_eq!(lexicon.nouns, ["otter"]);
+        assert_eq!(lexicon.adjectives, ["purple"]);
+        assert_eq!(lexicon.verbs, ["juggles"]);
+        assert_eq!(lexicon.adverbs, ["gladly", "sadly"]);
+
+        let partial = Lexicon::with_custom(&{
+            let mut only_nouns = CustomLists::default();
+            only_nouns.add_list(Role::Noun, "anvil\n").unwrap();
+            only_nouns
+        });
+        assert_eq!(partial.nouns, ["anvil"]);
+        assert_eq!(partial.verbs, Lexicon::builtin().verbs);
+
+        assert!(CustomLists::default().add_tagged("the DET\nquickly\n").is_err());
+        assert!(CustomLists::default().add_list(Role::Verb, "# nothing\n").is_err());
+        assert!(CustomLists::default().add_list(Role::Plural, "anvils\n").is_err());
+
+        assert_eq!(parse_role_list("adj=a.txt"), Ok((Role::Adjective, "a.txt".into())));
+        assert!(parse_role_list("pronoun=a.txt").is_err());
+        assert!(parse_role_list("noun=").is_err());
+    }
+
+    #[test]
+    fn test_builtin_lists_and_entropy() {
+        let lexicon = Lexicon::builtin();
+        for list in [&lexicon.adjectives, &lexicon.nouns, &lexicon.verbs, &lexicon.adverbs] {
+            let mut sorted = list.clone();
+            sorted.sort();
+            sorted.dedup();
+            assert_eq!(&sorted, list, "built-in lists are sorted and unique");
+        }
+        let template: Template = DEFAULT_TEMPLATE.parse().unwrap();
+        let expected = (lexicon.adjectives.len() as f64).log2()
+            + 2.0 * (lexicon.nouns.len() as f64).log2()
+            + (lexicon.verbs.len() as f64).log2();
+        assert!((lexicon.entropy_bits(&template) - expected).abs() < 1e-9);
+        assert!(lexicon.check(&template).is_ok());
+        let empty = Lexicon { adverbs: Vec::new(), ..lexicon };
+        assert!(empty.check(&"noun adverb".parse().unwrap()).is_err());
+    }
+}
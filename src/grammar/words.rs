@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/grammar/words.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+/// Built-in adjectives.
+pub const ADJECTIVES: &[&str] = &[
+    "able", "absent", "agile", "amber", "ample", "ancient", "angry", "arctic", "artful", "ashen",
+    "autumn", "awake", "bashful", "basic", "bitter", "bland", "blazing", "bleak", "blissful",
+    "blond", "blue", "blunt", "bold", "bony", "boring", "bouncy", "brave", "breezy", "brief",
+    "bright", "brisk", "broad", "bronze", "brown", "bubbly", "bulky", "bumpy", "busy", "calm",
+    "candid", "careful", "casual", "cheeky", "cheerful", "chilly", "chubby", "civil", "classic",
+    "clean", "clever", "cloudy", "clumsy", "coastal", "cobalt", "cold", "comic", "cool", "copper",
+    "cosmic", "cozy", "crafty", "crimson", "crisp", "cruel", "cuddly", "curly", "curvy", "daily",
+    "damp", "dapper", "daring", "dark", "dazzling", "deep", "dense", "dim", "dirty", "dizzy",
+    "dreamy", "drowsy", "dry", "dull", "dusty", "eager", "early", "earthy", "easy", "elastic",
+    "electric", "elegant", "emerald", "empty", "endless", "epic", "even", "exotic", "faint", "fair",
+    "famous", "fancy", "fast", "fearless", "feisty", "fiery", "filthy", "fine", "firm", "flaky",
+    "flat", "fluffy", "foamy", "foggy", "fond", "formal", "fragile", "frank", "free", "fresh",
+    "friendly", "frosty", "frozen", "frugal", "funky", "funny", "furry", "fuzzy", "gentle", "giant",
+    "giddy", "glad", "gleaming", "glossy", "golden", "goofy", "graceful", "grand", "grassy", "gray",
+    "great", "green", "grim", "grumpy", "hairy", "handy", "happy", "hardy", "harsh", "hasty",
+    "hazy", "healthy", "hearty", "heavy", "hidden", "hollow", "holy", "homely", "honest", "huge",
+    "humble", "hungry", "icy", "idle", "jagged", "jolly", "jovial", "juicy", "jumbo", "jumpy",
+    "keen", "kind", "lanky", "large", "lavish", "lazy", "leafy", "lean", "legal", "lemon", "level",
+    "light", "limp", "little", "lively", "local", "lofty", "lone", "loud", "lovely", "loyal",
+    "lucky", "lumpy", "lunar", "lush", "magic", "major", "mellow", "merry", "mighty", "mild",
+    "minty", "misty", "modern", "modest", "moist", "moody", "mossy", "muddy", "murky", "mushy",
+    "musty", "narrow", "nasty", "neat", "nervous", "nimble", "noble", "noisy", "normal", "nosy",
+    "nutty", "oaken", "odd", "oily", "olive", "orange", "ornate", "pale", "patient", "peaceful",
+    "pearly", "perky", "petite", "pink", "plain", "plucky", "plump", "plush", "polite", "posh",
+    "pretty", "prickly", "proud", "public", "puffy", "pure", "purple", "quaint", "quick", "quiet",
+    "quirky", "rainy", "rapid", "rare", "raspy", "raw", "ready", "regal", "rich", "rigid", "ripe",
+    "rocky", "rosy", "rough", "round", "royal", "ruby", "rugged", "rural", "rusty", "sacred",
+    "salty", "sandy", "scaly", "secret", "shaggy", "shaky", "sharp", "shiny", "shy", "silent",
+    "silky", "silly", "silver", "simple", "sleek", "sleepy", "slim", "slow", "small", "smart",
+    "smoky", "smooth", "snappy", "sneaky", "snowy", "snug", "soft", "solar", "solid", "sour",
+    "spicy", "spiky", "spotty", "square", "stable", "steady", "steep", "sticky", "stormy", "stout",
+    "strange", "strict", "striped", "strong", "sturdy", "subtle", "sugary", "sunny", "super",
+    "sweet", "swift", "tall", "tame", "tangy", "tart", "tender", "tepid", "thick", "thin",
+    "thirsty", "tidy", "tiny", "tired", "topaz", "tough", "tricky", "trim", "tropical", "true",
+    "twisty", "ugly", "unique", "upbeat", "urban", "useful", "vague", "valid", "vast", "velvet",
+    "violet", "vivid", "warm", "wary", "wavy", "weary", "weird", "wet", "whole", "wicked", "wide",
+    "wild", "windy", "wise", "witty", "wobbly", "wooden", "woolly", "worried", "young", "zany",
+    "zealous", "zesty",
+];
+
+/// Built-in singular nouns with regular plurals.
+pub const NOUNS: &[&str] = &[
+    "acorn", "actor", "adder", "admiral", "airship", "album", "alley", "anchor", "angel", "ankle",
+    "anvil", "apple", "apricot", "apron", "arch", "archer", "arena", "armchair", "arrow", "artist",
+    "atlas", "attic", "axle", "badger", "bagel", "balloon", "banana", "bandit", "banner", "barn",
+    "barrel", "basket", "bat", "beach", "beacon", "bead", "beagle", "beak", "beam", "bean", "beard",
+    "beaver", "bee", "beetle", "bell", "belt", "bench", "berry", "bicycle", "bird", "biscuit",
+    "bishop", "blanket", "blender", "blimp", "blossom", "boat", "bobcat", "bonnet", "book", "boot",
+    "bottle", "boulder", "bowl", "box", "bracelet", "brain", "branch", "bread", "brick", "bridge",
+    "broom", "bubble", "bucket", "bugle", "bull", "bunny", "bus", "bush", "butler", "button",
+    "buzzard", "cabin", "cable", "cake", "camel", "camera", "candle", "cannon", "canoe", "canyon",
+    "captain", "car", "caravan", "carpet", "carrot", "castle", "cat", "caterpillar", "cave",
+    "chair", "chapel", "cheese", "cherry", "chicken", "chimney", "chipmunk", "circus", "clam",
+    "clarinet", "clock", "cloud", "clown", "coach", "coat", "cobra", "coconut", "coin", "comet",
+    "compass", "cookie", "cougar", "cowboy", "crab", "crane", "crayon", "cricket", "crocodile",
+    "crow", "crown", "crystal", "cucumber", "cup", "cupboard", "curtain", "cushion", "cyclist",
+    "dagger", "daisy", "dancer", "desert", "desk", "diamond", "dinosaur", "diver", "dog", "doll",
+    "dolphin", "donkey", "donut", "door", "dragon", "drawer", "dream", "drum", "duck", "dune",
+    "eagle", "easel", "egg", "elbow", "elephant", "elk", "emerald", "emperor", "engine", "falcon",
+    "farmer", "feather", "fence", "fern", "ferret", "ferry", "fiddle", "fig", "finch", "flag",
+    "flower", "flute", "fork", "fountain", "fox", "frog", "galaxy", "garden", "gargoyle", "gate",
+    "gazelle", "gem", "ghost", "giant", "glacier", "glove", "goat", "goblin", "gondola", "gopher",
+    "gorilla", "grape", "guitar", "gull", "hammer", "hamster", "harbor", "harp", "hat", "hawk",
+    "hedgehog", "helmet", "heron", "hill", "hobbit", "hornet", "horse", "hotel", "hut", "iceberg",
+    "iguana", "island", "jacket", "jaguar", "jar", "jellyfish", "jester", "jet", "jewel", "juggler",
+    "kettle", "key", "kingdom", "kitten", "kiwi", "knight", "koala", "ladder", "lamp", "lantern",
+    "lemon", "lemur", "leopard", "letter", "lighthouse", "lily", "lion", "lizard", "llama",
+    "lobster", "lock", "locket", "lotus", "magnet", "magpie", "mammoth", "mantis", "map", "marble",
+    "marker", "mask", "meadow", "melon", "mermaid", "meteor", "microbe", "mirror", "mitten", "mole",
+    "monk", "monkey", "monster", "moth", "mountain", "muffin", "mule", "mushroom", "napkin",
+    "narwhal", "necklace", "needle", "nest", "newt", "noodle", "nugget", "oak", "oar", "ocean",
+    "ogre", "onion", "opera", "orange", "orbit", "orchid", "ostrich", "otter", "owl", "oyster",
+    "paddle", "pagoda", "painter", "palace", "panda", "panther", "parrot", "pebble", "pelican",
+    "pencil", "penguin", "pepper", "pickle", "pigeon", "pillow", "pilot", "pine", "pirate", "pizza",
+    "planet", "plum", "poet", "pony", "poodle", "portal", "possum", "potter", "prince", "printer",
+    "puffin", "pumpkin", "puppet", "puppy", "python", "quail", "queen", "quill", "rabbit",
+    "raccoon", "radish", "raft", "rainbow", "raven", "ribbon", "riddle", "robin", "robot", "rocket",
+    "rooster", "rose", "ruby", "saddle", "sailor", "salmon", "sandal", "satellite", "scarecrow",
+    "scooter", "scroll", "seagull", "seal", "serpent", "shark", "ship", "shovel", "shrimp",
+    "singer", "skater", "skeleton", "skunk", "sled", "sloth", "snail", "snake", "spider", "spoon",
+    "squid", "squirrel", "stable", "star", "statue", "storm", "sundae", "swan", "sword", "table",
+    "tadpole", "teapot", "telescope", "tent", "thimble", "thistle", "throne", "tiger", "toad",
+    "toaster", "torch", "tortoise", "tower", "tractor", "trail", "train", "tree", "trombone",
+    "trumpet", "tuba", "tulip", "tunnel", "turkey", "turnip", "turtle", "umbrella", "unicorn",
+    "valley", "vampire", "vase", "violin", "vulture", "waffle", "wagon", "walrus", "wand", "wasp",
+    "weasel", "whale", "whistle", "wizard", "wombat", "yak", "zebra", "zeppelin",
+];
+
+/// Built-in transitive verbs in the third person singular.
+pub const VERBS: &[&str] = &[
+    "admires", "adopts", "alarms", "amuses", "annoys", "applauds", "arrests", "attacks", "avoids",
+    "bakes", "balances", "bends", "blesses", "boosts", "borrows", "bothers", "bounces", "builds",
+    "bumps", "burns", "buys", "calls", "carries", "carves", "catches", "chases", "cheers", "chews",
+    "chops", "cleans", "climbs", "clutches", "collects", "comforts", "cooks", "copies", "counts",
+    "cracks", "crushes", "cuddles", "dazzles", "decorates", "defends", "delights", "delivers",
+    "digs", "discovers", "dodges", "drags", "draws", "drops", "dusts", "eats", "embraces",
+    "enchants", "escorts", "examines", "fetches", "fixes", "flings", "folds", "follows",
+    "frightens", "gathers", "grabs", "greets", "grills", "guards", "guides", "hatches", "haunts",
+    "heals", "helps", "hides", "hugs", "hunts", "imitates", "inspects", "invents", "invites",
+    "irons", "juggles", "kicks", "kisses", "knits", "launches", "licks", "lifts", "loves",
+    "measures", "melts", "mends", "mimics", "misses", "mocks", "nudges", "obeys", "paints", "pats",
+    "peels", "picks", "pinches", "plants", "pokes", "polishes", "praises", "protects", "pulls",
+    "punches", "pushes", "questions", "races", "raises", "reaches", "recycles", "repairs",
+    "rescues", "rides", "roasts", "rocks", "rules", "salutes", "saves", "scares", "scolds",
+    "scratches", "scrubs", "searches", "serves", "shakes", "sharpens", "shaves", "shields",
+    "shocks", "shovels", "sketches", "smashes", "snatches", "sniffs", "spins", "splashes", "spots",
+    "squashes", "squeezes", "stacks", "startles", "steers", "stings", "stirs", "stretches",
+    "strokes", "studies", "summons", "surprises", "sweeps", "swings", "tackles", "tames", "tastes",
+    "teaches", "teases", "tests", "thanks", "tickles", "tosses", "touches", "tows", "traces",
+    "tracks", "trains", "traps", "tricks", "trims", "tugs", "unlocks", "unwraps", "visits", "wakes",
+    "warms", "washes", "watches", "weighs", "whacks", "whisks", "wipes", "wraps", "wrestles",
+    "yanks", "zaps",
+];
+
+/// Built-in adverbs.
+pub const ADVERBS: &[&str] = &[
+    "boldly", "bravely", "briskly", "busily", "calmly", "carefully", "cheerfully", "clumsily",
+    "coyly", "crazily", "curiously", "daily", "deftly", "dimly", "eagerly", "easily", "elegantly",
+    "evenly", "fairly", "faithfully", "fiercely", "firmly", "fondly", "freely", "gently", "gladly",
+    "gleefully", "gracefully", "greedily", "happily", "hastily", "heavily", "honestly", "hungrily",
+    "idly", "jokingly", "joyfully", "keenly", "kindly", "lazily", "lightly", "loosely", "loudly",
+    "lovingly", "loyally", "madly", "meekly", "merrily", "mildly", "neatly", "nervously", "nimbly",
+    "noisily", "oddly", "openly", "patiently", "perfectly", "playfully", "politely", "promptly",
+    "proudly", "quickly", "quietly", "rapidly", "rarely", "readily", "really", "reluctantly",
+    "roughly", "rudely", "sadly", "safely", "sharply", "shyly", "silently", "sleepily", "slowly",
+    "smoothly", "softly", "solemnly", "speedily", "sternly", "stiffly", "strictly", "sweetly",
+    "swiftly", "tenderly", "tensely", "thankfully", "tightly", "truly", "vastly", "warmly",
+    "wearily", "wildly", "wisely", "yearly", "zealously",
+];
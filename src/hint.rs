@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/hint.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+/// What a hint reveals of each word of a passphrase.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HintStyle {
+    /// The first letter of every word.
+    Initials,
+    /// The number of letters of every word.
+    Lengths,
+}
+
+impl std::fmt::Display for HintStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HintStyle::Initials => write!(f, "initials"),
+            HintStyle::Lengths => write!(f, "lengths"),
+        }
+    }
+}
+
+impl std::str::FromStr for HintStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "initials" => Ok(HintStyle::Initials),
+            "lengths" => Ok(HintStyle::Lengths),
+            _ => Err(format!("Invalid hint style: {} (use initials or lengths)", s)),
+        }
+    }
+}
+
+impl HintStyle {
+    fn reveal(&self, word: &str) -> String {
+        match self {
+            HintStyle::Initials => word.chars().next().map(String::from).unwrap_or_default(),
+            HintStyle::Lengths => word.chars().count().to_string(),
+        }
+    }
+
+    /// The hint for a passphrase made of `words`, one part per word.
+    pub fn hint(&self, words: &[&str]) -> String {
+        words
+            .iter()
+            .map(|word| self.reveal(word))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The entropy left to someone who knows the hint: each word is still one
+    /// of the wordlist entries that give the same part of the hint.
+    pub fn remaining_bits(&self, wordlist: &[String], words: &[&str]) -> f64 {
+        words
+            .iter()
+            .map(|word| {
+                let shown = self.reveal(word);
+                let matching = wordlist.iter().filter(|w| self.reveal(w) == shown).count();
+                (matching.max(1) as f64).log2()
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hint() {
+        let wordlist: Vec<String> = ["apple", "arrow", "brick", "bee", "cat"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        let words = ["apple", "bee"];
+        assert_eq!(HintStyle::Initials.hint(&words), "a b");
+        assert_eq!(HintStyle::Lengths.hint(&words), "5 3");
+        // Two words each start with a and b; three have five letters, two three.
+        assert_eq!(HintStyle::Initials.remaining_bits(&wordlist, &words), 2.0);
+        assert_eq!(HintStyle::Lengths.remaining_bits(&wordlist, &words), 3f64.log2() + 1.0);
+        assert_eq!("Lengths".parse::<HintStyle>(), Ok(HintStyle::Lengths));
+        assert!("categories".parse::<HintStyle>().is_err());
+    }
+}
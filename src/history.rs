@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/history.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::strength::{estimate_entropy_bits, evaluate_generated_strength, get_strength_feedback};
+use zeroize::Zeroize;
+
+pub struct HistoryEntry {
+    pub kind: &'static str,
+    pub value: String,
+    pub score: f64,
+}
+
+impl HistoryEntry {
+    pub fn feedback(&self) -> String {
+        get_strength_feedback(self.score)
+    }
+
+    pub fn entropy_bits(&self) -> f64 {
+        estimate_entropy_bits(&self.value)
+    }
+}
+
+impl Drop for HistoryEntry {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+/// Candidates generated during one interactive session. Kept in memory only
+/// and wiped when the session ends.
+#[derive(Default)]
+pub struct SessionHistory {
+    entries: Vec<HistoryEntry>,
+}
+
+impl SessionHistory {
+    pub fn record(&mut self, kind: &'static str, values: &[String]) {
+        self.entries.extend(values.iter().map(|value| HistoryEntry {
+            kind,
+            value: value.clone(),
+            score: evaluate_generated_strength(value),
+        }));
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_keeps_order_and_scores() {
+        let mut history = SessionHistory::default();
+        assert!(history.is_empty());
+        history.record(
+            "password",
+            &["abc".to_string(), "Tr0ub4dor&3xQ!".to_string()],
+        );
+        history.record("mutated", &["abd".to_string()]);
+
+        let entries = history.entries();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].value, "Tr0ub4dor&3xQ!");
+        assert_eq!(entries[2].kind, "mutated");
+        assert!(entries[1].score > entries[0].score);
+    }
+}
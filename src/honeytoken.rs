@@ -0,0 +1,192 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/honeytoken.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::{PasswordGeneratorError, Result};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// Characters at the end of a honeytoken that carry its marker.
+pub const TAG_LENGTH: usize = 6;
+
+/// Random characters a token needs besides its prefix and marker, so it does
+/// not stand out as short.
+pub const MIN_BODY_LENGTH: usize = 8;
+
+/// The marker: HMAC-SHA256 over everything before it, spelled in the token
+/// alphabet. Without the key it is indistinguishable from the random part.
+fn tag(key: &[u8], body: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(body.as_bytes());
+    mac.finalize().into_bytes()[..TAG_LENGTH]
+        .iter()
+        .map(|&b| ALPHABET[b as usize % ALPHABET.len()] as char)
+        .collect()
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Checks the prefix and total length before generating.
+pub fn validate(prefix: &str, length: usize) -> Result<()> {
+    if !prefix.chars().all(is_token_char) {
+        return Err(PasswordGeneratorError::InvalidConfig(format!(
+            "Honeytoken prefix '{}' may only contain letters, digits, '_' and '-'",
+            prefix
+        )));
+    }
+    let minimum = prefix.len() + MIN_BODY_LENGTH + TAG_LENGTH;
+    if length < minimum {
+        return Err(PasswordGeneratorError::InvalidConfig(format!(
+            "Honeytoken length must be at least {} with this prefix",
+            minimum
+        )));
+    }
+    Ok(())
+}
+
+/// A credential-shaped string of `length` characters that starts with
+/// `prefix` and ends in the marker for `key`.
+pub fn generate(key: &[u8], prefix: &str, length: usize, rng: &mut impl Rng) -> String {
+    let mut body = prefix.to_string();
+    for _ in 0..length - prefix.len() - TAG_LENGTH {
+        body.push(ALPHABET[rng.random_range(0..ALPHABET.len())] as char);
+    }
+    let marker = tag(key, &body);
+    body + &marker
+}
+
+pub fn is_honeytoken(key: &[u8], candidate: &str) -> bool {
+    if candidate.len() < MIN_BODY_LENGTH + TAG_LENGTH || !candidate.is_ascii() {
+        return false;
+    }
+    let (body, marker) = candidate.split_at(candidate.len() - TAG_LENGTH);
+    tag(key, body) == marker
+}
+
+/// Every honeytoken for `key` in `text`, with its 1-based line number.
+/// Tokens are found as runs of letters, digits, '_' and '-'.
+pub fn scan<'a>(key: &[u8], text: &'a str) -> Vec<(usize, &'a str)> {
+    text.lines()
+        .enumerate()
+        .flat_map(|(number, line)| {
+            line.split(|c: char| !is_token_char(c))
+                .filter(|word| is_honeytoken(key, word))
+                .map(move |word| (number + 1, word))
+        })
+        .collect()
+}
+
+/// Short fingerprint of the key, so a manifest says which key made it
+/// without disclosing the key.
+pub fn key_fingerprint(key: &[u8]) -> String {
+    Sha256::digest(key)[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub id: usize,
+    pub label: Option<String>,
+    pub token: String,
+    pub sha256: String,
+}
+
+/// Record of a batch of honeytokens for the defenders who plant them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub npwg_version: String,
+    pub created: String,
+    pub scheme: String,
+    pub tag_length: usize,
+    pub key_fingerprint: String,
+    pub tokens: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn new(key: &[u8], created: String, tokens: &[String], label: Option<&str>) -> Self {
+        Manifest {
+            npwg_version: env!("CARGO_PKG_VERSION").to_string(),
+            created,
+            scheme: "hmac-sha256".to_string(),
+            tag_length: TAG_LENGTH,
+            key_fingerprint: key_fingerprint(key),
+            tokens: tokens
+                .iter()
+                .enumerate()
+                .map(|(index, token)| ManifestEntry {
+                    id: index + 1,
+                    label: label.map(str::to_string),
+                    token: token.clone(),
+                    sha256: Sha256::digest(token.as_bytes())
+                        .iter()
+                        .map(|b| format!("{:02x}", b))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const KEY: &[u8] = b"blue team key";
+
+    #[test]
+    fn test_generated_tokens_are_recognized() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let token = generate(KEY, "AKIA", 20, &mut rng);
+        assert_eq!(token.len(), 20);
+        assert!(token.starts_with("AKIA"));
+        assert!(is_honeytoken(KEY, &token));
+        assert!(!is_honeytoken(b"other key", &token));
+
+        let mut tampered = token.clone().into_bytes();
+        tampered[5] = if tampered[5] == b'x' { b'y' } else { b'x' };
+        assert!(!is_honeytoken(KEY, &String::from_utf8(tampered).unwrap()));
+    }
+
+    #[test]
+    fn test_scan_finds_tokens_in_logs() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let token = generate(KEY, "ghp_", 32, &mut rng);
+        let log = format!(
+            "GET /login 200\nPOST /api auth=\"{}\" from 10.0.0.5\nGET /health 200\n",
+            token
+        );
+        assert_eq!(scan(KEY, &log), vec![(2, token.as_str())]);
+        assert!(scan(KEY, "nothing to see here, 0123456789abcdef").is_empty());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("AKIA", 18).is_ok());
+        assert!(validate("AKIA", 17).is_err());
+        assert!(validate("sk live", 30).is_err());
+    }
+
+    #[test]
+    fn test_manifest() {
+        let tokens = vec!["AKIAabcdefgh123456".to_string()];
+        let manifest = Manifest::new(KEY, "2024-01-01T00:00:00Z".to_string(), &tokens, Some("s3"));
+        assert_eq!(manifest.tokens[0].id, 1);
+        assert_eq!(manifest.tokens[0].sha256.len(), 64);
+        assert_eq!(manifest.key_fingerprint.len(), 16);
+        assert!(!serde_json::to_string(&manifest).unwrap().contains("blue team key"));
+    }
+}
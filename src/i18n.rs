@@ -0,0 +1,584 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/i18n.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+}
+
+impl std::fmt::Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Locale::En => write!(f, "en"),
+            Locale::De => write!(f, "de"),
+        }
+    }
+}
+
+impl std::str::FromStr for Locale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "en" => Ok(Locale::En),
+            "de" => Ok(Locale::De),
+            _ => Err(format!("Invalid language: {} (use en or de)", s)),
+        }
+    }
+}
+
+impl Locale {
+    /// Maps a POSIX locale such as `de_DE.UTF-8` or `de-AT` to a supported
+    /// locale, falling back to English for anything else, including `C`.
+    pub fn from_posix(value: &str) -> Locale {
+        let language = value
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default();
+        language.parse().unwrap_or_default()
+    }
+
+    /// The locale the environment asks for, checked in the same order as
+    /// gettext: `LC_ALL`, then `LC_MESSAGES`, then `LANG`.
+    pub fn detect() -> Locale {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .map(|value| Locale::from_posix(&value))
+            .unwrap_or_default()
+    }
+
+    fn catalog(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::En => &[],
+            Locale::De => DE,
+        }
+    }
+}
+
+pub fn set_locale(locale: Locale) {
+    let _ = LOCALE.set(locale);
+}
+
+pub fn locale() -> Locale {
+    LOCALE.get().copied().unwrap_or_default()
+}
+
+fn lookup(locale: Locale, msgid: &str) -> &str {
+    locale
+        .catalog()
+        .iter()
+        .find(|(id, _)| *id == msgid)
+        .map(|(_, translation)| *translation)
+        .unwrap_or(msgid)
+}
+
+/// Translates an English message into the active locale. Messages without a
+/// translation are returned unchanged.
+pub fn tr(msgid: &str) -> &str {
+    lookup(locale(), msgid)
+}
+
+/// Translates a message and fills its `{}` placeholders in order.
+pub fn trf(msgid: &str, args: &[&dyn Display]) -> String {
+    fill(tr(msgid), args)
+}
+
+fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    let mut message = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            message.push_str(&arg.to_string());
+        }
+        message.push_str(part);
+    }
+    message
+}
+
+const DE: &[(&str, &str)] = &[
+    ("Error:", "Fehler:"),
+    ("Invalid configuration: {}", "Ungültige Konfiguration: {}"),
+    ("IO error: {}", "E/A-Fehler: {}"),
+    ("Network error: {}", "Netzwerkfehler: {}"),
+    ("Wordlist error: {}", "Fehler in der Wortliste: {}"),
+    ("Policy violation: {}", "Richtlinienverstoß: {}"),
+    ("Below the strength threshold: {}", "Unter der Stärkeschwelle: {}"),
+    (
+        "{} is not possible: npwg was built without the download feature",
+        "{} ist nicht möglich: npwg wurde ohne das Feature download gebaut",
+    ),
+    ("Not compliant with the policy: {}", "Nicht richtlinienkonform: {}"),
+    (
+        "Invalid password rules: {} at column {}",
+        "Ungültige Passwortregeln: {} in Spalte {}",
+    ),
+    ("hint:", "Hinweis:"),
+    (
+        "use upper, lower, digit, special, ascii-printable or a custom class such as [-_.]",
+        "upper, lower, digit, special, ascii-printable oder eine eigene Klasse wie [-_.] verwenden",
+    ),
+    ("close the class with ']'", "die Klasse mit ']' schließen"),
+    ("use a whole number such as 12", "eine ganze Zahl wie 12 angeben"),
+    (
+        "separate the rule name and its value with ':', e.g. minlength: 12",
+        "Regelname und Wert mit ':' trennen, z. B. minlength: 12",
+    ),
+    (
+        "known rules are minlength, maxlength, required, allowed and max-consecutive",
+        "bekannte Regeln sind minlength, maxlength, required, allowed und max-consecutive",
+    ),
+    (
+        "follow the backslash with the character to copy, or remove it",
+        "nach dem Backslash das zu kopierende Zeichen angeben oder ihn entfernen",
+    ),
+    (
+        "Invalid pattern '{}': {} at column {}; run --pattern-help for the grammar",
+        "Ungültiges Muster '{}': {} in Spalte {}; --pattern-help zeigt die Grammatik",
+    ),
+    ("Signature by key {} is valid", "Signatur von Schlüssel {} ist gültig"),
+    ("Trusted comment:", "Vertrauenswürdiger Kommentar:"),
+    ("No profiles saved.", "Keine Profile gespeichert."),
+    ("Password Comparison:", "Passwortvergleich:"),
+    ("Score:", "Bewertung:"),
+    ("Character pool:", "Zeichenvorrat:"),
+    ("Penalty:", "Abzug:"),
+    ("Issues:", "Probleme:"),
+    ("none", "keine"),
+    ("{} bits", "{} Bit"),
+    ("{} is stronger: {}.", "{} ist stärker: {}."),
+    ("Both passwords are equally strong.", "Beide Passwörter sind gleich stark."),
+    ("First password", "Erstes Passwort"),
+    ("Second password", "Zweites Passwort"),
+    ("it is longer", "es ist länger"),
+    ("it draws from more character classes", "es nutzt mehr Zeichenklassen"),
+    ("it has more entropy", "es hat mehr Entropie"),
+    (
+        "it avoids sequences, repeats or common words",
+        "es vermeidet Folgen, Wiederholungen oder häufige Wörter",
+    ),
+    ("it has fewer detected issues", "es hat weniger erkannte Probleme"),
+    ("Imported profile '{}' to {}", "Profil '{}' nach {} importiert"),
+    ("Save these settings as a profile?", "Diese Einstellungen als Profil speichern?"),
+    ("Profile name", "Profilname"),
+    (
+        "Profile '{}' already exists. Replace it?",
+        "Profil '{}' existiert bereits. Ersetzen?",
+    ),
+    ("Saved profile '{}' to {}", "Profil '{}' unter {} gespeichert"),
+    ("Use it with:", "Verwenden mit:"),
+    ("Hints:", "Hinweise:"),
+    ("(leaves {} of {} bits)", "(lässt {} von {} Bits übrig)"),
+    ("Warning: the Anki file is not encrypted and Anki syncs imported cards to AnkiWeb if sync is enabled.", "Warnung: Die Anki-Datei ist nicht verschlüsselt und Anki synchronisiert importierte Karten mit AnkiWeb, wenn die Synchronisation aktiv ist."),
+    ("Exported {} cards to {}", "{} Karten nach {} exportiert"),
+    ("{} ({}), press Enter to copy", "{} ({}), Enter zum Kopieren"),
+    (
+        "Installed the native messaging host for {} in {}",
+        "Native-Messaging-Host für {} unter {} installiert",
+    ),
+    (
+        "Listening on {} (Ctrl-C to stop)",
+        "Lausche auf {} (Strg-C zum Beenden)",
+    ),
+    (
+        "Offline mode: {} requires network access",
+        "Offline-Modus: {} erfordert Netzwerkzugriff",
+    ),
+    (
+        "Warning: --offline is set, skipping the breach check.",
+        "Warnung: --offline ist gesetzt, die Prüfung auf Datenlecks wird übersprungen.",
+    ),
+    (
+        "Download of {} failed after {} attempt(s): {}",
+        "Download von {} nach {} Versuch(en) fehlgeschlagen: {}",
+    ),
+    (
+        "Worldlist downloaded, restart the program to use it.",
+        "Wortliste heruntergeladen, starte das Programm neu, um sie zu verwenden.",
+    ),
+    ("Dialoguer error: {}", "Eingabefehler: {}"),
+    ("Export error: {}", "Exportfehler: {}"),
+    ("Store error: {}", "Speicherfehler: {}"),
+    ("External scorer error: {}", "Fehler des externen Bewerters: {}"),
+    ("Plugin error: {}", "Plugin-Fehler: {}"),
+    (
+        "Password quality check failed:\n {}",
+        "Passwortqualitätsprüfung fehlgeschlagen:\n {}",
+    ),
+    (
+        "The password is shorter than 8 characters",
+        "Das Passwort ist kürzer als 8 Zeichen",
+    ),
+    ("The password is a palindrome", "Das Passwort ist ein Palindrom"),
+    (
+        "The password fails the dictionary check - it is based on a dictionary word",
+        "Das Passwort besteht die Wörterbuchprüfung nicht - es basiert auf einem Wörterbuchwort",
+    ),
+    ("No password supplied", "Kein Passwort angegeben"),
+    ("Weak", "Schwach"),
+    ("Moderate", "Mittel"),
+    ("Strong", "Stark"),
+    ("Very Strong", "Sehr stark"),
+    ("strength {} of 10", "Stärke {} von 10"),
+    ("Use at least 12 characters", "Verwende mindestens 12 Zeichen"),
+    (
+        "Mix upper and lower case letters, digits and symbols",
+        "Mische Groß- und Kleinbuchstaben, Ziffern und Sonderzeichen",
+    ),
+    (
+        "Avoid runs of sequential characters such as 'abc' or '123'",
+        "Vermeide aufeinanderfolgende Zeichen wie 'abc' oder '123'",
+    ),
+    (
+        "Avoid repeating the same character three or more times",
+        "Vermeide es, dasselbe Zeichen drei- oder mehrmals zu wiederholen",
+    ),
+    (
+        "Avoid common words and patterns such as 'password' or 'qwerty'",
+        "Vermeide gängige Wörter und Muster wie 'password' oder 'qwerty'",
+    ),
+    ("yes", "ja"),
+    ("no", "nein"),
+    ("default", "Standard"),
+    ("pattern:", "Muster:"),
+    ("Wrote {} words to {}", "{} Wörter nach {} geschrieben"),
+    ("Wrote {} rows to {}", "{} Zeilen nach {} geschrieben"),
+    ("Wrote {} entries to {}", "{} Einträge nach {} geschrieben"),
+    ("{} (encrypted to {})", "{} (verschlüsselt für {})"),
+    ("Exported {} entries to {}", "{} Einträge nach {} exportiert"),
+    ("Stored in {}.", "In {} gespeichert."),
+    ("Recovery sheet written to", "Wiederherstellungsblatt geschrieben nach"),
+    ("Audit findings:", "Audit-Ergebnisse:"),
+    ("Line {}", "Zeile {}"),
+    ("Line {} ({})", "Zeile {} ({})"),
+    ("Policy violations:", "Richtlinienverstöße:"),
+    ("Rejected passwords:", "Abgelehnte Passwörter:"),
+    ("Summary:", "Zusammenfassung:"),
+    ("Entries:", "Einträge:"),
+    ("Weak:", "Schwach:"),
+    ("Duplicates:", "Duplikate:"),
+    ("Shared prefixes:", "Gemeinsame Präfixe:"),
+    ("Breached:", "Geleakt:"),
+    ("Password to check", "Zu prüfendes Passwort"),
+    ("Password Check:", "Passwortprüfung:"),
+    ("Entropy: {} bits", "Entropie: {} Bit"),
+    (
+        "Entropy per character: {} bits (uniform: {} bits)",
+        "Entropie pro Zeichen: {} Bit (gleichverteilt: {} Bit)",
+    ),
+    (
+        "Entropy: {} bits (shortest case)",
+        "Entropie: {} Bit (kürzester Fall)",
+    ),
+    ("Policy:", "Richtlinie:"),
+    ("compliant", "erfüllt"),
+    ("not compliant", "nicht erfüllt"),
+    ("Suggestions:", "Vorschläge:"),
+    (
+        "Wordlist downloaded. Please run the program again.",
+        "Wortliste heruntergeladen. Bitte starte das Programm erneut.",
+    ),
+    (
+        "Warning: KeePass XML exports are not encrypted, import and delete the file.",
+        "Warnung: KeePass-XML-Exporte sind nicht verschlüsselt, importiere die Datei und lösche sie danach.",
+    ),
+    (
+        "Master password for the KeePass database",
+        "Master-Passwort für die KeePass-Datenbank",
+    ),
+    ("Repeat master password", "Master-Passwort wiederholen"),
+    ("Passwords do not match", "Die Passwörter stimmen nicht überein"),
+    (
+        "Enter passwords to mutate (comma-separated)",
+        "Zu mutierende Passwörter eingeben (durch Kommas getrennt)",
+    ),
+    ("Mutated Passwords:", "Mutierte Passwörter:"),
+    ("Mutated Password:", "Mutiertes Passwort:"),
+    ("Original:", "Original:"),
+    ("Mutated:  {} (using {})", "Mutiert:  {} (mit {})"),
+    (
+        "Password {} copied to clipboard.",
+        "Passwort {} in die Zwischenablage kopiert.",
+    ),
+    (
+        "Passphrase {} copied to clipboard.",
+        "Passphrase {} in die Zwischenablage kopiert.",
+    ),
+    (
+        "Password(s) copied to clipboard.",
+        "Passwort/Passwörter in die Zwischenablage kopiert.",
+    ),
+    (
+        "Passphrase(s) copied to clipboard.",
+        "Passphrase(n) in die Zwischenablage kopiert.",
+    ),
+    (
+        "Clearing the clipboard in {} seconds...",
+        "Die Zwischenablage wird in {} Sekunden geleert...",
+    ),
+    ("Password Strength:", "Passwortstärke:"),
+    ("Password {}:", "Passwort {}:"),
+    ("Entropy:", "Entropie:"),
+    (
+        "Wordlist size: {} words ({} bits per word)",
+        "Größe der Wortliste: {} Wörter ({} Bit pro Wort)",
+    ),
+    (
+        "Target: {} bits -> ceil({} / {}) = {} words ({} bits)",
+        "Ziel: {} Bit -> ceil({} / {}) = {} Wörter ({} Bit)",
+    ),
+    (
+        "{} to {} words: at least {} bits (shortest case)",
+        "{} bis {} Wörter: mindestens {} Bit (kürzester Fall)",
+    ),
+    ("Effective configuration:", "Aktive Konfiguration:"),
+    ("Mode:", "Modus:"),
+    ("Length:", "Länge:"),
+    ("Count:", "Anzahl:"),
+    ("Seeded:", "Mit Seed:"),
+    ("random from {}", "zufällig aus {}"),
+    ("in turn from {}", "der Reihe nach aus {}"),
+    ("one from {} at a random position", "eines aus {} an zufälliger Stelle"),
+    ("Separator:", "Trennzeichen:"),
+    ("Join style:", "Verbindungsstil:"),
+    ("Words:", "Wörter:"),
+    ("Appended digits:", "Angehängte Ziffern:"),
+    ("Wordlist:", "Wortliste:"),
+    ("Wordlist size:", "Größe der Wortliste:"),
+    (
+        "unknown (wordlist not downloaded yet)",
+        "unbekannt (Wortliste noch nicht heruntergeladen)",
+    ),
+    ("Charsets:", "Zeichensätze:"),
+    ("Characters:", "Zeichen:"),
+    ("Pool size:", "Zeichenvorrat:"),
+    ("Pattern:", "Muster:"),
+    ("Pronounceable:", "Aussprechbar:"),
+    ("Language:", "Sprache:"),
+    ("Avoid repetition:", "Wiederholungen vermeiden:"),
+    ("Exclude confusables:", "Verwechselbare Zeichen auslassen:"),
+    ("Shell safe:", "Shell-sicher:"),
+    ("Escape profile:", "Escape-Profil:"),
+    (
+        "Warning: the policy allows at most {} characters, good for {} bits of entropy instead of the requested {} bits.",
+        "Warnung: Die Richtlinie erlaubt höchstens {} Zeichen, das reicht für {} Bits Entropie statt der geforderten {} Bits.",
+    ),
+    ("Normalization:", "Normalisierung:"),
+    ("Memorize:", "Einprägen:"),
+    (
+        "Press Enter to hide it and start typing.",
+        "Enter drücken, um es zu verbergen und mit dem Tippen zu beginnen.",
+    ),
+    ("Type it from memory ({}/{})", "Aus dem Gedächtnis eintippen ({}/{})"),
+    ("Correct in {}s.", "Richtig in {} s."),
+    (
+        "Correct in {}s, {}s faster than last time.",
+        "Richtig in {} s, {} s schneller als beim letzten Mal.",
+    ),
+    (
+        "Correct in {}s, {}s slower than last time.",
+        "Richtig in {} s, {} s langsamer als beim letzten Mal.",
+    ),
+    (
+        "Not quite, the first mistake is at character {}.",
+        "Nicht ganz, der erste Fehler ist bei Zeichen {}.",
+    ),
+    (
+        "Typed correctly {} times with {} mistakes; fastest {}s.",
+        "{}-mal richtig getippt, {} Fehler; am schnellsten {} s.",
+    ),
+    ("Store command:", "Speicherbefehl:"),
+    ("Statistics:", "Statistik:"),
+    ("Dice rolls:", "Würfelwürfe:"),
+    (
+        "Wrote a manifest of {} honeytoken(s) to {}",
+        "Manifest mit {} Honeytoken(s) nach {} geschrieben",
+    ),
+    ("{} honeytoken(s) found", "{} Honeytoken(s) gefunden"),
+    ("Wrote {} code hashes to {}", "{} Code-Hashes nach {} geschrieben"),
+    ("Passphrase:", "Passphrase:"),
+    ("QR payload:", "QR-Inhalt:"),
+    ("QR code written to {}", "QR-Code nach {} geschrieben"),
+    ("Generating", "Erzeuge"),
+    ("Downloading", "Lade herunter"),
+    ("Checking breaches", "Prüfe auf Datenlecks"),
+    ("External score:", "Externe Bewertung:"),
+    ("No plugins installed in {}", "Keine Plugins in {} installiert"),
+    (
+        "Wrote a {}-byte keyfile to {}",
+        "Schlüsseldatei mit {} Bytes nach {} geschrieben",
+    ),
+    ("Text copy written to {}", "Textkopie nach {} geschrieben"),
+    ("Mean:", "Mittelwert:"),
+    ("Variance:", "Varianz:"),
+    ("Skewness:", "Schiefe:"),
+    ("Kurtosis:", "Wölbung:"),
+    ("Min / p5 / median / p95 / max:", "Min. / P5 / Median / P95 / Max.:"),
+    (
+        "Statistics report written to {}",
+        "Statistikbericht nach {} geschrieben",
+    ),
+    (
+        "Welcome to NPWG Interactive Mode!",
+        "Willkommen im interaktiven Modus von NPWG!",
+    ),
+    ("Generate Password", "Passwort erzeugen"),
+    ("Generate Passphrase", "Passphrase erzeugen"),
+    ("Mutate Password", "Passwort mutieren"),
+    ("Session History", "Sitzungsverlauf"),
+    ("Collect Keyboard Entropy", "Tastatur-Entropie sammeln"),
+    (
+        "Collecting keyboard entropy needs an interactive terminal.",
+        "Zum Sammeln von Tastatur-Entropie wird ein interaktives Terminal benötigt.",
+    ),
+    (
+        "Type randomly for {} seconds...",
+        "Tippe {} Sekunden lang zufällig...",
+    ),
+    (
+        "Only {} keystrokes collected, nothing was mixed in.",
+        "Nur {} Tastenanschläge gesammelt, es wurde nichts eingemischt.",
+    ),
+    (
+        "Collected {} keystrokes; their timing is mixed into the generator for this session.",
+        "{} Tastenanschläge gesammelt; ihr Timing wird für diese Sitzung in den Generator eingemischt.",
+    ),
+    ("Exit", "Beenden"),
+    ("What would you like to do?", "Was möchtest du tun?"),
+    (
+        "Do you want to perform another action?",
+        "Möchtest du eine weitere Aktion ausführen?",
+    ),
+    ("Thank you for using NPWG!", "Danke, dass du NPWG verwendest!"),
+    (
+        "Nothing has been generated in this session yet.",
+        "In dieser Sitzung wurde noch nichts erzeugt.",
+    ),
+    ("Select a previous candidate", "Wähle einen früheren Kandidaten"),
+    ("Copy to clipboard", "In die Zwischenablage kopieren"),
+    (
+        "Compare with another candidate",
+        "Mit einem anderen Kandidaten vergleichen",
+    ),
+    ("Back", "Zurück"),
+    ("What would you like to do with it?", "Was möchtest du damit tun?"),
+    ("Copied to clipboard.", "In die Zwischenablage kopiert."),
+    ("Compare with", "Vergleichen mit"),
+    ("Comparison:", "Vergleich:"),
+    (
+        "Pick candidates to keep (space to toggle, enter to confirm)",
+        "Wähle die Kandidaten, die du behalten möchtest (Leertaste zum Auswählen, Enter zum Bestätigen)",
+    ),
+    ("Save to file", "In Datei speichern"),
+    ("Skip", "Überspringen"),
+    (
+        "What would you like to do with {} selected?",
+        "Was möchtest du mit den {} ausgewählten tun?",
+    ),
+    ("File to write", "Zieldatei"),
+    ("Saved to", "Gespeichert in"),
+    ("Password length", "Passwortlänge"),
+    ("Number of passwords", "Anzahl der Passwörter"),
+    ("Avoid repeating characters?", "Wiederholte Zeichen vermeiden?"),
+    (
+        "Generate pronounceable passwords?",
+        "Aussprechbare Passwörter erzeugen?",
+    ),
+    (
+        "Enter desired pattern or leave empty for no pattern",
+        "Gewünschtes Muster eingeben oder leer lassen für kein Muster",
+    ),
+    ("Generated Passwords:", "Erzeugte Passwörter:"),
+    ("Show strength meter?", "Stärkeanzeige anzeigen?"),
+    ("Show statistics?", "Statistik anzeigen?"),
+    ("Number of passphrases", "Anzahl der Passphrasen"),
+    (
+        "Separator (any string, 'random', 'set:<charset>', 'cycle', 'positional', or press Enter for space)",
+        "Trennzeichen (beliebiger Text, 'random', 'set:<Zeichensatz>', 'cycle', 'positional' oder Enter für Leerzeichen)",
+    ),
+    (
+        "Invalid separator. Using default (space).",
+        "Ungültiges Trennzeichen. Verwende Standard (Leerzeichen).",
+    ),
+    ("Generated Passphrases:", "Erzeugte Passphrasen:"),
+    ("Enter the password to mutate", "Zu mutierendes Passwort eingeben"),
+    (
+        "Increase the length of the password",
+        "Länge des Passworts erhöhen um",
+    ),
+    (
+        "Enter mutation strength (1-10)",
+        "Mutationsstärke eingeben (1-10)",
+    ),
+    (
+        "Please enter a number between 1 and 10",
+        "Bitte gib eine Zahl zwischen 1 und 10 ein",
+    ),
+    ("Select mutation type", "Mutationsart wählen"),
+    ("Grammar template:", "Grammatikvorlage:"),
+    (
+        "{} regenerate · {} copy · {} show/hide · {} done",
+        "{} neu erzeugen · {} kopieren · {} zeigen/verbergen · {} fertig",
+    ),
+    ("{}: {} words ({} bits)", "{}: {} Wörter ({} Bit)"),
+    (
+        "Select the character class to attach",
+        "Anzuhängende Zeichenklasse wählen",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_detection() {
+        assert_eq!(Locale::from_posix("de_DE.UTF-8"), Locale::De);
+        assert_eq!(Locale::from_posix("de-AT"), Locale::De);
+        assert_eq!(Locale::from_posix("C"), Locale::En);
+        assert_eq!(Locale::from_posix("fr_FR"), Locale::En);
+        assert_eq!("DE".parse::<Locale>(), Ok(Locale::De));
+        assert!("xx".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn test_lookup_and_fill() {
+        assert_eq!(lookup(Locale::De, "Summary:"), "Zusammenfassung:");
+        assert_eq!(lookup(Locale::En, "Summary:"), "Summary:");
+        assert_eq!(lookup(Locale::De, "not translated"), "not translated");
+        assert_eq!(
+            fill(lookup(Locale::De, "Wrote {} rows to {}"), &[&3, &"r.csv"]),
+            "3 Zeilen nach r.csv geschrieben"
+        );
+    }
+
+    #[test]
+    fn test_german_catalog_keeps_placeholders() {
+        for (msgid, translation) in DE {
+            assert_eq!(
+                msgid.matches("{}").count(),
+                translation.matches("{}").count(),
+                "{}",
+                msgid
+            );
+            assert_eq!(
+                DE.iter().filter(|(id, _)| id == msgid).count(),
+                1,
+                "duplicate {}",
+                msgid
+            );
+        }
+    }
+}
@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/jitter.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use zeroize::Zeroize;
+
+/// How long the interactive collection step listens for keystrokes.
+pub const COLLECTION_SECONDS: u64 = 5;
+/// Fewer keystrokes than this are discarded rather than mixed in.
+pub const MIN_SAMPLES: usize = 10;
+
+/// Accumulates inter-keystroke timings into a SHA-256 pool.
+#[derive(Default)]
+pub struct JitterPool {
+    hasher: Sha256,
+    samples: usize,
+}
+
+impl JitterPool {
+    pub fn add(&mut self, interval: Duration, key: char) {
+        self.hasher.update(interval.as_nanos().to_le_bytes());
+        let mut buf = [0u8; 4];
+        self.hasher.update(key.encode_utf8(&mut buf).as_bytes());
+        self.samples += 1;
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    pub fn finish(self) -> [u8; 32] {
+        self.hasher.finalize().into()
+    }
+}
+
+/// An RNG seeded from fresh operating system randomness hashed together with
+/// the collected jitter, so the result is never weaker than the OS alone.
+pub fn mixed_rng(jitter: &[u8; 32]) -> StdRng {
+    let mut os = [0u8; 32];
+    rand::rng().fill_bytes(&mut os);
+    let mut seed: [u8; 32] = Sha256::new()
+        .chain_update(os)
+        .chain_update(jitter)
+        .finalize()
+        .into();
+    os.zeroize();
+    let rng = StdRng::from_seed(seed);
+    seed.zeroize();
+    rng
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_depends_on_timings() {
+        let pool = |nanos: &[u64]| {
+            let mut pool = JitterPool::default();
+            for &n in nanos {
+                pool.add(Duration::from_nanos(n), 'k');
+            }
+            pool
+        };
+        assert_eq!(pool(&[120, 95]).samples(), 2);
+        assert_eq!(pool(&[120, 95]).finish(), pool(&[120, 95]).finish());
+        assert_ne!(pool(&[120, 95]).finish(), pool(&[120, 96]).finish());
+    }
+
+    #[test]
+    fn test_mixed_rng_is_not_reproducible() {
+        let jitter = [7u8; 32];
+        assert_ne!(mixed_rng(&jitter).next_u64(), mixed_rng(&jitter).next_u64());
+    }
+}
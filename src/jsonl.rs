@@ -0,0 +1,155 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/jsonl.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::{PasswordGeneratorError, Result};
+use crate::profile::{Profile, PROFILE_VERSION};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// Command line options a `--jsonl` request may set. Everything else either
+/// prints, stores or copies, which makes no sense for a single JSON line.
+pub const OPTIONS: &[&str] = &[
+    "acknowledge-weak",
+    "allowed",
+    "append-digits",
+    "avoid-repeating",
+    "avoid-sequential",
+    "count",
+    "end-with",
+    "escape-profile",
+    "exclude-confusables",
+    "forbid",
+    "force",
+    "grammar",
+    "join-style",
+    "language",
+    "length",
+    "max-repeat",
+    "min-bits",
+    "min-strength",
+    "normalize",
+    "pattern",
+    "pronounceable",
+    "reject-keyboard-runs",
+    "rules",
+    "separator",
+    "seed",
+    "shell-safe",
+    "site",
+    "start-with",
+    "use-words",
+    "words",
+];
+
+fn invalid(message: String) -> PasswordGeneratorError {
+    PasswordGeneratorError::InvalidConfig(message)
+}
+
+fn to_toml(key: &str, value: &Value) -> Result<toml::Value> {
+    Ok(match value {
+        Value::Bool(flag) => toml::Value::Boolean(*flag),
+        Value::String(text) => toml::Value::String(text.clone()),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => toml::Value::Integer(integer),
+            None => toml::Value::Float(number.as_f64().unwrap_or_default()),
+        },
+        Value::Array(items) => toml::Value::Array(
+            items
+                .iter()
+                .map(|item| to_toml(key, item))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Null | Value::Object(_) => {
+            return Err(invalid(format!(
+                "'{}' must be a string, number, boolean or array",
+                key
+            )))
+        }
+    })
+}
+
+/// Parses one request line such as `{"length":20,"allowed":"lowerletter,digit"}`
+/// into its optional `id` and its options, kept as a profile so they turn into
+/// arguments the same way. The `id` is returned even when the options are
+/// rejected, so the error can still be matched to its request.
+pub fn parse_request(line: &str) -> (Option<Value>, Result<Profile>) {
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(e) => return (None, Err(invalid(format!("Invalid JSON request: {}", e)))),
+    };
+    let Value::Object(mut fields) = value else {
+        return (None, Err(invalid("A request must be a JSON object".to_string())));
+    };
+    let id = fields.remove("id");
+    let mut options = BTreeMap::new();
+    for (key, value) in &fields {
+        if !OPTIONS.contains(&key.as_str()) {
+            return (
+                id,
+                Err(invalid(format!(
+                    "'{}' cannot be set in a request; use one of: {}",
+                    key,
+                    OPTIONS.join(", ")
+                ))),
+            );
+        }
+        match to_toml(key, value) {
+            Ok(value) => options.insert(key.clone(), value),
+            Err(e) => return (id, Err(e)),
+        };
+    }
+    let profile = Profile {
+        version: PROFILE_VERSION,
+        name: "jsonl".to_string(),
+        options,
+    };
+    (id, Ok(profile))
+}
+
+/// The response line for a request: the generated fields or an `error`,
+/// with the request's `id` when it had one.
+pub fn response(id: Option<Value>, result: Result<Value>) -> Value {
+    let mut response = match result {
+        Ok(Value::Object(fields)) => fields,
+        Ok(other) => serde_json::Map::from_iter([("result".to_string(), other)]),
+        Err(error) => serde_json::Map::from_iter([("error".to_string(), json!(error.to_string()))]),
+    };
+    if let Some(id) = id {
+        response.insert("id".to_string(), id);
+    }
+    Value::Object(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let (id, options) =
+            parse_request(r#"{"id":7,"length":20,"allowed":"lowerletter,digit","forbid":["acme"],"use-words":false}"#);
+        assert_eq!(id, Some(json!(7)));
+        assert_eq!(
+            options.unwrap().to_args(),
+            ["--allowed=lowerletter,digit", "--forbid=acme", "--length=20"]
+        );
+
+        let (id, rejected) = parse_request(r#"{"id":"a","output":"/tmp/x"}"#);
+        assert_eq!(id, Some(json!("a")));
+        assert!(rejected.unwrap_err().to_string().contains("'output' cannot be set"));
+        assert!(parse_request(r#"{"length":null}"#).1.is_err());
+        assert!(parse_request("[1]").1.is_err());
+        assert!(parse_request("not json").1.is_err());
+    }
+
+    #[test]
+    fn test_response() {
+        let ok = response(Some(json!(1)), Ok(json!({ "passwords": ["x"] })));
+        assert_eq!(ok, json!({ "id": 1, "passwords": ["x"] }));
+        let failed = response(None, Err(invalid("bad".to_string())));
+        assert_eq!(failed, json!({ "error": "Invalid configuration: bad" }));
+    }
+}
@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/keymap.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::{PasswordGeneratorError, Result};
+use crate::settings::KeySettings;
+
+/// What a key does while reviewing generated passwords in interactive mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Regenerate,
+    Copy,
+    ToggleMask,
+    Quit,
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Regenerate => write!(f, "regenerate"),
+            Action::Copy => write!(f, "copy"),
+            Action::ToggleMask => write!(f, "toggle_mask"),
+            Action::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+const ACTIONS: [Action; 4] = [Action::Regenerate, Action::Copy, Action::ToggleMask, Action::Quit];
+
+/// Keys that are named rather than typed as a single character.
+const NAMED_KEYS: &[&str] = &["enter", "esc", "space", "tab", "backspace"];
+
+/// Checks a key from `[keys]`: one character, matched as typed, or one of
+/// [`NAMED_KEYS`] in any case.
+pub fn parse_key(name: &str) -> Result<String> {
+    let lowered = name.to_lowercase();
+    if name.chars().count() == 1 {
+        Ok(name.to_string())
+    } else if NAMED_KEYS.contains(&lowered.as_str()) {
+        Ok(lowered)
+    } else {
+        Err(PasswordGeneratorError::InvalidConfig(format!(
+            "Invalid key '{}' in [keys]: use a single character or one of {}",
+            name,
+            NAMED_KEYS.join(", ")
+        )))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    bindings: Vec<(Action, String)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::preset("default").unwrap()
+    }
+}
+
+impl Keymap {
+    /// `default` binds r, c, m and q; `vim` binds n (next), y (yank),
+    /// v and q.
+    pub fn preset(name: &str) -> Result<Self> {
+        let keys = match name.to_lowercase().as_str() {
+            "default" => ["r", "c", "m", "q"],
+            "vim" => ["n", "y", "v", "q"],
+            _ => {
+                return Err(PasswordGeneratorError::InvalidConfig(format!(
+                    "Unknown key preset '{}' in [keys]: use default or vim",
+                    name
+                )))
+            }
+        };
+        Ok(Keymap {
+            bindings: ACTIONS
+                .iter()
+                .zip(keys)
+                .map(|(&action, key)| (action, key.to_string()))
+                .collect(),
+        })
+    }
+
+    /// The preset from `[keys]` with the keys given there put in place. Two
+    /// actions cannot share a key.
+    pub fn from_settings(settings: &KeySettings) -> Result<Self> {
+        let mut keymap = Keymap::preset(settings.preset.as_deref().unwrap_or("default"))?;
+        let overrides = [
+            (Action::Regenerate, &settings.regenerate),
+            (Action::Copy, &settings.copy),
+            (Action::ToggleMask, &settings.toggle_mask),
+            (Action::Quit, &settings.quit),
+        ];
+        for (action, key) in overrides {
+            if let Some(key) = key {
+                let key = parse_key(key)?;
+                if let Some(binding) = keymap.bindings.iter_mut().find(|(bound, _)| *bound == action) {
+                    binding.1 = key;
+                }
+            }
+        }
+        for (i, (action, key)) in keymap.bindings.iter().enumerate() {
+            if let Some((other, _)) = keymap.bindings[i + 1..].iter().find(|(_, bound)| bound == key) {
+                return Err(PasswordGeneratorError::InvalidConfig(format!(
+                    "Key '{}' in [keys] is bound to both {} and {}",
+                    key, action, other
+                )));
+            }
+        }
+        Ok(keymap)
+    }
+
+    pub fn key(&self, action: Action) -> &str {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or("", |(_, key)| key.as_str())
+    }
+
+    /// The action bound to a key name as produced by [`parse_key`].
+    pub fn action(&self, key: &str) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, bound)| bound == key)
+            .map(|(action, _)| *action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.action("r"), Some(Action::Regenerate));
+        assert_eq!(keymap.action("R"), None);
+        assert_eq!(keymap.key(Action::ToggleMask), "m");
+        let vim = Keymap::preset("vim").unwrap();
+        assert_eq!(vim.action("y"), Some(Action::Copy));
+        assert_eq!(vim.action("n"), Some(Action::Regenerate));
+        assert!(Keymap::preset("emacs").is_err());
+    }
+
+    #[test]
+    fn test_overrides() {
+        let settings = KeySettings {
+            preset: Some("vim".to_string()),
+            quit: Some("Esc".to_string()),
+            ..KeySettings::default()
+        };
+        let keymap = Keymap::from_settings(&settings).unwrap();
+        assert_eq!(keymap.action("esc"), Some(Action::Quit));
+        assert_eq!(keymap.action("q"), None);
+        assert_eq!(keymap.key(Action::Copy), "y");
+
+        let clash = KeySettings {
+            copy: Some("r".to_string()),
+            ..KeySettings::default()
+        };
+        let error = Keymap::from_settings(&clash).unwrap_err().to_string();
+        assert!(error.contains("bound to both regenerate and copy"), "{}", error);
+        assert!(parse_key("ctrl-x").is_err());
+        assert_eq!(parse_key("SPACE").unwrap(), "space");
+    }
+}
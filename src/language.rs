@@ -0,0 +1,214 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/language.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use rand::seq::IndexedRandom;
+use rand::Rng;
+
+const MAX_SYLLABLE_ATTEMPTS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    En,
+    De,
+    Fr,
+    Es,
+    It,
+}
+
+pub struct PhonemeTable {
+    pub onsets: &'static [&'static str],
+    pub nuclei: &'static [&'static str],
+    pub codas: &'static [&'static str],
+    pub avoid: &'static [&'static str],
+}
+
+const EN: PhonemeTable = PhonemeTable {
+    onsets: &[
+        "b", "c", "d", "f", "g", "h", "j", "k", "l", "m", "n", "p", "r", "s", "t", "v", "w", "y",
+        "z", "bl", "br", "ch", "cl", "cr", "dr", "fl", "fr", "gr", "pl", "pr", "sh", "sl", "st",
+        "th", "tr",
+    ],
+    nuclei: &["a", "e", "i", "o", "u", "ai", "ea", "ee", "oo", "ou"],
+    codas: &["", "", "n", "r", "s", "t", "l", "m", "nd", "st", "ck"],
+    avoid: &[
+        "hh", "ii", "jj", "uu", "vv", "ww", "yy", "ckh", "eee", "ooo",
+    ],
+};
+
+const DE: PhonemeTable = PhonemeTable {
+    onsets: &[
+        "b", "d", "f", "g", "h", "k", "l", "m", "n", "p", "r", "s", "t", "w", "z", "bl", "br",
+        "dr", "fl", "fr", "gl", "gr", "kl", "kr", "pf", "pl", "schl", "schw", "sp", "st", "tr",
+    ],
+    nuclei: &["a", "e", "i", "o", "u", "au", "ei", "ie", "eu"],
+    codas: &["", "n", "r", "s", "t", "l", "m", "ch", "ng", "nd"],
+    avoid: &["ii", "uu", "ww", "zz", "eie", "iei", "chs", "ngk"],
+};
+
+const FR: PhonemeTable = PhonemeTable {
+    onsets: &[
+        "b", "c", "d", "f", "g", "j", "l", "m", "n", "p", "r", "s", "t", "v", "bl", "br", "ch",
+        "cl", "cr", "dr", "fl", "fr", "gr", "pl", "pr", "tr",
+    ],
+    nuclei: &["a", "e", "i", "o", "u", "ou", "ai", "au", "eu", "oi"],
+    codas: &["", "", "n", "r", "s", "l"],
+    avoid: &["ii", "uu", "hh", "ww", "ouou", "eue", "sr", "nr"],
+};
+
+const ES: PhonemeTable = PhonemeTable {
+    onsets: &[
+        "b", "c", "d", "f", "g", "j", "l", "m", "n", "p", "r", "s", "t", "v", "ch", "ll", "bl",
+        "br", "cl", "cr", "dr", "fl", "fr", "gr", "pl", "pr", "tr",
+    ],
+    nuclei: &["a", "e", "i", "o", "u", "ia", "ie", "io", "ue", "ua"],
+    codas: &["", "", "n", "r", "s", "l"],
+    avoid: &["ii", "uu", "ss", "ll", "sr", "nll", "rll", "lll"],
+};
+
+const IT: PhonemeTable = PhonemeTable {
+    onsets: &[
+        "b", "c", "d", "f", "g", "l", "m", "n", "p", "r", "s", "t", "v", "z", "ch", "gh", "gl",
+        "gn", "sc", "br", "cr", "dr", "fr", "gr", "pr", "tr", "st",
+    ],
+    nuclei: &["a", "e", "i", "o", "u", "ia", "io"],
+    codas: &["", "", "n", "r", "l"],
+    avoid: &["ii", "uu", "iia", "iio", "rgn", "lgn", "ngn"],
+};
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let code = match self {
+            Language::En => "en",
+            Language::De => "de",
+            Language::Fr => "fr",
+            Language::Es => "es",
+            Language::It => "it",
+        };
+        write!(f, "{}", code)
+    }
+}
+
+impl std::str::FromStr for Language {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "en" => Ok(Language::En),
+            "de" => Ok(Language::De),
+            "fr" => Ok(Language::Fr),
+            "es" => Ok(Language::Es),
+            "it" => Ok(Language::It),
+            _ => Err(format!(
+                "Invalid language: {} (use en, de, fr, es or it)",
+                s
+            )),
+        }
+    }
+}
+
+impl Language {
+    pub fn table(&self) -> &'static PhonemeTable {
+        match self {
+            Language::En => &EN,
+            Language::De => &DE,
+            Language::Fr => &FR,
+            Language::Es => &ES,
+            Language::It => &IT,
+        }
+    }
+}
+
+impl PhonemeTable {
+    fn syllable(&self, rng: &mut impl Rng) -> String {
+        let pick = |parts: &[&'static str], rng: &mut _| *parts.choose(rng).unwrap();
+        format!(
+            "{}{}{}",
+            pick(self.onsets, rng),
+            pick(self.nuclei, rng),
+            pick(self.codas, rng)
+        )
+    }
+
+    fn is_awkward(&self, previous: &str, syllable: &str) -> bool {
+        let tail: String = previous
+            .chars()
+            .rev()
+            .take(2)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .collect();
+        let joined = format!("{}{}", tail, syllable);
+        self.avoid.iter().any(|digraph| joined.contains(digraph))
+    }
+
+    pub fn generate(&self, length: usize, rng: &mut impl Rng) -> String {
+        let mut password = String::with_capacity(length);
+        while password.len() < length {
+            let mut syllable = self.syllable(rng);
+            for _ in 0..MAX_SYLLABLE_ATTEMPTS {
+                if !self.is_awkward(&password, &syllable) {
+                    break;
+                }
+                syllable = self.syllable(rng);
+            }
+            password.push_str(&syllable);
+        }
+        password.truncate(length);
+        password
+    }
+
+    /// Approximate entropy per character: the bits of one syllable choice spread
+    /// over the average syllable length.
+    pub fn bits_per_char(&self) -> f64 {
+        let average = |parts: &[&str]| {
+            parts.iter().map(|p| p.len()).sum::<usize>() as f64 / parts.len() as f64
+        };
+        let syllable_length = average(self.onsets) + average(self.nuclei) + average(self.codas);
+        let combinations = (self.onsets.len() * self.nuclei.len() * self.codas.len()) as f64;
+        combinations.log2() / syllable_length
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_parse_language() {
+        assert_eq!("DE".parse::<Language>(), Ok(Language::De));
+        assert_eq!(Language::It.to_string(), "it");
+        assert!("nl".parse::<Language>().is_err());
+    }
+
+    #[test]
+    fn test_generate_avoids_awkward_digraphs() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for language in [
+            Language::En,
+            Language::De,
+            Language::Fr,
+            Language::Es,
+            Language::It,
+        ] {
+            let table = language.table();
+            for _ in 0..50 {
+                let password = table.generate(16, &mut rng);
+                assert_eq!(password.len(), 16);
+                assert!(password.chars().all(|c| c.is_ascii_lowercase()));
+                assert!(
+                    !table.avoid.iter().any(|d| password.contains(d)),
+                    "{} produced {}",
+                    language,
+                    password
+                );
+            }
+            assert!(table.bits_per_char() > 1.0);
+        }
+    }
+}
@@ -4,12 +4,51 @@
 // Author: Volker Schwaberow <volker@schwaberow.de>
 // Copyright (c) 2022 Volker Schwaberow
 
+pub mod audit;
+pub mod blocklist;
+pub mod clipboard;
 pub mod config;
+#[cfg(unix)]
+pub mod daemon;
 pub mod diceware;
+pub mod encrypt;
 pub mod error;
+pub mod export;
+pub mod fips;
 pub mod generator;
+pub mod grammar;
+pub mod hint;
+pub mod history;
+pub mod honeytoken;
+pub mod i18n;
+pub mod jitter;
+pub mod jsonl;
+pub mod keymap;
+pub mod language;
+pub mod mcp;
+pub mod memorize;
+pub mod minisign;
+pub mod native;
+pub mod net;
+pub mod output;
+pub mod picker;
+pub mod plugin;
+pub mod profile;
+pub mod progress;
+pub mod qr;
+pub mod receipt;
+pub mod recovery;
+pub mod rules;
+pub mod salt;
+pub mod scorer;
+pub mod settings;
+pub mod sites;
 pub mod stats;
+pub mod store;
 pub mod strength;
+pub mod theme;
+pub mod unicode;
+pub mod wifi;
 
 pub use config::{PasswordGeneratorConfig, PasswordGeneratorMode};
 pub use error::{PasswordGeneratorError, Result};
@@ -21,9 +60,9 @@ pub use stats::{show_stats, PasswordQuality};
 
 pub async fn generate_password_with_config(config: &PasswordGeneratorConfig) -> Result<String> {
     if config.pronounceable {
-        Ok(generate_pronounceable_password(config).await)
+        generate_pronounceable_password(config).await
     } else {
-        Ok(generate_password(config).await)
+        generate_password(config).await
     }
 }
 
@@ -31,9 +70,9 @@ pub async fn generate_passwords_with_config(
     config: &PasswordGeneratorConfig,
 ) -> Result<Vec<String>> {
     if config.pronounceable {
-        Ok(generate_pronounceable_passwords(config).await)
+        generate_pronounceable_passwords(config).await
     } else {
-        Ok(generate_passwords(config).await)
+        generate_passwords(config).await
     }
 }
 
@@ -41,5 +80,57 @@ pub async fn generate_diceware_passphrase_with_config(
     wordlist: &[String],
     config: &PasswordGeneratorConfig,
 ) -> Result<Vec<String>> {
-    Ok(generate_diceware_passphrase(wordlist, config).await)
+    generate_diceware_passphrase(wordlist, config).await
+}
+
+/// The passwords of [`generator::generate_strong_passwords`] one at a time,
+/// so async consumers can pull them at their own pace instead of waiting for
+/// the whole batch. The stream ends after `config.num_passwords` items, or after the
+/// first error: an invalid config, or a password the filters never accept.
+/// Seeded streams derive one seed per password and attempt, so they never
+/// repeat a password and are reproducible.
+pub fn password_stream(
+    config: PasswordGeneratorConfig,
+) -> impl futures::Stream<Item = Result<String>> {
+    let state = config.validate().map(|()| (config.clone(), config, 0));
+    futures::stream::unfold(Some(state), |state| async move {
+        let (config, mut candidate_config, index) = match state? {
+            Ok(state) => state,
+            Err(error) => return Some((Err(error), None)),
+        };
+        if index >= config.num_passwords as u64 {
+            return None;
+        }
+        match generator::generate_strong_password(&config, &mut candidate_config, index).await {
+            Ok(password) => Some((Ok(password), Some(Ok((config, candidate_config, index + 1))))),
+            Err(error) => Some((Err(error), None)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_password_stream() {
+        use futures::StreamExt;
+
+        let mut config = PasswordGeneratorConfig::new();
+        config.num_passwords = 3;
+        config.seed = Some(crate::config::Seed::Number(5));
+        let passwords: Vec<String> = password_stream(config.clone())
+            .map(|password| password.unwrap())
+            .collect()
+            .await;
+        assert_eq!(passwords.len(), 3);
+        assert_ne!(passwords[0], passwords[1]);
+        let again: Vec<_> = password_stream(config.clone()).take(1).collect().await;
+        assert_eq!(again[0].as_ref().unwrap(), &passwords[0]);
+
+        config.allowed_chars.clear();
+        let invalid: Vec<_> = password_stream(config).collect().await;
+        assert_eq!(invalid.len(), 1);
+        assert!(invalid[0].is_err());
+    }
 }
@@ -4,63 +4,195 @@
 // Author: Volker Schwaberow <volker@schwaberow.de>
 // Copyright (c) 2022 Volker Schwaberow
 
+mod audit;
+mod blocklist;
+mod clipboard;
 mod config;
+#[cfg(unix)]
+mod daemon;
 mod diceware;
+mod encrypt;
 mod error;
+mod export;
+mod fips;
 mod generator;
+mod grammar;
+mod hint;
+mod history;
+mod honeytoken;
+mod i18n;
+mod jitter;
+mod jsonl;
+mod keymap;
+mod language;
+mod mcp;
+mod memorize;
+mod minisign;
+mod native;
+mod net;
+mod output;
+mod picker;
+mod plugin;
+mod profile;
+mod progress;
+mod qr;
+mod receipt;
+mod recovery;
+mod rules;
+mod salt;
+mod scorer;
+mod settings;
+mod sites;
 mod stats;
+mod store;
 mod strength;
+mod theme;
+mod unicode;
+mod wifi;
 
+#[cfg(feature = "clipboard")]
 const DAEMONIZE_ARG: &str = "__internal_daemonize";
 
+use std::collections::BTreeMap;
 use std::process;
+use std::time::{Duration, Instant};
 
-use crate::config::DEFINE;
+#[cfg(feature = "clipboard")]
 use arboard::Clipboard;
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", feature = "clipboard"))]
 use arboard::SetExtLinux;
+#[cfg(all(target_os = "macos", feature = "clipboard"))]
+use arboard::SetExtApple;
+#[cfg(all(windows, feature = "clipboard"))]
+use arboard::SetExtWindows;
 use clap::{value_parser, Arg, ArgAction, ArgGroup, Command};
 use colored::*;
-use config::{PasswordGeneratorConfig, PasswordGeneratorMode, Separator};
-use console::Term;
-use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
-use error::{PasswordGeneratorError, Result};
+use config::{
+    parse_pattern_list, parse_separator, PasswordGeneratorConfig, PasswordGeneratorMode, Separator,
+};
+use console::{Key, Term};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect, Password, Select};
+use encrypt::Recipient;
+use error::{ClipboardErrorKind, PasswordGeneratorError, Result};
+use i18n::{tr, trf};
 use generator::{
-    generate_diceware_passphrase, generate_passwords, generate_pronounceable_passwords,
-    mutate_password, MutationType,
+    generate_allowed_passphrase_words, generate_diceware_passphrase, generate_passwords,
+    generate_pronounceable_passwords, generate_strong_passwords,
+    apply_mutation, MutationType,
 };
-use stats::show_stats;
-use strength::{evaluate_password_strength, get_strength_bar, get_strength_feedback};
+use output::{Layout, Template};
+use stats::{batch_report, show_stats, ReportFormat};
+use store::{StoreMetadata, StoreTarget};
+use strength::{evaluate_generated_strength, evaluate_password_strength, get_strength_feedback};
+use theme::{Paint, Role};
+use tracing::{debug, Level};
 use zeroize::Zeroize;
 
+#[cfg(feature = "clipboard")]
 impl From<arboard::Error> for PasswordGeneratorError {
     fn from(error: arboard::Error) -> Self {
-        PasswordGeneratorError::ClipboardError(error.to_string())
+        let kind = match error {
+            arboard::Error::ClipboardNotSupported => ClipboardErrorKind::Unsupported,
+            _ => ClipboardErrorKind::CopyFailed,
+        };
+        PasswordGeneratorError::clipboard(kind, error.to_string())
     }
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    #[cfg(target_os = "linux")]
+async fn main() {
+    #[cfg(all(target_os = "linux", feature = "clipboard"))]
     {
         use std::env;
 
         if env::args().any(|arg| arg == DAEMONIZE_ARG) {
-            return copy_to_clipboard("").map(|_| ());
+            if let Err(e) = copy_to_clipboard("", clipboard::CopyOptions::default()) {
+                process::exit(e.exit_code());
+            }
+            return;
+        }
+    }
+    let mut matches = cli().get_matches();
+    if let Some(name) = matches.get_one::<String>("profile") {
+        match load_profile(name) {
+            Ok(profile) => {
+                let mut args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+                args.splice(1..1, profile.to_args().into_iter().map(Into::into));
+                matches = cli().get_matches_from(args);
+            }
+            Err(e) => {
+                report_error(&e, matches.get_one::<String>("error-format").map(String::as_str));
+                process::exit(e.exit_code());
+            }
         }
     }
-    let matches = Command::new("npwg")
+
+    init_tracing(matches.get_count("verbose"));
+    if matches.get_flag("accessible") {
+        output::set_accessible(true);
+        colored::control::set_override(false);
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+    progress::set_enabled(
+        !matches.get_flag("quiet")
+            && !output::is_accessible()
+            && matches.subcommand_name() != Some("mcp")
+            && !matches.get_flag("jsonl")
+            && matches.subcommand_name() != Some("daemon")
+            && matches.subcommand_name() != Some("native-messaging")
+            && std::io::IsTerminal::is_terminal(&std::io::stderr()),
+    );
+    // A broken config.toml must not quietly drop `offline = true`.
+    let offline = match settings::Settings::load() {
+        Ok(settings) => settings.offline.unwrap_or(false),
+        Err(e) => {
+            report_error(&e, matches.get_one::<String>("error-format").map(String::as_str));
+            process::exit(e.exit_code());
+        }
+    };
+    net::set_offline(matches.get_flag("offline") || offline);
+    if let Some(dir) = matches.get_one::<std::path::PathBuf>("cache-dir") {
+        diceware::set_cache_dir(dir.clone());
+    }
+    net::set_download_options(net::DownloadOptions {
+        timeout: Duration::from_secs(*matches.get_one::<u64>("download-timeout").unwrap()),
+        retries: *matches.get_one::<u32>("download-retries").unwrap(),
+        backoff: Duration::from_millis(*matches.get_one::<u64>("download-backoff").unwrap()),
+    });
+    i18n::set_locale(
+        matches
+            .get_one::<i18n::Locale>("lang")
+            .copied()
+            .unwrap_or_else(i18n::Locale::detect),
+    );
+
+    if let Err(e) = run(&matches).await {
+        report_error(&e, matches.get_one::<String>("error-format").map(String::as_str));
+        process::exit(e.exit_code());
+    }
+}
+
+fn cli() -> Command {
+    Command::new("npwg")
         .version(clap::crate_version!())
         .author("Volker Schwaberow <volker@schwaberow.de>")
         .about("Generates secure passwords")
+        .args_override_self(true)
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .value_name("NAME")
+                .help("Apply the options saved in a profile; options given on the command line take precedence"),
+        )
         .arg(
             Arg::new("length")
                 .short('l')
                 .long("length")
                 .value_name("LENGTH")
-                .help("Sets the length of the password")
+                .help("Sets the length of the password, or a MIN-MAX range to pick a random length per password")
                 .default_value("16")
-                .value_parser(value_parser!(u8)),
+                .value_parser(value_parser!(config::LengthRange)),
         )
         .arg(
             Arg::new("count")
@@ -83,17 +215,175 @@ async fn main() -> Result<()> {
                 .help("Show statistics about the generated passwords")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("stats-report")
+                .long("report")
+                .value_name("FILE")
+                .help("With --stats, also write the statistics as an HTML (.html) or Markdown (.md) report")
+                .value_parser(value_parser!(std::path::PathBuf))
+                .requires("stats"),
+        )
         .arg(
             Arg::new("strength")
                 .long("strength")
                 .help("Show strength meter for the generated passwords")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("min-bits")
+                .long("min-bits")
+                .value_name("BITS")
+                .help("Refuse to generate passwords with less estimated entropy than BITS (default: min_bits in config.toml)")
+                .value_parser(value_parser!(f64)),
+        )
+        .arg(
+            Arg::new("min-strength")
+                .long("min-strength")
+                .value_name("SCORE")
+                .help("Regenerate each password until its strength score reaches SCORE (0.0-1.0)")
+                .value_parser(value_parser!(f64))
+                .conflicts_with_all(["use-words", "mutate"]),
+        )
+        .arg(
+            Arg::new("reject-words")
+                .long("reject-words")
+                .value_name("FILE")
+                .help("Regenerate passwords that contain a dictionary word of 4 or more letters, taken from FILE (one word per line) or the diceware wordlist")
+                .num_args(0..=1)
+                .value_parser(value_parser!(std::path::PathBuf))
+                .conflicts_with_all(["use-words", "mutate", "fips"]),
+        )
+        .arg(
+            Arg::new("exclude-confusables")
+                .long("exclude-confusables")
+                .help("Leave out characters that look like another one in the pool, using the Unicode confusables data")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("shell-safe")
+                .long("shell-safe")
+                .help("Leave out every character that needs quoting in a POSIX shell, YAML or .env file, keeping letters, digits and + - . / = _")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("mutate"),
+        )
+        .arg(
+            Arg::new("escape-profile")
+                .long("escape-profile")
+                .value_name("CONTEXT")
+                .help("Leave out characters that need escaping where the secret is embedded: sql, ldap or json")
+                .value_parser(value_parser!(config::EscapeProfile))
+                .conflicts_with("mutate"),
+        )
+        .arg(
+            Arg::new("normalize")
+                .long("normalize")
+                .value_name("FORM")
+                .help("Unicode normalization applied to generated output before it is shown or scored: nfc, nfd or none")
+                .value_parser(value_parser!(unicode::Normalization)),
+        )
+        .arg(
+            Arg::new("reject-keyboard-runs")
+                .long("reject-keyboard-runs")
+                .help("Regenerate passwords containing 4 or more adjacent keyboard keys such as 'asdf' or '7890'")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["use-words", "mutate", "fips"]),
+        )
+        .arg(
+            Arg::new("avoid-sequential")
+                .long("avoid-sequential")
+                .help("Regenerate passwords containing 3 or more sequential characters such as 'abc' or '987'")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["use-words", "mutate", "fips"]),
+        )
+        .arg(
+            Arg::new("start-with")
+                .long("start-with")
+                .value_name("CLASS")
+                .help("Character class the password must start with: letter, upper, lower, digit, alnum or symbol")
+                .value_parser(value_parser!(config::CharClass))
+                .conflicts_with_all(["use-words", "pattern", "patterns-file", "pronounceable", "mutate"]),
+        )
+        .arg(
+            Arg::new("end-with")
+                .long("end-with")
+                .value_name("CLASS")
+                .help("Character class the password must end with: letter, upper, lower, digit, alnum or symbol")
+                .value_parser(value_parser!(config::CharClass))
+                .conflicts_with_all(["use-words", "pattern", "patterns-file", "pronounceable", "mutate"]),
+        )
+        .arg(
+            Arg::new("max-repeat")
+                .long("max-repeat")
+                .value_name("N")
+                .help("Never repeat a character more than N times in a row")
+                .value_parser(value_parser!(usize))
+                .conflicts_with("use-words"),
+        )
+        .arg(
+            Arg::new("forbid")
+                .long("forbid")
+                .value_name("SUBSTRINGS")
+                .help("Comma-separated substrings the output must never contain, ignoring case and leetspeak (e.g. company name, username); may be repeated")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .conflicts_with_all(["mutate", "fips"]),
+        )
+        .arg(
+            Arg::new("force")
+                .long("force")
+                .help("Generate even when the estimated entropy is below --min-bits")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("acknowledge-weak")
+                .long("acknowledge-weak")
+                .help("Generate even when the maxlength of --site or --rules keeps passwords below the requested entropy")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("annotate")
+                .long("annotate")
+                .help("Show entropy bits and a strength label next to each password")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["layout", "template", "output"]),
+        )
         .group(
             ArgGroup::new("output_options")
                 .args(["stats", "strength"])
                 .multiple(true),
         )
+        .arg(
+            Arg::new("layout")
+                .long("layout")
+                .value_name("LAYOUT")
+                .help("Output layout: one-per-line, columns, joined:<sep> or null")
+                .value_parser(value_parser!(Layout))
+                .default_value("one-per-line"),
+        )
+        .arg(
+            Arg::new("template")
+                .long("template")
+                .value_name("TEMPLATE")
+                .help("Output template, e.g. \"{index}\\t{password}\\t{entropy_bits}\\t{strength}\"")
+                .value_parser(value_parser!(Template)),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("PATH")
+                .help("Write the generated passwords to a file instead of stdout")
+                .value_parser(value_parser!(std::path::PathBuf))
+                .conflicts_with("patterns-file"),
+        )
+        .arg(
+            Arg::new("encrypt-to")
+                .long("encrypt-to")
+                .value_name("RECIPIENT")
+                .help("Encrypt the output file to an age recipient (age1...) or GPG key (gpg:<key>)")
+                .value_parser(value_parser!(Recipient))
+                .requires("output"),
+        )
         .arg(
             Arg::new("allowed")
                 .short('a')
@@ -102,12 +392,79 @@ async fn main() -> Result<()> {
                 .help("Sets the allowed characters")
                 .default_value("allprint"),
         )
+        .arg(
+            Arg::new("site")
+                .long("site")
+                .value_name("NAME")
+                .help("Apply a built-in preset for a restrictive service (see 'npwg sites')"),
+        )
+        .arg(
+            Arg::new("rules")
+                .long("rules")
+                .value_name("RULES")
+                .help("Apply an Apple/WebKit passwordrules string, e.g. 'minlength: 12; required: upper; required: digit'"),
+        )
         .arg(
             Arg::new("use-words")
                 .long("use-words")
                 .help("Use words instead of characters")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("words-for-bits")
+                .long("words-for-bits")
+                .value_name("BITS")
+                .help("Use the minimum number of words reaching the given entropy in bits")
+                .value_parser(value_parser!(f64))
+                .requires("use-words"),
+        )
+        .arg(
+            Arg::new("words")
+                .long("words")
+                .value_name("N|MIN-MAX")
+                .help("Number of words per passphrase, or a range to pick from for each passphrase")
+                .value_parser(value_parser!(config::LengthRange))
+                .requires("use-words")
+                .conflicts_with_all(["length", "words-for-bits"]),
+        )
+        .arg(
+            Arg::new("show-rolls")
+                .long("show-rolls")
+                .help("After the passphrases, list each word with its dice index in the wordlist, to check against a printed copy")
+                .action(ArgAction::SetTrue)
+                .requires("use-words")
+                .conflicts_with_all(["output", "encrypt-to"]),
+        )
+        .arg(
+            Arg::new("hint")
+                .long("hint")
+                .value_name("STYLE")
+                .help("After the passphrases, print a hint for each that is safe to write down: the initials or lengths of its words")
+                .value_parser(value_parser!(hint::HintStyle))
+                .requires("use-words"),
+        )
+        .arg(
+            Arg::new("blocklist")
+                .long("blocklist")
+                .help("Leave mildly offensive or unfortunate words out of passphrases, using a built-in list")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("blocklist-file")
+                .long("blocklist-file")
+                .value_name("FILE")
+                .help("Also leave out the words in FILE, one per line (implies --blocklist; repeatable)")
+                .value_parser(value_parser!(std::path::PathBuf))
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("append-digits")
+                .long("append-digits")
+                .value_name("N")
+                .help("Append N random digits after the final word of each passphrase")
+                .value_parser(value_parser!(usize))
+                .requires("use-words"),
+        )
         .arg(
             Arg::new("interactive")
                 .short('i')
@@ -115,12 +472,29 @@ async fn main() -> Result<()> {
                 .help("Start interactive console mode")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("jsonl")
+                .long("jsonl")
+                .help("Read one JSON request per line on stdin, e.g. {\"length\":20}, and answer each with a line of JSON")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("interactive"),
+        )
         .arg(
             Arg::new("separator")
                 .long("separator")
                 .value_name("SEPARATOR")
-                .help("Sets the separator for diceware passphrases (single character or 'random')")
-                .requires("use-words"),
+                .help("Sets the separator for diceware and grammar passphrases (any string, 'random', 'set:<charset>', 'cycle[:<chars>]' or 'positional[:<charset>]')")
+                .allow_hyphen_values(true)
+                .requires("word-modes"),
+        )
+        .arg(
+            Arg::new("join-style")
+                .long("join-style")
+                .value_name("STYLE")
+                .help("Case and join passphrase words in one style: camel, pascal, kebab, snake or none")
+                .value_parser(value_parser!(config::JoinStyle))
+                .requires("use-words")
+                .conflicts_with("separator"),
         )
         .arg(
             Arg::new("pronounceable")
@@ -128,6 +502,63 @@ async fn main() -> Result<()> {
                 .help("Generate pronounceable passwords")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("grammar")
+                .long("grammar")
+                .value_name("TEMPLATE")
+                .help(format!(
+                    "Generate sentence-like passphrases from word roles: adjective, noun, nouns, verb, adverb [default template: {}]",
+                    grammar::DEFAULT_TEMPLATE
+                ))
+                .num_args(0..=1)
+                .default_missing_value(grammar::DEFAULT_TEMPLATE)
+                .value_parser(value_parser!(grammar::Template))
+                .conflicts_with_all(["pattern", "patterns-file", "pronounceable", "mutate", "mode", "keepass", "fips"]),
+        )
+        .arg(
+            Arg::new("pos-wordlist")
+                .long("pos-wordlist")
+                .value_name("PATH")
+                .help("Word list tagged with parts of speech ('word TAG' per line) for --grammar and role placeholders in --pattern")
+                .value_parser(value_parser!(std::path::PathBuf)),
+        )
+        .arg(
+            Arg::new("role-words")
+                .long("role-words")
+                .value_name("ROLE=PATH")
+                .help("Word list for one role (adjective, noun, verb or adverb) for --grammar and role placeholders in --pattern (can be repeated)")
+                .value_parser(grammar::parse_role_list)
+                .action(ArgAction::Append),
+        )
+        .group(
+            ArgGroup::new("word-modes")
+                .args(["use-words", "grammar"])
+                .required(false),
+        )
+        .arg(
+            Arg::new("mode")
+                .long("mode")
+                .value_name("PLUGIN")
+                .help("Generate with a plugin from ~/.npwg/plugins (see `npwg plugins`)")
+                .conflicts_with_all(["use-words", "pattern", "patterns-file", "pronounceable", "mutate", "keepass", "fips"]),
+        )
+        .arg(
+            Arg::new("plugin-option")
+                .long("plugin-option")
+                .value_name("KEY=VALUE")
+                .help("Option passed to the --mode plugin (can be repeated)")
+                .value_parser(plugin::parse_option)
+                .action(ArgAction::Append)
+                .requires("mode"),
+        )
+        .arg(
+            Arg::new("language")
+                .long("language")
+                .value_name("LANG")
+                .help("Syllable table for pronounceable passwords (en, de, fr, es, it)")
+                .value_parser(value_parser!(language::Language))
+                .requires("pronounceable"),
+        )
         .arg(
             Arg::new("mutate")
                 .long("mutate")
@@ -141,10 +572,19 @@ async fn main() -> Result<()> {
                 .value_parser(value_parser!(MutationType))
                 .default_value("replace"),
         )
+        .arg(
+            Arg::new("mutation_class")
+                .long("mutation-class")
+                .value_name("CLASS")
+                .help("Character class attached by the append and prepend mutations: letter, upper, lower, digit, alnum or symbol")
+                .value_parser(value_parser!(config::CharClass))
+                .default_value("digit"),
+        )
         .arg(
             Arg::new("mutation_strength")
                 .long("mutation-strength")
-                .help("Strength of mutation")
+                .help("Strength of mutation; the number of characters attached by append and prepend")
+                .value_parser(value_parser!(u32))
                 .default_value("1"),
         )
         .arg(
@@ -160,158 +600,2672 @@ async fn main() -> Result<()> {
                 .help("Copy the generated password to the clipboard")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("copy-index")
+                .long("copy-index")
+                .value_name("N")
+                .help("Copy only the N-th generated password (1-based) to the clipboard")
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("picker")
+                .long("picker")
+                .value_name("LAUNCHER")
+                .help("Choose one of the candidates in rofi or dmenu and copy it, or print them as an Alfred Script Filter")
+                .value_parser(value_parser!(picker::Picker))
+                .conflicts_with_all(["copy", "copy-index", "output", "template", "layout"]),
+        )
+        .group(
+            ArgGroup::new("copy-target")
+                .args(["copy", "copy-index", "picker"])
+                .multiple(true),
+        )
+        .arg(
+            Arg::new("clipboard-backend")
+                .long("clipboard-backend")
+                .value_name("BACKEND")
+                .help("Clipboard backend on Linux: auto, wayland or x11")
+                .value_parser(value_parser!(clipboard::Backend))
+                .requires("copy-target"),
+        )
+        .arg(
+            Arg::new("clear-after")
+                .long("clear-after")
+                .value_name("SECONDS")
+                .help("Clear the clipboard after SECONDS and restore what was there before the copy")
+                .value_parser(value_parser!(u64))
+                .requires("copy-target"),
+        )
+        .arg(
+            Arg::new("store")
+                .long("store")
+                .value_name("TARGET")
+                .help("Store the generated password in a password manager (pass:<entry>, bw:<name> or op:<name>)")
+                .value_parser(value_parser!(StoreTarget)),
+        )
+        .arg(
+            Arg::new("store-username")
+                .long("store-username")
+                .value_name("USERNAME")
+                .help("Username saved alongside the stored password")
+                .requires("store"),
+        )
+        .arg(
+            Arg::new("store-url")
+                .long("store-url")
+                .value_name("URL")
+                .help("URL saved alongside the stored password")
+                .requires("store"),
+        )
         .arg(
             Arg::new("pattern")
                 .short('p')
                 .long("pattern")
-                .help("Pattern for password generation (e.g., LLDDS)")
+                .help("Pattern for password generation (e.g., LLDDS); see --pattern-help")
                 .value_parser(value_parser!(String)),
         )
+        .arg(
+            Arg::new("pattern-help")
+                .long("pattern-help")
+                .help("Print the pattern grammar and exit")
+                .action(ArgAction::SetTrue)
+                .exclusive(true),
+        )
+        .arg(
+            Arg::new("patterns-file")
+                .long("patterns-file")
+                .value_name("PATH")
+                .help("Generate passwords for each pattern in a file (one '<pattern> [count]' per line)")
+                .value_parser(value_parser!(std::path::PathBuf))
+                .conflicts_with_all(["pattern", "use-words", "pronounceable", "mutate"]),
+        )
+        .arg(
+            Arg::new("keepass")
+                .long("keepass")
+                .value_name("PATH")
+                .help("Export the generated passwords to a KeePass .xml or .kdbx file")
+                .value_parser(value_parser!(std::path::PathBuf))
+                .conflicts_with_all(["use-words", "mutate", "patterns-file"]),
+        )
+        .arg(
+            Arg::new("accounts")
+                .long("accounts")
+                .value_name("CSV")
+                .help("CSV file with 'title,username,url' rows for the KeePass export")
+                .value_parser(value_parser!(std::path::PathBuf))
+                .requires("keepass"),
+        )
+        .arg(
+            Arg::new("anki")
+                .long("anki")
+                .value_name("PATH")
+                .help("Export the generated passphrases as Anki flashcards to a TSV file")
+                .value_parser(value_parser!(std::path::PathBuf))
+                .conflicts_with_all(["keepass", "patterns-file", "mutate", "mode", "output", "picker"]),
+        )
+        .arg(
+            Arg::new("anki-cards")
+                .long("anki-cards")
+                .value_name("TYPE")
+                .help("Anki note type: cloze asks for each word in turn, basic for the whole secret")
+                .value_parser(value_parser!(export::AnkiCards))
+                .default_value("cloze")
+                .requires("anki"),
+        )
+        .arg(
+            Arg::new("anki-deck")
+                .long("anki-deck")
+                .value_name("NAME")
+                .help("Anki deck the cards are imported into")
+                .default_value("npwg")
+                .requires("anki"),
+        )
         .group(
             ArgGroup::new("generation")
                 .args(["pattern", "avoid-repeating", "allowed", "use-words", "separator", "pronounceable", "mutate", "mutation_type", "mutation_strength", "lengthen"])
+                .multiple(true)
                 .required(false),
         )
+        .arg(
+            Arg::new("error-format")
+                .long("error-format")
+                .value_name("FORMAT")
+                .help("Format of error messages printed to stderr")
+                .value_parser(["text", "json"])
+                .default_value("text"),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .help("Do not show progress bars")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("accessible")
+                .long("accessible")
+                .help("Screen-reader friendly output: no colours or block characters, strength as text, each password announced once")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("lang")
+                .long("lang")
+                .value_name("LANG")
+                .help("Language of messages: en or de (defaults to LC_ALL, LC_MESSAGES or LANG)")
+                .value_parser(value_parser!(i18n::Locale))
+                .global(true),
+        )
+        .arg(
+            Arg::new("strength-threshold")
+                .long("strength-threshold")
+                .value_name("SCORE")
+                .help("Exit with code 8 when a password shown by --strength or scored by check falls below SCORE (0.0-1.0), or 9 when it breaks the check policy; defaults to strength.threshold in config.toml")
+                .value_parser(value_parser!(f64))
+                .global(true),
+        )
+        .arg(
+            Arg::new("wordlist-key")
+                .long("wordlist-key")
+                .value_name("KEY")
+                .env("NPWG_WORDLIST_KEY")
+                .help("Minisign public key (the key line or a .pub file) that must have signed the wordlist; the signature is read from the wordlist path plus .minisig")
+                .global(true),
+        )
+        .arg(
+            Arg::new("cache-dir")
+                .long("cache-dir")
+                .value_name("DIR")
+                .env("NPWG_CACHE_DIR")
+                .help("Directory of the diceware wordlist and its signature [default: npwg in the platform data directory, e.g. ~/.local/share/npwg]")
+                .value_parser(value_parser!(std::path::PathBuf))
+                .global(true),
+        )
+        .arg(
+            Arg::new("offline")
+                .long("offline")
+                .help("Never touch the network: use only the cached wordlist, skip breach checks, and fail anything else that needs a connection (also set by offline = true in config.toml)")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("download-timeout")
+                .long("download-timeout")
+                .value_name("SECS")
+                .help("Seconds before a single download attempt (wordlist, breach check) is abandoned")
+                .default_value("15")
+                .value_parser(value_parser!(u64).range(1..))
+                .global(true),
+        )
+        .arg(
+            Arg::new("download-retries")
+                .long("download-retries")
+                .value_name("N")
+                .help("Extra attempts after a timeout, dropped connection or 5xx/429 response")
+                .default_value("3")
+                .value_parser(value_parser!(u32))
+                .global(true),
+        )
+        .arg(
+            Arg::new("download-backoff")
+                .long("download-backoff")
+                .value_name("MS")
+                .help("Delay before the first retry in milliseconds, doubled for each further retry")
+                .default_value("500")
+                .value_parser(value_parser!(u64))
+                .global(true),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .help("Print diagnostic information to stderr (-v info, -vv debug, -vvv trace)")
+                .action(ArgAction::Count),
+        )
         .arg(
             Arg::new("seed")
                 .short('s')
                 .long("seed")
                 .value_name("SEED")
-                .help("Sets the seed for the random number generator")
-                .value_parser(value_parser!(u64)),
+                .help("Seed for reproducible output: a number, a 0x-prefixed hex string, or any passphrase (hashed into a 32-byte seed)")
+                .value_parser(value_parser!(config::Seed)),
         )
-        .get_matches();
-
-    if matches.get_flag("interactive") {
-        return interactive_mode().await;
-    }
+        .arg(
+            Arg::new("fips")
+                .long("fips")
+                .help("Restrict generation to an HMAC_DRBG (SP 800-90A) fed by the OS entropy source, refuse settings that skew the output, and print an attestation line")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["seed", "mutate", "interactive"]),
+        )
+        .arg(
+            Arg::new("receipt")
+                .long("receipt")
+                .value_name("FILE")
+                .help("Append a JSON receipt of the run (time, mode, charsets, length, policy, entropy, whether seeded) to FILE; the generated values are never recorded")
+                .value_parser(value_parser!(std::path::PathBuf)),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("Print the effective configuration without generating anything")
+                .action(ArgAction::SetTrue),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Inspect the effective configuration")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("show")
+                        .about("Print the effective configuration without generating anything"),
+                ),
+        )
+        .subcommand(Command::new("sites").about("List the built-in site presets for --site"))
+        .subcommand(Command::new("plugins").about("List the generator plugins available to --mode"))
+        .subcommand(
+            Command::new("profile")
+                .about("Share generation profiles between machines")
+                .subcommand_required(true)
+                .subcommand(Command::new("list").about("List the saved profiles"))
+                .subcommand(
+                    Command::new("export")
+                        .about("Print a profile as TOML")
+                        .arg(Arg::new("name").value_name("NAME").required(true)),
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("Save a profile from a TOML file")
+                        .arg(
+                            Arg::new("file")
+                                .value_name("FILE")
+                                .required(true)
+                                .value_parser(value_parser!(std::path::PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("name")
+                                .long("name")
+                                .value_name("NAME")
+                                .help("Save under this name instead of the one in the file"),
+                        )
+                        .arg(
+                            Arg::new("force")
+                                .long("force")
+                                .help("Replace an existing profile of the same name")
+                                .action(ArgAction::SetTrue),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("mcp")
+                .about("Serve generate, check and policy tools to AI assistants over the Model Context Protocol (stdio)"),
+        )
+        .subcommand(
+            Command::new("daemon")
+                .about("Answer --jsonl requests from local tools over a Unix domain socket")
+                .arg(
+                    Arg::new("socket")
+                        .long("socket")
+                        .value_name("PATH")
+                        .help("Socket to listen on [default: ~/.npwg/npwg.sock]")
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("memorize")
+                .about("Generate a secret from the options before the subcommand and practise typing it from memory")
+                .arg(
+                    Arg::new("rounds")
+                        .long("rounds")
+                        .value_name("N")
+                        .help("Correct entries needed to finish the drill")
+                        .default_value("3")
+                        .value_parser(value_parser!(u64).range(1..)),
+                ),
+        )
+        .subcommand(
+            Command::new("native-messaging")
+                .about("Answer --jsonl requests from a browser extension over native messaging (stdio)")
+                .arg(
+                    Arg::new("install")
+                        .long("install")
+                        .value_name("BROWSER")
+                        .help("Write the host manifest for chrome, chromium, edge or firefox and exit")
+                        .value_parser(value_parser!(native::Browser))
+                        .requires("extension-id"),
+                )
+                .arg(
+                    Arg::new("extension-id")
+                        .long("extension-id")
+                        .value_name("ID")
+                        .help("Extension allowed to start the host")
+                        .requires("install"),
+                )
+                .arg(
+                    Arg::new("caller")
+                        .help("Origin and manifest arguments passed by the browser")
+                        .num_args(0..)
+                        .trailing_var_arg(true)
+                        .allow_hyphen_values(true)
+                        .hide(true),
+                ),
+        )
+        .subcommand(
+            Command::new("wordlist")
+                .about("Manage diceware wordlists")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("verify")
+                        .about("Check a wordlist's minisign signature against --wordlist-key")
+                        .arg(
+                            Arg::new("file")
+                                .value_name("FILE")
+                                .help("Wordlist to verify (defaults to the cached diceware wordlist)")
+                                .value_parser(value_parser!(std::path::PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("signature")
+                                .long("signature")
+                                .value_name("SIG")
+                                .help("Signature file (defaults to FILE.minisig)")
+                                .value_parser(value_parser!(std::path::PathBuf)),
+                        ),
+                )
+                .subcommand(
+                    Command::new("build")
+                        .about("Build a diceware wordlist from a text corpus")
+                        .arg(
+                            Arg::new("from")
+                                .long("from")
+                                .value_name("CORPUS")
+                                .help("Text file to extract words from")
+                                .required(true)
+                                .value_parser(value_parser!(std::path::PathBuf)),
+                        )
+                        .arg(
+                            Arg::new("size")
+                                .long("size")
+                                .value_name("WORDS")
+                                .help("Number of words, must be a power of 6")
+                                .default_value("7776")
+                                .value_parser(value_parser!(usize)),
+                        )
+                        .arg(
+                            Arg::new("min-len")
+                                .long("min-len")
+                                .value_name("CHARS")
+                                .help("Minimum word length")
+                                .default_value("4")
+                                .value_parser(value_parser!(usize)),
+                        )
+                        .arg(
+                            Arg::new("max-len")
+                                .long("max-len")
+                                .value_name("CHARS")
+                                .help("Maximum word length")
+                                .default_value("8")
+                                .value_parser(value_parser!(usize)),
+                        )
+                        .arg(
+                            Arg::new("output")
+                                .short('o')
+                                .long("output")
+                                .value_name("PATH")
+                                .help("Write the wordlist to a file instead of stdout")
+                                .value_parser(value_parser!(std::path::PathBuf)),
+                        ),
+                ),
+        )
+        .subcommand(random_bytes_command(
+            "salt",
+            "Generate a random salt from the operating system CSPRNG",
+            "16",
+        ))
+        .subcommand(random_bytes_command(
+            "nonce",
+            "Generate a random nonce from the operating system CSPRNG",
+            "12",
+        ))
+        .subcommand(
+            Command::new("audit")
+                .about("Audit an existing list of passwords")
+                .arg(
+                    Arg::new("file")
+                        .value_name("FILE")
+                        .help("File with one password per line, or 'username,password' rows with --csv")
+                        .required(true)
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                )
+                .arg(
+                    Arg::new("csv")
+                        .long("csv")
+                        .help("Treat the input as 'username,password' rows (implied by a .csv extension)")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("hibp")
+                        .long("hibp")
+                        .help("Check each password against Have I Been Pwned (k-anonymity range API)")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("min-score")
+                        .long("min-score")
+                        .value_name("SCORE")
+                        .help("Strength score below which a password is reported as weak [default: --strength-threshold, or 0.3]")
+                        .value_parser(value_parser!(f64)),
+                )
+                .arg(
+                    Arg::new("rules")
+                        .long("rules")
+                        .value_name("RULES")
+                        .help("Report passwords that break a passwordrules policy, e.g. 'minlength: 12; required: digit'"),
+                )
+                .arg(
+                    Arg::new("site")
+                        .long("site")
+                        .value_name("NAME")
+                        .help("Report passwords that break a built-in site preset (see 'npwg sites')")
+                        .conflicts_with("rules"),
+                )
+                .arg(
+                    Arg::new("fail-on-weak")
+                        .long("fail-on-weak")
+                        .help("Exit with code 8 when a password scores below --min-score (--strength-threshold, or 0.3, when unset), or 9 when one breaks --rules or --site, listing the lines on stderr")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Score a password and suggest improvements")
+                .long_about("Score a password and suggest improvements. The password is read from a hidden prompt, or from the first line of standard input when it is not a terminal.")
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print the score, feedback and suggestions as JSON with stable codes")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("input"),
+                )
+                .arg(
+                    Arg::new("score-scale")
+                        .long("score-scale")
+                        .value_name("SCALE")
+                        .help("Score scale: npwg (0.0-1.0) or pwquality (0-100, failing like pwscore)")
+                        .value_parser(value_parser!(strength::ScoreScale))
+                        .default_value("npwg"),
+                )
+                .arg(
+                    Arg::new("compare")
+                        .long("compare")
+                        .value_names(["A", "B"])
+                        .num_args(0..=2)
+                        .help("Score two passwords side by side and say which is stronger; without values both are prompted for, keeping them out of shell history")
+                        .conflicts_with_all(["input", "score-scale"]),
+                )
+                .arg(
+                    Arg::new("input")
+                        .long("input")
+                        .value_name("FILE")
+                        .help("Score every password in FILE (one per line) instead of a single one")
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                )
+                .arg(
+                    Arg::new("report")
+                        .long("report")
+                        .value_name("FILE")
+                        .help("Write the per-password CSV report to FILE instead of standard output")
+                        .requires("input")
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                )
+                .arg(
+                    Arg::new("rules")
+                        .long("rules")
+                        .value_name("RULES")
+                        .help("Check compliance with a passwordrules policy, e.g. 'minlength: 12; required: digit'"),
+                )
+                .arg(
+                    Arg::new("site")
+                        .long("site")
+                        .value_name("NAME")
+                        .help("Check compliance with a built-in site preset (see 'npwg sites')")
+                        .conflicts_with("rules"),
+                )
+                .arg(
+                    Arg::new("fail-on-weak")
+                        .long("fail-on-weak")
+                        .help("Exit with code 8 when a password scores below --strength-threshold (0.3 when unset), or 9 when one breaks --rules or --site, listing the lines on stderr")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("compare"),
+                ),
+        )
+        .subcommand(
+            Command::new("honeytoken")
+                .about("Generate decoy credentials that carry a keyed marker, and find them again in logs")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("generate")
+                        .about("Generate honeytokens and optionally a JSON manifest")
+                        .arg(honeytoken_key_arg())
+                        .arg(
+                            Arg::new("length")
+                                .short('l')
+                                .long("length")
+                                .value_name("LENGTH")
+                                .help("Token length, including the prefix and marker")
+                                .default_value("20")
+                                .value_parser(value_parser!(usize)),
+                        )
+                        .arg(
+                            Arg::new("count")
+                                .short('c')
+                                .long("count")
+                                .value_name("COUNT")
+                                .help("Number of tokens to generate")
+                                .default_value("1")
+                                .value_parser(value_parser!(usize)),
+                        )
+                        .arg(
+                            Arg::new("prefix")
+                                .long("prefix")
+                                .value_name("PREFIX")
+                                .help("Fixed start that makes tokens look like a given credential, e.g. AKIA or ghp_")
+                                .default_value(""),
+                        )
+                        .arg(
+                            Arg::new("label")
+                                .long("label")
+                                .value_name("TEXT")
+                                .help("Where the tokens will be planted, recorded in the manifest"),
+                        )
+                        .arg(
+                            Arg::new("manifest")
+                                .long("manifest")
+                                .value_name("FILE")
+                                .help("Write a JSON manifest of the tokens to FILE")
+                                .value_parser(value_parser!(std::path::PathBuf)),
+                        ),
+                )
+                .subcommand(
+                    Command::new("scan")
+                        .about("Print every honeytoken found in a file or standard input")
+                        .arg(honeytoken_key_arg())
+                        .arg(
+                            Arg::new("file")
+                                .value_name("FILE")
+                                .help("File to scan (defaults to standard input)")
+                                .value_parser(value_parser!(std::path::PathBuf)),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("wifi")
+                .about("Generate a WPA2/WPA3 passphrase and a QR code guests can scan to join")
+                .arg(
+                    Arg::new("ssid")
+                        .long("ssid")
+                        .value_name("NAME")
+                        .help("Network name")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("length")
+                        .short('l')
+                        .long("length")
+                        .value_name("LENGTH")
+                        .help("Passphrase length (8-63)")
+                        .default_value("20")
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("hidden")
+                        .long("hidden")
+                        .help("Mark the network as hidden, so phones probe for it")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("PATH")
+                        .help("Also write the QR code to an SVG file")
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("keyfile")
+                .about("Write raw random bytes to a file readable only by you, e.g. for KeePass or LUKS")
+                .arg(
+                    Arg::new("bytes")
+                        .long("bytes")
+                        .value_name("BYTES")
+                        .help("Number of random bytes")
+                        .default_value("64")
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("PATH")
+                        .help("Keyfile to write")
+                        .value_parser(value_parser!(std::path::PathBuf))
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("sidecar")
+                        .long("sidecar")
+                        .value_name("ENCODING")
+                        .help("Also write the key as text next to it: hex (PATH.hex), base64 or base64url (PATH.b64)")
+                        .value_parser(value_parser!(salt::Encoding)),
+                )
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .help("Replace an existing keyfile")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("recovery-codes")
+                .about("Generate one-time backup codes from the print-safe alphabet")
+                .arg(
+                    Arg::new("count")
+                        .short('c')
+                        .long("count")
+                        .value_name("COUNT")
+                        .help("Number of codes")
+                        .default_value("10")
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Code layout: each X is a random character, anything else is kept as a separator")
+                        .default_value("XXXX-XXXX"),
+                )
+                .arg(
+                    Arg::new("hashes")
+                        .long("hashes")
+                        .value_name("FILE")
+                        .help("Also give the SHA-256 of each code for server-side storage: next to the code, or one per line in FILE")
+                        .num_args(0..=1)
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("recovery-sheet")
+                .about("Generate print-safe passwords laid out as a paper recovery sheet")
+                .arg(
+                    Arg::new("length")
+                        .short('l')
+                        .long("length")
+                        .value_name("LENGTH")
+                        .help("Password length, excluding the check character")
+                        .default_value("16")
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("count")
+                        .short('c')
+                        .long("count")
+                        .value_name("COUNT")
+                        .help("Number of passwords on the sheet")
+                        .default_value("10")
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("group")
+                        .long("group")
+                        .value_name("SIZE")
+                        .help("Characters per group, 0 to disable grouping")
+                        .default_value("4")
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("PATH")
+                        .help("Write the sheet to a file; a .pdf extension produces a PDF")
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                ),
+        )
+}
 
-    let config = build_config(&matches)?;
+fn honeytoken_key_arg() -> Arg {
+    Arg::new("key")
+        .long("key")
+        .value_name("KEY")
+        .env("NPWG_HONEYTOKEN_KEY")
+        .hide_env_values(true)
+        .help("Secret that marks the tokens; keep it to recognize them later")
+        .required(true)
+}
 
-    let copy = matches.get_flag("copy");
+fn random_bytes_command(
+    name: &'static str,
+    about: &'static str,
+    default_bytes: &'static str,
+) -> Command {
+    Command::new(name)
+        .about(about)
+        .arg(
+            Arg::new("bytes")
+                .long("bytes")
+                .value_name("BYTES")
+                .help("Number of random bytes")
+                .default_value(default_bytes)
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("encoding")
+                .long("encoding")
+                .value_name("ENCODING")
+                .help("Output encoding: hex, base64 or base64url")
+                .default_value("hex")
+                .value_parser(value_parser!(salt::Encoding)),
+        )
+        .arg(
+            Arg::new("count")
+                .short('c')
+                .long("count")
+                .value_name("COUNT")
+                .help("Number of values to generate")
+                .default_value("1")
+                .value_parser(value_parser!(usize)),
+        )
+}
 
-    if matches.get_flag("mutate") {
-        handle_mutation(&config, &matches, copy).await
-    } else {
-        match config.mode {
-            PasswordGeneratorMode::Diceware => handle_diceware(&config, &matches, copy).await,
-            PasswordGeneratorMode::Password => {
-                if config.pronounceable {
-                    handle_pronounceable(&config, &matches, copy).await
-                } else {
-                    handle_password(&config, &matches, copy).await
+fn report_error(error: &PasswordGeneratorError, format: Option<&str>) {
+    match format {
+        Some("json") => eprintln!(
+            "{}",
+            serde_json::json!({
+                "error": {
+                    "kind": error.kind(),
+                    "message": error.to_string(),
+                    "exit_code": error.exit_code(),
                 }
+            })
+        ),
+        _ => {
+            eprintln!("{} {}", tr("Error:").paint(Role::Error).bold(), error.localized());
+            if let Some(snippet) = error.snippet().filter(|_| !output::is_accessible()) {
+                eprintln!("{}", snippet);
             }
         }
     }
 }
 
-fn build_config(matches: &clap::ArgMatches) -> Result<PasswordGeneratorConfig> {
-    let mut config = PasswordGeneratorConfig::new();
-    config.length = *matches.get_one::<u8>("length").unwrap() as usize;
-    config.num_passwords = *matches.get_one::<u32>("count").unwrap() as usize;
-    config.set_avoid_repeating(matches.get_flag("avoid-repeating"));
-    config.seed = matches.get_one::<u64>("seed").copied();
-    config.clear_allowed_chars();
-
-    let allowed = matches.get_one::<String>("allowed").unwrap();
-    for charset in allowed.split(',').map(str::trim) {
-        if !DEFINE.iter().any(|&(key, _)| key == charset) {
-            eprintln!(
-                "Error: Unknown characterset '{}' was ignored. Use one of: {}",
-                charset.red(),
-                DEFINE
-                    .iter()
-                    .map(|&(key, _)| key)
-                    .collect::<Vec<_>>()
-                    .join(", ")
-                    .green()
-            );
-            process::exit(1);
+async fn run(matches: &clap::ArgMatches) -> Result<()> {
+    let settings = settings::Settings::load()?;
+    theme::init(theme::Theme::from_settings(&settings.theme)?);
+    output::set_bar_style(output::BarStyle::from_settings(&settings.strength, output::locale_is_utf8())?);
+    let wordlist_key = matches
+        .get_one::<String>("wordlist-key")
+        .map(|key| load_public_key(key))
+        .transpose()?;
+    if let Some(key) = &wordlist_key {
+        diceware::set_trusted_key(key.clone());
+    }
+    if matches.get_flag("blocklist") || matches.contains_id("blocklist-file") {
+        let mut blocklist = blocklist::Blocklist::builtin();
+        for path in matches.get_many::<std::path::PathBuf>("blocklist-file").into_iter().flatten() {
+            blocklist.extend_from_file(path)?;
         }
-        config.add_allowed_chars(charset);
+        diceware::set_blocklist(blocklist);
     }
-    config.mode = if matches.get_flag("use-words") {
-        PasswordGeneratorMode::Diceware
-    } else {
-        PasswordGeneratorMode::Password
+    if let Some(path) = matches.get_one::<std::path::PathBuf>("stats-report") {
+        ReportFormat::from_path(path)?;
+    }
+
+    if let Some(audit_matches) = matches.subcommand_matches("audit") {
+        return handle_audit(audit_matches).await;
+    }
+
+    if let Some(check_matches) = matches.subcommand_matches("check") {
+        return handle_check(check_matches);
+    }
+
+    if let Some((name @ ("salt" | "nonce" | "honeytoken" | "recovery-codes" | "keyfile"), sub_matches)) =
+        matches.subcommand()
+    {
+        if matches.contains_id("seed") {
+            return Err(PasswordGeneratorError::InvalidConfig(format!(
+                "--seed cannot be used with '{}': values always come from the operating system CSPRNG",
+                name
+            )));
+        }
+        match name {
+            "honeytoken" => return handle_honeytoken(sub_matches),
+            "recovery-codes" => return handle_recovery_codes(sub_matches),
+            "keyfile" => return handle_keyfile(sub_matches),
+            _ => {}
+        }
+        return handle_random_bytes(sub_matches);
+    }
+
+    if let Some(("wordlist", wordlist_matches)) = matches.subcommand() {
+        if let Some(build_matches) = wordlist_matches.subcommand_matches("build") {
+            return handle_wordlist_build(build_matches);
+        }
+        if let Some(verify_matches) = wordlist_matches.subcommand_matches("verify") {
+            return handle_wordlist_verify(verify_matches, wordlist_key.as_ref());
+        }
+    }
+
+    if let Some(wifi_matches) = matches.subcommand_matches("wifi") {
+        return handle_wifi(wifi_matches, matches.get_one::<config::Seed>("seed").copied()).await;
+    }
+
+    if let Some(sheet_matches) = matches.subcommand_matches("recovery-sheet") {
+        return handle_recovery_sheet(sheet_matches, matches.get_one::<config::Seed>("seed").copied()).await;
+    }
+
+    if matches.get_flag("pattern-help") {
+        println!("{}", config::PATTERN_HELP);
+        return Ok(());
+    }
+
+    if matches.subcommand_matches("mcp").is_some() {
+        return mcp::serve(std::io::stdin().lock(), std::io::stdout().lock()).await;
+    }
+
+    if matches.get_flag("jsonl") {
+        return handle_jsonl().await;
+    }
+
+    if let Some(daemon_matches) = matches.subcommand_matches("daemon") {
+        return handle_daemon(daemon_matches).await;
+    }
+
+    if let Some(native_matches) = matches.subcommand_matches("native-messaging") {
+        return handle_native_messaging(native_matches).await;
+    }
+
+    if matches.subcommand_matches("sites").is_some() {
+        print_site_presets();
+        return Ok(());
+    }
+
+    if matches.subcommand_matches("plugins").is_some() {
+        return print_plugins();
+    }
+
+    if let Some(profile_matches) = matches.subcommand_matches("profile") {
+        return handle_profile(profile_matches);
+    }
+
+    if matches.get_flag("interactive") {
+        return interactive_mode().await;
+    }
+
+    let mut config = build_config(matches)?;
+    if matches.contains_id("pos-wordlist") || matches.contains_id("role-words") {
+        config.lexicon = Some(grammar_lexicon(matches)?);
+    }
+    config.load_pattern_words().await?;
+    if matches.contains_id("reject-words") {
+        config.set_dictionary(
+            load_dictionary(matches.get_one::<std::path::PathBuf>("reject-words")).await?,
+        );
+    }
+
+    if let Some(memorize_matches) = matches.subcommand_matches("memorize") {
+        return handle_memorize(&config, matches, memorize_matches).await;
+    }
+
+    if matches.get_flag("dry-run") || matches.subcommand_matches("config").is_some() {
+        return print_effective_config(&config, matches).await;
+    }
+
+    let copy = matches.get_flag("copy") || matches.contains_id("copy-index");
+    let started = Instant::now();
+
+    let result = if let Some(path) = matches.get_one::<std::path::PathBuf>("keepass") {
+        handle_keepass_export(&config, matches, path).await
+    } else if let Some(path) = matches.get_one::<std::path::PathBuf>("anki") {
+        handle_anki_export(&config, matches, path).await
+    } else if let Some(path) = matches.get_one::<std::path::PathBuf>("patterns-file") {
+        handle_patterns_file(&config, matches, path, copy).await
+    } else if let Some(name) = matches.get_one::<String>("mode") {
+        handle_plugin(&config, matches, copy, name)
+    } else if let Some(template) = matches.get_one::<grammar::Template>("grammar") {
+        handle_grammar(&config, matches, copy, template)
+    } else if matches.get_flag("mutate") {
+        handle_mutation(&config, matches, copy).await
+    } else {
+        match config.mode {
+            PasswordGeneratorMode::Diceware => handle_diceware(&mut config, matches, copy).await,
+            PasswordGeneratorMode::Password => {
+                if config.pronounceable {
+                    handle_pronounceable(&config, matches, copy).await
+                } else {
+                    handle_password(&config, matches, copy).await
+                }
+            }
+        }
+    };
+
+    if config.fips && config.mode == PasswordGeneratorMode::Password && result.is_ok() {
+        eprintln!("{}", fips::attestation(&config, None));
+    }
+    if let (Some(path), Ok(())) = (matches.get_one::<std::path::PathBuf>("receipt"), &result) {
+        build_receipt(&config, matches).await?.append_to(path)?;
+    }
+    debug!(elapsed_ms = started.elapsed().as_millis() as u64, "finished");
+    result
+}
+
+async fn build_receipt(
+    config: &PasswordGeneratorConfig,
+    matches: &clap::ArgMatches,
+) -> Result<receipt::Receipt> {
+    let mode = if matches.contains_id("keepass") {
+        "keepass"
+    } else if matches.contains_id("anki") {
+        "anki"
+    } else if matches.contains_id("patterns-file") {
+        "patterns-file"
+    } else if matches.contains_id("mode") {
+        "plugin"
+    } else if matches.contains_id("grammar") {
+        "grammar"
+    } else if matches.get_flag("mutate") {
+        "mutation"
+    } else if config.mode == PasswordGeneratorMode::Diceware {
+        "diceware"
+    } else if config.pronounceable {
+        "pronounceable"
+    } else if config.pattern.is_some() {
+        "pattern"
+    } else {
+        "password"
+    };
+    let policy = receipt::Policy {
+        site: matches.get_one::<String>("site").cloned(),
+        rules: matches.get_one::<String>("rules").cloned(),
+        forced: matches.get_flag("force"),
+        ..receipt::Policy::from_config(config)
+    };
+    let mut receipt = receipt::Receipt::new(config, mode, policy);
+    match mode {
+        "diceware" => {
+            let words = diceware::get_wordlist().await?.len();
+            receipt.wordlist_size = Some(words);
+            receipt.entropy_bits = Some(config.passphrase_entropy_bits(words));
+        }
+        "pronounceable" => receipt.entropy_bits = Some(config.entropy_bits()),
+        "grammar" => {
+            let template = matches.get_one::<grammar::Template>("grammar").unwrap();
+            receipt.entropy_bits = Some(grammar_lexicon(matches)?.entropy_bits(template));
+        }
+        "password" => {
+            receipt.charsets = matches
+                .get_one::<String>("allowed")
+                .unwrap()
+                .split(',')
+                .map(|entry| config::split_charset_weight(entry.trim()).0.to_string())
+                .collect();
+            receipt.entropy_bits = Some(config.entropy_bits());
+        }
+        _ => {}
+    }
+    Ok(receipt)
+}
+
+fn init_tracing(verbosity: u8) {
+    let level = match verbosity {
+        0 => return,
+        1 => Level::INFO,
+        2 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .init();
+}
+
+const MAX_LENGTH: usize = 255;
+
+fn build_config(matches: &clap::ArgMatches) -> Result<PasswordGeneratorConfig> {
+    let mut config = PasswordGeneratorConfig::new();
+    let length = *matches.get_one::<config::LengthRange>("length").unwrap();
+    if length.max > MAX_LENGTH {
+        return Err(PasswordGeneratorError::InvalidConfig(format!(
+            "Password length must be at most {}",
+            MAX_LENGTH
+        )));
+    }
+    config.length = length.min;
+    config.length_range = (length.min != length.max).then_some(length);
+    config.num_passwords = *matches.get_one::<u32>("count").unwrap() as usize;
+    config.set_avoid_repeating(matches.get_flag("avoid-repeating"));
+    config.exclude_confusables = matches.get_flag("exclude-confusables");
+    config.shell_safe = matches.get_flag("shell-safe");
+    config.escape_profile = matches.get_one::<config::EscapeProfile>("escape-profile").copied();
+    config.normalization = matches
+        .get_one::<unicode::Normalization>("normalize")
+        .copied()
+        .unwrap_or_default();
+    config.seed = matches.get_one::<config::Seed>("seed").copied();
+    config.clear_allowed_chars();
+
+    let allowed = matches.get_one::<String>("allowed").unwrap();
+    let mut weights = Vec::new();
+    for entry in allowed.split(',').map(str::trim) {
+        let (charset, weight) = match config::split_charset_weight(entry) {
+            (charset, Some(weight)) => match weight.parse::<u32>() {
+                Ok(weight) if weight > 0 => (charset, Some(weight)),
+                _ => {
+                    return Err(PasswordGeneratorError::InvalidConfig(format!(
+                        "Invalid weight in '{}': use a positive whole number, e.g. lowerletter:4",
+                        entry
+                    )))
+                }
+            },
+            (charset, None) => (charset, None),
+        };
+        weights.push((charset.to_string(), weight));
+        config::charset_chars(charset)?;
+        config.add_allowed_chars(charset);
+    }
+    if weights.iter().any(|(_, weight)| weight.is_some()) {
+        config.charset_weights = weights
+            .into_iter()
+            .map(|(charset, weight)| (charset, weight.unwrap_or(1)))
+            .collect();
+    }
+    config.mode = if matches.get_flag("use-words") {
+        PasswordGeneratorMode::Diceware
+    } else {
+        PasswordGeneratorMode::Password
+    };
+
+    if matches.contains_id("grammar") {
+        config.separator = match matches.get_one::<String>("separator") {
+            Some(separator) => Some(parse_separator(separator)?),
+            None => Some(Separator::Fixed("-".to_string())),
+        };
+    }
+
+    config.pronounceable = matches.get_flag("pronounceable");
+    config.language = matches.get_one::<language::Language>("language").copied();
+
+    if config.mode == PasswordGeneratorMode::Diceware {
+        config.separator = if let Some(separator) = matches.get_one::<String>("separator") {
+            Some(parse_separator(separator)?)
+        } else if config.shell_safe || config.escape_profile.is_some() {
+            Some(Separator::Fixed("-".to_string()))
+        } else {
+            Some(Separator::Fixed(" ".to_string()))
+        };
+        config.append_digits = matches
+            .get_one::<usize>("append-digits")
+            .copied()
+            .unwrap_or(0);
+        config.word_range = config.length_range.take();
+        config.join_style = matches.get_one::<config::JoinStyle>("join-style").copied();
+        if let Some(range) = matches.get_one::<config::LengthRange>("words").copied() {
+            config.length = range.min;
+            config.word_range = (range.min != range.max).then_some(range);
+        }
+    }
+
+    config.pattern = matches.get_one::<String>("pattern").cloned();
+    config.min_entropy_bits = match matches.get_one::<f64>("min-bits") {
+        Some(bits) => Some(*bits),
+        None => settings::Settings::load()?.min_bits,
+    };
+    config.min_strength = matches.get_one::<f64>("min-strength").copied();
+
+    let requested_length = config.length;
+    let extra_rules = matches
+        .get_one::<String>("rules")
+        .map(|rules| rules::parse_password_rules(rules))
+        .transpose()?;
+    match matches.get_one::<String>("site") {
+        Some(site) => sites::find_site(site)?.apply(&mut config, extra_rules.as_ref())?,
+        None => {
+            if let Some(rules) = &extra_rules {
+                rules.apply(&mut config);
+            }
+        }
+    }
+
+    if let Some(max) = matches.get_one::<usize>("max-repeat").copied() {
+        config.max_consecutive = Some(config.max_consecutive.map_or(max, |rule| rule.min(max)));
+    }
+
+    debug!(
+        length = config.length,
+        count = config.num_passwords,
+        mode = ?config.mode,
+        charsets = %allowed,
+        charset_size = config.allowed_chars.len(),
+        avoid_repetition = config.avoid_repetition,
+        pronounceable = config.pronounceable,
+        language = ?config.language,
+        pattern = ?config.pattern,
+        seeded = config.seed.is_some(),
+        "effective configuration"
+    );
+
+    config.reject_keyboard_runs = matches.get_flag("reject-keyboard-runs");
+    config.avoid_sequential = matches.get_flag("avoid-sequential");
+    config.start_with = matches.get_one::<config::CharClass>("start-with").copied();
+    config.end_with = matches.get_one::<config::CharClass>("end-with").copied();
+    let escape_sequences = config
+        .escape_profile
+        .map_or(&[][..], |profile| profile.forbidden_sequences())
+        .iter()
+        .map(|sequence| sequence.to_string());
+    let forbidden = matches.get_many::<String>("forbid").into_iter().flatten().cloned();
+    config.set_forbidden(forbidden.chain(escape_sequences));
+    config.validate()?;
+    if matches.get_flag("fips") {
+        config.fips = true;
+        fips::validate(&config)?;
+    }
+    if let Some(index) = matches.get_one::<usize>("copy-index").copied() {
+        if !matches.contains_id("patterns-file") && (index == 0 || index > config.num_passwords) {
+            return Err(PasswordGeneratorError::InvalidConfig(format!(
+                "--copy-index {} is out of range (1-{})",
+                index, config.num_passwords
+            )));
+        }
+    }
+    if let Some(template) = matches.get_one::<grammar::Template>("grammar") {
+        let lexicon = grammar_lexicon(matches)?;
+        lexicon.check(template)?;
+        let bits = lexicon.entropy_bits(template);
+        match config.min_entropy_bits {
+            Some(min) if bits < min && !matches.get_flag("force") => {
+                return Err(PasswordGeneratorError::PolicyViolation(format!(
+                    "Estimated entropy of {:.1} bits (template {}) is below the minimum of {} bits; \
+                     add word slots to the template or pass --force",
+                    bits, template, min
+                )));
+            }
+            _ => {}
+        }
+    } else if config.mode == PasswordGeneratorMode::Password
+        && !matches.get_flag("mutate")
+        && !check_length_cap(&config, requested_length, matches)?
+    {
+        enforce_entropy_floor(&config, config.entropy_bits(), matches)?;
+    }
+    Ok(config)
+}
+
+/// A maxlength from `--site` or `--rules` can hold a password below the
+/// entropy asked for with `--min-bits` or an explicit `--length`. That is
+/// refused unless `--acknowledge-weak` is given, and then warned about.
+/// Returns true when the shortfall was acknowledged.
+fn check_length_cap(
+    config: &PasswordGeneratorConfig,
+    requested_length: usize,
+    matches: &clap::ArgMatches,
+) -> Result<bool> {
+    let Some(max) = config.max_length else {
+        return Ok(false);
+    };
+    let bits_at = |length: usize| {
+        let mut sized = config.clone();
+        sized.length = length;
+        sized.entropy_bits()
+    };
+    let length_given = matches.value_source("length") != Some(clap::parser::ValueSource::DefaultValue);
+    let Some(requested) = config
+        .min_entropy_bits
+        .or_else(|| length_given.then(|| bits_at(requested_length)))
+    else {
+        return Ok(false);
+    };
+    let achievable = bits_at(max);
+    if achievable >= requested {
+        return Ok(false);
+    }
+    if !matches.get_flag("acknowledge-weak") {
+        return Err(PasswordGeneratorError::PolicyViolation(format!(
+            "The policy allows at most {} characters, good for {:.1} bits of entropy, \
+             below the requested {:.1} bits; pass --acknowledge-weak to generate anyway",
+            max, achievable, requested
+        )));
+    }
+    eprintln!(
+        "{}",
+        trf(
+            "Warning: the policy allows at most {} characters, good for {} bits of entropy instead of the requested {} bits.",
+            &[&max, &format!("{:.1}", achievable), &format!("{:.1}", requested)]
+        )
+        .paint(Role::Warning)
+        .bold()
+    );
+    Ok(true)
+}
+
+/// Reads a plain word-per-line dictionary, or lines in diceware
+/// `index<TAB>word` form, falling back to the diceware wordlist.
+async fn load_dictionary(path: Option<&std::path::PathBuf>) -> Result<Vec<String>> {
+    match path {
+        Some(path) => Ok(std::fs::read_to_string(path)?
+            .lines()
+            .filter_map(|line| line.split_whitespace().last())
+            .map(str::to_string)
+            .collect()),
+        None => diceware::get_wordlist().await,
+    }
+}
+
+fn enforce_entropy_floor(
+    config: &PasswordGeneratorConfig,
+    bits: f64,
+    matches: &clap::ArgMatches,
+) -> Result<()> {
+    if matches.get_flag("force") {
+        return Ok(());
+    }
+    config.check_entropy_floor(bits)
+}
+
+fn load_profile(name: &str) -> Result<profile::Profile> {
+    let profile = profile::Profile::load(name)?;
+    profile.validate_options(&cli())?;
+    Ok(profile)
+}
+
+fn handle_profile(matches: &clap::ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("list", _)) => {
+            let names = profile::list()?;
+            if names.is_empty() {
+                println!("{}", tr("No profiles saved."));
+            }
+            for name in names {
+                println!("{}", name);
+            }
+        }
+        Some(("export", export_matches)) => {
+            let name = export_matches.get_one::<String>("name").unwrap();
+            print!("{}", load_profile(name)?.to_toml()?);
+        }
+        Some(("import", import_matches)) => {
+            let path = import_matches.get_one::<std::path::PathBuf>("file").unwrap();
+            let mut profile = profile::Profile::parse(&std::fs::read_to_string(path)?)?;
+            if let Some(name) = import_matches.get_one::<String>("name") {
+                profile::validate_name(name)?;
+                profile.name = name.clone();
+            }
+            profile.validate_options(&cli())?;
+            let saved = profile.save(import_matches.get_flag("force"))?;
+            println!(
+                "{}",
+                trf("Imported profile '{}' to {}", &[&profile.name, &saved.display()])
+                    .paint(Role::Success)
+                    .bold()
+            );
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Answers each request line on stdin with one response line until stdin
+/// closes; a bad request gets an `error` response and the next is read.
+async fn handle_jsonl() -> Result<()> {
+    use std::io::{BufRead, Write};
+
+    let mut stdout = std::io::stdout().lock();
+    for line in std::io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut text = jsonl_answer(line).await;
+        writeln!(stdout, "{}", text)?;
+        stdout.flush()?;
+        text.zeroize();
+    }
+    Ok(())
+}
+
+/// The response line for one request line, as used by `--jsonl` and the
+/// daemon.
+async fn jsonl_answer(mut line: String) -> String {
+    use mcp::ZeroizeStrings;
+
+    let (id, options) = jsonl::parse_request(&line);
+    line.zeroize();
+    let result = match options {
+        Ok(options) => jsonl_generate(&options).await,
+        Err(e) => Err(e),
+    };
+    let mut response = jsonl::response(id, result);
+    let text = response.to_string();
+    response.zeroize_strings();
+    text
+}
+
+async fn handle_native_messaging(matches: &clap::ArgMatches) -> Result<()> {
+    if let Some(&browser) = matches.get_one::<native::Browser>("install") {
+        let home = dirs::home_dir().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "Home directory not found")
+        })?;
+        let extension_id = matches.get_one::<String>("extension-id").unwrap();
+        let path = native::install(browser, extension_id, &std::env::current_exe()?, &home)?;
+        println!(
+            "{}",
+            trf("Installed the native messaging host for {} in {}", &[&browser, &path.display()])
+                .paint(Role::Success)
+                .bold()
+        );
+        return Ok(());
+    }
+
+    let caller: Vec<&String> = matches.get_many::<String>("caller").into_iter().flatten().collect();
+    debug!(?caller, "native messaging host started");
+    let mut stdin = std::io::stdin().lock();
+    let mut stdout = std::io::stdout().lock();
+    while let Some(message) = native::read_message(&mut stdin)? {
+        let mut text = match String::from_utf8(message) {
+            Ok(request) => jsonl_answer(request).await,
+            Err(e) => {
+                e.into_bytes().zeroize();
+                let error = PasswordGeneratorError::InvalidConfig("Native message is not UTF-8".to_string());
+                jsonl::response(None, Err(error)).to_string()
+            }
+        };
+        let written = native::write_message(&mut stdout, &text);
+        text.zeroize();
+        if let Err(error @ PasswordGeneratorError::InvalidConfig(_)) = written {
+            native::write_message(&mut stdout, &jsonl::response(None, Err(error)).to_string())?;
+        } else {
+            written?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn handle_daemon(matches: &clap::ArgMatches) -> Result<()> {
+    let path = match matches.get_one::<std::path::PathBuf>("socket") {
+        Some(path) => path.clone(),
+        None => daemon::default_socket()?,
+    };
+    let listener = daemon::bind(&path).await?;
+    eprintln!(
+        "{}",
+        trf("Listening on {} (Ctrl-C to stop)", &[&path.display()]).paint(Role::Success)
+    );
+    daemon::serve(listener, &path, jsonl_answer).await
+}
+
+#[cfg(not(unix))]
+async fn handle_daemon(_matches: &clap::ArgMatches) -> Result<()> {
+    Err(PasswordGeneratorError::InvalidConfig(
+        "npwg daemon needs Unix domain sockets, use --jsonl instead".to_string(),
+    ))
+}
+
+/// Generates for one `--jsonl` request by parsing its options exactly as if
+/// they had been given on the command line.
+async fn jsonl_generate(options: &profile::Profile) -> Result<serde_json::Value> {
+    let matches = cli()
+        .try_get_matches_from(std::iter::once("npwg".to_string()).chain(options.to_args()))
+        .map_err(|e| {
+            let message = e.to_string();
+            let first = message.lines().next().unwrap_or_default();
+            PasswordGeneratorError::InvalidConfig(first.trim_start_matches("error: ").to_string())
+        })?;
+    let mut config = build_config(&matches)?;
+    config.load_pattern_words().await?;
+    let (passwords, entropy_bits) = generate_candidates(&config, &matches).await?;
+    let response = serde_json::json!({ "passwords": passwords, "entropy_bits": entropy_bits });
+    passwords.into_iter().for_each(|mut p| p.zeroize());
+    Ok(response)
+}
+
+/// Grammar passphrases, diceware passphrases or passwords, whichever
+/// `matches` asks for, with their estimated entropy in bits.
+async fn generate_candidates(
+    config: &PasswordGeneratorConfig,
+    matches: &clap::ArgMatches,
+) -> Result<(Vec<String>, f64)> {
+    if let Some(template) = matches.get_one::<grammar::Template>("grammar") {
+        let lexicon = grammar_lexicon(matches)?;
+        Ok((
+            generator::generate_grammar_passphrases(template, &lexicon, config)?,
+            lexicon.entropy_bits(template),
+        ))
+    } else if config.mode == PasswordGeneratorMode::Diceware {
+        let wordlist = diceware::get_wordlist().await?;
+        let bits = config.passphrase_entropy_bits(wordlist.len());
+        enforce_entropy_floor(config, bits, matches)?;
+        Ok((generator::generate_allowed_passphrases(&wordlist, config).await?, bits))
+    } else {
+        Ok((generate_strong_passwords(config).await?, config.entropy_bits()))
+    }
+}
+
+/// Generates one secret from the top-level options and has the user type it
+/// from memory until it was right `--rounds` times.
+async fn handle_memorize(
+    config: &PasswordGeneratorConfig,
+    matches: &clap::ArgMatches,
+    memorize_matches: &clap::ArgMatches,
+) -> Result<()> {
+    let term = Term::stdout();
+    if !term.is_term() {
+        return Err(PasswordGeneratorError::InvalidConfig(
+            "npwg memorize needs an interactive terminal".to_string(),
+        ));
+    }
+    let mut config = config.clone();
+    config.num_passwords = 1;
+    let (mut secrets, _) = generate_candidates(&config, matches).await?;
+    let rounds = *memorize_matches.get_one::<u64>("rounds").unwrap() as usize;
+    let mut drill = memorize::Drill::new(secrets.remove(0), rounds);
+    let theme = ColorfulTheme::default();
+
+    show_secret(&term, drill.secret())?;
+    while !drill.is_done() {
+        let started = Instant::now();
+        let mut typed = Password::with_theme(&theme)
+            .with_prompt(trf(
+                "Type it from memory ({}/{})",
+                &[&(drill.correct() + 1), &drill.rounds()],
+            ))
+            .allow_empty_password(true)
+            .interact_on(&term)?;
+        let outcome = drill.attempt(&typed, started.elapsed());
+        typed.zeroize();
+        match outcome {
+            memorize::Outcome::Correct { took, previous } => {
+                let seconds = format!("{:.1}", took.as_secs_f64());
+                let noticeable = Duration::from_millis(50);
+                let message = match previous {
+                    Some(previous) if took + noticeable < previous => trf(
+                        "Correct in {}s, {}s faster than last time.",
+                        &[&seconds, &format!("{:.1}", (previous - took).as_secs_f64())],
+                    ),
+                    Some(previous) if took > previous + noticeable => trf(
+                        "Correct in {}s, {}s slower than last time.",
+                        &[&seconds, &format!("{:.1}", (took - previous).as_secs_f64())],
+                    ),
+                    _ => trf("Correct in {}s.", &[&seconds]),
+                };
+                println!("{}", message.paint(Role::Success));
+            }
+            memorize::Outcome::Wrong { position } => {
+                println!(
+                    "{}",
+                    trf("Not quite, the first mistake is at character {}.", &[&position]).paint(Role::Error)
+                );
+                show_secret(&term, drill.secret())?;
+            }
+        }
+    }
+
+    let fastest = drill.fastest().unwrap_or_default();
+    println!(
+        "\n{}",
+        trf(
+            "Typed correctly {} times with {} mistakes; fastest {}s.",
+            &[&drill.correct(), &drill.mistakes(), &format!("{:.1}", fastest.as_secs_f64())]
+        )
+        .paint(Role::Success)
+        .bold()
+    );
+    Ok(())
+}
+
+/// Shows the secret until Enter is pressed, then removes it from the screen.
+fn show_secret(term: &Term, secret: &str) -> Result<()> {
+    term.write_line(&format!("{} {}", tr("Memorize:").paint(Role::Heading), secret.paint(Role::Value)))?;
+    term.write_line(&tr("Press Enter to hide it and start typing.").dimmed().to_string())?;
+    while term.read_key()? != Key::Enter {}
+    term.clear_last_lines(2)?;
+    Ok(())
+}
+
+fn print_site_presets() {
+    for preset in sites::SITE_PRESETS {
+        println!("{} {}", preset.name.paint(Role::Heading), preset.description);
+        println!("  {}", preset.rules.paint(Role::Value));
+        if let Some(pattern) = preset.pattern {
+            println!("  {} {}", tr("pattern:"), pattern.paint(Role::Value));
+        }
+    }
+}
+
+fn print_plugins() -> Result<()> {
+    let dir = plugin::plugins_dir()?;
+    let plugins = plugin::discover(&dir)?;
+    if plugins.is_empty() {
+        println!("{}", trf("No plugins installed in {}", &[&dir.display()]));
+    }
+    for plugin in plugins {
+        println!("{} {}", plugin.name.paint(Role::Heading), plugin.path.display());
+    }
+    Ok(())
+}
+
+fn handle_plugin(
+    config: &PasswordGeneratorConfig,
+    matches: &clap::ArgMatches,
+    copy: bool,
+    name: &str,
+) -> Result<()> {
+    let plugin = plugin::find(name)?;
+    let options = matches
+        .get_many::<(String, String)>("plugin-option")
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+    let request = plugin::Request::new(config.num_passwords, config.length, config.seed, options);
+    let passwords = plugin::generate(&plugin, &request)?;
+    emit_passwords(&passwords, matches)?;
+
+    if copy {
+        copy_generated(&passwords, matches, "Password")?;
+    }
+
+    store_if_requested(&passwords, matches)?;
+
+    if matches.get_flag("strength") {
+        print_strength_meter(&passwords, evaluate_generated_strength, strength_threshold(matches)?)?;
+    }
+
+    if matches.get_flag("stats") {
+        print_stats(&passwords);
+        write_stats_report(&passwords, matches)?;
+    }
+
+    passwords.into_iter().for_each(|mut p| p.zeroize());
+    Ok(())
+}
+
+/// The built-in grammar word lists, with the roles given by `--pos-wordlist`
+/// and `--role-words` replaced.
+fn grammar_lexicon(matches: &clap::ArgMatches) -> Result<grammar::Lexicon> {
+    let mut custom = grammar::CustomLists::default();
+    if let Some(path) = matches.get_one::<std::path::PathBuf>("pos-wordlist") {
+        custom.add_tagged(&std::fs::read_to_string(path)?)?;
+    }
+    for (role, path) in matches
+        .get_many::<(grammar::Role, std::path::PathBuf)>("role-words")
+        .into_iter()
+        .flatten()
+    {
+        custom.add_list(*role, &std::fs::read_to_string(path)?)?;
+    }
+    Ok(grammar::Lexicon::with_custom(&custom))
+}
+
+fn handle_grammar(
+    config: &PasswordGeneratorConfig,
+    matches: &clap::ArgMatches,
+    copy: bool,
+    template: &grammar::Template,
+) -> Result<()> {
+    let lexicon = grammar_lexicon(matches)?;
+    let passphrases = generator::generate_grammar_passphrases(template, &lexicon, config)?;
+    emit_passwords(&passphrases, matches)?;
+
+    if matches.get_flag("stats") {
+        print_grammar_entropy(&lexicon, template);
+    }
+
+    if copy {
+        copy_generated(&passphrases, matches, "Passphrase")?;
+    }
+
+    store_if_requested(&passphrases, matches)?;
+
+    if matches.get_flag("strength") {
+        print_strength_meter(&passphrases, evaluate_generated_strength, strength_threshold(matches)?)?;
+    }
+
+    if matches.get_flag("stats") {
+        print_stats(&passphrases);
+        write_stats_report(&passphrases, matches)?;
+    }
+
+    passphrases.into_iter().for_each(|mut p| p.zeroize());
+    Ok(())
+}
+
+/// The entropy of a grammar template, slot by slot.
+fn print_grammar_entropy(lexicon: &grammar::Lexicon, template: &grammar::Template) {
+    println!("\n{}", tr("Entropy:").paint(Role::Heading));
+    for (role, bits) in lexicon.slot_bits(template) {
+        println!(
+            "{}",
+            trf(
+                "{}: {} words ({} bits)",
+                &[
+                    &role,
+                    &lexicon.words(role).len().to_string().paint(Role::Value),
+                    &format!("{:.2}", bits).paint(Role::Value)
+                ]
+            )
+        );
+    }
+    let bits = format!("{:.2}", lexicon.entropy_bits(template)).paint(Role::Value);
+    println!("{}", trf("Entropy: {} bits", &[&bits]));
+}
+
+fn handle_random_bytes(matches: &clap::ArgMatches) -> Result<()> {
+    let bytes = *matches.get_one::<usize>("bytes").unwrap();
+    if bytes == 0 {
+        return Err(PasswordGeneratorError::InvalidConfig(
+            "Number of bytes must be greater than 0".to_string(),
+        ));
+    }
+    let encoding = *matches.get_one::<salt::Encoding>("encoding").unwrap();
+    for _ in 0..*matches.get_one::<usize>("count").unwrap() {
+        let mut value = salt::random_bytes(bytes)?;
+        println!("{}", salt::encode(&value, encoding));
+        value.zeroize();
+    }
+    Ok(())
+}
+
+async fn handle_wifi(matches: &clap::ArgMatches, seed: Option<config::Seed>) -> Result<()> {
+    let ssid = matches.get_one::<String>("ssid").unwrap();
+    let length = *matches.get_one::<usize>("length").unwrap();
+    wifi::validate(ssid, length)?;
+
+    let mut config = PasswordGeneratorConfig::new();
+    config.clear_allowed_chars();
+    for charset in ["upperletter", "lowerletter", "digit"] {
+        config.add_allowed_chars(charset);
+    }
+    config.length = length;
+    config.seed = seed;
+    config.validate()?;
+    let mut passphrase = generate_passwords(&config).await?.remove(0);
+    let mut payload = wifi::payload(ssid, &passphrase, matches.get_flag("hidden"));
+    let qr = qr::QrCode::encode(payload.as_bytes())?;
+
+    println!("{} {}", tr("SSID:"), ssid.paint(Role::Value));
+    println!("{} {}", tr("Passphrase:"), passphrase.paint(Role::Value));
+    println!("{} {}", tr("QR payload:"), payload);
+    if !output::is_accessible() {
+        print!("\n{}", qr.to_terminal());
+    }
+    if let Some(path) = matches.get_one::<std::path::PathBuf>("output") {
+        encrypt::write_private(path, qr.to_svg().as_bytes())?;
+        println!(
+            "{}",
+            trf("QR code written to {}", &[&path.display()]).paint(Role::Success)
+        );
+    }
+    passphrase.zeroize();
+    payload.zeroize();
+    Ok(())
+}
+
+/// The largest key file cryptsetup accepts.
+const MAX_KEYFILE_BYTES: usize = 8 * 1024 * 1024;
+
+fn handle_keyfile(matches: &clap::ArgMatches) -> Result<()> {
+    let bytes = *matches.get_one::<usize>("bytes").unwrap();
+    if bytes == 0 || bytes > MAX_KEYFILE_BYTES {
+        return Err(PasswordGeneratorError::InvalidConfig(format!(
+            "Keyfile size must be between 1 and {} bytes",
+            MAX_KEYFILE_BYTES
+        )));
+    }
+    let path = matches.get_one::<std::path::PathBuf>("output").unwrap();
+    let encoding = matches.get_one::<salt::Encoding>("sidecar").copied();
+    let sidecar = encoding.map(|encoding| {
+        let mut sidecar = path.clone().into_os_string();
+        sidecar.push(if encoding == salt::Encoding::Hex { ".hex" } else { ".b64" });
+        std::path::PathBuf::from(sidecar)
+    });
+    if !matches.get_flag("force") {
+        if let Some(existing) = std::iter::once(path).chain(&sidecar).find(|p| p.exists()) {
+            return Err(PasswordGeneratorError::InvalidConfig(format!(
+                "{} already exists, use --force to replace it",
+                existing.display()
+            )));
+        }
+    }
+
+    let mut key = salt::random_bytes(bytes)?;
+    let written = encrypt::write_private(path, &key).and_then(|_| match (&sidecar, encoding) {
+        (Some(sidecar), Some(encoding)) => {
+            let mut text = salt::encode(&key, encoding) + "\n";
+            let written = encrypt::write_private(sidecar, text.as_bytes());
+            text.zeroize();
+            written
+        }
+        _ => Ok(()),
+    });
+    key.zeroize();
+    written?;
+
+    println!(
+        "{}",
+        trf("Wrote a {}-byte keyfile to {}", &[&bytes, &path.display()]).paint(Role::Success)
+    );
+    if let Some(sidecar) = &sidecar {
+        println!("{}", trf("Text copy written to {}", &[&sidecar.display()]).paint(Role::Success));
+    }
+    Ok(())
+}
+
+fn handle_recovery_codes(matches: &clap::ArgMatches) -> Result<()> {
+    let alphabet = recovery::print_safe_chars();
+    let format = matches.get_one::<String>("format").unwrap();
+    recovery::validate_code_format(format, &alphabet)?;
+    let mut rng = rand::rng();
+    let mut codes: Vec<String> = (0..*matches.get_one::<usize>("count").unwrap())
+        .map(|_| recovery::recovery_code(format, &alphabet, &mut rng))
+        .collect();
+    let hashes: Option<Vec<String>> = matches.contains_id("hashes").then(|| {
+        codes
+            .iter()
+            .map(|code| recovery::code_hash(code, &alphabet))
+            .collect()
+    });
+
+    match (&hashes, matches.get_one::<std::path::PathBuf>("hashes")) {
+        (Some(hashes), None) => {
+            for (code, hash) in codes.iter().zip(hashes) {
+                println!("{}  {}", code, hash);
+            }
+        }
+        (Some(hashes), Some(path)) => {
+            codes.iter().for_each(|code| println!("{}", code));
+            std::fs::write(path, hashes.join("\n") + "\n")?;
+            eprintln!(
+                "{}",
+                trf("Wrote {} code hashes to {}", &[&hashes.len(), &path.display()]).paint(Role::Success)
+            );
+        }
+        (None, _) => codes.iter().for_each(|code| println!("{}", code)),
+    }
+    codes.iter_mut().for_each(|code| code.zeroize());
+    Ok(())
+}
+
+fn handle_honeytoken(matches: &clap::ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("generate", generate_matches)) => {
+            let key = generate_matches.get_one::<String>("key").unwrap().as_bytes();
+            let prefix = generate_matches.get_one::<String>("prefix").unwrap();
+            let length = *generate_matches.get_one::<usize>("length").unwrap();
+            honeytoken::validate(prefix, length)?;
+            let mut rng = rand::rng();
+            let tokens: Vec<String> = (0..*generate_matches.get_one::<usize>("count").unwrap())
+                .map(|_| honeytoken::generate(key, prefix, length, &mut rng))
+                .collect();
+            for token in &tokens {
+                println!("{}", token);
+            }
+            if let Some(path) = generate_matches.get_one::<std::path::PathBuf>("manifest") {
+                let manifest = honeytoken::Manifest::new(
+                    key,
+                    receipt::format_timestamp(std::time::SystemTime::now()),
+                    &tokens,
+                    generate_matches.get_one::<String>("label").map(String::as_str),
+                );
+                let json = serde_json::to_string_pretty(&manifest).map_err(std::io::Error::other)?;
+                // The manifest names every token, so only the owner may read it.
+                encrypt::write_private(path, (json + "\n").as_bytes())?;
+                eprintln!(
+                    "{}",
+                    trf("Wrote a manifest of {} honeytoken(s) to {}", &[&tokens.len(), &path.display()])
+                        .paint(Role::Success)
+                );
+            }
+        }
+        Some(("scan", scan_matches)) => {
+            let key = scan_matches.get_one::<String>("key").unwrap().as_bytes();
+            let text = match scan_matches.get_one::<std::path::PathBuf>("file") {
+                Some(path) => std::fs::read_to_string(path)?,
+                None => std::io::read_to_string(std::io::stdin())?,
+            };
+            let hits = honeytoken::scan(key, &text);
+            for (line, token) in &hits {
+                println!("{}: {}", line, token.paint(Role::Value));
+            }
+            eprintln!("{}", trf("{} honeytoken(s) found", &[&hits.len()]).bold());
+        }
+        _ => unreachable!("clap requires a honeytoken subcommand"),
+    }
+    Ok(())
+}
+
+fn handle_wordlist_build(matches: &clap::ArgMatches) -> Result<()> {
+    let corpus = std::fs::read_to_string(matches.get_one::<std::path::PathBuf>("from").unwrap())?;
+    let words = diceware::build_wordlist(
+        &corpus,
+        *matches.get_one::<usize>("size").unwrap(),
+        *matches.get_one::<usize>("min-len").unwrap(),
+        *matches.get_one::<usize>("max-len").unwrap(),
+    )?;
+    let wordlist = diceware::format_wordlist(&words);
+
+    match matches.get_one::<std::path::PathBuf>("output") {
+        Some(path) => {
+            std::fs::write(path, wordlist)?;
+            println!(
+                "{}",
+                trf("Wrote {} words to {}", &[&words.len(), &path.display()])
+                    .paint(Role::Success)
+                    .bold()
+            );
+        }
+        None => print!("{}", wordlist),
+    }
+    Ok(())
+}
+
+/// A minisign key given inline, or the path of a `.pub` file holding it.
+fn load_public_key(value: &str) -> Result<minisign::PublicKey> {
+    let path = std::path::Path::new(value);
+    if path.is_file() {
+        minisign::PublicKey::parse(&std::fs::read_to_string(path)?)
+    } else {
+        minisign::PublicKey::parse(value)
+    }
+}
+
+fn handle_wordlist_verify(
+    matches: &clap::ArgMatches,
+    key: Option<&minisign::PublicKey>,
+) -> Result<()> {
+    let key = key.ok_or_else(|| {
+        PasswordGeneratorError::InvalidConfig(
+            "'wordlist verify' needs the signer's public key via --wordlist-key or NPWG_WORDLIST_KEY".to_string(),
+        )
+    })?;
+    let path = match matches.get_one::<std::path::PathBuf>("file") {
+        Some(path) => path.clone(),
+        None => diceware::wordlist_path()?,
     };
+    let comment = diceware::verify_wordlist(
+        &path,
+        matches.get_one::<std::path::PathBuf>("signature").map(|p| p.as_path()),
+        key,
+    )?;
+    println!(
+        "{}",
+        trf("Signature by key {} is valid", &[&key.key_id()]).paint(Role::Success).bold()
+    );
+    println!("{} {}", tr("Trusted comment:").paint(Role::Heading), comment.paint(Role::Value));
+    Ok(())
+}
 
-    config.pronounceable = matches.get_flag("pronounceable");
+async fn handle_recovery_sheet(
+    matches: &clap::ArgMatches,
+    seed: Option<config::Seed>,
+) -> Result<()> {
+    let mut config = PasswordGeneratorConfig::new();
+    config.set_allowed_chars(recovery::PRINT_SAFE_CHARSET);
+    config.length = *matches.get_one::<usize>("length").unwrap();
+    config.num_passwords = *matches.get_one::<usize>("count").unwrap();
+    config.seed = seed;
+    config.validate()?;
 
-    if config.mode == PasswordGeneratorMode::Diceware {
-        config.separator = if let Some(separator) = matches.get_one::<String>("separator") {
-            match separator.as_str() {
-                "random" => Some(Separator::Random(('a'..='z').chain('0'..='9').collect())),
-                s if s.len() == 1 => Some(Separator::Fixed(s.chars().next().unwrap())),
-                _ => {
-                    eprintln!("Error: Separator must be a single character or 'random'");
-                    process::exit(1);
-                }
+    let mut passwords = generate_passwords(&config).await?;
+    let mut sheet = recovery::render_sheet(&passwords, *matches.get_one::<usize>("group").unwrap())?;
+    passwords.iter_mut().for_each(|p| p.zeroize());
+
+    let result = match matches.get_one::<std::path::PathBuf>("output") {
+        Some(path) => {
+            let is_pdf = path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+            let mut bytes = if is_pdf {
+                recovery::render_pdf(&sheet)
+            } else {
+                sheet.as_bytes().to_vec()
+            };
+            let written = encrypt::write_output(path, &bytes, None);
+            bytes.zeroize();
+            written.map(|_| {
+                println!(
+                    "{} {}",
+                    tr("Recovery sheet written to").paint(Role::Heading),
+                    path.display().to_string().paint(Role::Value)
+                )
+            })
+        }
+        None => {
+            print!("{}", sheet);
+            Ok(())
+        }
+    };
+    sheet.zeroize();
+    result
+}
+
+async fn handle_audit(matches: &clap::ArgMatches) -> Result<()> {
+    let path = matches.get_one::<std::path::PathBuf>("file").unwrap();
+    let csv = matches.get_flag("csv")
+        || path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    let min_score = match matches.get_one::<f64>("min-score") {
+        Some(score) => *score,
+        None => strength_threshold(matches)?.unwrap_or(WEAK_SCORE),
+    };
+
+    let mut contents = std::fs::read_to_string(path)?;
+    let entries = audit::parse_audit_input(&contents, csv);
+    contents.zeroize();
+
+    let mut report = audit::audit(&entries, min_score);
+    if let Some(policy) = check_policy(matches)? {
+        report.check_policy(&entries, &policy);
+    }
+    let hibp = matches.get_flag("hibp") && !net::is_offline();
+    if matches.get_flag("hibp") && !hibp {
+        eprintln!(
+            "{}",
+            tr("Warning: --offline is set, skipping the breach check.").paint(Role::Warning)
+        );
+    }
+    if hibp {
+        let mut progress = progress::Progress::new(
+            "Checking breaches",
+            Some(entries.len() as u64),
+            progress::Unit::Items,
+        );
+        for (entry, entry_report) in entries.iter().zip(report.entries.iter_mut()) {
+            let count = audit::pwned_count(&entry.password).await?;
+            progress.inc(1);
+            if count > 0 {
+                entry_report.findings.push(audit::Finding::Breached(count));
             }
-        } else {
-            Some(Separator::Fixed(' '))
+        }
+    }
+
+    println!("{}", tr("Audit findings:").paint(Role::Heading));
+    for entry in report.entries.iter().filter(|e| !e.findings.is_empty()) {
+        let label = match &entry.username {
+            Some(username) => trf("Line {} ({})", &[&entry.line, username]),
+            None => trf("Line {}", &[&entry.line]),
         };
+        println!(
+            "{}: {:.2} {}",
+            label,
+            entry.score,
+            tr(&entry.feedback).paint(Role::Value)
+        );
+        for finding in &entry.findings {
+            println!("  - {}", finding.to_string().paint(Role::Error));
+        }
     }
 
-    config.pattern = matches.get_one::<String>("pattern").cloned();
+    println!("\n{}", tr("Summary:").paint(Role::Heading));
+    println!("{} {}", tr("Entries:"), report.entries.len().to_string().paint(Role::Value));
+    println!(
+        "{} {}",
+        tr("Weak:"),
+        report
+            .count(|f| matches!(f, audit::Finding::Weak))
+            .to_string()
+            .paint(Role::Value)
+    );
+    println!(
+        "{} {}",
+        tr("Duplicates:"),
+        report
+            .count(|f| matches!(f, audit::Finding::Duplicate(_)))
+            .to_string()
+            .paint(Role::Value)
+    );
+    println!(
+        "{} {}",
+        tr("Shared prefixes:"),
+        report
+            .count(|f| matches!(f, audit::Finding::SharedPrefix(_)))
+            .to_string()
+            .paint(Role::Value)
+    );
+    if hibp {
+        println!(
+            "{} {}",
+            tr("Breached:"),
+            report
+                .count(|f| matches!(f, audit::Finding::Breached(_)))
+                .to_string()
+                .paint(Role::Value)
+        );
+    }
+    if matches.contains_id("rules") || matches.contains_id("site") {
+        println!(
+            "{} {}",
+            tr("Policy violations:"),
+            report
+                .count(|f| matches!(f, audit::Finding::Policy(_)))
+                .to_string()
+                .paint(Role::Value)
+        );
+    }
 
-    config.validate()?;
-    Ok(config)
+    if matches.get_flag("fail-on-weak") {
+        return fail_on_weak(&report, min_score);
+    }
+    Ok(())
+}
+
+/// Lists the passwords `--fail-on-weak` rejects on stderr, by line and
+/// without the passwords, and fails with the `--strength-threshold` codes.
+fn fail_on_weak(report: &audit::AuditReport, threshold: f64) -> Result<()> {
+    let mut rejected = report.rejected().peekable();
+    if rejected.peek().is_none() {
+        return Ok(());
+    }
+    eprintln!("{}", tr("Rejected passwords:").paint(Role::Heading));
+    for entry in rejected {
+        let label = match &entry.username {
+            Some(username) => trf("Line {} ({})", &[&entry.line, username]),
+            None => trf("Line {}", &[&entry.line]),
+        };
+        let violations: Vec<String> = entry
+            .findings
+            .iter()
+            .filter(|f| matches!(f, audit::Finding::Policy(_)))
+            .map(ToString::to_string)
+            .collect();
+        eprintln!(
+            "  {}: {:.2} {}{}",
+            label,
+            entry.score,
+            tr(&entry.feedback).paint(Role::for_strength(&entry.feedback)),
+            violations.iter().map(|v| format!(", {}", v)).collect::<String>()
+        );
+    }
+    let scores: Vec<f64> = report.entries.iter().map(|e| e.score).collect();
+    check_thresholds(
+        Some(threshold),
+        &scores,
+        report.count(|f| matches!(f, audit::Finding::Policy(_))),
+    )
+}
+
+fn read_password_to_check(prompt: &str) -> Result<String> {
+    use std::io::IsTerminal;
+
+    if std::io::stdin().is_terminal() {
+        return Ok(Password::with_theme(&ColorfulTheme::default())
+            .with_prompt(tr(prompt))
+            .interact()?);
+    }
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    let password = line.trim_end_matches(['\r', '\n']).to_string();
+    line.zeroize();
+    Ok(password)
+}
+
+fn check_policy(matches: &clap::ArgMatches) -> Result<Option<rules::PasswordRules>> {
+    if let Some(name) = matches.get_one::<String>("site") {
+        return Ok(Some(rules::parse_password_rules(sites::find_site(name)?.rules)?));
+    }
+    matches
+        .get_one::<String>("rules")
+        .map(|rules| rules::parse_password_rules(rules))
+        .transpose()
+}
+
+fn handle_check_list(matches: &clap::ArgMatches, policy: Option<&rules::PasswordRules>) -> Result<()> {
+    let path = matches.get_one::<std::path::PathBuf>("input").unwrap();
+    let mut contents = std::fs::read_to_string(path)?;
+    let entries = audit::parse_audit_input(&contents, false);
+    contents.zeroize();
+
+    let scale = *matches.get_one::<strength::ScoreScale>("score-scale").unwrap();
+    let report = audit::check_report_csv(&entries, policy, scale);
+    let threshold = strength_threshold(matches)?;
+    let mut gate = audit::audit(&entries, threshold.unwrap_or(WEAK_SCORE));
+    if let Some(policy) = policy {
+        gate.check_policy(&entries, policy);
+    }
+    match matches.get_one::<std::path::PathBuf>("report") {
+        Some(report_path) => {
+            std::fs::write(report_path, report)?;
+            println!(
+                "{}",
+                trf("Wrote {} rows to {}", &[&entries.len(), &report_path.display()])
+                .paint(Role::Success)
+                .bold()
+            );
+        }
+        None => print!("{}", report),
+    }
+    finish_check(matches, &gate, threshold)
+}
+
+/// Score below which `check --fail-on-weak` and `audit --fail-on-weak`
+/// reject a password when no `--strength-threshold` is set; the bound of
+/// "Weak".
+const WEAK_SCORE: f64 = 0.3;
+
+/// How `check` exits: with `--fail-on-weak`, after listing the rejected
+/// passwords, or else by the policy and `--strength-threshold`.
+fn finish_check(matches: &clap::ArgMatches, gate: &audit::AuditReport, threshold: Option<f64>) -> Result<()> {
+    if matches.get_flag("fail-on-weak") {
+        return fail_on_weak(gate, threshold.unwrap_or(WEAK_SCORE));
+    }
+    let scores: Vec<f64> = gate.entries.iter().map(|e| e.score).collect();
+    check_thresholds(threshold, &scores, gate.count(|f| matches!(f, audit::Finding::Policy(_))))
+}
+
+fn print_policy_violations(violations: Option<Vec<&str>>) {
+    match violations {
+        Some(violations) if violations.is_empty() => {
+            println!("{} {}", tr("Policy:"), tr("compliant").paint(Role::Success));
+        }
+        Some(violations) => {
+            println!(
+                "{} {} ({})",
+                tr("Policy:"),
+                tr("not compliant").paint(Role::Error),
+                violations.join(", ")
+            );
+        }
+        None => {}
+    }
+}
+
+fn handle_check(matches: &clap::ArgMatches) -> Result<()> {
+    let policy = check_policy(matches)?;
+    if matches.contains_id("input") {
+        return handle_check_list(matches, policy.as_ref());
+    }
+
+    if matches.contains_id("compare") {
+        return handle_check_compare(matches, policy.as_ref());
+    }
+
+    let scale = *matches.get_one::<strength::ScoreScale>("score-scale").unwrap();
+    let settings = settings::Settings::load()?;
+    let threshold = strength_threshold(matches)?;
+    // The password moves into the entry and back, so no second copy is left
+    // behind for the gate.
+    let mut entries = [audit::AuditEntry {
+        line: 1,
+        username: None,
+        password: read_password_to_check("Password to check")?,
+    }];
+    let mut gate = audit::audit(&entries, threshold.unwrap_or(WEAK_SCORE));
+    if let Some(policy) = &policy {
+        gate.check_policy(&entries, policy);
+    }
+    let mut password = std::mem::take(&mut entries[0].password);
+    let score = gate.entries[0].score;
+    let external = match &settings.strength.external_command {
+        Some(command) if scale == strength::ScoreScale::Npwg => {
+            match scorer::external_score(command, &password) {
+                Ok(external) => Some(external),
+                Err(error) => {
+                    password.zeroize();
+                    return Err(error);
+                }
+            }
+        }
+        _ => None,
+    };
+    if matches.get_flag("json") {
+        let mut report = audit::check_report(&password, policy.as_ref(), scale);
+        password.zeroize();
+        if let Some(external) = external {
+            report["external"] = external;
+        }
+        println!("{}", report);
+        return finish_check(matches, &gate, threshold);
+    }
+    if scale == strength::ScoreScale::Pwquality {
+        let pwquality = strength::pwquality_score(&password);
+        let violations = policy.map(|policy| policy.violations(&password));
+        password.zeroize();
+        if let Ok(pwquality) = &pwquality {
+            println!("{}", pwquality);
+        }
+        print_policy_violations(violations);
+        pwquality.map_err(|failure| PasswordGeneratorError::QualityCheckFailed(failure.to_string()))?;
+        return finish_check(matches, &gate, threshold);
+    }
+
+    println!("{}", tr("Password Check:").paint(Role::Heading));
+    if output::is_accessible() {
+        println!(
+            "{}, {}",
+            output::strength_indicator(score),
+            tr(&get_strength_feedback(score))
+        );
+    } else {
+        println!(
+            "{} {:.2} {}",
+            output::strength_indicator(score),
+            score,
+            tr(&get_strength_feedback(score)).paint(Role::Value)
+        );
+    }
+    println!(
+        "{}",
+        trf(
+            "Entropy: {} bits",
+            &[&format!("{:.1}", strength::estimate_entropy_bits(&password)).paint(Role::Value)]
+        )
+    );
+    if let Some(external) = &external {
+        println!("{} {}", tr("External score:"), external["score"].to_string().paint(Role::Value));
+        if let Some(feedback) = external["feedback"].as_str() {
+            println!("  {}", feedback);
+        }
+    }
+    let suggestions = strength::get_improvement_suggestions(&password);
+    let violations = policy.map(|policy| policy.violations(&password));
+    password.zeroize();
+    print_policy_violations(violations);
+    let external_suggestions: Vec<&str> = external
+        .as_ref()
+        .and_then(|external| external["suggestions"].as_array())
+        .map(|list| list.iter().filter_map(serde_json::Value::as_str).collect())
+        .unwrap_or_default();
+    if !suggestions.is_empty() || !external_suggestions.is_empty() {
+        println!("\n{}", tr("Suggestions:").paint(Role::Heading));
+        for suggestion in suggestions {
+            println!("  - {}", tr(suggestion.message));
+        }
+        for suggestion in external_suggestions {
+            println!("  - {}", suggestion);
+        }
+    }
+    finish_check(matches, &gate, threshold)
+}
+
+fn handle_check_compare(
+    matches: &clap::ArgMatches,
+    policy: Option<&rules::PasswordRules>,
+) -> Result<()> {
+    let mut passwords: Vec<String> = matches
+        .get_many::<String>("compare")
+        .map(|values| values.cloned().collect())
+        .unwrap_or_default();
+    match passwords.len() {
+        0 => {
+            passwords.push(read_password_to_check("First password")?);
+            passwords.push(read_password_to_check("Second password")?);
+        }
+        2 => {}
+        _ => {
+            return Err(PasswordGeneratorError::InvalidConfig(
+                "--compare takes two passwords, or none to be prompted for both".to_string(),
+            ))
+        }
+    }
+    let breakdowns = [
+        strength::strength_breakdown(&passwords[0]),
+        strength::strength_breakdown(&passwords[1]),
+    ];
+    let violations: Option<Vec<Vec<&str>>> =
+        policy.map(|policy| passwords.iter().map(|p| policy.violations(p)).collect());
+    let (ordering, reasons) = strength::compare_strength(&breakdowns[0], &breakdowns[1]);
+    let stronger = match ordering {
+        std::cmp::Ordering::Greater => Some("A"),
+        std::cmp::Ordering::Less => Some("B"),
+        std::cmp::Ordering::Equal => None,
+    };
+
+    if matches.get_flag("json") {
+        let reports: Vec<serde_json::Value> = passwords
+            .iter()
+            .map(|password| audit::check_report(password, policy, strength::ScoreScale::Npwg))
+            .collect();
+        passwords.iter_mut().for_each(|p| p.zeroize());
+        println!(
+            "{}",
+            serde_json::json!({
+                "a": reports[0],
+                "b": reports[1],
+                "stronger": stronger.map(str::to_lowercase),
+                "reasons": reasons
+                    .iter()
+                    .map(|r| serde_json::json!({ "code": r.code, "message": r.message }))
+                    .collect::<Vec<_>>(),
+            })
+        );
+        return Ok(());
+    }
+    passwords.iter_mut().for_each(|p| p.zeroize());
+
+    let mut rows: Vec<(&str, [String; 2])> = vec![
+        (
+            "Score:",
+            breakdowns.each_ref().map(|b| {
+                format!("{:.2} ({})", b.score, tr(&get_strength_feedback(b.score)))
+            }),
+        ),
+        ("Length:", breakdowns.each_ref().map(|b| b.length.to_string())),
+        ("Character pool:", breakdowns.each_ref().map(|b| b.pool_size.to_string())),
+        (
+            "Entropy:",
+            breakdowns
+                .each_ref()
+                .map(|b| trf("{} bits", &[&format!("{:.1}", b.entropy_bits)])),
+        ),
+        (
+            "Penalty:",
+            breakdowns.each_ref().map(|b| {
+                if b.penalty < 1.0 {
+                    format!("x{:.2}", b.penalty)
+                } else {
+                    tr("none").to_string()
+                }
+            }),
+        ),
+        (
+            "Issues:",
+            breakdowns.each_ref().map(|b| {
+                if b.issues.is_empty() {
+                    tr("none").to_string()
+                } else {
+                    b.issues.join(", ")
+                }
+            }),
+        ),
+    ];
+    if let Some(violations) = &violations {
+        let compliance = |v: &Vec<&str>| {
+            if v.is_empty() {
+                tr("compliant").to_string()
+            } else {
+                format!("{} ({})", tr("not compliant"), v.join(", "))
+            }
+        };
+        rows.push(("Policy:", [compliance(&violations[0]), compliance(&violations[1])]));
+    }
+    let label_width = rows.iter().map(|(label, _)| tr(label).chars().count()).max().unwrap_or(0) + 2;
+    let value_width = rows.iter().map(|(_, values)| values[0].chars().count()).max().unwrap_or(0) + 3;
+
+    println!("{}", tr("Password Comparison:").paint(Role::Heading));
+    println!("{:<label_width$}{:<value_width$}{}", "", "A".bold(), "B".bold());
+    for (label, [a, b]) in &rows {
+        println!(
+            "{:<label_width$}{:<value_width$}{}",
+            tr(label),
+            a.paint(Role::Value),
+            b.paint(Role::Value)
+        );
+    }
+    println!();
+    match stronger {
+        Some(label) => {
+            let reasons: Vec<&str> = reasons.iter().map(|r| tr(r.message)).collect();
+            println!(
+                "{}",
+                trf("{} is stronger: {}.", &[&label, &reasons.join(", ")]).paint(Role::Success)
+            );
+        }
+        None => println!("{}", tr("Both passwords are equally strong.")),
+    }
+    Ok(())
 }
 
 async fn handle_diceware(
-    config: &PasswordGeneratorConfig,
+    config: &mut PasswordGeneratorConfig,
     matches: &clap::ArgMatches,
     copy: bool,
 ) -> Result<()> {
     let wordlist = match diceware::get_wordlist().await {
         Ok(list) => list,
         Err(PasswordGeneratorError::WordlistDownloaded) => {
-            println!("Wordlist downloaded. Please run the program again.");
+            println!("{}", tr("Wordlist downloaded. Please run the program again."));
             return Ok(());
         }
         Err(e) => return Err(e),
     };
 
-    let passphrases = generate_diceware_passphrase(&wordlist, config).await;
-    passphrases.iter().for_each(|p| println!("{}", p.green()));
+    let target_bits = matches.get_one::<f64>("words-for-bits").copied();
+    if let Some(bits) = target_bits {
+        config.length = diceware::words_for_entropy(wordlist.len(), bits)?;
+    }
+    enforce_entropy_floor(
+        config,
+        config.passphrase_entropy_bits(wordlist.len()),
+        matches,
+    )?;
+
+    if config.fips {
+        fips::validate_wordlist(&wordlist)?;
+    }
+    let (passphrases, positions): (Vec<String>, Vec<Vec<usize>>) =
+        generate_allowed_passphrase_words(&wordlist, config)
+            .await?
+            .into_iter()
+            .unzip();
+    emit_passwords(&passphrases, matches)?;
+    if config.fips {
+        eprintln!("{}", fips::attestation(config, Some(wordlist.len())));
+    }
+    if matches.get_flag("show-rolls") {
+        print_dice_rolls(&wordlist, &positions)?;
+    }
+    if let Some(style) = matches.get_one::<hint::HintStyle>("hint") {
+        print_hints(
+            *style,
+            &wordlist,
+            &positions,
+            config.passphrase_entropy_bits(wordlist.len()),
+        );
+    }
+
+    if let Some(bits) = target_bits {
+        print_words_for_bits(wordlist.len(), bits, config.length);
+    }
+    if let Some(range) = config.word_range {
+        print_word_range_entropy(range, config.passphrase_entropy_bits(wordlist.len()));
+    }
 
-    if copy && !passphrases.is_empty() {
-        copy_to_clipboard(&passphrases.join("\n"))?;
-        println!("{}", "Passphrase(s) copied to clipboard.".bold().green());
+    if copy {
+        copy_generated(&passphrases, matches, "Passphrase")?;
     }
 
+    store_if_requested(&passphrases, matches)?;
+
     if matches.get_flag("strength") {
-        print_strength_meter(&passphrases);
+        print_strength_meter(&passphrases, evaluate_generated_strength, strength_threshold(matches)?)?;
     }
 
     if matches.get_flag("stats") {
         print_stats(&passphrases);
+        write_stats_report(&passphrases, matches)?;
+    }
+
+    Ok(())
+}
+
+async fn handle_password(
+    config: &PasswordGeneratorConfig,
+    matches: &clap::ArgMatches,
+    copy: bool,
+) -> Result<()> {
+    let passwords = generate_strong_passwords(config).await?;
+    emit_passwords(&passwords, matches)?;
+
+    if copy {
+        copy_generated(&passwords, matches, "Password")?;
+    }
+
+    store_if_requested(&passwords, matches)?;
+
+    if matches.get_flag("strength") {
+        print_strength_meter(&passwords, evaluate_generated_strength, strength_threshold(matches)?)?;
+    }
+
+    if matches.get_flag("stats") {
+        print_stats(&passwords);
+        write_stats_report(&passwords, matches)?;
+    }
+
+    passwords.into_iter().for_each(|mut p| p.zeroize());
+    Ok(())
+}
+
+async fn handle_patterns_file(
+    config: &PasswordGeneratorConfig,
+    matches: &clap::ArgMatches,
+    path: &std::path::Path,
+    copy: bool,
+) -> Result<()> {
+    let entries = parse_pattern_list(&std::fs::read_to_string(path)?);
+    if entries.is_empty() {
+        return Err(PasswordGeneratorError::InvalidConfig(format!(
+            "No patterns found in {}",
+            path.display()
+        )));
+    }
+
+    let mut all_passwords = Vec::new();
+    for entry in entries {
+        let mut pattern_config = config.clone();
+        pattern_config.pattern = Some(entry.pattern.clone());
+        pattern_config.num_passwords = entry.count.unwrap_or(config.num_passwords);
+        pattern_config.validate()?;
+        if pattern_config.fips {
+            fips::validate(&pattern_config)?;
+        }
+        pattern_config.load_pattern_words().await?;
+
+        let passwords = generate_strong_passwords(&pattern_config).await?;
+        println!("{} {}", "Pattern".paint(Role::Heading), entry.pattern.paint(Role::Heading));
+        emit_passwords(&passwords, matches)?;
+        all_passwords.extend(passwords);
+    }
+
+    if copy {
+        copy_generated(&all_passwords, matches, "Password")?;
+    }
+
+    if matches.get_flag("strength") {
+        print_strength_meter(&all_passwords, evaluate_generated_strength, strength_threshold(matches)?)?;
+    }
+
+    if matches.get_flag("stats") {
+        print_stats(&all_passwords);
+        write_stats_report(&all_passwords, matches)?;
+    }
+
+    all_passwords.into_iter().for_each(|mut p| p.zeroize());
+    Ok(())
+}
+
+async fn handle_anki_export(
+    config: &PasswordGeneratorConfig,
+    matches: &clap::ArgMatches,
+    path: &std::path::Path,
+) -> Result<()> {
+    let cards = *matches.get_one::<export::AnkiCards>("anki-cards").unwrap();
+    let passphrases =
+        matches.contains_id("grammar") || config.mode == PasswordGeneratorMode::Diceware;
+    if cards == export::AnkiCards::Cloze && !passphrases {
+        return Err(PasswordGeneratorError::InvalidConfig(
+            "Cloze cards need passphrases (--use-words or --grammar); use --anki-cards basic for passwords"
+                .to_string(),
+        ));
     }
+    let deck = matches.get_one::<String>("anki-deck").unwrap();
+    let (mut secrets, _) = generate_candidates(config, matches).await?;
+    let label = if passphrases { "Passphrase" } else { "Password" };
+    let mut tsv = export::anki_tsv(&secrets, cards, deck, label)?;
+    let written = encrypt::write_private(path, tsv.as_bytes());
+    tsv.zeroize();
+    written?;
+    eprintln!(
+        "{}",
+        tr("Warning: the Anki file is not encrypted and Anki syncs imported cards to AnkiWeb if sync is enabled.")
+            .paint(Role::Warning)
+    );
+    println!(
+        "{}",
+        trf("Exported {} cards to {}", &[&secrets.len(), &path.display()])
+            .paint(Role::Success)
+            .bold()
+    );
 
-    Ok(())
+    let checked = if matches.get_flag("strength") {
+        strength_threshold(matches)
+            .and_then(|threshold| print_strength_meter(&secrets, evaluate_generated_strength, threshold))
+    } else {
+        Ok(())
+    };
+    secrets.iter_mut().for_each(|s| s.zeroize());
+    checked
 }
 
-async fn handle_password(
+async fn handle_keepass_export(
     config: &PasswordGeneratorConfig,
     matches: &clap::ArgMatches,
-    copy: bool,
+    path: &std::path::Path,
 ) -> Result<()> {
-    let passwords = generate_passwords(config).await;
-    passwords.iter().for_each(|p| println!("{}", p.green()));
+    let format = export::KeepassFormat::from_path(path)?;
+    let accounts = match matches.get_one::<std::path::PathBuf>("accounts") {
+        Some(csv) => export::parse_accounts_csv(&std::fs::read_to_string(csv)?)?,
+        None => export::default_accounts(config.num_passwords),
+    };
+    if accounts.is_empty() {
+        return Err(PasswordGeneratorError::InvalidConfig(
+            "No accounts to export".to_string(),
+        ));
+    }
+
+    let mut export_config = config.clone();
+    export_config.num_passwords = accounts.len();
+    let passwords = generate_strong_passwords(&export_config).await?;
 
-    if copy && !passwords.is_empty() {
-        copy_to_clipboard(&passwords.join("\n"))?;
-        println!("{}", "Password(s) copied to clipboard.".bold().green());
+    match format {
+        export::KeepassFormat::Xml => {
+            let mut xml = export::keepass_xml(&accounts, &passwords);
+            let written = encrypt::write_private(path, xml.as_bytes());
+            xml.zeroize();
+            written?;
+            eprintln!(
+                "{}",
+                tr("Warning: KeePass XML exports are not encrypted, import and delete the file.")
+                    .paint(Role::Warning)
+            );
+        }
+        export::KeepassFormat::Kdbx => {
+            let mut master_password = match std::env::var("NPWG_KEEPASS_PASSWORD") {
+                Ok(password) => password,
+                Err(_) => Password::with_theme(&ColorfulTheme::default())
+                    .with_prompt(tr("Master password for the KeePass database"))
+                    .with_confirmation(tr("Repeat master password"), tr("Passwords do not match"))
+                    .interact()?,
+            };
+            let written = export::write_kdbx(path, &accounts, &passwords, &master_password);
+            master_password.zeroize();
+            written?;
+        }
     }
 
+    println!(
+        "{}",
+        trf("Exported {} entries to {}", &[&accounts.len(), &path.display()])
+            .paint(Role::Success)
+            .bold()
+    );
+
     if matches.get_flag("strength") {
-        print_strength_meter(&passwords);
+        print_strength_meter(&passwords, evaluate_generated_strength, strength_threshold(matches)?)?;
     }
 
     if matches.get_flag("stats") {
         print_stats(&passwords);
+        write_stats_report(&passwords, matches)?;
     }
 
     passwords.into_iter().for_each(|mut p| p.zeroize());
@@ -323,20 +3277,22 @@ async fn handle_pronounceable(
     matches: &clap::ArgMatches,
     copy: bool,
 ) -> Result<()> {
-    let passwords = generate_pronounceable_passwords(config).await;
-    passwords.iter().for_each(|p| println!("{}", p.green()));
+    let passwords = generate_strong_passwords(config).await?;
+    emit_passwords(&passwords, matches)?;
 
-    if copy && !passwords.is_empty() {
-        copy_to_clipboard(&passwords.join("\n"))?;
-        println!("{}", "Passphrase(s) copied to clipboard.".bold().green());
+    if copy {
+        copy_generated(&passwords, matches, "Passphrase")?;
     }
 
+    store_if_requested(&passwords, matches)?;
+
     if matches.get_flag("strength") {
-        print_strength_meter(&passwords);
+        print_strength_meter(&passwords, evaluate_generated_strength, strength_threshold(matches)?)?;
     }
 
     if matches.get_flag("stats") {
         print_stats(&passwords);
+        write_stats_report(&passwords, matches)?;
     }
 
     passwords.into_iter().for_each(|mut p| p.zeroize());
@@ -349,7 +3305,7 @@ async fn handle_mutation(
     copy: bool,
 ) -> Result<()> {
     let passwords: Vec<String> = Input::<String>::new()
-        .with_prompt("Enter passwords to mutate (comma-separated)")
+        .with_prompt(tr("Enter passwords to mutate (comma-separated)"))
         .interact_text()?
         .split(',')
         .map(|s| s.trim().to_string())
@@ -361,180 +3317,1106 @@ async fn handle_mutation(
         .get_one::<MutationType>("mutation_type")
         .unwrap_or(&MutationType::Replace);
 
+    let mutation_class = matches
+        .get_one::<config::CharClass>("mutation_class")
+        .copied()
+        .unwrap_or(config::CharClass::Digit);
+
     let mutation_strength = matches.get_one::<u32>("mutation_strength").unwrap_or(&1);
 
     let passwords_clone = passwords.clone();
 
-    println!("\n{}", "Mutated Passwords:".bold().green());
+    println!("\n{}", tr("Mutated Passwords:").paint(Role::Success).bold());
     for password in passwords {
-        let mutated = mutate_password(&password, config, *lengthen, *mutation_strength);
-        println!("Original: {}", password.yellow());
-        println!("Mutated:  {} (using {})", mutated.green(), mutation_type);
+        let mutated = apply_mutation(
+            &password,
+            config,
+            mutation_type,
+            mutation_class,
+            *lengthen,
+            *mutation_strength,
+        )?;
+        println!("{} {}", tr("Original:"), password.paint(Role::Value));
+        println!(
+            "{}",
+            trf("Mutated:  {} (using {})", &[&mutated.paint(Role::Password), mutation_type])
+        );
         println!();
     }
 
-    if copy && !passwords_clone.is_empty() {
-        copy_to_clipboard(&passwords_clone.join("\n"))?;
-        println!("{}", "Passphrase(s) copied to clipboard.".bold().green());
+    if copy {
+        copy_generated(&passwords_clone, matches, "Passphrase")?;
     }
 
     if matches.get_flag("strength") {
-        print_strength_meter(&passwords_clone);
+        print_strength_meter(&passwords_clone, evaluate_password_strength, strength_threshold(matches)?)?;
     }
 
     if matches.get_flag("stats") {
         print_stats(&passwords_clone);
+        write_stats_report(&passwords_clone, matches)?;
+    }
+
+    Ok(())
+}
+
+fn store_metadata(matches: &clap::ArgMatches) -> StoreMetadata {
+    StoreMetadata {
+        username: matches.get_one::<String>("store-username").cloned(),
+        url: matches.get_one::<String>("store-url").cloned(),
+    }
+}
+
+fn store_if_requested(passwords: &[String], matches: &clap::ArgMatches) -> Result<()> {
+    let Some(target) = matches.get_one::<StoreTarget>("store") else {
+        return Ok(());
+    };
+    match passwords {
+        [password] => {
+            store::store_password(target, password, &store_metadata(matches))?;
+            println!("{}", trf("Stored in {}.", &[target]).paint(Role::Success).bold());
+            Ok(())
+        }
+        _ => Err(PasswordGeneratorError::InvalidConfig(
+            "--store requires generating exactly one password".to_string(),
+        )),
     }
+}
 
+fn copy_generated(passwords: &[String], matches: &clap::ArgMatches, noun: &str) -> Result<()> {
+    if passwords.is_empty() {
+        return Ok(());
+    }
+    let (mut text, message) = match matches.get_one::<usize>("copy-index").copied() {
+        Some(index) => {
+            let password = passwords.get(index.wrapping_sub(1)).ok_or_else(|| {
+                PasswordGeneratorError::InvalidConfig(format!(
+                    "--copy-index {} is out of range (1-{})",
+                    index,
+                    passwords.len()
+                ))
+            })?;
+            let message = match noun {
+                "Passphrase" => "Passphrase {} copied to clipboard.",
+                _ => "Password {} copied to clipboard.",
+            };
+            (password.clone(), trf(message, &[&index]))
+        }
+        None => {
+            let message = match noun {
+                "Passphrase" => "Passphrase(s) copied to clipboard.",
+                _ => "Password(s) copied to clipboard.",
+            };
+            (passwords.join("\n"), tr(message).to_string())
+        }
+    };
+    let copied = copy_to_clipboard(&text, copy_options(matches));
+    text.zeroize();
+    copied?;
+    println!("{}", message.paint(Role::Success).bold());
     Ok(())
 }
 
-fn copy_to_clipboard(text: &str) -> Result<()> {
+#[cfg(feature = "clipboard")]
+fn copy_to_clipboard(text: &str, options: clipboard::CopyOptions) -> Result<()> {
     #[cfg(target_os = "linux")]
     {
         use std::{env, process};
 
         if env::args().any(|arg| arg == DAEMONIZE_ARG) {
             let text = env::var("CLIPBOARD_TEXT").map_err(|_| {
-                PasswordGeneratorError::ClipboardError(
-                    "Failed to read CLIPBOARD_TEXT environment variable".to_string(),
+                PasswordGeneratorError::clipboard(
+                    ClipboardErrorKind::Daemon,
+                    "Failed to read CLIPBOARD_TEXT environment variable",
                 )
             })?;
-            Clipboard::new()?.set().wait().text(text).map_err(|e| {
-                PasswordGeneratorError::ClipboardError(format!(
-                    "Failed to copy to clipboard: {}",
-                    e
+            let clear_after = env::var("CLIPBOARD_CLEAR_AFTER")
+                .ok()
+                .and_then(|secs| secs.parse::<u64>().ok());
+            let backend = env::var("CLIPBOARD_BACKEND")
+                .ok()
+                .and_then(|backend| backend.parse::<clipboard::Backend>().ok())
+                .unwrap_or_default();
+            let copy_failed = |e: arboard::Error| {
+                PasswordGeneratorError::clipboard(ClipboardErrorKind::CopyFailed, format!(
+                    "Failed to copy to clipboard via {}: {} ({})",
+                    backend,
+                    e,
+                    backend.hint()
                 ))
-            })?;
-            loop {
-                std::thread::sleep(std::time::Duration::from_secs(1));
+            };
+            let mut clipboard = Clipboard::new().map_err(copy_failed)?;
+
+            let Some(secs) = clear_after else {
+                clipboard
+                    .set()
+                    .wait()
+                    .text(text.clone())
+                    .map_err(copy_failed)?;
+                loop {
+                    std::thread::sleep(std::time::Duration::from_secs(1));
+                }
+            };
+
+            let fingerprint = clipboard::fingerprint_path();
+            let restore = clipboard::restorable(fingerprint.as_deref(), clipboard.get_text().ok());
+            if let Some(path) = &fingerprint {
+                clipboard::remember_secret(path, &text)?;
+            }
+            let deadline = Instant::now() + std::time::Duration::from_secs(secs);
+            let copied = clipboard
+                .set()
+                .wait_until(deadline)
+                .text(text.clone())
+                .map_err(copy_failed);
+            if let Some(path) = &fingerprint {
+                clipboard::forget_secret(path, &text);
+            }
+            copied?;
+            if clipboard.get_text().ok().as_deref() != Some(text.as_str()) {
+                return Ok(());
+            }
+            match restore {
+                Some(previous) => clipboard.set().wait().text(previous)?,
+                None => clipboard.clear()?,
             }
         } else {
-            process::Command::new(env::current_exe()?)
+            let backend = options.backend.resolve(
+                env::var_os("WAYLAND_DISPLAY").is_some(),
+                env::var_os("DISPLAY").is_some(),
+            )?;
+            tracing::info!(%backend, "copying to the clipboard");
+            let mut daemon = process::Command::new(env::current_exe()?);
+            daemon
                 .arg(DAEMONIZE_ARG)
                 .stdin(process::Stdio::null())
                 .stdout(process::Stdio::null())
                 .stderr(process::Stdio::null())
                 .env("CLIPBOARD_TEXT", text)
-                .current_dir("/")
-                .spawn()
-                .map_err(|e| {
-                    PasswordGeneratorError::ClipboardError(format!(
-                        "Failed to spawn daemon process: {}",
-                        e
-                    ))
-                })?;
+                .current_dir("/");
+            daemon.env("CLIPBOARD_BACKEND", backend.to_string());
+            if backend == clipboard::Backend::X11 {
+                daemon.env_remove("WAYLAND_DISPLAY");
+            }
+            if let Some(secs) = options.clear_after {
+                daemon.env("CLIPBOARD_CLEAR_AFTER", secs.to_string());
+            }
+            daemon.spawn().map_err(|e| {
+                PasswordGeneratorError::clipboard(ClipboardErrorKind::Daemon, format!(
+                    "Failed to spawn daemon process: {}",
+                    e
+                ))
+            })?;
         }
     }
 
     #[cfg(not(target_os = "linux"))]
     {
+        if options.backend != clipboard::Backend::Auto {
+            return Err(PasswordGeneratorError::clipboard(
+                ClipboardErrorKind::Unsupported,
+                "--clipboard-backend is only supported on Linux",
+            ));
+        }
         let mut clipboard = Clipboard::new().map_err(|e| {
-            PasswordGeneratorError::ClipboardError(format!("Failed to access clipboard: {}", e))
+            PasswordGeneratorError::clipboard(
+                ClipboardErrorKind::Unavailable,
+                format!("Failed to access clipboard: {}", e),
+            )
         })?;
+        let fingerprint = clipboard::fingerprint_path();
+        let restore = clipboard::restorable(fingerprint.as_deref(), clipboard.get_text().ok());
 
-        clipboard.set_text(text.to_owned()).map_err(|e| {
-            PasswordGeneratorError::ClipboardError(format!("Failed to copy to clipboard: {}", e))
+        set_secret_text(&mut clipboard, text).map_err(|e| {
+            PasswordGeneratorError::clipboard(
+                ClipboardErrorKind::CopyFailed,
+                format!("Failed to copy to clipboard: {}", e),
+            )
         })?;
+
+        if let Some(secs) = options.clear_after {
+            if let Some(path) = &fingerprint {
+                clipboard::remember_secret(path, text)?;
+            }
+            println!(
+                "{}",
+                trf("Clearing the clipboard in {} seconds...", &[&secs]).paint(Role::Warning)
+            );
+            std::thread::sleep(std::time::Duration::from_secs(secs));
+            if let Some(path) = &fingerprint {
+                clipboard::forget_secret(path, text);
+            }
+            if clipboard.get_text().ok().as_deref() == Some(text) {
+                match restore {
+                    Some(previous) => clipboard.set_text(previous)?,
+                    None => clipboard.clear()?,
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Places a secret on the clipboard, marked so that clipboard managers do
+/// not record it where the platform has a convention for that.
+#[cfg(all(feature = "clipboard", not(target_os = "linux")))]
+fn set_secret_text(clipboard: &mut Clipboard, text: &str) -> std::result::Result<(), arboard::Error> {
+    // On macOS this adds org.nspasteboard.ConcealedType to the pasteboard item.
+    #[cfg(target_os = "macos")]
+    let set = clipboard.set().exclude_from_history();
+    // On Windows it sets ExcludeClipboardContentFromMonitorProcessing and zeroes
+    // CanIncludeInClipboardHistory and CanUploadToCloudClipboard, which keeps
+    // the secret out of Win+V history and the cloud clipboard.
+    #[cfg(windows)]
+    let set = clipboard
+        .set()
+        .exclude_from_monitoring()
+        .exclude_from_history()
+        .exclude_from_cloud();
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let set = clipboard.set();
+    set.text(text.to_owned())
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn copy_to_clipboard(_text: &str, _options: clipboard::CopyOptions) -> Result<()> {
+    Err(PasswordGeneratorError::clipboard(
+        ClipboardErrorKind::Unsupported,
+        "npwg was built without clipboard support (enable the 'clipboard' feature)",
+    ))
+}
+
+/// Hands the candidates to a launcher and copies the one chosen there.
+/// Alfred does the choosing and copying in its workflow, so it only gets
+/// the Script Filter JSON.
+fn pick_password(launcher: picker::Picker, passwords: &[String], matches: &clap::ArgMatches) -> Result<()> {
+    if launcher == picker::Picker::Alfred {
+        let mut items = picker::alfred_items(passwords);
+        println!("{}", items);
+        items.zeroize();
+        return Ok(());
     }
+    let Some(mut chosen) = picker::pick(launcher, passwords)? else {
+        return Ok(());
+    };
+    let copied = copy_to_clipboard(&chosen, copy_options(matches));
+    chosen.zeroize();
+    copied
+}
+
+fn copy_options(matches: &clap::ArgMatches) -> clipboard::CopyOptions {
+    clipboard::CopyOptions {
+        clear_after: matches.get_one::<u64>("clear-after").copied(),
+        backend: matches
+            .get_one::<clipboard::Backend>("clipboard-backend")
+            .copied()
+            .unwrap_or_default(),
+    }
+}
+
+fn emit_passwords(passwords: &[String], matches: &clap::ArgMatches) -> Result<()> {
+    if let Some(&launcher) = matches.get_one::<picker::Picker>("picker") {
+        return pick_password(launcher, passwords, matches);
+    }
+    let Some(path) = matches.get_one::<std::path::PathBuf>("output") else {
+        print_passwords(passwords, matches);
+        return Ok(());
+    };
 
+    let rendered: Vec<String> = match matches.get_one::<Template>("template") {
+        Some(template) => passwords
+            .iter()
+            .enumerate()
+            .map(|(i, p)| template.render(i + 1, p))
+            .collect(),
+        None => passwords.to_vec(),
+    };
+    let layout = matches
+        .get_one::<Layout>("layout")
+        .unwrap_or(&Layout::OnePerLine);
+    let (_, width) = Term::stdout().size();
+    let mut contents = output::render_plain(&rendered, layout, width as usize);
+    rendered.into_iter().for_each(|mut p| p.zeroize());
+
+    let recipient = matches.get_one::<Recipient>("encrypt-to");
+    let written = encrypt::write_output(path, contents.as_bytes(), recipient);
+    contents.zeroize();
+    written?;
+
+    let target = match recipient {
+        Some(recipient) => trf("{} (encrypted to {})", &[&path.display(), recipient]),
+        None => path.display().to_string(),
+    };
+    println!(
+        "{}",
+        trf("Wrote {} entries to {}", &[&passwords.len(), &target])
+            .paint(Role::Success)
+            .bold()
+    );
     Ok(())
 }
 
-fn print_strength_meter(data: &[String]) {
-    println!("\n{}", "Password Strength:".blue().bold());
+fn print_passwords(passwords: &[String], matches: &clap::ArgMatches) {
+    let rendered: Vec<String>;
+    let passwords = match matches.get_one::<Template>("template") {
+        Some(template) => {
+            rendered = passwords
+                .iter()
+                .enumerate()
+                .map(|(i, p)| template.render(i + 1, p))
+                .collect();
+            &rendered
+        }
+        None => passwords,
+    };
+    let layout = matches
+        .get_one::<Layout>("layout")
+        .unwrap_or(&Layout::OnePerLine);
+    match layout {
+        Layout::OnePerLine if matches.get_flag("annotate") => output::annotate(passwords)
+            .iter()
+            .for_each(|(p, note)| println!("{}  {}", p.paint(Role::Password), note.dimmed())),
+        Layout::OnePerLine => passwords.iter().for_each(|p| println!("{}", p.paint(Role::Password))),
+        Layout::Columns => {
+            let (_, width) = Term::stdout().size();
+            output::format_columns(passwords, width as usize)
+                .iter()
+                .for_each(|row| println!("{}", row.paint(Role::Password)));
+        }
+        Layout::Joined(sep) => println!("{}", passwords.join(sep)),
+        Layout::Null => {
+            use std::io::Write;
+            let mut stdout = std::io::stdout().lock();
+            for password in passwords {
+                let _ = write!(stdout, "{}\0", password);
+            }
+            let _ = stdout.flush();
+        }
+    }
+}
+
+/// Scores `data` with `score`: [`evaluate_generated_strength`] for npwg's own
+/// output, [`evaluate_password_strength`] for passwords the user typed.
+fn print_strength_meter(data: &[String], score: fn(&str) -> f64, threshold: Option<f64>) -> Result<()> {
+    println!("\n{}", tr("Password Strength:").paint(Role::Heading));
+    let mut scores = Vec::with_capacity(data.len());
     for (i, password) in data.iter().enumerate() {
-        let strength = evaluate_password_strength(password);
+        let strength = score(password);
+        scores.push(strength);
         let feedback = get_strength_feedback(strength);
-        let strength_bar = get_strength_bar(strength);
+        if output::is_accessible() {
+            println!(
+                "{} {}, {}",
+                trf("Password {}:", &[&(i + 1)]),
+                output::strength_indicator(strength),
+                tr(&feedback)
+            );
+            continue;
+        }
+        let strength_bar = output::strength_indicator(strength);
         println!(
-            "Password {}: {} {:.2} {} {}",
-            i + 1,
+            "{} {} {:.2} {} {}",
+            trf("Password {}:", &[&(i + 1)]),
             strength_bar,
             strength,
-            feedback.color(match &*feedback {
-                "Very Weak" => "red",
-                "Weak" => "yellow",
-                "Moderate" => "blue",
-                "Strong" => "green",
-                "Very Strong" => "bright green",
-                _ => "white",
-            }),
-            password.yellow()
+            tr(&feedback).paint(Role::for_strength(&feedback)),
+            password.paint(Role::Value)
+        );
+    }
+    check_thresholds(threshold, &scores, 0)
+}
+
+/// `--strength-threshold`, or `strength.threshold` from config.toml.
+fn strength_threshold(matches: &clap::ArgMatches) -> Result<Option<f64>> {
+    let threshold = match matches.get_one::<f64>("strength-threshold") {
+        Some(threshold) => *threshold,
+        None => return Ok(settings::Settings::load()?.strength.threshold),
+    };
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(PasswordGeneratorError::InvalidConfig(
+            "--strength-threshold must be between 0.0 and 1.0".to_string(),
+        ));
+    }
+    Ok(Some(threshold))
+}
+
+/// Fails with exit code 9 when any of the passwords broke the policy, or
+/// else, with a threshold set, 8 when any scored below it.
+fn check_thresholds(threshold: Option<f64>, scores: &[f64], non_compliant: usize) -> Result<()> {
+    if non_compliant > 0 {
+        return Err(PasswordGeneratorError::NonCompliant(format!(
+            "{} of {} passwords break the policy",
+            non_compliant,
+            scores.len()
+        )));
+    }
+    let Some(threshold) = threshold else {
+        return Ok(());
+    };
+    let weak = scores.iter().filter(|&&score| score < threshold).count();
+    if weak > 0 {
+        return Err(PasswordGeneratorError::BelowThreshold(format!(
+            "{} of {} passwords score below {:.2}",
+            weak,
+            scores.len(),
+            threshold
+        )));
+    }
+    Ok(())
+}
+
+fn print_dice_rolls(wordlist: &[String], positions: &[Vec<usize>]) -> Result<()> {
+    let indices = diceware::dice_indices()?;
+    println!("\n{}", tr("Dice rolls:").paint(Role::Heading));
+    for (number, words) in positions.iter().enumerate() {
+        let rolls: Vec<String> = words
+            .iter()
+            .map(|&position| {
+                let word = &wordlist[position];
+                let index = indices.get(word).map_or("?", String::as_str);
+                format!("{} {}", index.paint(Role::Value), word)
+            })
+            .collect();
+        println!("{:>3}: {}", number + 1, rolls.join("  "));
+    }
+    Ok(())
+}
+
+/// Prints a hint per passphrase with the entropy left to whoever reads it.
+/// Whatever the passphrase adds beyond its words, such as random separators
+/// or digits, is not revealed by the hint.
+fn print_hints(style: hint::HintStyle, wordlist: &[String], positions: &[Vec<usize>], bits: f64) {
+    let per_word = diceware::bits_per_word(wordlist.len());
+    println!("\n{}", tr("Hints:").paint(Role::Heading));
+    for (number, words) in positions.iter().enumerate() {
+        let words: Vec<&str> = words.iter().map(|&position| wordlist[position].as_str()).collect();
+        let revealed = words.len() as f64 * per_word - style.remaining_bits(wordlist, &words);
+        let left = format!("{:.2}", (bits - revealed).max(0.0));
+        println!(
+            "{:>3}: {}  {}",
+            number + 1,
+            style.hint(&words).paint(Role::Value),
+            trf("(leaves {} of {} bits)", &[&left, &format!("{:.2}", bits)])
+        );
+    }
+}
+
+fn print_words_for_bits(wordlist_len: usize, target_bits: f64, words: usize) {
+    let per_word = diceware::bits_per_word(wordlist_len);
+    println!("\n{}", tr("Entropy:").paint(Role::Heading));
+    println!(
+        "{}",
+        trf(
+            "Wordlist size: {} words ({} bits per word)",
+            &[
+                &wordlist_len.to_string().paint(Role::Value),
+                &format!("{:.2}", per_word).paint(Role::Value)
+            ]
+        )
+    );
+    println!(
+        "{}",
+        trf(
+            "Target: {} bits -> ceil({} / {}) = {} words ({} bits)",
+            &[
+                &format!("{:.2}", target_bits).paint(Role::Value),
+                &format!("{:.2}", target_bits),
+                &format!("{:.2}", per_word),
+                &words.to_string().paint(Role::Value),
+                &format!("{:.2}", per_word * words as f64).paint(Role::Value)
+            ]
+        )
+    );
+}
+
+fn print_word_range_entropy(range: config::LengthRange, shortest_bits: f64) {
+    println!("\n{}", tr("Entropy:").paint(Role::Heading));
+    println!(
+        "{}",
+        trf(
+            "{} to {} words: at least {} bits (shortest case)",
+            &[
+                &range.min.to_string().paint(Role::Value),
+                &range.max.to_string().paint(Role::Value),
+                &format!("{:.2}", shortest_bits).paint(Role::Value)
+            ]
+        )
+    );
+}
+
+async fn print_effective_config(
+    config: &PasswordGeneratorConfig,
+    matches: &clap::ArgMatches,
+) -> Result<()> {
+    let yes_no = |flag: bool| tr(if flag { "yes" } else { "no" });
+    println!("{}", tr("Effective configuration:").paint(Role::Heading));
+    let mode = if matches.contains_id("grammar") {
+        "Grammar".to_string()
+    } else {
+        format!("{:?}", config.mode)
+    };
+    println!("{} {}", tr("Mode:"), mode.paint(Role::Value));
+    let length = config.length_range.map_or(config.length.to_string(), |range| range.to_string());
+    println!("{} {}", tr("Length:"), length.paint(Role::Value));
+    println!("{} {}", tr("Count:"), config.num_passwords.to_string().paint(Role::Value));
+    println!("{} {}", tr("Seeded:"), yes_no(config.seed.is_some()).paint(Role::Value));
+
+    if let Some(template) = matches.get_one::<grammar::Template>("grammar") {
+        println!("{} {}", tr("Grammar template:"), template.to_string().paint(Role::Value));
+        print_grammar_entropy(&grammar_lexicon(matches)?, template);
+    } else {
+        match config.mode {
+            PasswordGeneratorMode::Diceware => {
+                let separator = match &config.separator {
+                    Some(Separator::Fixed(separator)) => format!("{:?}", separator),
+                    Some(Separator::Random(chars)) => {
+                        trf("random from {}", &[&format!("{:?}", chars.iter().collect::<String>())])
+                    }
+                    Some(Separator::Cycle(chars)) => {
+                        trf("in turn from {}", &[&format!("{:?}", chars.iter().collect::<String>())])
+                    }
+                    Some(Separator::Positional(chars)) => trf(
+                        "one from {} at a random position",
+                        &[&format!("{:?}", chars.iter().collect::<String>())]
+                    ),
+                    None => tr("default").to_string(),
+                };
+                match config.join_style {
+                    Some(style) => println!("{} {}", tr("Join style:"), style.to_string().paint(Role::Value)),
+                    None => println!("{} {}", tr("Separator:"), separator.paint(Role::Value)),
+                }
+                if let Some(range) = config.word_range {
+                    println!("{} {}", tr("Words:"), range.to_string().paint(Role::Value));
+                }
+                if config.append_digits > 0 {
+                    println!(
+                        "{} {}",
+                        tr("Appended digits:"),
+                        config.append_digits.to_string().paint(Role::Value)
+                    );
+                }
+
+                let wordlist_path = diceware::wordlist_path()?;
+                println!(
+                    "{} {}",
+                    tr("Wordlist:"),
+                    wordlist_path.display().to_string().paint(Role::Value)
+                );
+                if wordlist_path.exists() {
+                    let words = diceware::get_wordlist().await?.len();
+                    println!("{} {}", tr("Wordlist size:"), words.to_string().paint(Role::Value));
+                    let bits = format!("{:.2}", config.passphrase_entropy_bits(words)).paint(Role::Value);
+                    if config.word_range.is_some() {
+                        println!("{}", trf("Entropy: {} bits (shortest case)", &[&bits]));
+                    } else {
+                        println!("{}", trf("Entropy: {} bits", &[&bits]));
+                    }
+                } else {
+                    println!(
+                        "{} {}",
+                        tr("Entropy:"),
+                        tr("unknown (wordlist not downloaded yet)").paint(Role::Value)
+                    );
+                }
+            }
+            PasswordGeneratorMode::Password => {
+                let charsets = matches.get_one::<String>("allowed").unwrap();
+                println!("{} {}", tr("Charsets:"), charsets.paint(Role::Value));
+                println!(
+                    "{} {}",
+                    tr("Characters:"),
+                    config.available_chars().iter().collect::<String>().paint(Role::Value)
+                );
+                println!("{} {}", tr("Pool size:"), config.pool_size().to_string().paint(Role::Value));
+                if let Some(pattern) = &config.pattern {
+                    println!("{} {}", tr("Pattern:"), pattern.paint(Role::Value));
+                }
+                println!(
+                    "{} {}",
+                    tr("Pronounceable:"),
+                    yes_no(config.pronounceable).paint(Role::Value)
+                );
+                if let Some(language) = config.language {
+                    println!("{} {}", tr("Language:"), language.to_string().paint(Role::Value));
+                }
+                println!(
+                    "{} {}",
+                    tr("Avoid repetition:"),
+                    yes_no(config.avoid_repetition).paint(Role::Value)
+                );
+                if config.exclude_confusables {
+                    println!("{} {}", tr("Exclude confusables:"), yes_no(true).paint(Role::Value));
+                }
+                if config.shell_safe {
+                    println!("{} {}", tr("Shell safe:"), yes_no(true).paint(Role::Value));
+                }
+                if let Some(profile) = config.escape_profile {
+                    println!("{} {}", tr("Escape profile:"), profile.to_string().paint(Role::Value));
+                }
+                if config.normalization != unicode::Normalization::None {
+                    println!("{} {}", tr("Normalization:"), config.normalization.to_string().paint(Role::Value));
+                }
+                let bits = format!("{:.2}", config.entropy_bits()).paint(Role::Value);
+                if config.length_range.is_some() {
+                    println!("{}", trf("Entropy: {} bits (shortest case)", &[&bits]));
+                } else {
+                    println!("{}", trf("Entropy: {} bits", &[&bits]));
+                }
+                if config.char_weights().is_some() && config.length > 0 {
+                    println!(
+                        "{}",
+                        trf(
+                            "Entropy per character: {} bits (uniform: {} bits)",
+                            &[
+                                &format!("{:.2}", config.entropy_bits() / config.length as f64).paint(Role::Value),
+                                &format!("{:.2}", (config.pool_size() as f64).log2()),
+                            ]
+                        )
+                    );
+                }
+            }
+        }
+    }
+
+    if let Some(target) = matches.get_one::<StoreTarget>("store") {
+        println!(
+            "{} {}",
+            tr("Store command:"),
+            store::describe_command(target, &store_metadata(matches)).paint(Role::Value)
         );
     }
+
+    Ok(())
 }
 
 fn print_stats(data: &[String]) {
     let pq = show_stats(data);
-    println!("\n{}", "Statistics:".blue().bold());
-    println!("Mean: {:.6}", pq.mean.to_string().yellow());
-    println!("Variance: {:.6}", pq.variance.to_string().yellow());
-    println!("Skewness: {:.6}", pq.skewness.to_string().yellow());
-    println!("Kurtosis: {:.6}", pq.kurtosis.to_string().yellow());
+    println!("\n{}", tr("Statistics:").paint(Role::Heading));
+    println!("{} {:.6}", tr("Mean:"), pq.mean.to_string().paint(Role::Value));
+    println!("{} {:.6}", tr("Variance:"), pq.variance.to_string().paint(Role::Value));
+    println!("{} {:.6}", tr("Skewness:"), pq.skewness.to_string().paint(Role::Value));
+    println!("{} {:.6}", tr("Kurtosis:"), pq.kurtosis.to_string().paint(Role::Value));
+    let range = [pq.min, pq.p5, pq.median, pq.p95, pq.max]
+        .map(|x| format!("{:.6}", x))
+        .join(" / ");
+    println!("{} {}", tr("Min / p5 / median / p95 / max:"), range.paint(Role::Value));
+}
+
+fn write_stats_report(data: &[String], matches: &clap::ArgMatches) -> Result<()> {
+    let Some(path) = matches.get_one::<std::path::PathBuf>("stats-report") else {
+        return Ok(());
+    };
+    let format = ReportFormat::from_path(path)?;
+    std::fs::write(path, batch_report(data).render(format))?;
+    println!(
+        "{}",
+        trf("Statistics report written to {}", &[&path.display()]).paint(Role::Success)
+    );
+    Ok(())
 }
 
 async fn interactive_mode() -> Result<()> {
     let term = Term::stdout();
     let theme = ColorfulTheme::default();
+    let mut history = history::SessionHistory::default();
+    let mut jitter: Option<[u8; 32]> = None;
+    let keymap = keymap::Keymap::from_settings(&settings::Settings::load()?.keys)?;
+    let mut last_options = None;
 
     loop {
         term.clear_screen()?;
-        println!("{}", "Welcome to NPWG Interactive Mode!".bold().cyan());
+        println!("{}", tr("Welcome to NPWG Interactive Mode!").paint(Role::Title));
 
         let options = vec![
-            "Generate Password",
-            "Generate Passphrase",
-            "Mutate Password",
-            "Exit",
+            tr("Generate Password"),
+            tr("Generate Passphrase"),
+            tr("Mutate Password"),
+            tr("Collect Keyboard Entropy"),
+            tr("Session History"),
+            tr("Exit"),
         ];
         let selection = Select::with_theme(&theme)
-            .with_prompt("What would you like to do?")
+            .with_prompt(tr("What would you like to do?"))
             .items(&options)
             .default(0)
             .interact_on(&term)
-            .map_err(|e| PasswordGeneratorError::DialoguerError(e))?;
+            .map_err(PasswordGeneratorError::DialoguerError)?;
 
         match selection {
-            0 => generate_interactive_password(&term, &theme).await?,
-            1 => generate_interactive_passphrase(&term, &theme).await?,
-            2 => mutate_interactive_password(&term, &theme).await?,
-            3 => break,
+            0 => {
+                last_options =
+                    Some(generate_interactive_password(&term, &theme, &keymap, &mut history, jitter).await?)
+            }
+            1 => {
+                if let Some(options) =
+                    generate_interactive_passphrase(&term, &theme, &keymap, &mut history, jitter).await?
+                {
+                    last_options = Some(options);
+                }
+            }
+            2 => mutate_interactive_password(&term, &theme, &mut history).await?,
+            3 => {
+                if let Some(collected) = collect_keyboard_entropy(&term)? {
+                    jitter = Some(collected);
+                }
+            }
+            4 => review_history(&term, &theme, &history)?,
+            5 => break,
             _ => unreachable!(),
         }
 
         if !Confirm::with_theme(&theme)
-            .with_prompt("Do you want to perform another action?")
+            .with_prompt(tr("Do you want to perform another action?"))
             .default(true)
             .interact_on(&term)
-            .map_err(|e| PasswordGeneratorError::DialoguerError(e))?
+            .map_err(PasswordGeneratorError::DialoguerError)?
         {
             break;
         }
     }
 
-    println!("{}", "Thank you for using NPWG!".bold().green());
+    jitter.zeroize();
+    if let Some(options) = last_options {
+        offer_profile_save(&term, &theme, options)?;
+    }
+    println!("{}", tr("Thank you for using NPWG!").paint(Role::Success).bold());
+    Ok(())
+}
+
+/// Lets the settings of the last password or passphrase generated in the
+/// session be reused from scripts with `--profile`.
+fn offer_profile_save(
+    term: &Term,
+    theme: &ColorfulTheme,
+    options: BTreeMap<String, toml::Value>,
+) -> Result<()> {
+    if !Confirm::with_theme(theme)
+        .with_prompt(tr("Save these settings as a profile?"))
+        .default(false)
+        .interact_on(term)?
+    {
+        return Ok(());
+    }
+    let name: String = Input::with_theme(theme)
+        .with_prompt(tr("Profile name"))
+        .validate_with(|input: &String| profile::validate_name(input).map_err(|e| e.to_string()))
+        .interact_on(term)?;
+    let profile = profile::Profile {
+        version: profile::PROFILE_VERSION,
+        name,
+        options,
+    };
+    profile.validate_options(&cli())?;
+    let exists = profile::list()?.contains(&profile.name);
+    if exists
+        && !Confirm::with_theme(theme)
+            .with_prompt(trf("Profile '{}' already exists. Replace it?", &[&profile.name]))
+            .default(false)
+            .interact_on(term)?
+    {
+        return Ok(());
+    }
+    let saved = profile.save(exists)?;
+    println!(
+        "{}",
+        trf("Saved profile '{}' to {}", &[&profile.name, &saved.display()])
+            .paint(Role::Success)
+            .bold()
+    );
+    println!("{} npwg --profile {}", tr("Use it with:"), profile.name.paint(Role::Value));
+    Ok(())
+}
+
+/// Times the gaps between keystrokes typed at random and returns the hashed
+/// pool, or None when too few keys were pressed to be worth mixing in.
+fn collect_keyboard_entropy(term: &Term) -> Result<Option<[u8; 32]>> {
+    if !term.is_term() {
+        println!("{}", tr("Collecting keyboard entropy needs an interactive terminal."));
+        return Ok(None);
+    }
+    println!(
+        "{}",
+        trf("Type randomly for {} seconds...", &[&jitter::COLLECTION_SECONDS]).bold()
+    );
+    let mut pool = jitter::JitterPool::default();
+    crossterm::terminal::enable_raw_mode()?;
+    let collected = read_keystrokes(&mut pool, Duration::from_secs(jitter::COLLECTION_SECONDS));
+    crossterm::terminal::disable_raw_mode()?;
+    collected?;
+    if pool.samples() < jitter::MIN_SAMPLES {
+        println!(
+            "{}",
+            trf("Only {} keystrokes collected, nothing was mixed in.", &[&pool.samples()]).paint(Role::Warning)
+        );
+        return Ok(None);
+    }
+    println!(
+        "{}",
+        trf(
+            "Collected {} keystrokes; their timing is mixed into the generator for this session.",
+            &[&pool.samples()]
+        )
+        .paint(Role::Success)
+    );
+    Ok(Some(pool.finish()))
+}
+
+/// Feeds key presses into `pool` until `window` has passed. Waiting through
+/// `poll` keeps the collection from overrunning its window when nobody types.
+fn read_keystrokes(pool: &mut jitter::JitterPool, window: Duration) -> Result<()> {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+
+    let started = Instant::now();
+    let mut last = started;
+    while let Some(remaining) = window.checked_sub(started.elapsed()) {
+        if !event::poll(remaining)? {
+            break;
+        }
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let now = Instant::now();
+            pool.add(now - last, if let KeyCode::Char(c) = key.code { c } else { '\0' });
+            last = now;
+        }
+    }
+    Ok(())
+}
+
+fn review_history(term: &Term, theme: &ColorfulTheme, history: &history::SessionHistory) -> Result<()> {
+    if history.is_empty() {
+        println!(
+            "{}",
+            tr("Nothing has been generated in this session yet.").paint(Role::Warning)
+        );
+        return Ok(());
+    }
+
+    let items: Vec<String> = history
+        .entries()
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            format!(
+                "{:>2}. {:<10} {:.2} {:<11} {}",
+                i + 1,
+                entry.kind,
+                entry.score,
+                tr(&entry.feedback()),
+                entry.value
+            )
+        })
+        .collect();
+    let selected = Select::with_theme(theme)
+        .with_prompt(tr("Select a previous candidate"))
+        .items(&items)
+        .default(items.len() - 1)
+        .interact_on(term)?;
+    let entry = &history.entries()[selected];
+
+    let actions = [
+        tr("Copy to clipboard"),
+        tr("Compare with another candidate"),
+        tr("Back"),
+    ];
+    match Select::with_theme(theme)
+        .with_prompt(tr("What would you like to do with it?"))
+        .items(&actions)
+        .default(0)
+        .interact_on(term)?
+    {
+        0 => {
+            copy_to_clipboard(&entry.value, clipboard::CopyOptions::default())?;
+            println!("{}", tr("Copied to clipboard.").paint(Role::Success));
+        }
+        1 => {
+            let other = Select::with_theme(theme)
+                .with_prompt(tr("Compare with"))
+                .items(&items)
+                .default(0)
+                .interact_on(term)?;
+            println!("\n{}", tr("Comparison:").paint(Role::Heading));
+            for (i, entry) in [selected, other]
+                .into_iter()
+                .map(|i| (i, &history.entries()[i]))
+            {
+                if output::is_accessible() {
+                    println!(
+                        "{}. {}, {} ({:.1} bits)",
+                        i + 1,
+                        output::strength_indicator(entry.score),
+                        tr(&entry.feedback()),
+                        entry.entropy_bits()
+                    );
+                    continue;
+                }
+                println!(
+                    "{:>2}. {} {} {:.2} {} ({:.1} bits)",
+                    i + 1,
+                    entry.value.paint(Role::Value),
+                    output::strength_indicator(entry.score),
+                    entry.score,
+                    tr(&entry.feedback()),
+                    entry.entropy_bits()
+                );
+            }
+        }
+        _ => {}
+    }
     Ok(())
 }
 
-async fn generate_interactive_password(term: &Term, theme: &ColorfulTheme) -> Result<()> {
+/// What to do with candidates after [`review_candidates`].
+enum Review {
+    Keep,
+    Regenerate,
+}
+
+/// A key as it is named in the `[keys]` section of config.toml.
+fn key_name(key: Key) -> Option<String> {
+    let name = match key {
+        Key::Char(' ') => "space",
+        Key::Char(c) => return Some(c.to_string()),
+        Key::Enter => "enter",
+        Key::Escape => "esc",
+        Key::Tab => "tab",
+        Key::Backspace => "backspace",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+/// Shows freshly generated candidates until the quit key is pressed. Copying
+/// and masking happen in place; regenerating goes back to the caller.
+fn review_candidates(term: &Term, keymap: &keymap::Keymap, candidates: &[String]) -> Result<Review> {
+    let show = |masked: bool| {
+        candidates.iter().map(move |candidate| {
+            if masked {
+                "•".repeat(candidate.chars().count()).paint(Role::Value)
+            } else {
+                candidate.paint(Role::Value)
+            }
+        })
+    };
+    if !term.is_term() {
+        show(false).for_each(|line| println!("{}", line));
+        return Ok(Review::Keep);
+    }
+    let hint = trf(
+        "{} regenerate · {} copy · {} show/hide · {} done",
+        &[
+            &keymap.key(keymap::Action::Regenerate),
+            &keymap.key(keymap::Action::Copy),
+            &keymap.key(keymap::Action::ToggleMask),
+            &keymap.key(keymap::Action::Quit),
+        ],
+    );
+    let mut masked = false;
+    let mut status = String::new();
+    loop {
+        for line in show(masked) {
+            term.write_line(&line.to_string())?;
+        }
+        term.write_line(&format!("{} {}", hint.dimmed(), status))?;
+        let action = key_name(term.read_key()?).and_then(|key| keymap.action(&key));
+        term.clear_last_lines(candidates.len() + 1)?;
+        match action {
+            Some(keymap::Action::Regenerate) => return Ok(Review::Regenerate),
+            Some(keymap::Action::Copy) => {
+                let mut text = candidates.join("\n");
+                status = match copy_to_clipboard(&text, clipboard::CopyOptions::default()) {
+                    Ok(()) => tr("Copied to clipboard.").paint(Role::Success).to_string(),
+                    Err(e) => e.to_string().paint(Role::Error).to_string(),
+                };
+                text.zeroize();
+            }
+            Some(keymap::Action::ToggleMask) => masked = !masked,
+            Some(keymap::Action::Quit) => {
+                show(masked).for_each(|line| println!("{}", line));
+                return Ok(Review::Keep);
+            }
+            None => {}
+        }
+    }
+}
+
+fn pick_candidates(term: &Term, theme: &ColorfulTheme, candidates: &[String]) -> Result<()> {
+    let selected: Vec<usize> = if candidates.len() > 1 {
+        let items: Vec<String> = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, candidate)| format!("{:>2}. {}", i + 1, candidate))
+            .collect();
+        MultiSelect::with_theme(theme)
+            .with_prompt(tr("Pick candidates to keep (space to toggle, enter to confirm)"))
+            .items(&items)
+            .interact_on(term)?
+    } else {
+        vec![0]
+    };
+    if selected.is_empty() {
+        return Ok(());
+    }
+
+    let actions = [tr("Copy to clipboard"), tr("Save to file"), tr("Skip")];
+    let action = Select::with_theme(theme)
+        .with_prompt(trf(
+            "What would you like to do with {} selected?",
+            &[&selected.len()],
+        ))
+        .items(&actions)
+        .default(0)
+        .interact_on(term)?;
+    if action == 2 {
+        return Ok(());
+    }
+
+    let mut text = selected
+        .iter()
+        .map(|&i| candidates[i].as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let result = if action == 0 {
+        copy_to_clipboard(&text, clipboard::CopyOptions::default())
+            .map(|_| println!("{}", tr("Copied to clipboard.").paint(Role::Success)))
+    } else {
+        let path: String = Input::with_theme(theme)
+            .with_prompt(tr("File to write"))
+            .interact_on(term)?;
+        text.push('\n');
+        encrypt::write_output(std::path::Path::new(&path), text.as_bytes(), None)
+            .map(|_| println!("{} {}", tr("Saved to").paint(Role::Success), path.paint(Role::Value)))
+    };
+    text.zeroize();
+    result
+}
+
+async fn generate_interactive_password(
+    term: &Term,
+    theme: &ColorfulTheme,
+    keymap: &keymap::Keymap,
+    history: &mut history::SessionHistory,
+    jitter: Option<[u8; 32]>,
+) -> Result<BTreeMap<String, toml::Value>> {
     let length: u8 = Input::with_theme(theme)
-        .with_prompt("Password length")
+        .with_prompt(tr("Password length"))
         .default(16)
         .interact_on(term)?;
 
     let count: u32 = Input::with_theme(theme)
-        .with_prompt("Number of passwords")
+        .with_prompt(tr("Number of passwords"))
         .default(1)
         .interact_on(term)?;
 
     let avoid_repeating = Confirm::with_theme(theme)
-        .with_prompt("Avoid repeating characters?")
+        .with_prompt(tr("Avoid repeating characters?"))
         .default(false)
         .interact_on(term)?;
 
     let pronounceable = Confirm::with_theme(theme)
-        .with_prompt("Generate pronounceable passwords?")
+        .with_prompt(tr("Generate pronounceable passwords?"))
         .default(false)
         .interact_on(term)?;
 
@@ -543,36 +4425,52 @@ async fn generate_interactive_password(term: &Term, theme: &ColorfulTheme) -> Re
     config.num_passwords = count as usize;
     config.set_avoid_repeating(avoid_repeating);
     config.pronounceable = pronounceable;
+    config.jitter = jitter;
     config.validate()?;
 
     let pattern = Input::with_theme(theme)
-        .with_prompt("Enter desired pattern or leave empty for no pattern")
+        .with_prompt(tr("Enter desired pattern or leave empty for no pattern"))
         .default("".to_string())
+        .validate_with(|input: &String| config::validate_pattern(input).map_err(|e| e.to_string()))
         .interact_text()?;
 
+    let mut options = BTreeMap::from([
+        ("length".to_string(), toml::Value::Integer(length.into())),
+        ("count".to_string(), toml::Value::Integer(count.into())),
+        ("avoid-repeating".to_string(), toml::Value::Boolean(avoid_repeating)),
+        ("pronounceable".to_string(), toml::Value::Boolean(pronounceable)),
+    ]);
     if !pattern.is_empty() {
+        options.insert("pattern".to_string(), toml::Value::String(pattern.clone()));
         config.pattern = Some(pattern);
+        config.load_pattern_words().await?;
     }
 
-    let passwords = if pronounceable {
-        generate_pronounceable_passwords(&config).await
-    } else {
-        generate_passwords(&config).await
+    println!("\n{}", tr("Generated Passwords:").paint(Role::Success).bold());
+    let passwords = loop {
+        let passwords = if pronounceable {
+            generate_pronounceable_passwords(&config).await?
+        } else {
+            generate_passwords(&config).await?
+        };
+        match review_candidates(term, keymap, &passwords)? {
+            Review::Keep => break passwords,
+            Review::Regenerate => passwords.into_iter().for_each(|mut p| p.zeroize()),
+        }
     };
-
-    println!("\n{}", "Generated Passwords:".bold().green());
-    passwords.iter().for_each(|p| println!("{}", p.yellow()));
+    history.record("password", &passwords);
+    pick_candidates(term, theme, &passwords)?;
 
     if Confirm::with_theme(theme)
-        .with_prompt("Show strength meter?")
+        .with_prompt(tr("Show strength meter?"))
         .default(true)
         .interact_on(term)?
     {
-        print_strength_meter(&passwords);
+        print_strength_meter(&passwords, evaluate_generated_strength, None)?;
     }
 
     if Confirm::with_theme(theme)
-        .with_prompt("Show statistics?")
+        .with_prompt(tr("Show statistics?"))
         .default(false)
         .interact_on(term)?
     {
@@ -580,25 +4478,31 @@ async fn generate_interactive_password(term: &Term, theme: &ColorfulTheme) -> Re
     }
 
     passwords.into_iter().for_each(|mut p| p.zeroize());
-    Ok(())
+    Ok(options)
 }
 
-async fn generate_interactive_passphrase(term: &Term, theme: &ColorfulTheme) -> Result<()> {
+async fn generate_interactive_passphrase(
+    term: &Term,
+    theme: &ColorfulTheme,
+    keymap: &keymap::Keymap,
+    history: &mut history::SessionHistory,
+    jitter: Option<[u8; 32]>,
+) -> Result<Option<BTreeMap<String, toml::Value>>> {
     let count: u32 = Input::with_theme(theme)
-        .with_prompt("Number of passphrases")
+        .with_prompt(tr("Number of passphrases"))
         .default(1)
         .interact_on(term)?;
 
     let separator: String = Input::with_theme(theme)
-        .with_prompt("Separator (single character, 'random', or press Enter for space)")
+        .with_prompt(tr("Separator (any string, 'random', 'set:<charset>', 'cycle', 'positional', or press Enter for space)"))
         .allow_empty(true)
         .interact_on(term)?;
 
     let wordlist = match diceware::get_wordlist().await {
         Ok(list) => list,
         Err(PasswordGeneratorError::WordlistDownloaded) => {
-            println!("Wordlist downloaded. Please run the program again.");
-            return Ok(());
+            println!("{}", tr("Wordlist downloaded. Please run the program again."));
+            return Ok(None);
         }
         Err(e) => return Err(e),
     };
@@ -606,65 +4510,78 @@ async fn generate_interactive_passphrase(term: &Term, theme: &ColorfulTheme) ->
     let mut config = PasswordGeneratorConfig::new();
     config.num_passwords = count as usize;
     config.set_use_words(true);
+    config.jitter = jitter;
 
-    config.separator = if separator.is_empty() {
-        Some(Separator::Fixed(' '))
+    let separator = if separator.is_empty() {
+        " ".to_string()
+    } else if parse_separator(&separator).is_ok() {
+        separator
     } else {
-        match separator.as_str() {
-            "random" => Some(Separator::Random(('a'..='z').chain('0'..='9').collect())),
-            s if s.len() == 1 => Some(Separator::Fixed(s.chars().next().unwrap())),
-            _ => {
-                println!("Invalid separator. Using default (space).");
-                Some(Separator::Fixed(' '))
-            }
-        }
+        println!("{}", tr("Invalid separator. Using default (space)."));
+        " ".to_string()
     };
+    config.separator = parse_separator(&separator).ok();
 
     config.validate()?;
 
-    let passphrases = generate_diceware_passphrase(&wordlist, &config).await;
-    println!("\n{}", "Generated Passphrases:".bold().green());
-    passphrases.iter().for_each(|p| println!("{}", p.yellow()));
+    println!("\n{}", tr("Generated Passphrases:").paint(Role::Success).bold());
+    let passphrases = loop {
+        let passphrases = generate_diceware_passphrase(&wordlist, &config).await?;
+        match review_candidates(term, keymap, &passphrases)? {
+            Review::Keep => break passphrases,
+            Review::Regenerate => passphrases.into_iter().for_each(|mut p| p.zeroize()),
+        }
+    };
+    history.record("passphrase", &passphrases);
+    pick_candidates(term, theme, &passphrases)?;
 
     if Confirm::with_theme(theme)
-        .with_prompt("Show strength meter?")
+        .with_prompt(tr("Show strength meter?"))
         .default(true)
         .interact_on(term)?
     {
-        print_strength_meter(&passphrases);
+        print_strength_meter(&passphrases, evaluate_generated_strength, None)?;
     }
 
     if Confirm::with_theme(theme)
-        .with_prompt("Show statistics?")
+        .with_prompt(tr("Show statistics?"))
         .default(false)
         .interact_on(term)?
     {
         print_stats(&passphrases);
     }
 
-    Ok(())
+    Ok(Some(BTreeMap::from([
+        ("count".to_string(), toml::Value::Integer(count.into())),
+        ("use-words".to_string(), toml::Value::Boolean(true)),
+        ("separator".to_string(), toml::Value::String(separator)),
+    ])))
 }
 
-async fn mutate_interactive_password(term: &Term, theme: &ColorfulTheme) -> Result<()> {
+async fn mutate_interactive_password(
+    term: &Term,
+    theme: &ColorfulTheme,
+    history: &mut history::SessionHistory,
+) -> Result<()> {
     let password: String = Input::with_theme(theme)
-        .with_prompt("Enter the password to mutate")
+        .with_prompt(tr("Enter the password to mutate"))
         .interact_on(term)?;
 
     let config = PasswordGeneratorConfig::new();
     config.validate()?;
 
     let lengthen: usize = Input::with_theme(theme)
-        .with_prompt("Increase the length of the password")
+        .with_prompt(tr("Increase the length of the password"))
         .default(0)
         .interact_on(term)?;
 
     let mutation_strength: u32 = Input::with_theme(theme)
-        .with_prompt("Enter mutation strength (1-10)")
+        .with_prompt(tr("Enter mutation strength (1-10)"))
         .validate_with(|input: &u32| {
             if *input >= 1 && *input <= 10 {
                 Ok(())
             } else {
-                Err("Please enter a number between 1 and 10")
+                Err(tr("Please enter a number between 1 and 10"))
             }
         })
         .default(1)
@@ -676,34 +4593,69 @@ async fn mutate_interactive_password(term: &Term, theme: &ColorfulTheme) -> Resu
         MutationType::Remove,
         MutationType::Swap,
         MutationType::Shift,
+        MutationType::Append,
+        MutationType::Prepend,
     ];
     let mutation_type_index = Select::with_theme(theme)
-        .with_prompt("Select mutation type")
+        .with_prompt(tr("Select mutation type"))
         .items(&mutation_types)
         .default(0)
         .interact_on(term)?;
     let mutation_type = &mutation_types[mutation_type_index];
 
-    let mutated = mutate_password(&password, &config, lengthen, mutation_strength);
+    let mutation_class = if matches!(mutation_type, MutationType::Append | MutationType::Prepend) {
+        let classes = [
+            config::CharClass::Digit,
+            config::CharClass::Symbol,
+            config::CharClass::Upper,
+            config::CharClass::Lower,
+            config::CharClass::Letter,
+            config::CharClass::Alnum,
+        ];
+        let class_index = Select::with_theme(theme)
+            .with_prompt(tr("Select the character class to attach"))
+            .items(&classes)
+            .default(0)
+            .interact_on(term)?;
+        classes[class_index]
+    } else {
+        config::CharClass::Digit
+    };
+
+    let mutated = apply_mutation(
+        &password,
+        &config,
+        mutation_type,
+        mutation_class,
+        lengthen,
+        mutation_strength,
+    )?;
 
-    println!("\n{}", "Mutated Password:".bold().green());
-    println!("Original: {}", password.yellow());
-    println!("Mutated:  {} (using {:?})", mutated.green(), mutation_type);
+    println!("\n{}", tr("Mutated Password:").paint(Role::Success).bold());
+    println!("{} {}", tr("Original:"), password.paint(Role::Value));
+    println!(
+        "{}",
+        trf(
+            "Mutated:  {} (using {})",
+            &[&mutated.paint(Role::Password), &format!("{:?}", mutation_type)]
+        )
+    );
+    history.record("mutated", std::slice::from_ref(&mutated));
 
     if Confirm::with_theme(theme)
-        .with_prompt("Show strength meter?")
+        .with_prompt(tr("Show strength meter?"))
         .default(true)
         .interact_on(term)?
     {
-        print_strength_meter(&vec![password.clone(), mutated.clone()]);
+        print_strength_meter(&[password.clone(), mutated.clone()], evaluate_password_strength, None)?;
     }
 
     if Confirm::with_theme(theme)
-        .with_prompt("Show statistics?")
+        .with_prompt(tr("Show statistics?"))
         .default(false)
         .interact_on(term)?
     {
-        print_stats(&vec![password, mutated]);
+        print_stats(&[password, mutated]);
     }
 
     Ok(())
@@ -0,0 +1,398 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/mcp.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::audit::check_report;
+use crate::config::{PasswordGeneratorConfig, Separator, DEFINE};
+use crate::diceware::get_wordlist;
+use crate::error::{PasswordGeneratorError, Result};
+use crate::generator::{generate_allowed_passphrases, generate_strong_passwords};
+use crate::rules::{parse_password_rules, PasswordRules};
+use crate::settings::Settings;
+use crate::sites::find_site;
+use crate::strength::ScoreScale;
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+use tracing::debug;
+use zeroize::Zeroize;
+
+const SUPPORTED_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+const MAX_COUNT: u64 = 100;
+const MAX_LENGTH: u64 = 255;
+/// The entropy floor of `generate` when `config.toml` sets no `min_bits`.
+/// An assistant cannot pass `--force`, so weak requests are always refused.
+const DEFAULT_MIN_BITS: f64 = 60.0;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+fn policy_properties() -> Value {
+    json!({
+        "rules": {
+            "type": "string",
+            "description": "Password policy in passwordrules syntax, e.g. 'minlength: 12; required: upper; required: digit'"
+        },
+        "site": {
+            "type": "string",
+            "description": "Name of a built-in site preset; with rules, passwords satisfy both"
+        }
+    })
+}
+
+fn tool_definitions() -> Value {
+    let mut generate = policy_properties();
+    generate["length"] = json!({
+        "type": "integer", "minimum": 1, "maximum": MAX_LENGTH, "default": 16,
+        "description": "Characters per password, or words per passphrase"
+    });
+    generate["count"] = json!({
+        "type": "integer", "minimum": 1, "maximum": MAX_COUNT, "default": 1
+    });
+    generate["charsets"] = json!({
+        "type": "string", "default": "allprint",
+        "description": "Comma-separated character sets: ".to_string()
+            + &DEFINE.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ")
+    });
+    generate["passphrase"] = json!({
+        "type": "boolean", "default": false,
+        "description": "Generate diceware passphrases instead of passwords"
+    });
+
+    let mut check = policy_properties();
+    check["password"] = json!({ "type": "string", "description": "The password to score" });
+
+    json!([
+        {
+            "name": "generate",
+            "description": "Generate passwords or passphrases, optionally satisfying a password policy",
+            "inputSchema": { "type": "object", "properties": generate, "additionalProperties": false }
+        },
+        {
+            "name": "check",
+            "description": "Score a password, suggest improvements and test it against a policy",
+            "inputSchema": {
+                "type": "object",
+                "properties": check,
+                "required": ["password"],
+                "additionalProperties": false
+            }
+        },
+        {
+            "name": "policy",
+            "description": "Describe what a password policy allows and the entropy of a compliant password",
+            "inputSchema": { "type": "object", "properties": policy_properties(), "additionalProperties": false }
+        }
+    ])
+}
+
+fn invalid(message: impl Into<String>) -> PasswordGeneratorError {
+    PasswordGeneratorError::InvalidConfig(message.into())
+}
+
+fn optional_u64(args: &Value, name: &str, default: u64, max: u64) -> Result<u64> {
+    match args.get(name) {
+        None | Some(Value::Null) => Ok(default),
+        Some(value) => value
+            .as_u64()
+            .filter(|n| (1..=max).contains(n))
+            .ok_or_else(|| invalid(format!("'{}' must be an integer between 1 and {}", name, max))),
+    }
+}
+
+fn optional_str<'a>(args: &'a Value, name: &str) -> Result<Option<&'a str>> {
+    match args.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => Err(invalid(format!("'{}' must be a string", name))),
+    }
+}
+
+/// The policy of `site` and `rules` together, the same one `generate` applies.
+fn policy_rules(args: &Value) -> Result<Option<PasswordRules>> {
+    let rules = optional_str(args, "rules")?.map(parse_password_rules).transpose()?;
+    match optional_str(args, "site")? {
+        Some(site) => find_site(site)?.policy(rules.as_ref()).map(Some),
+        None => Ok(rules),
+    }
+}
+
+fn tool_config(args: &Value) -> Result<PasswordGeneratorConfig> {
+    let mut config = PasswordGeneratorConfig::new();
+    config.length = optional_u64(args, "length", 16, MAX_LENGTH)? as usize;
+    config.num_passwords = optional_u64(args, "count", 1, MAX_COUNT)? as usize;
+    if let Some(charsets) = optional_str(args, "charsets")? {
+        config.clear_allowed_chars();
+        for charset in charsets.split(',').map(str::trim) {
+            if crate::config::charset_chars(charset).is_err() {
+                return Err(invalid(format!("Unknown characterset '{}'", charset)));
+            }
+            config.add_allowed_chars(charset);
+        }
+    }
+    let rules = optional_str(args, "rules")?.map(parse_password_rules).transpose()?;
+    match optional_str(args, "site")? {
+        Some(site) => find_site(site)?.apply(&mut config, rules.as_ref())?,
+        None => {
+            if let Some(rules) = rules {
+                rules.apply(&mut config);
+            }
+        }
+    }
+    config.validate()?;
+    config.min_entropy_bits = Some(Settings::load()?.min_bits.unwrap_or(DEFAULT_MIN_BITS));
+    Ok(config)
+}
+
+async fn call_generate(args: &Value) -> Result<Value> {
+    let mut config = tool_config(args)?;
+    let passphrase = args.get("passphrase").and_then(Value::as_bool).unwrap_or(false);
+    if passphrase {
+        config.set_use_words(true);
+        config.separator = Some(Separator::Fixed(" ".to_string()));
+        let wordlist = get_wordlist().await?;
+        config.check_entropy_floor(config.passphrase_entropy_bits(wordlist.len()))?;
+        let passphrases = generate_allowed_passphrases(&wordlist, &config).await?;
+        return Ok(json!({
+            "passphrases": passphrases,
+            "entropy_bits": config.passphrase_entropy_bits(wordlist.len()),
+        }));
+    }
+    config.check_entropy_floor(config.entropy_bits())?;
+    let passwords = generate_strong_passwords(&config).await?;
+    Ok(json!({
+        "passwords": passwords,
+        "entropy_bits": config.entropy_bits(),
+    }))
+}
+
+fn call_check(args: &Value) -> Result<Value> {
+    let password = optional_str(args, "password")?
+        .ok_or_else(|| invalid("'password' is required"))?;
+    let policy = policy_rules(args)?;
+    Ok(check_report(password, policy.as_ref(), ScoreScale::Npwg))
+}
+
+fn call_policy(args: &Value) -> Result<Value> {
+    let Some(rules) = policy_rules(args)? else {
+        return Err(invalid("Pass 'rules' or 'site' to describe a policy"));
+    };
+    let config = tool_config(args)?;
+    Ok(json!({
+        "min_length": rules.min_length,
+        "max_length": rules.max_length,
+        "required_classes": rules
+            .required
+            .iter()
+            .map(|class| class.iter().collect::<String>())
+            .collect::<Vec<_>>(),
+        "allowed": config.available_chars().iter().collect::<String>(),
+        "max_consecutive": rules.max_consecutive,
+        "generated_length": config.length,
+        "pool_size": config.pool_size(),
+        "entropy_bits": config.entropy_bits(),
+    }))
+}
+
+async fn call_tool(params: &Value) -> std::result::Result<Value, (i64, String)> {
+    let name = params.get("name").and_then(Value::as_str).unwrap_or_default();
+    let args = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+    let result = match name {
+        "generate" => call_generate(&args).await,
+        "check" => call_check(&args),
+        "policy" => call_policy(&args),
+        _ => return Err((INVALID_PARAMS, format!("Unknown tool '{}'", name))),
+    };
+    Ok(match result {
+        Ok(mut value) => {
+            let text = value.to_string();
+            value.zeroize_strings();
+            json!({ "content": [{ "type": "text", "text": text }], "isError": false })
+        }
+        Err(error) => json!({
+            "content": [{ "type": "text", "text": error.to_string() }],
+            "isError": true
+        }),
+    })
+}
+
+/// Wipes every string in a JSON value once it has been written out.
+pub trait ZeroizeStrings {
+    fn zeroize_strings(&mut self);
+}
+
+impl ZeroizeStrings for Value {
+    fn zeroize_strings(&mut self) {
+        match self {
+            Value::String(text) => text.zeroize(),
+            Value::Array(items) => items.iter_mut().for_each(Value::zeroize_strings),
+            Value::Object(map) => map.values_mut().for_each(Value::zeroize_strings),
+            _ => {}
+        }
+    }
+}
+
+/// Answers one JSON-RPC message. Notifications get no response.
+pub async fn handle_message(message: &Value) -> Option<Value> {
+    let id = message.get("id").cloned();
+    let Some(method) = message.get("method").and_then(Value::as_str) else {
+        return Some(error_response(id.unwrap_or(Value::Null), INVALID_REQUEST, "Invalid request"));
+    };
+    let id = id?;
+    let params = message.get("params").cloned().unwrap_or_else(|| json!({}));
+    debug!(method, "mcp request");
+    let result = match method {
+        "initialize" => {
+            let requested = params.get("protocolVersion").and_then(Value::as_str);
+            let version = requested
+                .filter(|version| SUPPORTED_VERSIONS.contains(version))
+                .unwrap_or(SUPPORTED_VERSIONS[0]);
+            Ok(json!({
+                "protocolVersion": version,
+                "capabilities": { "tools": { "listChanged": false } },
+                "serverInfo": { "name": "npwg", "version": env!("CARGO_PKG_VERSION") }
+            }))
+        }
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+        "tools/call" => call_tool(&params).await,
+        _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+    };
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, &message),
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Serves the Model Context Protocol over newline-delimited JSON-RPC until
+/// the input closes. Only protocol messages are written to `output`.
+pub async fn serve(input: impl BufRead, mut output: impl Write) -> Result<()> {
+    for line in input.lines() {
+        let mut line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle_message(&message).await,
+            Err(error) => Some(error_response(Value::Null, PARSE_ERROR, &error.to_string())),
+        };
+        line.zeroize();
+        if let Some(mut response) = response {
+            let mut text = response.to_string();
+            response.zeroize_strings();
+            writeln!(output, "{}", text)?;
+            output.flush()?;
+            text.zeroize();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn call(message: Value) -> Value {
+        handle_message(&message).await.unwrap()
+    }
+
+    fn tool_payload(response: &Value) -> Value {
+        let text = response["result"]["content"][0]["text"].as_str().unwrap();
+        serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+    }
+
+    #[tokio::test]
+    async fn test_initialize_and_list_tools() {
+        let response = call(json!({
+            "jsonrpc": "2.0", "id": 1, "method": "initialize",
+            "params": { "protocolVersion": "2024-11-05" }
+        }))
+        .await;
+        assert_eq!(response["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(response["result"]["serverInfo"]["name"], "npwg");
+
+        assert!(handle_message(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .await
+            .is_none());
+
+        let tools = call(json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" })).await;
+        let names: Vec<&str> = tools["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["generate", "check", "policy"]);
+
+        let missing = call(json!({ "jsonrpc": "2.0", "id": 3, "method": "resources/list" })).await;
+        assert_eq!(missing["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_tools() {
+        let generated = call(json!({
+            "jsonrpc": "2.0", "id": 1, "method": "tools/call",
+            "params": { "name": "generate", "arguments": {
+                "count": 3, "rules": "minlength: 20; required: digit; allowed: lower"
+            } }
+        }))
+        .await;
+        assert_eq!(generated["result"]["isError"], false);
+        let passwords = tool_payload(&generated)["passwords"].clone();
+        assert_eq!(passwords.as_array().unwrap().len(), 3);
+        for password in passwords.as_array().unwrap() {
+            let password = password.as_str().unwrap();
+            assert_eq!(password.len(), 20);
+            assert!(password.chars().any(|c| c.is_ascii_digit()));
+        }
+
+        let checked = call(json!({
+            "jsonrpc": "2.0", "id": 2, "method": "tools/call",
+            "params": { "name": "check", "arguments": { "password": "abc", "rules": "minlength: 8" } }
+        }))
+        .await;
+        assert_eq!(tool_payload(&checked)["policy"]["compliant"], false);
+
+        let policy = call(json!({
+            "jsonrpc": "2.0", "id": 3, "method": "tools/call",
+            "params": { "name": "policy", "arguments": { "rules": "minlength: 12; allowed: digit" } }
+        }))
+        .await;
+        assert_eq!(tool_payload(&policy)["pool_size"], 10);
+
+        let failed = call(json!({
+            "jsonrpc": "2.0", "id": 4, "method": "tools/call",
+            "params": { "name": "generate", "arguments": { "count": 0 } }
+        }))
+        .await;
+        assert_eq!(failed["result"]["isError"], true);
+
+        let weak = call(json!({
+            "jsonrpc": "2.0", "id": 5, "method": "tools/call",
+            "params": { "name": "generate", "arguments": { "length": 4, "charsets": "digit" } }
+        }))
+        .await;
+        assert_eq!(weak["result"]["isError"], true);
+    }
+
+    #[tokio::test]
+    async fn test_serve_answers_each_line() {
+        let input = b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\nnot json\n";
+        let mut output = Vec::new();
+        serve(&input[..], &mut output).await.unwrap();
+        let lines: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines[0]["result"], json!({}));
+        assert_eq!(lines[1]["error"]["code"], PARSE_ERROR);
+    }
+}
@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/memorize.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use std::time::Duration;
+use zeroize::Zeroize;
+
+/// How one attempt at typing the secret from memory went.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    /// Typed correctly, with the time of the previous correct attempt.
+    Correct {
+        took: Duration,
+        previous: Option<Duration>,
+    },
+    /// Wrong from the character at this 1-based position on.
+    Wrong { position: usize },
+}
+
+/// A typing drill: the secret has to be typed correctly `rounds` times.
+pub struct Drill {
+    secret: String,
+    rounds: usize,
+    times: Vec<Duration>,
+    mistakes: usize,
+}
+
+impl Drill {
+    pub fn new(secret: String, rounds: usize) -> Self {
+        Drill {
+            secret,
+            rounds,
+            times: Vec::new(),
+            mistakes: 0,
+        }
+    }
+
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    pub fn attempt(&mut self, typed: &str, took: Duration) -> Outcome {
+        if typed == self.secret {
+            let previous = self.times.last().copied();
+            self.times.push(took);
+            return Outcome::Correct { took, previous };
+        }
+        self.mistakes += 1;
+        let position = typed
+            .chars()
+            .zip(self.secret.chars())
+            .take_while(|(typed, expected)| typed == expected)
+            .count();
+        Outcome::Wrong { position: position + 1 }
+    }
+
+    pub fn correct(&self) -> usize {
+        self.times.len()
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.rounds
+    }
+
+    pub fn mistakes(&self) -> usize {
+        self.mistakes
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.correct() >= self.rounds
+    }
+
+    pub fn fastest(&self) -> Option<Duration> {
+        self.times.iter().min().copied()
+    }
+}
+
+impl Drop for Drill {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drill() {
+        let mut drill = Drill::new("correct horse".to_string(), 2);
+        assert_eq!(
+            drill.attempt("correct house", Duration::from_secs(9)),
+            Outcome::Wrong { position: 11 }
+        );
+        assert_eq!(
+            drill.attempt("correct horse", Duration::from_secs(6)),
+            Outcome::Correct { took: Duration::from_secs(6), previous: None }
+        );
+        assert!(!drill.is_done());
+        assert_eq!(drill.attempt("", Duration::from_secs(1)), Outcome::Wrong { position: 1 });
+        assert_eq!(
+            drill.attempt("correct horse", Duration::from_secs(4)),
+            Outcome::Correct { took: Duration::from_secs(4), previous: Some(Duration::from_secs(6)) }
+        );
+        assert!(drill.is_done());
+        assert_eq!(drill.mistakes(), 2);
+        assert_eq!(drill.fastest(), Some(Duration::from_secs(4)));
+        // A prefix of the secret is wrong right after its last character.
+        assert_eq!(drill.attempt("correct", Duration::ZERO), Outcome::Wrong { position: 8 });
+    }
+}
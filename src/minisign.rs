@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/minisign.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::{PasswordGeneratorError, Result, WordlistErrorKind};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use minisign_verify::{Error as VerifyError, Signature};
+
+const KEY_ID_LENGTH: usize = 8;
+
+/// A minisign public key: the ed25519 key plus the id that signatures name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublicKey {
+    key_id: [u8; KEY_ID_LENGTH],
+    key: minisign_verify::PublicKey,
+}
+
+fn invalid(reason: &str) -> PasswordGeneratorError {
+    PasswordGeneratorError::wordlist(
+        WordlistErrorKind::Signature,
+        format!("signature check failed: {}", reason),
+    )
+}
+
+impl PublicKey {
+    /// Accepts the contents of a `minisign.pub` file or just its key line.
+    pub fn parse(text: &str) -> Result<Self> {
+        let line = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with("untrusted comment:"))
+            .ok_or_else(|| invalid("the public key is empty"))?;
+        let bytes = STANDARD
+            .decode(line)
+            .map_err(|_| invalid("the public key is not valid base64"))?;
+        if bytes.len() != 2 + KEY_ID_LENGTH + 32 || &bytes[..2] != b"Ed" {
+            return Err(invalid("the public key is not a minisign ed25519 key"));
+        }
+        let key = minisign_verify::PublicKey::from_base64(line)
+            .map_err(|_| invalid("the public key is not a minisign ed25519 key"))?;
+        Ok(PublicKey {
+            key_id: bytes[2..10].try_into().unwrap(),
+            key,
+        })
+    }
+
+    /// The key id in the upper-case hex form minisign prints.
+    pub fn key_id(&self) -> String {
+        self.key_id.iter().rev().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    /// Checks a `.minisig` signature over `message` and returns its trusted
+    /// comment. Both legacy (`Ed`) and prehashed (`ED`) signatures are accepted.
+    pub fn verify(&self, message: &[u8], signature: &str) -> Result<String> {
+        let lines: Vec<&str> = signature.lines().map(str::trim_end).collect();
+        let signature = Signature::decode(&lines.join("\n")).map_err(|error| match error {
+            VerifyError::UnsupportedAlgorithm => invalid("the signature uses an unknown algorithm"),
+            _ => invalid("the signature file is not a minisign signature"),
+        })?;
+        self.key.verify(message, &signature, true).map_err(|error| match error {
+            VerifyError::UnexpectedKeyId => invalid(&format!(
+                "the signature was made with a different key than {}",
+                self.key_id()
+            )),
+            _ => invalid("the signature does not match the wordlist and its trusted comment"),
+        })?;
+        Ok(signature.trusted_comment().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBLIC_KEY: &str = "untrusted comment: minisign public key 0807060504030201\nRWQBAgMEBQYHCAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4\n";
+    const WORDLIST: &[u8] = b"11111\tabacus\n11112\tabdomen\n";
+    const PREHASHED: &str = "untrusted comment: signature from minisign secret key\nRUQBAgMEBQYHCOjOYfOAImVK7LIMGyqSkp6MERO4+M1djLWoZLztSHmhOEnTnGO3evmDCoZ53LqebYvXnSdtDNJvjGpBp3Z2hAQ=\ntrusted comment: timestamp:1700000000\tfile:wordlist.txt\thashed\nBkG4ccK9PuRNfipyiGKRWIuLl+SAFNCWOOUA3Px3QJMJlqfFlt6aCsmzKvXdJt98deICv+a6h01LL27V1UVpDA==\n";
+    const LEGACY: &str = "untrusted comment: signature from minisign secret key\nRWQBAgMEBQYHCJ1dfdq6/jBKMNIeDuI3YiO+7vpi2kvJOE66iPuCCngD66Uf6TvkzTIiTdzP+Zmu/hKjOh9niL1kw3E0gnZ4Zgw=\ntrusted comment: timestamp:1700000000\tfile:wordlist.txt\thashed\nxX5pBUuW22eoTBR2hVd6xVmVHkizFcGH07/W1gkFAd67biMN8N8y9kUAlLg/tziDgEvy/0J5Ymj0lMH1WpurBQ==\n";
+
+    #[test]
+    fn test_verify_minisign_signatures() {
+        let key = PublicKey::parse(PUBLIC_KEY).unwrap();
+        assert_eq!(key.key_id(), "0807060504030201");
+        for signature in [PREHASHED, LEGACY] {
+            let comment = key.verify(WORDLIST, signature).unwrap();
+            assert!(comment.starts_with("timestamp:1700000000"));
+        }
+        assert!(key.verify(b"11111\tabacus\n", PREHASHED).is_err());
+        let tampered = PREHASHED.replace("file:wordlist.txt", "file:other.txt");
+        assert!(key.verify(WORDLIST, &tampered).is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_other_keys() {
+        assert!(PublicKey::parse("untrusted comment: x\n").is_err());
+        assert!(PublicKey::parse("not base64!").is_err());
+        assert!(PublicKey::parse(&STANDARD.encode([0u8; 42])).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/native.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::{PasswordGeneratorError, Result};
+use serde_json::json;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Name of the host in the manifest, as an extension passes it to
+/// `runtime.connectNative`.
+pub const HOST_NAME: &str = "de.schwaberow.npwg";
+
+/// Browsers may send up to 4 GiB; a password request is never near this.
+const MAX_REQUEST: u32 = 64 * 1024;
+/// Chrome and Firefox refuse larger messages from a host.
+const MAX_RESPONSE: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Browser {
+    Chrome,
+    Chromium,
+    Edge,
+    Firefox,
+}
+
+impl std::fmt::Display for Browser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Browser::Chrome => write!(f, "chrome"),
+            Browser::Chromium => write!(f, "chromium"),
+            Browser::Edge => write!(f, "edge"),
+            Browser::Firefox => write!(f, "firefox"),
+        }
+    }
+}
+
+impl std::str::FromStr for Browser {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "chrome" => Ok(Browser::Chrome),
+            "chromium" => Ok(Browser::Chromium),
+            "edge" => Ok(Browser::Edge),
+            "firefox" => Ok(Browser::Firefox),
+            _ => Err(format!(
+                "Invalid browser: {} (use chrome, chromium, edge or firefox)",
+                s
+            )),
+        }
+    }
+}
+
+fn invalid(message: String) -> PasswordGeneratorError {
+    PasswordGeneratorError::InvalidConfig(message)
+}
+
+/// Reads one message: a 32-bit length in native byte order, then that many
+/// bytes of JSON. Returns None once the browser closes the pipe.
+pub fn read_message(reader: &mut impl Read) -> Result<Option<Vec<u8>>> {
+    let mut length = [0u8; 4];
+    match reader.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let length = u32::from_ne_bytes(length);
+    if length > MAX_REQUEST {
+        return Err(invalid(format!(
+            "Native message of {} bytes exceeds the limit of {} bytes",
+            length, MAX_REQUEST
+        )));
+    }
+    let mut message = vec![0u8; length as usize];
+    reader.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+pub fn write_message(writer: &mut impl Write, message: &str) -> Result<()> {
+    if message.len() > MAX_RESPONSE {
+        return Err(invalid(format!(
+            "Response of {} bytes exceeds the browser limit of {} bytes; request fewer passwords",
+            message.len(),
+            MAX_RESPONSE
+        )));
+    }
+    writer.write_all(&(message.len() as u32).to_ne_bytes())?;
+    writer.write_all(message.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Chrome-based browsers identify an extension by 32 letters from a to p;
+/// Firefox uses the id from the extension's manifest, such as
+/// `npwg@example.org`.
+fn validate_extension_id(browser: Browser, id: &str) -> Result<()> {
+    let valid = match browser {
+        Browser::Firefox => !id.is_empty() && !id.chars().any(|c| c.is_whitespace() || c == '"'),
+        _ => id.len() == 32 && id.chars().all(|c| ('a'..='p').contains(&c)),
+    };
+    if !valid {
+        return Err(invalid(format!("'{}' is not a valid {} extension id", id, browser)));
+    }
+    Ok(())
+}
+
+/// The host manifest letting only `extension_id` start `path`.
+pub fn manifest(browser: Browser, path: &Path, extension_id: &str) -> serde_json::Value {
+    let mut manifest = json!({
+        "name": HOST_NAME,
+        "description": "Generate passwords with npwg",
+        "path": path,
+        "type": "stdio",
+    });
+    match browser {
+        Browser::Firefox => manifest["allowed_extensions"] = json!([extension_id]),
+        _ => {
+            manifest["allowed_origins"] = json!([format!("chrome-extension://{}/", extension_id)])
+        }
+    }
+    manifest
+}
+
+/// Where the browser looks for host manifests of the current user.
+fn manifest_dir(browser: Browser, home: &Path) -> PathBuf {
+    if cfg!(target_os = "macos") {
+        let support = home.join("Library").join("Application Support");
+        match browser {
+            Browser::Chrome => support.join("Google").join("Chrome"),
+            Browser::Chromium => support.join("Chromium"),
+            Browser::Edge => support.join("Microsoft Edge"),
+            Browser::Firefox => support.join("Mozilla"),
+        }
+        .join("NativeMessagingHosts")
+    } else {
+        match browser {
+            Browser::Chrome => home.join(".config").join("google-chrome").join("NativeMessagingHosts"),
+            Browser::Chromium => home.join(".config").join("chromium").join("NativeMessagingHosts"),
+            Browser::Edge => home.join(".config").join("microsoft-edge").join("NativeMessagingHosts"),
+            Browser::Firefox => home.join(".mozilla").join("native-messaging-hosts"),
+        }
+    }
+}
+
+/// Installs the host for `browser` under `home`: a launcher in
+/// `~/.npwg/native-messaging` that runs `exe native-messaging`, since a
+/// manifest cannot pass arguments, and the manifest pointing at it. Returns
+/// the manifest path.
+#[cfg(unix)]
+pub fn install(browser: Browser, extension_id: &str, exe: &Path, home: &Path) -> Result<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    validate_extension_id(browser, extension_id)?;
+    let launcher_dir = home.join(".npwg").join("native-messaging");
+    std::fs::create_dir_all(&launcher_dir)?;
+    let launcher = launcher_dir.join("npwg-host");
+    let quoted = exe.display().to_string().replace('\'', r"'\''");
+    std::fs::write(
+        &launcher,
+        format!("#!/bin/sh\nexec '{}' native-messaging \"$@\"\n", quoted),
+    )?;
+    std::fs::set_permissions(&launcher, std::fs::Permissions::from_mode(0o755))?;
+
+    let dir = manifest_dir(browser, home);
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.json", HOST_NAME));
+    let text = serde_json::to_string_pretty(&manifest(browser, &launcher, extension_id))
+        .map_err(std::io::Error::other)?;
+    std::fs::write(&path, text + "\n")?;
+    Ok(path)
+}
+
+#[cfg(not(unix))]
+pub fn install(browser: Browser, extension_id: &str, _exe: &Path, _home: &Path) -> Result<PathBuf> {
+    validate_extension_id(browser, extension_id)?;
+    Err(invalid(
+        "Installing the native messaging host needs a registry entry on Windows; register a manifest by hand"
+            .to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages() {
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, r#"{"length":20}"#).unwrap();
+        assert_eq!(&buffer[..4], &13u32.to_ne_bytes());
+
+        let mut reader = &buffer[..];
+        assert_eq!(read_message(&mut reader).unwrap().unwrap(), br#"{"length":20}"#);
+        assert!(read_message(&mut reader).unwrap().is_none());
+
+        let oversized = (MAX_REQUEST + 1).to_ne_bytes();
+        assert!(read_message(&mut &oversized[..]).is_err());
+        let truncated = [&5u32.to_ne_bytes()[..], b"{}"].concat();
+        assert!(read_message(&mut &truncated[..]).is_err());
+        assert!(write_message(&mut Vec::new(), &"x".repeat(MAX_RESPONSE + 1)).is_err());
+    }
+
+    #[test]
+    fn test_manifest() {
+        let chrome = manifest(Browser::Chrome, Path::new("/opt/npwg-host"), "abcdefghijklmnopabcdefghijklmnop");
+        assert_eq!(chrome["name"], HOST_NAME);
+        assert_eq!(chrome["type"], "stdio");
+        assert_eq!(chrome["allowed_origins"][0], "chrome-extension://abcdefghijklmnopabcdefghijklmnop/");
+        let firefox = manifest(Browser::Firefox, Path::new("/opt/npwg-host"), "npwg@example.org");
+        assert_eq!(firefox["allowed_extensions"][0], "npwg@example.org");
+        assert!(firefox.get("allowed_origins").is_none());
+
+        assert!(validate_extension_id(Browser::Chrome, "abcdefghijklmnopabcdefghijklmnop").is_ok());
+        assert!(validate_extension_id(Browser::Edge, "npwg@example.org").is_err());
+        assert!(validate_extension_id(Browser::Firefox, "npwg@example.org").is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path();
+        let path = install(Browser::Firefox, "npwg@example.org", Path::new("/usr/bin/np'wg"), home).unwrap();
+        let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let launcher = std::fs::read_to_string(written["path"].as_str().unwrap()).unwrap();
+
+        assert_eq!(path, manifest_dir(Browser::Firefox, home).join("de.schwaberow.npwg.json"));
+        assert_eq!(launcher, "#!/bin/sh\nexec '/usr/bin/np'\\''wg' native-messaging \"$@\"\n");
+    }
+}
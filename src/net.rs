@@ -0,0 +1,519 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/net.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+#![cfg_attr(not(feature = "download"), allow(dead_code))]
+
+use crate::error::{PasswordGeneratorError, Result};
+#[cfg(feature = "download")]
+use crate::progress::{Progress, Unit};
+#[cfg(feature = "download")]
+use std::error::Error as _;
+#[cfg(feature = "download")]
+use std::io::Write;
+use std::path::Path;
+#[cfg(feature = "download")]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+#[cfg(feature = "download")]
+use tracing::{debug, warn};
+
+static OPTIONS: OnceLock<DownloadOptions> = OnceLock::new();
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DownloadOptions {
+    pub timeout: Duration,
+    pub retries: u32,
+    pub backoff: Duration,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        DownloadOptions {
+            timeout: Duration::from_secs(15),
+            retries: 3,
+            backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+impl DownloadOptions {
+    /// Delay before retry number `attempt` (starting at 1), doubling each time.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+    }
+}
+
+pub fn set_download_options(options: DownloadOptions) {
+    let _ = OPTIONS.set(options);
+}
+
+pub fn download_options() -> DownloadOptions {
+    OPTIONS.get().copied().unwrap_or_default()
+}
+
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Fails with an `Offline` error naming `action` when `--offline` is set.
+pub fn ensure_online(action: &str) -> Result<()> {
+    if is_offline() {
+        return Err(PasswordGeneratorError::Offline(action.to_string()));
+    }
+    Ok(())
+}
+
+/// Why a download failed, separated so users can tell a missing network from a
+/// proxy intercepting TLS or a server-side problem.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadFailure {
+    Dns(String),
+    Tls(String),
+    Connect(String),
+    Timeout,
+    HttpStatus(u16),
+    Other(String),
+}
+
+impl std::fmt::Display for DownloadFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DownloadFailure::Dns(detail) => write!(f, "DNS lookup failed ({})", detail),
+            DownloadFailure::Tls(detail) => write!(f, "TLS handshake failed ({})", detail),
+            DownloadFailure::Connect(detail) => write!(f, "connection failed ({})", detail),
+            DownloadFailure::Timeout => write!(f, "timed out"),
+            DownloadFailure::HttpStatus(status) => write!(f, "server answered HTTP {}", status),
+            DownloadFailure::Other(detail) => write!(f, "{}", detail),
+        }
+    }
+}
+
+impl DownloadFailure {
+    #[cfg(feature = "download")]
+    fn from_reqwest(error: &reqwest::Error) -> Self {
+        if error.is_timeout() {
+            return DownloadFailure::Timeout;
+        }
+        if let Some(status) = error.status() {
+            return DownloadFailure::HttpStatus(status.as_u16());
+        }
+        let mut chain = Vec::new();
+        let mut source = error.source();
+        while let Some(cause) = source {
+            chain.push(cause.to_string());
+            source = cause.source();
+        }
+        let detail = chain.last().cloned().unwrap_or_else(|| error.to_string());
+        let lowered = chain.join(": ").to_lowercase();
+        if lowered.contains("dns") || lowered.contains("lookup address") {
+            DownloadFailure::Dns(detail)
+        } else if ["tls", "ssl", "certificate", "handshake"]
+            .iter()
+            .any(|needle| lowered.contains(needle))
+        {
+            DownloadFailure::Tls(detail)
+        } else if error.is_connect() {
+            DownloadFailure::Connect(detail)
+        } else {
+            DownloadFailure::Other(detail)
+        }
+    }
+
+    /// Whether trying again could help: name resolution and TLS problems
+    /// persist, while timeouts, dropped connections and 5xx/429 often do not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            DownloadFailure::Timeout | DownloadFailure::Connect(_) | DownloadFailure::Other(_) => {
+                true
+            }
+            DownloadFailure::HttpStatus(status) => *status == 429 || *status >= 500,
+            DownloadFailure::Dns(_) | DownloadFailure::Tls(_) => false,
+        }
+    }
+}
+
+#[cfg(feature = "download")]
+async fn fetch_once(client: &reqwest::Client, url: &str) -> std::result::Result<String, reqwest::Error> {
+    let mut response = client.get(url).send().await?.error_for_status()?;
+    let mut progress = Progress::new("Downloading", response.content_length(), Unit::Bytes);
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        body.extend_from_slice(&chunk);
+        progress.inc(chunk.len() as u64);
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+#[cfg(feature = "download")]
+/// GETs `url` as text with the configured timeout, retrying transient
+/// failures with exponential backoff.
+pub async fn fetch_text(url: &str) -> Result<String> {
+    ensure_online(&format!("downloading {}", url))?;
+    let options = download_options();
+    let client = reqwest::Client::builder().timeout(options.timeout).build()?;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match fetch_once(&client, url).await {
+            Ok(body) => {
+                debug!(url, attempt, bytes = body.len(), "download complete");
+                return Ok(body);
+            }
+            Err(error) => {
+                let failure = DownloadFailure::from_reqwest(&error);
+                if attempt > options.retries || !failure.is_retryable() {
+                    return Err(PasswordGeneratorError::Download {
+                        url: url.to_string(),
+                        attempts: attempt,
+                        failure,
+                    });
+                }
+                let delay = options.delay(attempt);
+                warn!(url, attempt, %failure, delay_ms = delay.as_millis() as u64, "download failed, retrying");
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "download")]
+/// Where a resumable download keeps the `ETag` or `Last-Modified` value of
+/// the partial file, so a resumed range is known to belong to the same file.
+fn validator_path(partial: &Path) -> PathBuf {
+    let mut path = partial.as_os_str().to_owned();
+    path.push(".validator");
+    PathBuf::from(path)
+}
+
+#[cfg(feature = "download")]
+/// A validator usable in `If-Range`: a strong `ETag`, else `Last-Modified`.
+fn response_validator(response: &reqwest::Response) -> Option<String> {
+    let headers = response.headers();
+    headers
+        .get(reqwest::header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| {
+            headers
+                .get(reqwest::header::LAST_MODIFIED)
+                .and_then(|value| value.to_str().ok())
+        })
+        .map(str::to_string)
+}
+
+#[cfg(feature = "download")]
+/// The first byte of a `Content-Range: bytes <start>-<end>/<size>` answer.
+fn content_range_start(response: &reqwest::Response) -> Option<u64> {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .parse()
+        .ok()
+}
+
+#[cfg(feature = "download")]
+/// Bytes of `partial` worth resuming from: none unless its validator was
+/// saved and `is_valid_prefix` accepts what is there.
+fn resumable_prefix(partial: &Path, is_valid_prefix: fn(&[u8]) -> bool) -> Option<(u64, String)> {
+    let validator = std::fs::read_to_string(validator_path(partial)).ok()?;
+    let contents = std::fs::read(partial).ok()?;
+    if contents.is_empty() || !is_valid_prefix(&contents) {
+        debug!(path = %partial.display(), "discarding partial download");
+        return None;
+    }
+    Some((contents.len() as u64, validator.trim().to_string()))
+}
+
+#[cfg(feature = "download")]
+/// One attempt at fetching into `partial`. Local I/O errors end the download;
+/// network errors are handed back for the retry decision, keeping whatever
+/// arrived so the next attempt can resume from there.
+async fn fetch_once_to_file(
+    client: &reqwest::Client,
+    url: &str,
+    partial: &Path,
+    is_valid_prefix: fn(&[u8]) -> bool,
+) -> Result<std::result::Result<(), reqwest::Error>> {
+    let resume = resumable_prefix(partial, is_valid_prefix);
+    let mut request = client.get(url);
+    if let Some((offset, validator)) = &resume {
+        debug!(url, offset, "resuming download");
+        request = request
+            .header(reqwest::header::RANGE, format!("bytes={}-", offset))
+            .header(reqwest::header::IF_RANGE, validator.as_str());
+    }
+    let mut response = match request.send().await {
+        Ok(response) => response,
+        Err(error) => return Ok(Err(error)),
+    };
+    if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        // The partial file is at least as long as the resource, so it cannot
+        // be a prefix of it; the next attempt starts over.
+        let _ = std::fs::remove_file(validator_path(partial));
+        let _ = std::fs::remove_file(partial);
+    }
+    if let Err(error) = response.error_for_status_ref() {
+        return Ok(Err(error));
+    }
+
+    let offset = match resume {
+        Some((offset, _))
+            if response.status() == reqwest::StatusCode::PARTIAL_CONTENT
+                && content_range_start(&response) == Some(offset) =>
+        {
+            offset
+        }
+        _ => 0,
+    };
+    let mut file = if offset > 0 {
+        std::fs::OpenOptions::new().append(true).open(partial)?
+    } else {
+        if let Some(parent) = partial.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        match response_validator(&response) {
+            Some(validator) => std::fs::write(validator_path(partial), validator)?,
+            None => {
+                let _ = std::fs::remove_file(validator_path(partial));
+            }
+        }
+        std::fs::File::create(partial)?
+    };
+
+    let total = response.content_length().map(|length| length + offset);
+    let mut progress = Progress::new("Downloading", total, Unit::Bytes);
+    progress.inc(offset);
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                file.write_all(&chunk)?;
+                progress.inc(chunk.len() as u64);
+            }
+            Ok(None) => return Ok(Ok(())),
+            Err(error) => return Ok(Err(error)),
+        }
+    }
+}
+
+#[cfg(feature = "download")]
+/// Downloads `url` into the file `partial` with the configured timeout and
+/// retries. An interrupted transfer, whether in this run or an earlier one,
+/// resumes with an HTTP range request when the server supports it and the
+/// bytes already there pass `is_valid_prefix`; otherwise it starts over.
+/// The caller validates the finished file and moves it into place.
+pub async fn fetch_to_file(url: &str, partial: &Path, is_valid_prefix: fn(&[u8]) -> bool) -> Result<()> {
+    ensure_online(&format!("downloading {}", url))?;
+    let options = download_options();
+    let client = reqwest::Client::builder().timeout(options.timeout).build()?;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match fetch_once_to_file(&client, url, partial, is_valid_prefix).await? {
+            Ok(()) => {
+                let _ = std::fs::remove_file(validator_path(partial));
+                debug!(url, attempt, "download complete");
+                return Ok(());
+            }
+            Err(error) => {
+                let failure = DownloadFailure::from_reqwest(&error);
+                if failure == DownloadFailure::HttpStatus(416) && attempt <= options.retries {
+                    debug!(url, "partial download rejected by the server, starting over");
+                    continue;
+                }
+                if attempt > options.retries || !failure.is_retryable() {
+                    return Err(PasswordGeneratorError::Download {
+                        url: url.to_string(),
+                        attempts: attempt,
+                        failure,
+                    });
+                }
+                let delay = options.delay(attempt);
+                warn!(url, attempt, %failure, delay_ms = delay.as_millis() as u64, "download failed, retrying");
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Fails with a `DownloadUnavailable` error naming `action` in builds
+/// without the `download` feature, after the `--offline` check.
+pub fn ensure_download(action: &str) -> Result<()> {
+    ensure_online(action)?;
+    if cfg!(feature = "download") {
+        Ok(())
+    } else {
+        Err(PasswordGeneratorError::DownloadUnavailable(action.to_string()))
+    }
+}
+
+#[cfg(not(feature = "download"))]
+pub async fn fetch_text(url: &str) -> Result<String> {
+    Err(download_unavailable(url))
+}
+
+#[cfg(not(feature = "download"))]
+pub async fn fetch_to_file(url: &str, _partial: &Path, _is_valid_prefix: fn(&[u8]) -> bool) -> Result<()> {
+    Err(download_unavailable(url))
+}
+
+#[cfg(not(feature = "download"))]
+fn download_unavailable(url: &str) -> PasswordGeneratorError {
+    let action = format!("downloading {}", url);
+    match ensure_online(&action) {
+        Err(offline) => offline,
+        Ok(()) => PasswordGeneratorError::DownloadUnavailable(action),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Held by tests that download or toggle offline mode, which is global.
+    static NETWORK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    #[test]
+    fn test_backoff_doubles() {
+        let options = DownloadOptions::default();
+        assert_eq!(options.delay(1), Duration::from_millis(500));
+        assert_eq!(options.delay(2), Duration::from_millis(1000));
+        assert_eq!(options.delay(4), Duration::from_millis(4000));
+    }
+
+    #[tokio::test]
+    async fn test_offline_blocks_downloads() {
+        let _network = NETWORK.lock().await;
+        set_offline(true);
+        let result = fetch_text("https://example.invalid/list.txt").await;
+        set_offline(false);
+        assert!(matches!(result, Err(PasswordGeneratorError::Offline(action)) if action.contains("example.invalid")));
+    }
+
+    #[cfg(not(feature = "download"))]
+    #[tokio::test]
+    async fn test_builds_without_download_refuse() {
+        let _network = NETWORK.lock().await;
+        let error = fetch_text("https://example.invalid/list.txt").await.unwrap_err();
+        assert_eq!(error.exit_code(), 4);
+        assert!(matches!(error, PasswordGeneratorError::DownloadUnavailable(action) if action.contains("example.invalid")));
+    }
+
+    #[cfg(feature = "download")]
+    /// Answers the first request with half of `body` and then drops the
+    /// connection, and later ones with the requested range.
+    async fn flaky_server(body: &'static [u8]) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/list.txt", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for attempt in 0..2 {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = vec![0; 4096];
+                let read = socket.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..read]).to_lowercase();
+                let start = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("range: bytes="))
+                    .and_then(|range| range.trim_end_matches('-').parse::<usize>().ok());
+                requests.push(request);
+                let response = match (attempt, start) {
+                    (0, _) => [
+                        format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nETag: \"v1\"\r\n\r\n", body.len()).into_bytes(),
+                        body[..body.len() / 2].to_vec(),
+                    ]
+                    .concat(),
+                    (_, Some(start)) => [
+                        format!(
+                            "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                            body.len() - start,
+                            start,
+                            body.len() - 1,
+                            body.len()
+                        )
+                        .into_bytes(),
+                        body[start..].to_vec(),
+                    ]
+                    .concat(),
+                    (_, None) => [
+                        format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len()).into_bytes(),
+                        body.to_vec(),
+                    ]
+                    .concat(),
+                };
+                socket.write_all(&response).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+            requests
+        });
+        (url, server)
+    }
+
+    #[cfg(feature = "download")]
+    #[tokio::test]
+    async fn test_interrupted_download_resumes() {
+        let _network = NETWORK.lock().await;
+        const BODY: &[u8] = b"11111\tabacus\n11112\tabdomen\n11113\tabdominal\n11114\tabide\n";
+        let dir = tempfile::tempdir().unwrap();
+        let partial = dir.path().join("wordlist.part");
+        let (url, server) = flaky_server(BODY).await;
+
+        fetch_to_file(&url, &partial, |_| true).await.unwrap();
+        let contents = std::fs::read(&partial).unwrap();
+        let requests = server.await.unwrap();
+
+        assert_eq!(contents, BODY);
+        assert!(!requests[0].contains("range:"));
+        assert!(requests[1].contains(&format!("range: bytes={}-", BODY.len() / 2)));
+        assert!(requests[1].contains("if-range: \"v1\""));
+        assert!(!validator_path(&partial).exists());
+    }
+
+    #[cfg(feature = "download")]
+    #[tokio::test]
+    async fn test_invalid_partial_restarts() {
+        let _network = NETWORK.lock().await;
+        const BODY: &[u8] = b"11111\tabacus\n11112\tabdomen\n";
+        let dir = tempfile::tempdir().unwrap();
+        let partial = dir.path().join("wordlist.part");
+        let (url, server) = flaky_server(BODY).await;
+
+        // The first request is cut off; the check then rejects what arrived,
+        // so the second one downloads everything again.
+        fetch_to_file(&url, &partial, |bytes| bytes.len() > BODY.len()).await.unwrap();
+        let contents = std::fs::read(&partial).unwrap();
+        let requests = server.await.unwrap();
+
+        assert_eq!(contents, BODY);
+        assert!(!requests[1].contains("range:"));
+    }
+
+    #[test]
+    fn test_retryable_failures() {
+        assert!(DownloadFailure::Timeout.is_retryable());
+        assert!(DownloadFailure::HttpStatus(503).is_retryable());
+        assert!(DownloadFailure::HttpStatus(429).is_retryable());
+        assert!(!DownloadFailure::HttpStatus(404).is_retryable());
+        assert!(!DownloadFailure::Dns("no such host".to_string()).is_retryable());
+        assert_eq!(
+            DownloadFailure::HttpStatus(404).to_string(),
+            "server answered HTTP 404"
+        );
+    }
+}
@@ -0,0 +1,424 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/output.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::PasswordGeneratorError;
+use crate::settings::StrengthSettings;
+use crate::strength::{
+    describe_strength, estimate_entropy_bits, evaluate_generated_strength, get_strength_feedback,
+    strength_bar,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+static ACCESSIBLE: AtomicBool = AtomicBool::new(false);
+static BAR_STYLE: OnceLock<BarStyle> = OnceLock::new();
+
+const MAX_BAR_WIDTH: usize = 200;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Layout {
+    OnePerLine,
+    Columns,
+    Joined(String),
+    Null,
+}
+
+impl std::fmt::Display for Layout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Layout::OnePerLine => write!(f, "one-per-line"),
+            Layout::Columns => write!(f, "columns"),
+            Layout::Joined(sep) => write!(f, "joined:{}", sep),
+            Layout::Null => write!(f, "null"),
+        }
+    }
+}
+
+impl std::str::FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(sep) = s.strip_prefix("joined:") {
+            return Ok(Layout::Joined(unescape(sep)));
+        }
+        match s.to_lowercase().as_str() {
+            "one-per-line" => Ok(Layout::OnePerLine),
+            "columns" => Ok(Layout::Columns),
+            "joined" => Ok(Layout::Joined(",".to_string())),
+            "null" => Ok(Layout::Null),
+            _ => Err(format!(
+                "Invalid layout: {} (use one-per-line, columns, joined:<sep> or null)",
+                s
+            )),
+        }
+    }
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("\\t", "\t").replace("\\n", "\n")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TemplatePart {
+    Literal(String),
+    Index,
+    Password,
+    Length,
+    EntropyBits,
+    Score,
+    Strength,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+pub const TEMPLATE_PLACEHOLDERS: &[&str] = &[
+    "index",
+    "password",
+    "length",
+    "entropy_bits",
+    "score",
+    "strength",
+];
+
+impl std::str::FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("Unclosed placeholder '{{{}'", name)),
+                        }
+                    }
+                    let part = match name.as_str() {
+                        "index" => TemplatePart::Index,
+                        "password" => TemplatePart::Password,
+                        "length" => TemplatePart::Length,
+                        "entropy_bits" => TemplatePart::EntropyBits,
+                        "score" => TemplatePart::Score,
+                        "strength" => TemplatePart::Strength,
+                        _ => {
+                            return Err(format!(
+                                "Unknown placeholder '{{{}}}' (use one of: {})",
+                                name,
+                                TEMPLATE_PLACEHOLDERS.join(", ")
+                            ))
+                        }
+                    };
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(unescape(&literal)));
+                        literal.clear();
+                    }
+                    parts.push(part);
+                }
+                '}' => {
+                    return Err(
+                        "Unmatched '}' in template (use '}}' for a literal brace)".to_string()
+                    )
+                }
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(unescape(&literal)));
+        }
+
+        Ok(Template { parts })
+    }
+}
+
+impl Template {
+    pub fn render(&self, index: usize, password: &str) -> String {
+        let mut rendered = String::new();
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(text) => rendered.push_str(text),
+                TemplatePart::Index => rendered.push_str(&index.to_string()),
+                TemplatePart::Password => rendered.push_str(password),
+                TemplatePart::Length => rendered.push_str(&password.chars().count().to_string()),
+                TemplatePart::EntropyBits => {
+                    rendered.push_str(&format!("{:.2}", estimate_entropy_bits(password)))
+                }
+                TemplatePart::Score => {
+                    rendered.push_str(&format!("{:.2}", evaluate_generated_strength(password)))
+                }
+                TemplatePart::Strength => {
+                    rendered.push_str(&get_strength_feedback(evaluate_generated_strength(password)))
+                }
+            }
+        }
+        rendered
+    }
+}
+
+pub fn format_columns(passwords: &[String], width: usize) -> Vec<String> {
+    let column_width = passwords
+        .iter()
+        .map(|p| p.chars().count())
+        .max()
+        .unwrap_or(0)
+        + 2;
+    let columns = (width / column_width).max(1);
+
+    passwords
+        .chunks(columns)
+        .map(|row| {
+            row.iter()
+                .map(|p| format!("{:<width$}", p, width = column_width))
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+pub fn render_plain(passwords: &[String], layout: &Layout, width: usize) -> String {
+    match layout {
+        Layout::OnePerLine => passwords.iter().map(|p| format!("{}\n", p)).collect(),
+        Layout::Columns => format_columns(passwords, width)
+            .iter()
+            .map(|row| format!("{}\n", row))
+            .collect(),
+        Layout::Joined(sep) => format!("{}\n", passwords.join(sep)),
+        Layout::Null => passwords.iter().map(|p| format!("{}\0", p)).collect(),
+    }
+}
+
+/// Screen-reader friendly output: descriptive text instead of block characters
+/// and colour cues, and passwords read out only once.
+pub fn set_accessible(enabled: bool) {
+    ACCESSIBLE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_accessible() -> bool {
+    ACCESSIBLE.load(Ordering::Relaxed)
+}
+
+/// How the strength bar is drawn, from the `bar_*` keys of `[strength]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BarStyle {
+    pub width: usize,
+    pub filled: char,
+    pub empty: char,
+}
+
+impl Default for BarStyle {
+    fn default() -> Self {
+        BarStyle {
+            width: 20,
+            filled: '█',
+            empty: '░',
+        }
+    }
+}
+
+impl BarStyle {
+    const ASCII: (char, char) = ('#', '-');
+
+    /// The configured bar. Block characters fall back to `#` and `-` when
+    /// `bar_ascii` is set, or when it is not set and the locale is not UTF-8.
+    pub fn from_settings(settings: &StrengthSettings, utf8: bool) -> crate::error::Result<Self> {
+        let invalid = |message: String| PasswordGeneratorError::InvalidConfig(format!("Invalid config.toml: {}", message));
+        let mut style = BarStyle::default();
+        if let Some(width) = settings.bar_width {
+            if !(1..=MAX_BAR_WIDTH).contains(&width) {
+                return Err(invalid(format!("strength.bar_width must be between 1 and {}", MAX_BAR_WIDTH)));
+            }
+            style.width = width;
+        }
+        if let Some(chars) = &settings.bar_chars {
+            match chars.chars().collect::<Vec<_>>()[..] {
+                [filled, empty] if !filled.is_control() && !empty.is_control() => {
+                    (style.filled, style.empty) = (filled, empty);
+                }
+                _ => {
+                    return Err(invalid(
+                        "strength.bar_chars must be two characters, the filled and the empty cell".to_string(),
+                    ))
+                }
+            }
+        }
+        let ascii = settings.bar_ascii.unwrap_or(!utf8);
+        if ascii && !(style.filled.is_ascii() && style.empty.is_ascii()) {
+            (style.filled, style.empty) = Self::ASCII;
+        }
+        Ok(style)
+    }
+
+    pub fn render(&self, score: f64) -> String {
+        strength_bar(score, self.width, self.filled, self.empty)
+    }
+}
+
+/// Whether the locale from `LC_ALL`, `LC_CTYPE` or `LANG`, the first one set,
+/// uses UTF-8. Windows consoles are taken to do so.
+pub fn locale_is_utf8() -> bool {
+    if cfg!(windows) {
+        return true;
+    }
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .is_some_and(|locale| {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
+
+/// Sets the strength bar for the rest of the run; only the first call counts.
+pub fn set_bar_style(style: BarStyle) {
+    let _ = BAR_STYLE.set(style);
+}
+
+/// The bar set with [`set_bar_style`], or the default one.
+pub fn bar_style() -> &'static BarStyle {
+    BAR_STYLE.get_or_init(BarStyle::default)
+}
+
+pub fn strength_indicator(score: f64) -> String {
+    if is_accessible() {
+        describe_strength(score)
+    } else {
+        bar_style().render(score)
+    }
+}
+
+/// The dimmed column shown next to each password with `--annotate`.
+pub fn annotate(passwords: &[String]) -> Vec<(String, String)> {
+    let width = passwords.iter().map(|p| p.chars().count()).max().unwrap_or(0);
+    passwords
+        .iter()
+        .map(|password| {
+            let padding = " ".repeat(width - password.chars().count());
+            let note = format!(
+                "{:>6.1} bits  {}",
+                estimate_entropy_bits(password),
+                get_strength_feedback(evaluate_generated_strength(password))
+            );
+            (format!("{}{}", password, padding), note)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_layout() {
+        assert_eq!("one-per-line".parse::<Layout>(), Ok(Layout::OnePerLine));
+        assert_eq!("columns".parse::<Layout>(), Ok(Layout::Columns));
+        assert_eq!("null".parse::<Layout>(), Ok(Layout::Null));
+        assert_eq!(
+            "joined:\\t".parse::<Layout>(),
+            Ok(Layout::Joined("\t".to_string()))
+        );
+        assert_eq!(
+            "joined:, ".parse::<Layout>(),
+            Ok(Layout::Joined(", ".to_string()))
+        );
+        assert!("sideways".parse::<Layout>().is_err());
+    }
+
+    #[test]
+    fn test_bar_style() {
+        let defaults = StrengthSettings::default();
+        let style = BarStyle::from_settings(&defaults, true).unwrap();
+        assert_eq!(style.render(0.5), format!("[{}{}]", "█".repeat(10), "░".repeat(10)));
+        assert_eq!(BarStyle::from_settings(&defaults, false).unwrap().render(0.25), "[#####---------------]");
+
+        let settings = StrengthSettings {
+            bar_width: Some(4),
+            bar_chars: Some("=.".to_string()),
+            ..StrengthSettings::default()
+        };
+        assert_eq!(BarStyle::from_settings(&settings, false).unwrap().render(0.75), "[===.]");
+        let forced = StrengthSettings {
+            bar_ascii: Some(false),
+            ..StrengthSettings::default()
+        };
+        assert_eq!(BarStyle::from_settings(&forced, false).unwrap(), BarStyle::default());
+
+        for invalid in [
+            StrengthSettings { bar_width: Some(0), ..StrengthSettings::default() },
+            StrengthSettings { bar_chars: Some("#".to_string()), ..StrengthSettings::default() },
+        ] {
+            assert!(BarStyle::from_settings(&invalid, true).is_err());
+        }
+    }
+
+    #[test]
+    fn test_format_columns() {
+        let passwords: Vec<String> = ["aaaa", "bbbb", "cccc", "dddd", "eeee"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            format_columns(&passwords, 14),
+            vec!["aaaa  bbbb", "cccc  dddd", "eeee"]
+        );
+        assert_eq!(format_columns(&passwords, 3).len(), 5);
+    }
+
+    #[test]
+    fn test_render_plain() {
+        let passwords = vec!["ab".to_string(), "cd".to_string()];
+        assert_eq!(
+            render_plain(&passwords, &Layout::OnePerLine, 80),
+            "ab\ncd\n"
+        );
+        assert_eq!(
+            render_plain(&passwords, &Layout::Joined(",".to_string()), 80),
+            "ab,cd\n"
+        );
+        assert_eq!(render_plain(&passwords, &Layout::Null, 80), "ab\0cd\0");
+    }
+
+    #[test]
+    fn test_template_render() {
+        let template: Template = "{index}\\t{password}\\t{length} {{x}}".parse().unwrap();
+        assert_eq!(template.render(3, "abcdef"), "3\tabcdef\t6 {x}");
+
+        let template: Template = "{password}:{strength}".parse().unwrap();
+        assert_eq!(template.render(1, "aaaa"), "aaaa:Weak");
+
+        assert!("{nope}".parse::<Template>().is_err());
+        assert!("{password".parse::<Template>().is_err());
+        assert!("password}".parse::<Template>().is_err());
+    }
+
+    #[test]
+    fn test_annotate_aligns_notes() {
+        let annotated = annotate(&["abcd".to_string(), "abcdefgh".to_string()]);
+        assert_eq!(annotated[0].0, "abcd    ");
+        assert_eq!(annotated[1].0, "abcdefgh");
+        assert_eq!(
+            annotated[0].1,
+            format!("{:>6.1} bits  {}", 4.0 * 26f64.log2(), "Weak")
+        );
+    }
+}
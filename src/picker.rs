@@ -0,0 +1,147 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/picker.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::{PasswordGeneratorError, Result};
+use crate::i18n::{tr, trf};
+use crate::strength::{evaluate_generated_strength, get_strength_feedback};
+use serde_json::json;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use zeroize::Zeroize;
+
+/// A desktop launcher to choose one of the generated candidates in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Picker {
+    Rofi,
+    Dmenu,
+    Alfred,
+}
+
+impl std::fmt::Display for Picker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Picker::Rofi => write!(f, "rofi"),
+            Picker::Dmenu => write!(f, "dmenu"),
+            Picker::Alfred => write!(f, "alfred"),
+        }
+    }
+}
+
+impl std::str::FromStr for Picker {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rofi" => Ok(Picker::Rofi),
+            "dmenu" => Ok(Picker::Dmenu),
+            "alfred" => Ok(Picker::Alfred),
+            _ => Err(format!("Invalid picker: {} (use rofi, dmenu or alfred)", s)),
+        }
+    }
+}
+
+/// The candidates as an Alfred Script Filter result. Alfred shows the list
+/// itself and hands the chosen `arg` to the next action of the workflow.
+pub fn alfred_items(candidates: &[String]) -> String {
+    let items: Vec<serde_json::Value> = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, candidate)| {
+            let score = evaluate_generated_strength(candidate);
+            json!({
+                "uid": (i + 1).to_string(),
+                "title": candidate,
+                "subtitle": trf(
+                    "{} ({}), press Enter to copy",
+                    &[&tr(&get_strength_feedback(score)), &format!("{:.2}", score)]
+                ),
+                "arg": candidate,
+                "text": { "copy": candidate, "largetype": candidate },
+            })
+        })
+        .collect();
+    json!({ "items": items }).to_string()
+}
+
+/// Lets the user choose a candidate in rofi or dmenu. Returns None when the
+/// menu is dismissed.
+pub fn pick(picker: Picker, candidates: &[String]) -> Result<Option<String>> {
+    match picker {
+        Picker::Rofi => run_menu("rofi", &["-dmenu", "-p", "npwg", "-no-custom"], candidates),
+        Picker::Dmenu => run_menu("dmenu", &["-p", "npwg"], candidates),
+        Picker::Alfred => Err(PasswordGeneratorError::InvalidConfig(
+            "Alfred reads the candidates from a Script Filter and picks on its own".to_string(),
+        )),
+    }
+}
+
+/// Writes one candidate per line to the menu and reads the chosen line
+/// back. Both launchers exit with a failure status when dismissed.
+fn run_menu(program: &str, args: &[&str], candidates: &[String]) -> Result<Option<String>> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => PasswordGeneratorError::InvalidConfig(format!(
+                "--picker needs {} on the PATH",
+                program
+            )),
+            _ => e.into(),
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let mut lines = candidates.join("\n");
+        lines.push('\n');
+        let _ = stdin.write_all(lines.as_bytes());
+        lines.zeroize();
+    }
+    let mut output = child.wait_with_output()?;
+    let mut choice = String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches(['\r', '\n'])
+        .to_string();
+    output.stdout.zeroize();
+    if !output.status.success() || choice.is_empty() {
+        return Ok(None);
+    }
+    if !candidates.contains(&choice) {
+        choice.zeroize();
+        return Err(PasswordGeneratorError::InvalidConfig(format!(
+            "The text chosen in {} is not one of the generated candidates",
+            program
+        )));
+    }
+    Ok(Some(choice))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alfred_items() {
+        let candidates = vec!["abc".to_string(), "Tr0ub4dor&3xyz!".to_string()];
+        let items: serde_json::Value = serde_json::from_str(&alfred_items(&candidates)).unwrap();
+        let items = items["items"].as_array().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1]["uid"], "2");
+        assert_eq!(items[1]["arg"], "Tr0ub4dor&3xyz!");
+        assert_eq!(items[1]["text"]["copy"], "Tr0ub4dor&3xyz!");
+        assert!(items[0]["subtitle"].as_str().unwrap().starts_with("Weak"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_menu() {
+        let candidates = vec!["one".to_string(), "two".to_string()];
+        assert_eq!(run_menu("sh", &["-c", "sed -n 2p"], &candidates).unwrap(), Some("two".to_string()));
+        assert_eq!(run_menu("sh", &["-c", "cat >/dev/null; exit 1"], &candidates).unwrap(), None);
+        assert!(run_menu("sh", &["-c", "cat >/dev/null; echo three"], &candidates).is_err());
+        assert!(run_menu("npwg-no-such-menu", &[], &candidates).is_err());
+        assert_eq!("Rofi".parse::<Picker>(), Ok(Picker::Rofi));
+        assert!("fzf".parse::<Picker>().is_err());
+    }
+}
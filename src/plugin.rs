@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/plugin.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::config::Seed;
+use crate::error::{PasswordGeneratorError, Result};
+use dirs::home_dir;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use zeroize::Zeroize;
+
+/// Version of the JSON exchanged with plugins.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// An executable in the plugins directory, named after its file stem, so
+/// `~/.npwg/plugins/cvc.py` provides `--mode cvc`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plugin {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// What npwg sends to a plugin on stdin, as one line of JSON.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Request {
+    pub protocol: u32,
+    pub count: usize,
+    pub length: usize,
+    /// 64 hex digits derived from `--seed`, so plugins can be reproducible too.
+    pub seed: Option<String>,
+    pub options: BTreeMap<String, String>,
+}
+
+impl Request {
+    pub fn new(count: usize, length: usize, seed: Option<Seed>, options: BTreeMap<String, String>) -> Self {
+        let seed = seed.map(|seed| {
+            let mut bytes = [0u8; 32];
+            seed.rng().fill_bytes(&mut bytes);
+            bytes.iter().map(|b| format!("{:02x}", b)).collect()
+        });
+        Request {
+            protocol: PROTOCOL_VERSION,
+            count,
+            length,
+            seed,
+            options,
+        }
+    }
+}
+
+/// What a plugin must print on stdout.
+#[derive(Deserialize)]
+struct Response {
+    passwords: Vec<String>,
+}
+
+fn plugin_error(plugin: &Plugin, message: impl std::fmt::Display) -> PasswordGeneratorError {
+    PasswordGeneratorError::Plugin(format!("{}: {}", plugin.name, message))
+}
+
+pub fn plugins_dir() -> Result<PathBuf> {
+    let home = home_dir().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "Home directory not found")
+    })?;
+    Ok(home.join(".npwg").join("plugins"))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// The plugins in `dir`, sorted by name. A missing directory has none.
+pub fn discover(dir: &Path) -> Result<Vec<Plugin>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error.into()),
+    };
+    let mut plugins: Vec<Plugin> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| is_executable(path))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            (!name.starts_with('.')).then_some(Plugin { name, path })
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(plugins)
+}
+
+pub fn find(name: &str) -> Result<Plugin> {
+    let dir = plugins_dir()?;
+    let plugins = discover(&dir)?;
+    let names: Vec<&str> = plugins.iter().map(|plugin| plugin.name.as_str()).collect();
+    let hint = if names.is_empty() {
+        format!("no plugins are installed in {}", dir.display())
+    } else {
+        format!("installed: {}", names.join(", "))
+    };
+    plugins
+        .iter()
+        .find(|plugin| plugin.name == name)
+        .cloned()
+        .ok_or_else(|| PasswordGeneratorError::InvalidConfig(format!("Unknown mode '{}' ({})", name, hint)))
+}
+
+/// Parses `--plugin-option KEY=VALUE`.
+pub fn parse_option(value: &str) -> std::result::Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", value)),
+    }
+}
+
+/// Runs the plugin once for the whole request and returns exactly
+/// `request.count` passwords.
+pub fn generate(plugin: &Plugin, request: &Request) -> Result<Vec<String>> {
+    let mut child = Command::new(&plugin.path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| plugin_error(plugin, format!("cannot run {}: {}", plugin.path.display(), e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let line = serde_json::to_string(request).map_err(std::io::Error::other)?;
+        let _ = writeln!(stdin, "{}", line);
+    }
+    let mut output = child.wait_with_output()?;
+    let parsed = if output.status.success() {
+        serde_json::from_slice::<Response>(&output.stdout)
+            .map_err(|e| plugin_error(plugin, format!("invalid response: {}", e)))
+    } else {
+        Err(plugin_error(plugin, format!("failed ({})", output.status)))
+    };
+    output.stdout.zeroize();
+    let mut passwords = parsed?.passwords;
+    if passwords.len() != request.count {
+        let returned = passwords.len();
+        passwords.iter_mut().for_each(|p| p.zeroize());
+        return Err(plugin_error(
+            plugin,
+            format!("returned {} passwords, {} were requested", returned, request.count),
+        ));
+    }
+    Ok(passwords)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_json() {
+        let options = BTreeMap::from([("scheme".to_string(), "cvc".to_string())]);
+        let request = Request::new(2, 12, Some(Seed::Number(7)), options);
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["protocol"], 1);
+        assert_eq!(json["count"], 2);
+        assert_eq!(json["options"]["scheme"], "cvc");
+        assert_eq!(json["seed"].as_str().unwrap().len(), 64);
+        assert_eq!(request.seed, Request::new(1, 1, Some(Seed::Number(7)), BTreeMap::new()).seed);
+        assert!(Request::new(1, 8, None, BTreeMap::new()).seed.is_none());
+    }
+
+    #[test]
+    fn test_parse_option() {
+        assert_eq!(parse_option("a=b=c"), Ok(("a".to_string(), "b=c".to_string())));
+        assert!(parse_option("novalue").is_err());
+        assert!(parse_option("=x").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_discover_and_generate() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let script = dir.join("fixed.sh");
+        std::fs::write(&script, "#!/bin/sh\nread request\necho '{\"passwords\": [\"ba-ko-ru\", \"mi-ta-se\"]}'\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a plugin").unwrap();
+
+        let plugins = discover(&dir).unwrap();
+        let generated = generate(&plugins[0], &Request::new(2, 8, None, BTreeMap::new()));
+        let short = generate(&plugins[0], &Request::new(3, 8, None, BTreeMap::new()));
+        tmp.close().unwrap();
+
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].name, "fixed");
+        assert_eq!(generated.unwrap(), ["ba-ko-ru", "mi-ta-se"]);
+        assert!(matches!(short, Err(PasswordGeneratorError::Plugin(_))));
+        assert!(discover(&dir).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,259 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/profile.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::{PasswordGeneratorError, Result};
+use dirs::home_dir;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use toml::Value;
+
+/// Layout version of the profile files written by this build. Files without
+/// a `version` key have the same layout.
+pub const PROFILE_VERSION: i64 = 1;
+
+fn current_version() -> i64 {
+    PROFILE_VERSION
+}
+
+/// A named set of command line options, stored as
+/// `~/.npwg/profiles/<name>.toml` with the long option names as keys.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default = "current_version")]
+    pub version: i64,
+    pub name: String,
+    #[serde(default)]
+    pub options: BTreeMap<String, Value>,
+}
+
+fn invalid(message: String) -> PasswordGeneratorError {
+    PasswordGeneratorError::InvalidConfig(message)
+}
+
+pub fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(invalid(format!(
+            "Profile name '{}' may only contain letters, digits, '-' and '_'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+pub fn profiles_dir() -> Result<PathBuf> {
+    let home = home_dir().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "Home directory not found")
+    })?;
+    Ok(home.join(".npwg").join("profiles"))
+}
+
+fn profile_path(name: &str) -> Result<PathBuf> {
+    validate_name(name)?;
+    Ok(profiles_dir()?.join(format!("{}.toml", name)))
+}
+
+impl Profile {
+    /// Parses a profile file, refusing one written in a layout newer than
+    /// this build reads.
+    pub fn parse(text: &str) -> Result<Self> {
+        let profile: Profile =
+            toml::from_str(text).map_err(|e| invalid(format!("Invalid profile: {}", e)))?;
+        if !(1..=PROFILE_VERSION).contains(&profile.version) {
+            return Err(invalid(format!(
+                "Profile uses layout version {}, but this npwg only reads versions 1 to {}; upgrade npwg",
+                profile.version, PROFILE_VERSION
+            )));
+        }
+        validate_name(&profile.name)?;
+        Ok(profile)
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string(self).map_err(|e| invalid(format!("Cannot serialize profile: {}", e)))
+    }
+
+    /// Loads a saved profile.
+    pub fn load(name: &str) -> Result<Self> {
+        let path = profile_path(name)?;
+        let text = std::fs::read_to_string(&path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => invalid(format!("No profile named '{}'", name)),
+            _ => e.into(),
+        })?;
+        Profile::parse(&text)
+    }
+
+    /// Writes the profile, refusing to replace an existing one unless `force`.
+    pub fn save(&self, force: bool) -> Result<PathBuf> {
+        let path = profile_path(&self.name)?;
+        if path.exists() && !force {
+            return Err(invalid(format!(
+                "Profile '{}' already exists, use --force to replace it",
+                self.name
+            )));
+        }
+        std::fs::create_dir_all(profiles_dir()?)?;
+        std::fs::write(&path, self.to_toml()?)?;
+        Ok(path)
+    }
+
+    /// Checks every option against the top-level arguments of `command`, so a
+    /// typo or a value of the wrong shape fails at import rather than at use.
+    pub fn validate_options(&self, command: &clap::Command) -> Result<()> {
+        for (key, value) in &self.options {
+            let arg = command
+                .get_arguments()
+                .find(|arg| arg.get_long() == Some(key.as_str()))
+                .filter(|arg| arg.get_id() != "profile")
+                .ok_or_else(|| invalid(format!("Profile option '{}' is not an npwg option", key)))?;
+            let takes_value = arg.get_action().takes_values();
+            let ok = match value {
+                Value::Boolean(_) => !takes_value,
+                Value::String(_) | Value::Integer(_) | Value::Float(_) => takes_value,
+                Value::Array(items) => {
+                    takes_value
+                        && items.iter().all(|item| {
+                            matches!(item, Value::String(_) | Value::Integer(_) | Value::Float(_))
+                        })
+                }
+                _ => false,
+            };
+            if !ok {
+                return Err(invalid(format!(
+                    "Profile option '{}' {}",
+                    key,
+                    if takes_value {
+                        "needs a value, not true/false"
+                    } else {
+                        "is a switch and must be true or false"
+                    }
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The options as command line arguments, in `--key=value` form so values
+    /// that start with '-' are not mistaken for options.
+    pub fn to_args(&self) -> Vec<String> {
+        let scalar = |value: &Value| match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        let mut args = Vec::new();
+        for (key, value) in &self.options {
+            match value {
+                Value::Boolean(true) => args.push(format!("--{}", key)),
+                Value::Boolean(false) => {}
+                Value::Array(items) => {
+                    args.extend(items.iter().map(|item| format!("--{}={}", key, scalar(item))))
+                }
+                other => args.push(format!("--{}={}", key, scalar(other))),
+            }
+        }
+        args
+    }
+}
+
+/// Names of the saved profiles, sorted.
+pub fn list() -> Result<Vec<String>> {
+    let dir = profiles_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut names: Vec<String> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            (path.extension()? == "toml").then(|| path.file_stem()?.to_str().map(str::to_string))?
+        })
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{Arg, ArgAction, Command};
+
+    const WORK: &str = r#"
+version = 1
+name = "work"
+
+[options]
+length = 20
+allowed = "upperletter,lowerletter,digit"
+strength = true
+avoid-repeating = false
+forbid = ["acme", "-corp"]
+"#;
+
+    fn command() -> Command {
+        Command::new("npwg")
+            .arg(Arg::new("length").long("length"))
+            .arg(Arg::new("allowed").long("allowed"))
+            .arg(Arg::new("forbid").long("forbid").action(ArgAction::Append))
+            .arg(Arg::new("strength").long("strength").action(ArgAction::SetTrue))
+            .arg(Arg::new("avoid-repeating").long("avoid-repeating").action(ArgAction::SetTrue))
+    }
+
+    #[test]
+    fn test_profile_round_trip() {
+        let profile = Profile::parse(WORK).unwrap();
+        assert_eq!(profile.name, "work");
+        assert_eq!(Profile::parse(&profile.to_toml().unwrap()).unwrap(), profile);
+        assert_eq!(
+            profile.to_args(),
+            vec![
+                "--allowed=upperletter,lowerletter,digit",
+                "--forbid=acme",
+                "--forbid=-corp",
+                "--length=20",
+                "--strength",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_layout_version() {
+        let profile = Profile::parse("name = \"old\"\n[options]\nlength = 12\n").unwrap();
+        assert_eq!(profile.version, PROFILE_VERSION);
+        assert!(profile.to_toml().unwrap().starts_with("version = 1\n"));
+
+        let newer = format!("version = {}\nname = \"x\"\n", PROFILE_VERSION + 1);
+        assert!(Profile::parse(&newer).is_err());
+        assert!(Profile::parse("version = \"1\"\nname = \"x\"").is_err());
+    }
+
+    #[test]
+    fn test_validate_options() {
+        let command = command();
+        assert!(Profile::parse(WORK).unwrap().validate_options(&command).is_ok());
+
+        let bad = |options: &str| {
+            Profile::parse(&format!("name = \"x\"\n[options]\n{}", options))
+                .unwrap()
+                .validate_options(&command)
+                .is_err()
+        };
+        assert!(bad("lenght = 20"));
+        assert!(bad("length = true"));
+        assert!(bad("strength = \"yes\""));
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("team-2024_a").is_ok());
+        assert!(validate_name("../etc").is_err());
+        assert!(validate_name("").is_err());
+        assert!(Profile::parse("name = \"a b\"").is_err());
+    }
+}
@@ -0,0 +1,122 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/progress.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::i18n::tr;
+use crate::output::bar_style;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Operations finishing sooner than this never show a bar.
+const SHOW_AFTER: Duration = Duration::from_millis(300);
+const REDRAWS_PER_SECOND: u8 = 10;
+const BAR_WIDTH: usize = 30;
+
+/// Off by default, so library users and the MCP server never see bars; the
+/// CLI turns them on for interactive terminals.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    Items,
+    #[cfg_attr(not(feature = "download"), allow(dead_code))]
+    Bytes,
+}
+
+/// One line of progress, e.g. `Generating [██████░░░░]  60% (600/1000)`, in
+/// the cells of the strength bar. Without a known total only the amount so
+/// far is shown.
+fn style(total: Option<u64>, unit: Unit) -> ProgressStyle {
+    let amount = match unit {
+        Unit::Items => ("{pos}", "{len}"),
+        Unit::Bytes => ("{binary_bytes}", "{binary_total_bytes}"),
+    };
+    let template = match total {
+        Some(_) => format!(
+            "{{msg}} [{{bar:{}}}] {{percent:>3}}% ({}/{})",
+            BAR_WIDTH, amount.0, amount.1
+        ),
+        None => format!("{{msg}} {}", amount.0),
+    };
+    let cells = bar_style();
+    ProgressStyle::with_template(&template)
+        .expect("progress templates are valid")
+        .progress_chars(&format!("{}{}", cells.filled, cells.empty))
+}
+
+/// A progress bar on stderr that stays hidden unless bars are enabled and
+/// the operation runs long enough to be worth one. It clears itself when
+/// dropped.
+pub struct Progress {
+    bar: ProgressBar,
+    started: Instant,
+    shown: bool,
+    enabled: bool,
+}
+
+impl Progress {
+    /// `label` is a message id and is translated.
+    pub fn new(label: &str, total: Option<u64>, unit: Unit) -> Self {
+        let total = total.filter(|&total| total > 0);
+        let bar = ProgressBar::with_draw_target(total, ProgressDrawTarget::hidden())
+            .with_style(style(total, unit))
+            .with_message(tr(label).to_string());
+        Progress {
+            bar,
+            started: Instant::now(),
+            shown: false,
+            enabled: is_enabled(),
+        }
+    }
+
+    pub fn inc(&mut self, amount: u64) {
+        self.bar.inc(amount);
+        if self.enabled && !self.shown && self.started.elapsed() >= SHOW_AFTER {
+            self.shown = true;
+            self.bar
+                .set_draw_target(ProgressDrawTarget::stderr_with_hz(REDRAWS_PER_SECOND));
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if self.shown {
+            self.bar.finish_and_clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_styles() {
+        for total in [Some(100), None] {
+            for unit in [Unit::Items, Unit::Bytes] {
+                style(total, unit);
+            }
+        }
+    }
+
+    #[test]
+    fn test_disabled_progress_stays_hidden() {
+        let mut progress = Progress::new("Generating", Some(10), Unit::Items);
+        progress.started -= SHOW_AFTER;
+        progress.inc(5);
+        assert_eq!(progress.bar.position(), 5);
+        assert!(!progress.shown);
+    }
+}
@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/qr.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+//! QR codes for the short payloads npwg renders, at error correction level
+//! M, drawn for a terminal or as SVG.
+
+use crate::error::{PasswordGeneratorError, Result};
+use qrcode::{Color, EcLevel};
+
+/// Modules of light border the standard asks for around the symbol.
+const QUIET_ZONE: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encodes `payload` in the smallest version that holds it.
+    pub fn encode(payload: &[u8]) -> Result<Self> {
+        let code = qrcode::QrCode::with_error_correction_level(payload, EcLevel::M).map_err(|e| {
+            PasswordGeneratorError::InvalidConfig(format!(
+                "QR payload of {} bytes cannot be encoded: {}",
+                payload.len(),
+                e
+            ))
+        })?;
+        Ok(QrCode {
+            size: code.width(),
+            modules: code.to_colors().into_iter().map(|c| c == Color::Dark).collect(),
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether the module at column `x`, row `y` is dark.
+    pub fn module(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    /// Text rendering for a terminal, two rows per line with half blocks and
+    /// the quiet zone drawn light.
+    pub fn to_terminal(&self) -> String {
+        let border = QUIET_ZONE as i32;
+        let extent = self.size() as i32 + border;
+        let dark = |x: i32, y: i32| {
+            (0..self.size as i32).contains(&x)
+                && (0..self.size as i32).contains(&y)
+                && self.module(x as usize, y as usize)
+        };
+        let mut out = String::new();
+        let mut y = -border;
+        while y < extent {
+            for x in -border..extent {
+                out.push(match (dark(x, y), dark(x, y + 1)) {
+                    (false, false) => '█',
+                    (false, true) => '▀',
+                    (true, false) => '▄',
+                    (true, true) => ' ',
+                });
+            }
+            out.push('\n');
+            y += 2;
+        }
+        out
+    }
+
+    /// A standalone SVG with one unit per module.
+    pub fn to_svg(&self) -> String {
+        let extent = self.size + 2 * QUIET_ZONE;
+        let mut path = String::new();
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if self.module(x, y) {
+                    path.push_str(&format!("M{},{}h1v1h-1z", x + QUIET_ZONE, y + QUIET_ZONE));
+                }
+            }
+        }
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {0} {0}\" width=\"{1}\" height=\"{1}\" shape-rendering=\"crispEdges\">\n<rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>\n<path d=\"{2}\" fill=\"#000\"/>\n</svg>\n",
+            extent,
+            extent * 8,
+            path
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capacities() {
+        assert_eq!(QrCode::encode(&[b'a'; 14]).unwrap().size(), 21);
+        assert_eq!(QrCode::encode(&[b'a'; 15]).unwrap().size(), 25);
+        assert!(QrCode::encode(&[b'a'; 4000]).is_err());
+    }
+
+    #[test]
+    fn test_symbol_layout() {
+        let qr = QrCode::encode(b"WIFI:T:WPA;S:guest;P:correct horse;;").unwrap();
+        let size = qr.size();
+        // Finder corners, their separators and the dark module.
+        for (x, y) in [(0, 0), (size - 1, 0), (0, size - 1)] {
+            assert!(qr.module(x, y));
+        }
+        assert!(!qr.module(7, 7));
+        assert!(qr.module(8, size - 8));
+
+        let lines = qr.to_terminal().lines().count();
+        assert_eq!(lines, (size + 2 * QUIET_ZONE).div_ceil(2));
+        assert!(qr.to_svg().starts_with("<?xml"));
+    }
+}
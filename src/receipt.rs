@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/receipt.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::config::PasswordGeneratorConfig;
+use crate::error::Result;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The rules a run was held to. Only settings are recorded: forbidden
+/// substrings and dictionaries appear as counts, never as their entries.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Policy {
+    pub site: Option<String>,
+    pub rules: Option<String>,
+    pub fips: bool,
+    pub min_entropy_bits: Option<f64>,
+    pub min_strength: Option<f64>,
+    pub avoid_repetition: bool,
+    pub max_consecutive: Option<usize>,
+    pub start_with: Option<String>,
+    pub end_with: Option<String>,
+    pub reject_keyboard_runs: bool,
+    pub avoid_sequential: bool,
+    pub dictionary_words: usize,
+    pub forbidden_substrings: usize,
+    pub forced: bool,
+}
+
+impl Policy {
+    pub fn from_config(config: &PasswordGeneratorConfig) -> Self {
+        Policy {
+            fips: config.fips,
+            min_entropy_bits: config.min_entropy_bits,
+            min_strength: config.min_strength,
+            avoid_repetition: config.avoid_repetition,
+            max_consecutive: config.max_consecutive,
+            start_with: config.start_with.map(|class| class.to_string()),
+            end_with: config.end_with.map(|class| class.to_string()),
+            reject_keyboard_runs: config.reject_keyboard_runs,
+            avoid_sequential: config.avoid_sequential,
+            dictionary_words: config.dictionary.len(),
+            forbidden_substrings: config.forbidden.len(),
+            ..Policy::default()
+        }
+    }
+}
+
+/// An audit record of one generation run. It says how the values were made,
+/// never what they are, and whether a seed was used but not which.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Receipt {
+    pub npwg_version: &'static str,
+    pub timestamp: String,
+    pub mode: String,
+    pub count: usize,
+    pub length: String,
+    pub charsets: Vec<String>,
+    pub wordlist_size: Option<usize>,
+    pub entropy_bits: Option<f64>,
+    pub seeded: bool,
+    pub policy: Policy,
+}
+
+impl Receipt {
+    pub fn new(config: &PasswordGeneratorConfig, mode: &str, policy: Policy) -> Self {
+        let length = config
+            .length_range
+            .or(config.word_range)
+            .map_or(config.length.to_string(), |range| range.to_string());
+        Receipt {
+            npwg_version: env!("CARGO_PKG_VERSION"),
+            timestamp: format_timestamp(SystemTime::now()),
+            mode: mode.to_string(),
+            count: config.num_passwords,
+            length,
+            charsets: Vec::new(),
+            wordlist_size: None,
+            entropy_bits: None,
+            seeded: config.seed.is_some(),
+            policy,
+        }
+    }
+
+    /// Appends the receipt as one line of JSON, so a single file can collect
+    /// every provisioning run.
+    pub fn append_to(&self, path: &Path) -> Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        let line = serde_json::to_string(self).map_err(std::io::Error::other)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+}
+
+/// `time` as an RFC 3339 UTC timestamp with second precision.
+pub fn format_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    let (days, rest) = (secs / 86_400, secs % 86_400);
+    // Days since the epoch to a proleptic Gregorian date, after H. Hinnant.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest / 60 % 60,
+        rest % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Seed;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_timestamp() {
+        let at = |secs| format_timestamp(UNIX_EPOCH + Duration::from_secs(secs));
+        assert_eq!(at(0), "1970-01-01T00:00:00Z");
+        assert_eq!(at(1_700_000_000), "2023-11-14T22:13:20Z");
+        assert_eq!(at(951_782_400), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn test_receipt_records_seed_presence_only() {
+        let mut config = PasswordGeneratorConfig::new();
+        config.seed = Some("provisioning-seed".parse::<Seed>().unwrap());
+        config.set_forbidden(["acme".to_string()]);
+        let receipt = Receipt::new(&config, "password", Policy::from_config(&config));
+        let json = serde_json::to_value(&receipt).unwrap();
+        assert_eq!(json["seeded"], true);
+        assert_eq!(json["policy"]["forbidden_substrings"], 1);
+        let text = json.to_string();
+        assert!(!text.contains("provisioning-seed"));
+        assert!(!text.contains("acme"));
+    }
+
+    #[test]
+    fn test_append_writes_json_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("receipts.jsonl");
+        let config = PasswordGeneratorConfig::new();
+        let receipt = Receipt::new(&config, "password", Policy::from_config(&config));
+        receipt.append_to(&path).unwrap();
+        receipt.append_to(&path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text.lines().count(), 2);
+        let first: serde_json::Value = serde_json::from_str(text.lines().next().unwrap()).unwrap();
+        assert_eq!(first["mode"], "password");
+        assert_eq!(first["length"], "8");
+    }
+}
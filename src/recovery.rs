@@ -0,0 +1,290 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/recovery.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::config::DEFINE;
+use crate::error::{PasswordGeneratorError, Result};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+pub const PRINT_SAFE_CHARSET: &str = "printsafe";
+
+/// Stands for one random character in a recovery code format.
+pub const CODE_PLACEHOLDER: char = 'X';
+
+const PAGE_WIDTH: usize = 595;
+const PAGE_HEIGHT: usize = 842;
+const MARGIN: usize = 56;
+const FONT_SIZE: usize = 11;
+const LEADING: usize = 14;
+
+pub fn print_safe_chars() -> Vec<char> {
+    DEFINE
+        .iter()
+        .find(|(name, _)| *name == PRINT_SAFE_CHARSET)
+        .map(|(_, chars)| chars.chars().collect())
+        .unwrap_or_default()
+}
+
+/// Luhn mod N check character over the print-safe alphabet. Catches any
+/// single misread character and most swaps of adjacent characters.
+pub fn check_char(password: &str, alphabet: &[char]) -> Result<char> {
+    let n = alphabet.len();
+    let mut factor = 2;
+    let mut sum = 0;
+    for c in password.chars().rev() {
+        let code = alphabet.iter().position(|&a| a == c).ok_or_else(|| {
+            PasswordGeneratorError::InvalidConfig(format!(
+                "Character '{}' is not in the print-safe alphabet",
+                c
+            ))
+        })?;
+        let addend = factor * code;
+        sum += addend / n + addend % n;
+        factor = 3 - factor;
+    }
+    Ok(alphabet[(n - sum % n) % n])
+}
+
+pub fn group(password: &str, size: usize) -> String {
+    if size == 0 {
+        return password.to_string();
+    }
+    password
+        .chars()
+        .collect::<Vec<_>>()
+        .chunks(size)
+        .map(|chunk| chunk.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Checks a recovery code format such as `XXXX-XXXX`: it needs at least one
+/// placeholder, and its separators must not be alphabet characters, so a
+/// code can always be reduced to just its random part.
+pub fn validate_code_format(format: &str, alphabet: &[char]) -> Result<()> {
+    if !format.contains(CODE_PLACEHOLDER) {
+        return Err(PasswordGeneratorError::InvalidConfig(format!(
+            "Recovery code format '{}' needs at least one '{}' for a random character",
+            format, CODE_PLACEHOLDER
+        )));
+    }
+    if let Some(c) = format
+        .chars()
+        .find(|&c| c != CODE_PLACEHOLDER && alphabet.contains(&c))
+    {
+        return Err(PasswordGeneratorError::InvalidConfig(format!(
+            "Recovery code format '{}' uses '{}' as a separator, but it is also a code character",
+            format, c
+        )));
+    }
+    Ok(())
+}
+
+pub fn recovery_code(format: &str, alphabet: &[char], rng: &mut impl Rng) -> String {
+    format
+        .chars()
+        .map(|c| match c {
+            CODE_PLACEHOLDER => alphabet[rng.random_range(0..alphabet.len())],
+            separator => separator,
+        })
+        .collect()
+}
+
+/// SHA-256 (hex) of a code with separators dropped and letters upper-cased,
+/// the form a server should also reduce user input to before comparing.
+pub fn code_hash(code: &str, alphabet: &[char]) -> String {
+    let normalized: String = code
+        .chars()
+        .map(|c| c.to_ascii_uppercase())
+        .filter(|c| alphabet.contains(c))
+        .collect();
+    Sha256::digest(normalized.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+pub fn render_sheet(passwords: &[String], group_size: usize) -> Result<String> {
+    let alphabet = print_safe_chars();
+    let width = passwords
+        .iter()
+        .map(|p| group(p, group_size).len())
+        .max()
+        .unwrap_or(0)
+        .max("Password".len());
+
+    let mut sheet = String::new();
+    sheet.push_str("NPWG RECOVERY SHEET\n\n");
+    sheet.push_str(&format!(
+        "Characters: {}\n",
+        alphabet.iter().collect::<String>()
+    ));
+    sheet.push_str(
+        "Spaces only separate groups. The check character is not part of the password.\n\n",
+    );
+    sheet.push_str(&format!(
+        "{:>3}  {:<width$}  Check  Used for\n",
+        "#",
+        "Password",
+        width = width
+    ));
+    for (i, password) in passwords.iter().enumerate() {
+        sheet.push_str(&format!(
+            "{:>3}  {:<width$}  [{}]    ____________________\n",
+            i + 1,
+            group(password, group_size),
+            check_char(password, &alphabet)?,
+            width = width
+        ));
+    }
+    Ok(sheet)
+}
+
+fn pdf_escape(line: &str) -> String {
+    line.replace('\\', "\\\\")
+        .replace('(', "\\(")
+        .replace(')', "\\)")
+}
+
+/// Lays the plain-text sheet out as a minimal PDF using the built-in Courier
+/// font, so it prints with the same alignment as the text version.
+pub fn render_pdf(text: &str) -> Vec<u8> {
+    let lines_per_page = (PAGE_HEIGHT - 2 * MARGIN) / LEADING;
+    let lines: Vec<&str> = text.lines().collect();
+    let pages: Vec<&[&str]> = if lines.is_empty() {
+        vec![&[]]
+    } else {
+        lines.chunks(lines_per_page).collect()
+    };
+
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        String::new(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier >>".to_string(),
+    ];
+    let mut kids = Vec::new();
+    for page in &pages {
+        let mut content = format!(
+            "BT /F1 {} Tf {} TL {} {} Td\n",
+            FONT_SIZE,
+            LEADING,
+            MARGIN,
+            PAGE_HEIGHT - MARGIN
+        );
+        for line in page.iter() {
+            content.push_str(&format!("({}) '\n", pdf_escape(line)));
+        }
+        content.push_str("ET");
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}\nendstream",
+            content.len(),
+            content
+        ));
+        let content_id = objects.len();
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH, PAGE_HEIGHT, content_id
+        ));
+        kids.push(format!("{} 0 R", objects.len()));
+    }
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        kids.len()
+    );
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+    let xref = pdf.len();
+    pdf.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .as_bytes(),
+    );
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn luhn_valid(with_check: &str, alphabet: &[char]) -> bool {
+        let n = alphabet.len();
+        let mut factor = 1;
+        let mut sum = 0;
+        for c in with_check.chars().rev() {
+            let addend = factor * alphabet.iter().position(|&a| a == c).unwrap();
+            sum += addend / n + addend % n;
+            factor = 3 - factor;
+        }
+        sum % n == 0
+    }
+
+    #[test]
+    fn test_check_char_detects_single_errors() {
+        let alphabet = print_safe_chars();
+        let password = "ACDEFHJKLMNP";
+        let check = check_char(password, &alphabet).unwrap();
+        assert!(luhn_valid(&format!("{}{}", password, check), &alphabet));
+        assert!(!luhn_valid(&format!("ACDEFHJKLMNR{}", check), &alphabet));
+        assert!(!luhn_valid(&format!("CADEFHJKLMNP{}", check), &alphabet));
+        assert!(check_char("O0", &alphabet).is_err());
+    }
+
+    #[test]
+    fn test_recovery_codes() {
+        let alphabet = print_safe_chars();
+        let mut rng = rand::rng();
+        let code = recovery_code("XXXX-XXXX", &alphabet, &mut rng);
+        assert_eq!(code.len(), 9);
+        assert_eq!(code.as_bytes()[4], b'-');
+        assert!(code.chars().filter(|&c| c != '-').all(|c| alphabet.contains(&c)));
+
+        assert!(validate_code_format("XXXX-XXXX", &alphabet).is_ok());
+        assert!(validate_code_format("----", &alphabet).is_err());
+        assert!(validate_code_format("XXXX7XXXX", &alphabet).is_err());
+
+        assert_eq!(
+            code_hash("acde-fhjk", &alphabet),
+            code_hash("ACDEFHJK", &alphabet)
+        );
+        assert_eq!(
+            code_hash("ACDEFHJK", &alphabet),
+            "a65aa9d4329d2f235d794046a0ac88f2eed8b6b868fb658a8ea538e09cd5edf5"
+        );
+    }
+
+    #[test]
+    fn test_render_sheet() {
+        let sheet = render_sheet(&["ACDEFHJK".to_string(), "LMNP".to_string()], 4).unwrap();
+        assert!(sheet.contains("  1  ACDE FHJK  ["));
+        assert!(sheet.contains("  2  LMNP       ["));
+        assert_eq!(group("ACDEFHJKL", 4), "ACDE FHJK L");
+    }
+
+    #[test]
+    fn test_render_pdf_structure() {
+        let pdf = render_pdf("line (one)\nline two");
+        let text = String::from_utf8(pdf).unwrap();
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.contains("(line \\(one\\)) '"));
+        assert!(text.contains("/Count 1"));
+        assert!(text.ends_with("%%EOF\n"));
+    }
+}
@@ -0,0 +1,410 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/rules.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::config::PasswordGeneratorConfig;
+use crate::error::{PasswordGeneratorError, Result};
+
+const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const DIGIT: &str = "0123456789";
+const SPECIAL: &str = "-~!@#$%^&*_+=`|(){}[:;\"'<>,.?]";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PasswordRules {
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub required: Vec<Vec<char>>,
+    pub allowed: Vec<char>,
+    pub max_consecutive: Option<usize>,
+}
+
+fn named_class(name: &str) -> Option<Vec<char>> {
+    let chars = match name.to_lowercase().as_str() {
+        "upper" => UPPER.chars().collect(),
+        "lower" => LOWER.chars().collect(),
+        "digit" => DIGIT.chars().collect(),
+        "special" => SPECIAL.chars().collect(),
+        "ascii-printable" | "unicode" => ('!'..='~').collect(),
+        _ => return None,
+    };
+    Some(chars)
+}
+
+const CLASS_HINT: &str =
+    "use upper, lower, digit, special, ascii-printable or a custom class such as [-_.]";
+
+/// An error pointing at `part`, which must be a slice of `rules`.
+fn rules_error(rules: &str, part: &str, reason: String, hint: &str) -> PasswordGeneratorError {
+    let offset = (part.as_ptr() as usize)
+        .checked_sub(rules.as_ptr() as usize)
+        .filter(|offset| *offset <= rules.len())
+        .unwrap_or(0);
+    PasswordGeneratorError::RulesError {
+        rules: rules.to_string(),
+        column: rules[..offset].chars().count() + 1,
+        width: part.chars().count().max(1),
+        reason,
+        hint: hint.to_string(),
+    }
+}
+
+fn parse_classes(rules: &str, value: &str) -> Result<Vec<Vec<char>>> {
+    let mut classes = Vec::new();
+    let mut rest = value.trim();
+
+    while !rest.is_empty() {
+        if let Some(custom) = rest.strip_prefix('[') {
+            let end = custom
+                .char_indices()
+                .skip(1)
+                .find(|&(i, c)| {
+                    c == ']' && {
+                        let after = custom[i + 1..].trim_start();
+                        after.is_empty() || after.starts_with(',')
+                    }
+                })
+                .map(|(i, _)| i)
+                .or_else(|| (custom == "]").then_some(0))
+                .ok_or_else(|| {
+                    rules_error(
+                        rules,
+                        &rest[..1],
+                        format!("unterminated character class '[{}'", custom),
+                        "close the class with ']'",
+                    )
+                })?;
+            let mut chars: Vec<char> = custom[..end].chars().filter(|c| *c != ' ').collect();
+            chars.dedup();
+            classes.push(chars);
+            rest = custom[end + 1..].trim_start();
+        } else {
+            let (name, tail) = rest.split_once(',').unwrap_or((rest, ""));
+            let class = named_class(name.trim()).ok_or_else(|| {
+                rules_error(
+                    rules,
+                    name.trim(),
+                    format!("unknown character class '{}'", name.trim()),
+                    CLASS_HINT,
+                )
+            })?;
+            classes.push(class);
+            rest = tail;
+        }
+        rest = rest
+            .trim_start()
+            .strip_prefix(',')
+            .unwrap_or(rest)
+            .trim_start();
+    }
+
+    Ok(classes)
+}
+
+fn parse_number(rules: &str, name: &str, value: &str) -> Result<usize> {
+    value.trim().parse().map_err(|_| {
+        rules_error(
+            rules,
+            value.trim(),
+            format!("invalid value '{}' for '{}', expected a number", value.trim(), name),
+            "use a whole number such as 12",
+        )
+    })
+}
+
+fn split_properties(rules: &str) -> Vec<&str> {
+    let mut properties = Vec::new();
+    let mut start = 0;
+    let mut in_class = false;
+    for (i, c) in rules.char_indices() {
+        match c {
+            '[' if !in_class => in_class = true,
+            ']' if in_class => in_class = false,
+            ';' if !in_class => {
+                properties.push(&rules[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    properties.push(&rules[start..]);
+    properties
+}
+
+pub fn parse_password_rules(rules: &str) -> Result<PasswordRules> {
+    let mut parsed = PasswordRules::default();
+
+    for property in split_properties(rules)
+        .into_iter()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+    {
+        let (raw_name, value) = property.split_once(':').ok_or_else(|| {
+            rules_error(
+                rules,
+                property,
+                format!("invalid rule '{}', expected 'name: value'", property),
+                "separate the rule name and its value with ':', e.g. minlength: 12",
+            )
+        })?;
+        let name = raw_name.trim().to_lowercase();
+        match name.as_str() {
+            "minlength" => parsed.min_length = Some(parse_number(rules, &name, value)?),
+            "maxlength" => parsed.max_length = Some(parse_number(rules, &name, value)?),
+            "max-consecutive" => {
+                parsed.max_consecutive = Some(parse_number(rules, &name, value)?)
+            }
+            "required" => {
+                let classes = parse_classes(rules, value)?;
+                let mut merged: Vec<char> = classes.into_iter().flatten().collect();
+                merged.sort_unstable();
+                merged.dedup();
+                parsed.required.push(merged);
+            }
+            "allowed" => parsed
+                .allowed
+                .extend(parse_classes(rules, value)?.into_iter().flatten()),
+            _ => {
+                return Err(rules_error(
+                    rules,
+                    raw_name.trim(),
+                    format!("unknown rule '{}'", name),
+                    "known rules are minlength, maxlength, required, allowed and max-consecutive",
+                ))
+            }
+        }
+    }
+
+    if let (Some(min), Some(max)) = (parsed.min_length, parsed.max_length) {
+        if min > max {
+            return Err(PasswordGeneratorError::InvalidConfig(format!(
+                "minlength {} is greater than maxlength {}",
+                min, max
+            )));
+        }
+    }
+
+    Ok(parsed)
+}
+
+impl PasswordRules {
+    pub fn apply(&self, config: &mut PasswordGeneratorConfig) {
+        if let Some(min) = self.min_length {
+            config.length = config.length.max(min);
+        }
+        if let Some(max) = self.max_length {
+            config.length = config.length.min(max);
+            config.max_length = Some(config.max_length.map_or(max, |cap| cap.min(max)));
+        }
+
+        if !self.required.is_empty() || !self.allowed.is_empty() {
+            let mut pool: Vec<char> = self
+                .required
+                .iter()
+                .flatten()
+                .chain(self.allowed.iter())
+                .copied()
+                .collect();
+            pool.sort_unstable();
+            pool.dedup();
+            config.allowed_chars = pool;
+        }
+
+        config.required_sets = self.required.clone();
+        if self.max_consecutive.is_some() {
+            config.max_consecutive = self.max_consecutive;
+        }
+    }
+
+    /// The rules of `self` and `other` together, for a password that has to
+    /// satisfy both: the tighter length limits and `max-consecutive`, only the
+    /// characters both allow, and the required classes of both.
+    pub fn and(&self, other: &PasswordRules) -> Result<PasswordRules> {
+        fn pool(rules: &PasswordRules) -> Option<Vec<char>> {
+            (!rules.required.is_empty() || !rules.allowed.is_empty())
+                .then(|| rules.required.iter().flatten().chain(&rules.allowed).copied().collect())
+        }
+        fn tighter(a: Option<usize>, b: Option<usize>, pick: fn(usize, usize) -> usize) -> Option<usize> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(pick(a, b)),
+                (a, b) => a.or(b),
+            }
+        }
+
+        let mut allowed = match (pool(self), pool(other)) {
+            (Some(first), Some(second)) => first.into_iter().filter(|c| second.contains(c)).collect(),
+            (first, second) => first.or(second).unwrap_or_default(),
+        };
+        allowed.sort_unstable();
+        allowed.dedup();
+        let mut required = Vec::new();
+        for class in self.required.iter().chain(&other.required) {
+            let class: Vec<char> = class.iter().copied().filter(|c| allowed.contains(c)).collect();
+            if class.is_empty() {
+                return Err(PasswordGeneratorError::InvalidConfig(
+                    "The combined rules allow no character of a required class".to_string(),
+                ));
+            }
+            required.push(class);
+        }
+        let combined = PasswordRules {
+            min_length: tighter(self.min_length, other.min_length, usize::max),
+            max_length: tighter(self.max_length, other.max_length, usize::min),
+            required,
+            allowed,
+            max_consecutive: tighter(self.max_consecutive, other.max_consecutive, usize::min),
+        };
+        if let (Some(min), Some(max)) = (combined.min_length, combined.max_length) {
+            if min > max {
+                return Err(PasswordGeneratorError::InvalidConfig(format!(
+                    "The combined rules need minlength {} but allow at most {}",
+                    min, max
+                )));
+            }
+        }
+        Ok(combined)
+    }
+
+    /// The rules an existing password breaks, named after the passwordrules
+    /// property it fails.
+    pub fn violations(&self, password: &str) -> Vec<&'static str> {
+        let chars: Vec<char> = password.chars().collect();
+        let mut violations = Vec::new();
+        if self.min_length.is_some_and(|min| chars.len() < min) {
+            violations.push("minlength");
+        }
+        if self.max_length.is_some_and(|max| chars.len() > max) {
+            violations.push("maxlength");
+        }
+        if self
+            .required
+            .iter()
+            .any(|class| !chars.iter().any(|c| class.contains(c)))
+        {
+            violations.push("required");
+        }
+        if (!self.required.is_empty() || !self.allowed.is_empty())
+            && !chars.iter().all(|c| {
+                self.allowed.contains(c) || self.required.iter().any(|class| class.contains(c))
+            })
+        {
+            violations.push("allowed");
+        }
+        if let Some(max) = self.max_consecutive {
+            if chars
+                .chunk_by(|a, b| a == b)
+                .any(|run| run.len() > max)
+            {
+                violations.push("max-consecutive");
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_password_rules() {
+        let rules = parse_password_rules(
+            "minlength: 12; maxlength: 20; required: upper; required: digit; allowed: [-().&@?#]; max-consecutive: 2",
+        )
+        .unwrap();
+        assert_eq!(rules.min_length, Some(12));
+        assert_eq!(rules.max_length, Some(20));
+        assert_eq!(rules.max_consecutive, Some(2));
+        assert_eq!(rules.required.len(), 2);
+        assert_eq!(rules.required[1], DIGIT.chars().collect::<Vec<char>>());
+        assert_eq!(rules.allowed, "-().&@?#".chars().collect::<Vec<char>>());
+    }
+
+    #[test]
+    fn test_parse_combined_and_custom_classes() {
+        let rules = parse_password_rules("required: lower, upper; allowed: [;], [-]]").unwrap();
+        assert_eq!(rules.required[0].len(), 52);
+        assert_eq!(rules.allowed, vec![';', '-', ']']);
+    }
+
+    #[test]
+    fn test_parse_password_rules_errors() {
+        assert!(parse_password_rules("minlength: twelve").is_err());
+        assert!(parse_password_rules("required: symbols").is_err());
+        assert!(parse_password_rules("colour: blue").is_err());
+        assert!(parse_password_rules("allowed: [abc").is_err());
+        assert!(parse_password_rules("minlength: 20; maxlength: 10").is_err());
+    }
+
+    #[test]
+    fn test_rules_errors_point_at_the_input() {
+        let span = |rules: &str| match parse_password_rules(rules) {
+            Err(PasswordGeneratorError::RulesError { column, width, .. }) => (column, width),
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(span("minlength: 8; colour: blue"), (15, 6));
+        assert_eq!(span("required: upper, symbols"), (18, 7));
+        assert_eq!(span("maxlength: twelve"), (12, 6));
+        assert_eq!(span("allowed: [abc"), (10, 1));
+        assert_eq!(span("minlength 8"), (1, 11));
+    }
+
+    #[test]
+    fn test_rule_violations() {
+        let rules = parse_password_rules(
+            "minlength: 8; maxlength: 10; required: upper; required: digit; allowed: [-]; max-consecutive: 2",
+        )
+        .unwrap();
+        assert!(rules.violations("ABC-123X").is_empty());
+        assert_eq!(rules.violations("ABC"), ["minlength", "required"]);
+        assert_eq!(rules.violations("ABCD-EFGH!"), ["required", "allowed"]);
+        assert_eq!(rules.violations("AAA1234567X"), ["maxlength", "max-consecutive"]);
+        assert!(PasswordRules::default().violations("anything").is_empty());
+    }
+
+    #[test]
+    fn test_combine_rules() {
+        let site = parse_password_rules("minlength: 8; maxlength: 16; required: upper; allowed: lower, digit").unwrap();
+        let extra = parse_password_rules("minlength: 12; required: digit; allowed: upper; max-consecutive: 2").unwrap();
+        let both = site.and(&extra).unwrap();
+        assert_eq!((both.min_length, both.max_length, both.max_consecutive), (Some(12), Some(16), Some(2)));
+        assert_eq!(both.required.len(), 2);
+        assert_eq!(both.allowed.len(), 36);
+        assert!(both.violations("ABCDEFGH1234").is_empty());
+        assert_eq!(both.violations("ABCDEFGHIJKL"), ["required"]);
+        assert_eq!(both.violations("abcdefgh1234"), ["required", "allowed"]);
+
+        let alone = site.and(&PasswordRules::default()).unwrap();
+        assert_eq!(alone.violations("Abcdefgh"), site.violations("Abcdefgh"));
+        assert_eq!(alone.allowed.len(), 62);
+        assert!(site.and(&parse_password_rules("allowed: digit; required: digit").unwrap()).is_err());
+        assert!(site.and(&parse_password_rules("minlength: 20").unwrap()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_apply_rules_to_config() {
+        let mut config = PasswordGeneratorConfig::new();
+        config.length = 8;
+        parse_password_rules("minlength: 12; required: upper; required: digit")
+            .unwrap()
+            .apply(&mut config);
+        assert_eq!(config.length, 12);
+        assert_eq!(config.allowed_chars.len(), 36);
+        assert!(config.validate().is_ok());
+
+        for _ in 0..20 {
+            let password = crate::generator::generate_password(&config).await.unwrap();
+            assert_eq!(password.len(), 12);
+            assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+            assert!(password.chars().any(|c| c.is_ascii_digit()));
+        }
+        assert_eq!(config.max_length, None);
+
+        // A site preset and --rules together keep the tighter cap.
+        parse_password_rules("maxlength: 10").unwrap().apply(&mut config);
+        parse_password_rules("maxlength: 14").unwrap().apply(&mut config);
+        assert_eq!((config.length, config.max_length), (10, Some(10)));
+    }
+}
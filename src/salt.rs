@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/salt.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::Result;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use rand::rngs::OsRng;
+use rand::TryRngCore;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Hex,
+    Base64,
+    Base64Url,
+}
+
+impl std::str::FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hex" => Ok(Encoding::Hex),
+            "base64" => Ok(Encoding::Base64),
+            "base64url" => Ok(Encoding::Base64Url),
+            _ => Err(format!(
+                "Invalid encoding: {} (use hex, base64 or base64url)",
+                s
+            )),
+        }
+    }
+}
+
+pub fn random_bytes(count: usize) -> Result<Vec<u8>> {
+    let mut bytes = vec![0u8; count];
+    OsRng
+        .try_fill_bytes(&mut bytes)
+        .map_err(std::io::Error::other)?;
+    Ok(bytes)
+}
+
+pub fn encode(bytes: &[u8], encoding: Encoding) -> String {
+    match encoding {
+        Encoding::Hex => bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+        Encoding::Base64 => STANDARD.encode(bytes),
+        Encoding::Base64Url => URL_SAFE_NO_PAD.encode(bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let bytes = [0x00, 0xff, 0x10, 0xfb];
+        assert_eq!(encode(&bytes, Encoding::Hex), "00ff10fb");
+        assert_eq!(encode(&bytes, Encoding::Base64), "AP8Q+w==");
+        assert_eq!(encode(&bytes, Encoding::Base64Url), "AP8Q-w");
+    }
+
+    #[test]
+    fn test_random_bytes() {
+        let first = random_bytes(32).unwrap();
+        let second = random_bytes(32).unwrap();
+        assert_eq!(first.len(), 32);
+        assert_ne!(first, second);
+    }
+}
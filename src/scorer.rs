@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/scorer.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::{PasswordGeneratorError, Result};
+use crate::settings::CommandLine;
+use serde_json::Value;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use zeroize::Zeroize;
+
+/// Runs an external scorer: the password and a newline go to its stdin, and
+/// it must print a JSON object with a numeric `score`. Optional `feedback`
+/// (a string) and `suggestions` (a list of strings) are shown in the text
+/// report; the whole object is kept for the JSON report.
+pub fn external_score(command: &CommandLine, password: &str) -> Result<Value> {
+    let argv = command.argv();
+    let program = argv.first().ok_or_else(|| {
+        PasswordGeneratorError::ExternalScorer("no command configured".to_string())
+    })?;
+    let mut child = Command::new(program)
+        .args(&argv[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| PasswordGeneratorError::ExternalScorer(format!("Failed to run '{}': {}", program, e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let mut input = format!("{}\n", password);
+        // A scorer may exit without reading its input; its answer decides.
+        let _ = stdin.write_all(input.as_bytes());
+        input.zeroize();
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(PasswordGeneratorError::ExternalScorer(format!(
+            "'{}' failed ({})",
+            argv.join(" "),
+            output.status
+        )));
+    }
+    parse_score(&output.stdout)
+}
+
+fn parse_score(stdout: &[u8]) -> Result<Value> {
+    let invalid = |reason: &str| {
+        PasswordGeneratorError::ExternalScorer(format!("the scorer's output {}", reason))
+    };
+    let value: Value =
+        serde_json::from_slice(stdout).map_err(|_| invalid("is not valid JSON"))?;
+    if !value.is_object() {
+        return Err(invalid("is not a JSON object"));
+    }
+    if !value["score"].is_number() {
+        return Err(invalid("has no numeric \"score\""));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_score() {
+        let value = parse_score(br#"{"score": 3, "feedback": "add a word"}"#).unwrap();
+        assert_eq!(value["score"], 3);
+        assert!(parse_score(b"3").is_err());
+        assert!(parse_score(br#"{"score": "high"}"#).is_err());
+        assert!(parse_score(b"not json").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_external_score_reads_password_from_stdin() {
+        let command = CommandLine::Args(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "read p; printf '{\"score\": %d}' ${#p}".to_string(),
+        ]);
+        assert_eq!(external_score(&command, "hunter22").unwrap()["score"], 8);
+
+        let failing = CommandLine::Line("false".to_string());
+        assert!(matches!(
+            external_score(&failing, "x"),
+            Err(PasswordGeneratorError::ExternalScorer(_))
+        ));
+    }
+}
@@ -0,0 +1,222 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/settings.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::{PasswordGeneratorError, Result};
+use dirs::home_dir;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// A command as a single string split on whitespace, or as a list of the
+/// program and its arguments when an argument contains spaces.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum CommandLine {
+    Line(String),
+    Args(Vec<String>),
+}
+
+impl CommandLine {
+    /// The program followed by its arguments.
+    pub fn argv(&self) -> Vec<String> {
+        match self {
+            CommandLine::Line(line) => line.split_whitespace().map(str::to_string).collect(),
+            CommandLine::Args(args) => args.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StrengthSettings {
+    /// Scorer that reads a password on stdin and prints a JSON score.
+    pub external_command: Option<CommandLine>,
+    /// Cells of the strength bar, 20 by default.
+    pub bar_width: Option<usize>,
+    /// The filled and the empty cell, such as `"█░"`.
+    pub bar_chars: Option<String>,
+    /// Draw the bar in ASCII; decided by the locale when not set.
+    pub bar_ascii: Option<bool>,
+    /// Default for `--strength-threshold`.
+    pub threshold: Option<f64>,
+}
+
+/// Keys for reviewing generated passwords in interactive mode; see
+/// [`crate::keymap::Keymap`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeySettings {
+    /// `default` or `vim`; the keys below replace single bindings of it.
+    pub preset: Option<String>,
+    pub regenerate: Option<String>,
+    pub copy: Option<String>,
+    pub toggle_mask: Option<String>,
+    pub quit: Option<String>,
+}
+
+/// Styles for the roles of the output, such as `"bold blue"` or
+/// `"#005f87"`; see [`crate::theme::Theme`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeSettings {
+    pub password: Option<String>,
+    pub heading: Option<String>,
+    pub title: Option<String>,
+    pub value: Option<String>,
+    pub success: Option<String>,
+    pub warning: Option<String>,
+    pub error: Option<String>,
+    pub weak: Option<String>,
+    pub moderate: Option<String>,
+    pub strong: Option<String>,
+    pub very_strong: Option<String>,
+}
+
+/// Layout version of `config.toml` read by this build. Files without a
+/// `version` key have the same layout.
+pub const SETTINGS_VERSION: i64 = 1;
+
+/// Settings read from `~/.npwg/config.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    /// Layout version the file was written for.
+    pub version: Option<i64>,
+    /// Default for `--offline`.
+    pub offline: Option<bool>,
+    /// Default for `--min-bits`.
+    pub min_bits: Option<f64>,
+    #[serde(default)]
+    pub strength: StrengthSettings,
+    #[serde(default)]
+    pub keys: KeySettings,
+    #[serde(default)]
+    pub theme: ThemeSettings,
+}
+
+impl Settings {
+    pub fn parse(text: &str) -> Result<Self> {
+        let settings: Settings = toml::from_str(text)
+            .map_err(|e| PasswordGeneratorError::InvalidConfig(format!("Invalid config.toml: {}", e)))?;
+        if let Some(version) = settings.version.filter(|v| !(1..=SETTINGS_VERSION).contains(v)) {
+            return Err(PasswordGeneratorError::InvalidConfig(format!(
+                "config.toml uses layout version {}, but this npwg only reads versions 1 to {}; upgrade npwg",
+                version, SETTINGS_VERSION
+            )));
+        }
+        if settings
+            .strength
+            .external_command
+            .as_ref()
+            .is_some_and(|command| command.argv().is_empty())
+        {
+            return Err(PasswordGeneratorError::InvalidConfig(
+                "Invalid config.toml: strength.external_command is empty".to_string(),
+            ));
+        }
+        if settings.min_bits.is_some_and(|bits| !(bits >= 0.0 && bits.is_finite())) {
+            return Err(PasswordGeneratorError::InvalidConfig(
+                "Invalid config.toml: min_bits must be a non-negative number".to_string(),
+            ));
+        }
+        crate::keymap::Keymap::from_settings(&settings.keys)?;
+        crate::theme::Theme::from_settings(&settings.theme)?;
+        crate::output::BarStyle::from_settings(&settings.strength, true)?;
+        if settings
+            .strength
+            .threshold
+            .is_some_and(|threshold| !(0.0..=1.0).contains(&threshold))
+        {
+            return Err(PasswordGeneratorError::InvalidConfig(
+                "Invalid config.toml: strength.threshold must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+        Ok(settings)
+    }
+
+    /// The settings in `path`, or the defaults when there is no such file.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Settings::parse(&text),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn load() -> Result<Self> {
+        Settings::load_from(&settings_path()?)
+    }
+}
+
+pub fn settings_path() -> Result<PathBuf> {
+    let home = home_dir().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "Home directory not found")
+    })?;
+    Ok(home.join(".npwg").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_external_command() {
+        let settings = Settings::parse("[strength]\nexternal_command = \"zxcvbn-score --json\"\n").unwrap();
+        assert_eq!(
+            settings.strength.external_command.unwrap().argv(),
+            ["zxcvbn-score", "--json"]
+        );
+        let settings =
+            Settings::parse("[strength]\nexternal_command = [\"python3\", \"/opt/my scorer.py\"]\n").unwrap();
+        assert_eq!(
+            settings.strength.external_command.unwrap().argv(),
+            ["python3", "/opt/my scorer.py"]
+        );
+        assert_eq!(Settings::parse("").unwrap(), Settings::default());
+        assert!(Settings::parse("[strength]\nexternal_command = \"  \"\n").is_err());
+        assert!(Settings::parse("[strength]\nexternal_comand = \"x\"\n").is_err());
+    }
+
+    #[test]
+    fn test_layout_version() {
+        assert_eq!(Settings::parse("version = 1\n").unwrap().version, Some(1));
+        assert!(Settings::parse(&format!("version = {}\n", SETTINGS_VERSION + 1)).is_err());
+        assert!(Settings::parse("version = 0\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_top_level_defaults() {
+        let settings = Settings::parse("offline = true\nmin_bits = 60.0\n").unwrap();
+        assert_eq!(settings.offline, Some(true));
+        assert_eq!(settings.min_bits, Some(60.0));
+        assert!(Settings::parse("min_bits = -1.0\n").is_err());
+        assert!(Settings::parse("offline = \"yes\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        let settings = Settings::parse("[keys]\npreset = \"vim\"\ncopy = \"c\"\n").unwrap();
+        assert_eq!(settings.keys.preset.as_deref(), Some("vim"));
+        assert_eq!(settings.keys.copy.as_deref(), Some("c"));
+        assert!(Settings::parse("[keys]\npreset = \"emacs\"\n").is_err());
+        assert!(Settings::parse("[keys]\ncopy = \"q\"\n").is_err());
+        assert!(Settings::parse("[keys]\npaste = \"p\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_theme() {
+        let settings = Settings::parse("[theme]\nvalue = \"bold magenta\"\nvery_strong = \"#00aa00\"\n").unwrap();
+        assert_eq!(settings.theme.value.as_deref(), Some("bold magenta"));
+        assert!(Settings::parse("[theme]\nvalue = \"mauve\"\n").is_err());
+        assert!(Settings::parse("[theme]\nbanner = \"red\"\n").is_err());
+    }
+
+    #[test]
+    fn test_missing_file_gives_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        assert_eq!(Settings::load_from(&path).unwrap(), Settings::default());
+    }
+}
@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/sites.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::config::PasswordGeneratorConfig;
+use crate::error::{PasswordGeneratorError, Result};
+use crate::rules::{parse_password_rules, PasswordRules};
+
+pub struct SitePreset {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub rules: &'static str,
+    pub pattern: Option<&'static str>,
+}
+
+pub const SITE_PRESETS: &[SitePreset] = &[
+    SitePreset {
+        name: "legacy-bank",
+        description: "Online banking with a short length cap and no special characters",
+        rules: "minlength: 8; maxlength: 12; required: lower; required: upper; required: digit",
+        pattern: None,
+    },
+    SitePreset {
+        name: "bank-pin",
+        description: "Numeric telephone or card PIN",
+        rules: "minlength: 6; maxlength: 6; required: digit",
+        pattern: None,
+    },
+    SitePreset {
+        name: "airline",
+        description: "Airline loyalty accounts with a small set of accepted symbols",
+        rules: "minlength: 8; maxlength: 16; required: upper; required: lower; required: digit; allowed: [!#$%&*]",
+        pattern: None,
+    },
+    SitePreset {
+        name: "gov-portal",
+        description: "Government portals requiring every class and no long runs",
+        rules: "minlength: 12; maxlength: 20; required: upper; required: lower; required: digit; required: [!@#$%^&*]; max-consecutive: 2",
+        pattern: None,
+    },
+    SitePreset {
+        name: "mainframe",
+        description: "RACF-style logins: exactly 8 characters starting with a letter",
+        rules: "minlength: 8; maxlength: 8; required: upper; required: digit; allowed: [#@$]",
+        pattern: Some("L"),
+    },
+    SitePreset {
+        name: "no-symbols",
+        description: "Services that reject all punctuation",
+        rules: "minlength: 16; maxlength: 32; required: lower; required: upper; required: digit",
+        pattern: None,
+    },
+];
+
+pub fn find_site(name: &str) -> Result<&'static SitePreset> {
+    SITE_PRESETS
+        .iter()
+        .find(|preset| preset.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            PasswordGeneratorError::InvalidConfig(format!(
+                "Unknown site preset '{}'. Use one of: {}",
+                name,
+                SITE_PRESETS
+                    .iter()
+                    .map(|preset| preset.name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })
+}
+
+impl SitePreset {
+    /// The preset's rules, combined with `extra` rules from `--rules` that a
+    /// password has to satisfy as well.
+    pub fn policy(&self, extra: Option<&PasswordRules>) -> Result<PasswordRules> {
+        let rules = parse_password_rules(self.rules)?;
+        match extra {
+            Some(extra) => rules.and(extra),
+            None => Ok(rules),
+        }
+    }
+
+    pub fn apply(&self, config: &mut PasswordGeneratorConfig, extra: Option<&PasswordRules>) -> Result<()> {
+        self.policy(extra)?.apply(config);
+        if config.pattern.is_none() {
+            config.pattern = self.pattern.map(str::to_string);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_site_presets_are_valid() {
+        for preset in SITE_PRESETS {
+            let mut config = PasswordGeneratorConfig::new();
+            preset.apply(&mut config, None).unwrap();
+            assert!(config.validate().is_ok(), "preset {}", preset.name);
+        }
+    }
+
+    #[test]
+    fn test_find_site() {
+        assert_eq!(find_site("Bank-PIN").unwrap().name, "bank-pin");
+        assert!(find_site("unknown").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mainframe_preset_starts_with_letter() {
+        let mut config = PasswordGeneratorConfig::new();
+        find_site("mainframe").unwrap().apply(&mut config, None).unwrap();
+        for _ in 0..20 {
+            let password = crate::generator::generate_password(&config).await.unwrap();
+            assert_eq!(password.len(), 8);
+            assert!(password.starts_with(|c: char| c.is_ascii_uppercase()));
+            assert!(password.chars().any(|c| c.is_ascii_digit()));
+        }
+    }
+}
@@ -1,8 +1,26 @@
+#[derive(Debug, Clone)]
 pub struct PasswordQuality {
     pub mean: f64,
     pub variance: f64,
     pub skewness: f64,
     pub kurtosis: f64,
+    pub min: f64,
+    pub max: f64,
+    pub median: f64,
+    /// 5th percentile: the entropy that 95% of the batch reaches.
+    pub p5: f64,
+    pub p95: f64,
+}
+
+/// The `p`th percentile (0–100) of sorted values, interpolating linearly
+/// between the two closest ranks. NaN for an empty batch, like the mean.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let Some(last) = sorted.len().checked_sub(1) else {
+        return f64::NAN;
+    };
+    let rank = p / 100.0 * last as f64;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64)
 }
 
 pub fn show_stats(passwords: &[String]) -> PasswordQuality {
@@ -19,11 +37,19 @@ pub fn show_stats(passwords: &[String]) -> PasswordQuality {
     let kurtosis =
         entropies.iter().map(|&x| (x - mean).powi(4)).sum::<f64>() / (n * variance.powi(2)) - 3.0;
 
+    let mut sorted = entropies;
+    sorted.sort_by(f64::total_cmp);
+
     PasswordQuality {
         mean,
         variance,
         skewness,
         kurtosis,
+        min: percentile(&sorted, 0.0),
+        max: percentile(&sorted, 100.0),
+        median: percentile(&sorted, 50.0),
+        p5: percentile(&sorted, 5.0),
+        p95: percentile(&sorted, 95.0),
     }
 }
 fn calculate_entropy(password: &str) -> f64 {
@@ -42,3 +68,473 @@ fn calculate_entropy(password: &str) -> f64 {
         acc - p * p.log2()
     })
 }
+
+/// File format of a `--report`, chosen by the file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Html,
+    Markdown,
+}
+
+impl ReportFormat {
+    pub fn from_path(path: &std::path::Path) -> crate::error::Result<Self> {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_lowercase)
+            .as_deref()
+        {
+            Some("html") | Some("htm") => Ok(ReportFormat::Html),
+            Some("md") | Some("markdown") => Ok(ReportFormat::Markdown),
+            _ => Err(crate::error::PasswordGeneratorError::InvalidConfig(format!(
+                "Cannot determine report format of '{}' (use a .html or .md extension)",
+                path.display()
+            ))),
+        }
+    }
+}
+
+/// One bar of the entropy histogram: the half-open range and how many
+/// passwords fell into it (the last bin also holds its upper bound).
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramBin {
+    pub low: f64,
+    pub high: f64,
+    pub count: usize,
+}
+
+/// Pearson's chi-square test of the character frequencies against a uniform
+/// distribution over the characters that occur in the batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChiSquare {
+    pub statistic: f64,
+    pub degrees_of_freedom: usize,
+    pub critical: f64,
+}
+
+impl ChiSquare {
+    pub fn passed(&self) -> bool {
+        self.statistic <= self.critical
+    }
+}
+
+/// Everything a statistics report shows. It holds counts only, never the
+/// passwords themselves, so the report can be shared.
+#[derive(Debug, Clone)]
+pub struct BatchReport {
+    pub count: usize,
+    pub quality: PasswordQuality,
+    pub histogram: Vec<HistogramBin>,
+    pub classes: Vec<(&'static str, usize)>,
+    pub characters: Vec<(char, usize)>,
+    pub chi_square: Option<ChiSquare>,
+    pub duplicates: usize,
+}
+
+const HISTOGRAM_BINS: usize = 10;
+
+/// Smaller batches get no character frequency table: for a single password it
+/// would list every one of its characters.
+const MIN_FREQUENCY_BATCH: usize = 50;
+
+fn entropy_histogram(entropies: &[f64]) -> Vec<HistogramBin> {
+    let Some(min) = entropies.iter().copied().reduce(f64::min) else {
+        return Vec::new();
+    };
+    let max = entropies.iter().copied().fold(min, f64::max);
+    if (max - min).abs() < f64::EPSILON {
+        return vec![HistogramBin {
+            low: min,
+            high: max,
+            count: entropies.len(),
+        }];
+    }
+    let width = (max - min) / HISTOGRAM_BINS as f64;
+    let mut bins: Vec<HistogramBin> = (0..HISTOGRAM_BINS)
+        .map(|i| HistogramBin {
+            low: min + width * i as f64,
+            high: min + width * (i + 1) as f64,
+            count: 0,
+        })
+        .collect();
+    for &entropy in entropies {
+        let index = (((entropy - min) / width) as usize).min(HISTOGRAM_BINS - 1);
+        bins[index].count += 1;
+    }
+    bins
+}
+
+fn character_class(c: char) -> &'static str {
+    if c.is_ascii_uppercase() {
+        "Uppercase"
+    } else if c.is_ascii_lowercase() {
+        "Lowercase"
+    } else if c.is_ascii_digit() {
+        "Digits"
+    } else if c.is_ascii_punctuation() {
+        "Symbols"
+    } else if c.is_whitespace() {
+        "Whitespace"
+    } else {
+        "Other"
+    }
+}
+
+/// Upper 1% point by the Wilson-Hilferty approximation, good enough to flag
+/// a batch whose characters are visibly skewed.
+fn chi_square_critical(degrees_of_freedom: usize) -> f64 {
+    const Z_99: f64 = 2.326_348;
+    let k = degrees_of_freedom as f64;
+    let c = 2.0 / (9.0 * k);
+    k * (1.0 - c + Z_99 * c.sqrt()).powi(3)
+}
+
+pub fn batch_report(passwords: &[String]) -> BatchReport {
+    let entropies: Vec<f64> = passwords.iter().map(|s| calculate_entropy(s)).collect();
+
+    let mut counts: std::collections::BTreeMap<char, usize> = std::collections::BTreeMap::new();
+    for c in passwords.iter().flat_map(|p| p.chars()) {
+        *counts.entry(c).or_insert(0) += 1;
+    }
+    let mut classes: Vec<(&'static str, usize)> = Vec::new();
+    for (&c, &count) in &counts {
+        match classes.iter_mut().find(|(name, _)| *name == character_class(c)) {
+            Some((_, total)) => *total += count,
+            None => classes.push((character_class(c), count)),
+        }
+    }
+    classes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    let total: usize = counts.values().sum();
+    let chi_square = (counts.len() > 1).then(|| {
+        let expected = total as f64 / counts.len() as f64;
+        let statistic = counts
+            .values()
+            .map(|&observed| (observed as f64 - expected).powi(2) / expected)
+            .sum();
+        ChiSquare {
+            statistic,
+            degrees_of_freedom: counts.len() - 1,
+            critical: chi_square_critical(counts.len() - 1),
+        }
+    });
+
+    let unique: std::collections::HashSet<&String> = passwords.iter().collect();
+
+    BatchReport {
+        count: passwords.len(),
+        quality: show_stats(passwords),
+        histogram: entropy_histogram(&entropies),
+        classes,
+        characters: counts.into_iter().collect(),
+        chi_square,
+        duplicates: passwords.len() - unique.len(),
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&#39;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+fn markdown_char(c: char) -> String {
+    match c {
+        ' ' => "space".to_string(),
+        '\t' => "tab".to_string(),
+        '`' => "`` ` ``".to_string(),
+        '|' => "`\\|`".to_string(),
+        c => format!("`{}`", c),
+    }
+}
+
+fn html_char(c: char) -> String {
+    match c {
+        ' ' => "space".to_string(),
+        '\t' => "tab".to_string(),
+        c => format!("<code>{}</code>", html_escape(&c.to_string())),
+    }
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / whole as f64
+    }
+}
+
+impl BatchReport {
+    fn summary(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("Passwords", self.count.to_string()),
+            ("Mean entropy (bits/char)", format!("{:.6}", self.quality.mean)),
+            ("Variance", format!("{:.6}", self.quality.variance)),
+            ("Skewness", format!("{:.6}", self.quality.skewness)),
+            ("Kurtosis", format!("{:.6}", self.quality.kurtosis)),
+            ("Minimum", format!("{:.6}", self.quality.min)),
+            ("5th percentile", format!("{:.6}", self.quality.p5)),
+            ("Median", format!("{:.6}", self.quality.median)),
+            ("95th percentile", format!("{:.6}", self.quality.p95)),
+            ("Maximum", format!("{:.6}", self.quality.max)),
+        ]
+    }
+
+    fn tests(&self) -> Vec<(&'static str, String, bool)> {
+        let mut tests = vec![(
+            "Uniqueness",
+            format!("{} duplicate(s)", self.duplicates),
+            self.duplicates == 0,
+        )];
+        if let Some(chi) = &self.chi_square {
+            tests.push((
+                "Chi-square (character frequencies, 1%)",
+                format!(
+                    "χ² = {:.2}, df = {}, critical = {:.2}",
+                    chi.statistic, chi.degrees_of_freedom, chi.critical
+                ),
+                chi.passed(),
+            ));
+        }
+        tests
+    }
+
+    fn shows_frequencies(&self) -> bool {
+        self.count >= MIN_FREQUENCY_BATCH
+    }
+
+    fn frequencies_left_out(&self) -> String {
+        format!(
+            "Left out for batches of fewer than {} passwords, where they would give away the passwords' characters.",
+            MIN_FREQUENCY_BATCH
+        )
+    }
+
+    fn character_total(&self) -> usize {
+        self.characters.iter().map(|(_, count)| count).sum()
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Html => self.to_html(),
+            ReportFormat::Markdown => self.to_markdown(),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        const BAR: usize = 40;
+        let mut out = format!(
+            "# npwg statistics report\n\nGenerated by npwg {}. The report holds counts only, no passwords.\n\n## Summary\n\n| Metric | Value |\n|---|---:|\n",
+            env!("CARGO_PKG_VERSION")
+        );
+        for (metric, value) in self.summary() {
+            out.push_str(&format!("| {} | {} |\n", metric, value));
+        }
+
+        out.push_str("\n## Entropy distribution\n\n| Bits/char | Count | |\n|---|---:|---|\n");
+        let peak = self.histogram.iter().map(|bin| bin.count).max().unwrap_or(0);
+        for bin in &self.histogram {
+            let bar = (bin.count * BAR).checked_div(peak).unwrap_or(0);
+            out.push_str(&format!(
+                "| {:.3} – {:.3} | {} | {} |\n",
+                bin.low,
+                bin.high,
+                bin.count,
+                "█".repeat(bar)
+            ));
+        }
+
+        let total = self.character_total();
+        out.push_str("\n## Character classes\n\n| Class | Count | Share |\n|---|---:|---:|\n");
+        for (class, count) in &self.classes {
+            out.push_str(&format!("| {} | {} | {:.1}% |\n", class, count, percent(*count, total)));
+        }
+
+        out.push_str("\n## Character frequencies\n\n");
+        if self.shows_frequencies() {
+            out.push_str("| Character | Count | Share |\n|---|---:|---:|\n");
+            for (c, count) in &self.characters {
+                out.push_str(&format!(
+                    "| {} | {} | {:.2}% |\n",
+                    markdown_char(*c),
+                    count,
+                    percent(*count, total)
+                ));
+            }
+        } else {
+            out.push_str(&format!("{}\n", self.frequencies_left_out()));
+        }
+
+        out.push_str("\n## Tests\n\n| Test | Result | Outcome |\n|---|---|---|\n");
+        for (test, result, passed) in self.tests() {
+            out.push_str(&format!(
+                "| {} | {} | {} |\n",
+                test,
+                result,
+                if passed { "pass" } else { "FAIL" }
+            ));
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut out = String::from(concat!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n",
+            "<title>npwg statistics report</title>\n<style>\n",
+            "body{font-family:system-ui,sans-serif;max-width:52rem;margin:2rem auto;padding:0 1rem;color:#222}\n",
+            "table{border-collapse:collapse;margin-bottom:1.5rem}\n",
+            "th,td{border:1px solid #ccc;padding:.25rem .6rem;text-align:left}\n",
+            "td.num{text-align:right}\n",
+            ".bar{background:#3b6fd4;height:.9rem}\n",
+            ".pass{color:#1a7f37}.fail{color:#cf222e;font-weight:bold}\n",
+            "</style>\n</head>\n<body>\n<h1>npwg statistics report</h1>\n",
+        ));
+        out.push_str(&format!(
+            "<p>Generated by npwg {}. The report holds counts only, no passwords.</p>\n",
+            env!("CARGO_PKG_VERSION")
+        ));
+
+        out.push_str("<h2>Summary</h2>\n<table>\n");
+        for (metric, value) in self.summary() {
+            out.push_str(&format!("<tr><th>{}</th><td class=\"num\">{}</td></tr>\n", metric, value));
+        }
+        out.push_str("</table>\n");
+
+        out.push_str("<h2>Entropy distribution</h2>\n<table>\n<tr><th>Bits/char</th><th>Count</th><th style=\"width:20rem\"></th></tr>\n");
+        let peak = self.histogram.iter().map(|bin| bin.count).max().unwrap_or(0);
+        for bin in &self.histogram {
+            out.push_str(&format!(
+                "<tr><td>{:.3} – {:.3}</td><td class=\"num\">{}</td><td><div class=\"bar\" style=\"width:{:.1}%\"></div></td></tr>\n",
+                bin.low,
+                bin.high,
+                bin.count,
+                percent(bin.count, peak)
+            ));
+        }
+        out.push_str("</table>\n");
+
+        let total = self.character_total();
+        out.push_str("<h2>Character classes</h2>\n<table>\n<tr><th>Class</th><th>Count</th><th>Share</th></tr>\n");
+        for (class, count) in &self.classes {
+            out.push_str(&format!(
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{:.1}%</td></tr>\n",
+                class,
+                count,
+                percent(*count, total)
+            ));
+        }
+        out.push_str("</table>\n");
+
+        out.push_str("<h2>Character frequencies</h2>\n");
+        if self.shows_frequencies() {
+            out.push_str("<table>\n<tr><th>Character</th><th>Count</th><th>Share</th></tr>\n");
+            for (c, count) in &self.characters {
+                out.push_str(&format!(
+                    "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{:.2}%</td></tr>\n",
+                    html_char(*c),
+                    count,
+                    percent(*count, total)
+                ));
+            }
+            out.push_str("</table>\n");
+        } else {
+            out.push_str(&format!("<p>{}</p>\n", self.frequencies_left_out()));
+        }
+
+        out.push_str("<h2>Tests</h2>\n<table>\n<tr><th>Test</th><th>Result</th><th>Outcome</th></tr>\n");
+        for (test, result, passed) in self.tests() {
+            let (class, label) = if passed { ("pass", "pass") } else { ("fail", "FAIL") };
+            out.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td class=\"{}\">{}</td></tr>\n",
+                test, result, class, label
+            ));
+        }
+        out.push_str("</table>\n</body>\n</html>\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn batch() -> Vec<String> {
+        ["aB3$efgh", "Zy9<x|wv", "aB3$efgh", "pppppppp"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_report_format_from_path() {
+        assert_eq!(ReportFormat::from_path(Path::new("out.HTML")).unwrap(), ReportFormat::Html);
+        assert_eq!(ReportFormat::from_path(Path::new("out.md")).unwrap(), ReportFormat::Markdown);
+        assert!(ReportFormat::from_path(Path::new("out.txt")).is_err());
+    }
+
+    #[test]
+    fn test_batch_report_counts() {
+        let report = batch_report(&batch());
+        assert_eq!(report.count, 4);
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(report.histogram.iter().map(|bin| bin.count).sum::<usize>(), 4);
+        assert_eq!(report.histogram[0].count, 1);
+        assert_eq!(report.histogram.last().unwrap().count, 3);
+        assert_eq!(report.classes[0], ("Lowercase", 22));
+        assert_eq!(report.characters.iter().find(|(c, _)| *c == 'p'), Some(&('p', 8)));
+        assert_eq!(report.chi_square.as_ref().unwrap().degrees_of_freedom, 16);
+
+        let skewed = batch_report(&["aaaaaaaaaaaaaaab".to_string()]);
+        assert!(!skewed.chi_square.unwrap().passed());
+    }
+
+    #[test]
+    fn test_entropy_range() {
+        let quality = show_stats(&batch());
+        assert_eq!(quality.min, 0.0);
+        assert_eq!(quality.max, 3.0);
+        assert_eq!(quality.median, 3.0);
+        // "pppppppp" drags the 5th percentile down but not the 95th.
+        assert!((quality.p5 - 0.45).abs() < 1e-9);
+        assert_eq!(quality.p95, 3.0);
+
+        assert_eq!(percentile(&[1.0, 2.0, 3.0, 4.0, 5.0], 25.0), 2.0);
+        assert_eq!(percentile(&[1.0, 3.0], 50.0), 2.0);
+        assert_eq!(percentile(&[7.0], 95.0), 7.0);
+        assert!(percentile(&[], 50.0).is_nan());
+    }
+
+    #[test]
+    fn test_chi_square_critical_values() {
+        assert!((chi_square_critical(10) - 23.209).abs() < 0.1);
+        assert!((chi_square_critical(93) - 127.6).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_rendered_reports_leave_out_passwords() {
+        let report = batch_report(&batch());
+        let html = report.render(ReportFormat::Html);
+        let markdown = report.render(ReportFormat::Markdown);
+        for text in [&html, &markdown] {
+            assert!(!text.contains("aB3$efgh"));
+            assert!(!text.contains("Zy9<x|wv"));
+            assert!(text.contains("Chi-square"));
+        }
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(!html.contains("<code>&lt;</code>"));
+        assert!(markdown.contains("Left out for batches of fewer than 50 passwords"));
+
+        let large: Vec<String> = batch().into_iter().cycle().take(MIN_FREQUENCY_BATCH).collect();
+        let report = batch_report(&large);
+        assert!(report.render(ReportFormat::Html).contains("<code>&lt;</code>"));
+        assert!(report.render(ReportFormat::Markdown).contains("| `\\|` | 13 |"));
+    }
+}
@@ -0,0 +1,263 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/store.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::{PasswordGeneratorError, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::json;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use zeroize::Zeroize;
+
+const REDACTED: &str = "<generated password>";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoreTarget {
+    Pass(String),
+    Bitwarden(String),
+    OnePassword(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StoreMetadata {
+    pub username: Option<String>,
+    pub url: Option<String>,
+}
+
+impl std::fmt::Display for StoreTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoreTarget::Pass(entry) => write!(f, "pass:{}", entry),
+            StoreTarget::Bitwarden(name) => write!(f, "bw:{}", name),
+            StoreTarget::OnePassword(name) => write!(f, "op:{}", name),
+        }
+    }
+}
+
+impl std::str::FromStr for StoreTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (kind, name) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid store target: {} (use pass:, bw: or op:)", s))?;
+        if name.is_empty() || name.starts_with('-') {
+            return Err(format!("Invalid entry name for store target '{}'", kind));
+        }
+        match kind {
+            "pass" => Ok(StoreTarget::Pass(name.to_string())),
+            "bw" => Ok(StoreTarget::Bitwarden(name.to_string())),
+            "op" => Ok(StoreTarget::OnePassword(name.to_string())),
+            _ => Err(format!(
+                "Invalid store target: {} (use pass:, bw: or op:)",
+                s
+            )),
+        }
+    }
+}
+
+struct StoreCommand {
+    program: &'static str,
+    args: Vec<String>,
+    stdin: String,
+}
+
+fn pass_stdin(password: &str, metadata: &StoreMetadata) -> String {
+    let mut stdin = format!("{}\n", password);
+    if let Some(username) = &metadata.username {
+        stdin.push_str(&format!("login: {}\n", username));
+    }
+    if let Some(url) = &metadata.url {
+        stdin.push_str(&format!("url: {}\n", url));
+    }
+    stdin
+}
+
+fn bitwarden_item(name: &str, password: &str, metadata: &StoreMetadata) -> String {
+    let uris: Vec<_> = metadata
+        .url
+        .iter()
+        .map(|url| json!({ "match": null, "uri": url }))
+        .collect();
+    json!({
+        "type": 1,
+        "name": name,
+        "login": {
+            "username": metadata.username,
+            "password": password,
+            "uris": uris,
+        },
+    })
+    .to_string()
+}
+
+fn onepassword_item(name: &str, password: &str, metadata: &StoreMetadata) -> String {
+    let mut fields = vec![json!({
+        "id": "password",
+        "type": "CONCEALED",
+        "purpose": "PASSWORD",
+        "label": "password",
+        "value": password,
+    })];
+    if let Some(username) = &metadata.username {
+        fields.push(json!({
+            "id": "username",
+            "type": "STRING",
+            "purpose": "USERNAME",
+            "label": "username",
+            "value": username,
+        }));
+    }
+    let urls: Vec<_> = metadata
+        .url
+        .iter()
+        .map(|url| json!({ "href": url, "primary": true }))
+        .collect();
+    json!({
+        "title": name,
+        "category": "LOGIN",
+        "fields": fields,
+        "urls": urls,
+    })
+    .to_string()
+}
+
+fn build_command(target: &StoreTarget, password: &str, metadata: &StoreMetadata) -> StoreCommand {
+    match target {
+        StoreTarget::Pass(entry) => {
+            let mode = if metadata.username.is_some() || metadata.url.is_some() {
+                "--multiline"
+            } else {
+                "--echo"
+            };
+            StoreCommand {
+                program: "pass",
+                args: vec![
+                    "insert".to_string(),
+                    mode.to_string(),
+                    "--".to_string(),
+                    entry.clone(),
+                ],
+                stdin: pass_stdin(password, metadata),
+            }
+        }
+        StoreTarget::Bitwarden(name) => StoreCommand {
+            program: "bw",
+            args: vec!["create".to_string(), "item".to_string()],
+            stdin: STANDARD.encode(bitwarden_item(name, password, metadata)),
+        },
+        StoreTarget::OnePassword(name) => StoreCommand {
+            program: "op",
+            args: vec!["item".to_string(), "create".to_string()],
+            stdin: onepassword_item(name, password, metadata),
+        },
+    }
+}
+
+pub fn describe_command(target: &StoreTarget, metadata: &StoreMetadata) -> String {
+    let command = build_command(target, REDACTED, metadata);
+    let stdin = match target {
+        StoreTarget::Bitwarden(name) => {
+            format!("base64({})", bitwarden_item(name, REDACTED, metadata))
+        }
+        _ => command.stdin.trim_end().replace('\n', "\\n"),
+    };
+    format!(
+        "{} {} <<< '{}'",
+        command.program,
+        command.args.join(" "),
+        stdin
+    )
+}
+
+pub fn store_password(
+    target: &StoreTarget,
+    password: &str,
+    metadata: &StoreMetadata,
+) -> Result<()> {
+    if matches!(target, StoreTarget::Bitwarden(_) | StoreTarget::OnePassword(_)) {
+        crate::net::ensure_online(&format!("storing in {}", target))?;
+    }
+    let mut command = build_command(target, password, metadata);
+    let mut child = Command::new(command.program)
+        .args(&command.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| {
+            PasswordGeneratorError::Store(format!("Failed to run '{}': {}", command.program, e))
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let written = stdin.write_all(command.stdin.as_bytes());
+        command.stdin.zeroize();
+        written?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(PasswordGeneratorError::Store(format!(
+            "'{} {}' failed for {} ({})",
+            command.program,
+            command.args.join(" "),
+            target,
+            status
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_store_target() {
+        assert_eq!(
+            "pass:web/example.com".parse::<StoreTarget>(),
+            Ok(StoreTarget::Pass("web/example.com".to_string()))
+        );
+        assert_eq!(
+            "bw:Example Login".parse::<StoreTarget>(),
+            Ok(StoreTarget::Bitwarden("Example Login".to_string()))
+        );
+        assert_eq!(
+            "op:Example".parse::<StoreTarget>(),
+            Ok(StoreTarget::OnePassword("Example".to_string()))
+        );
+        assert!("pass:".parse::<StoreTarget>().is_err());
+        assert!("pass:--force".parse::<StoreTarget>().is_err());
+        assert!("vault:entry".parse::<StoreTarget>().is_err());
+    }
+
+    #[test]
+    fn test_describe_command_redacts_password() {
+        let metadata = StoreMetadata {
+            username: Some("alice".to_string()),
+            url: Some("https://example.com".to_string()),
+        };
+        let described = describe_command(&StoreTarget::Pass("web/example".to_string()), &metadata);
+        assert_eq!(
+            described,
+            "pass insert --multiline -- web/example <<< '<generated password>\\nlogin: alice\\nurl: https://example.com'"
+        );
+
+        let described = describe_command(&StoreTarget::Bitwarden("Example".to_string()), &metadata);
+        assert!(described.starts_with("bw create item <<< 'base64("));
+        assert!(described.contains("\"password\":\"<generated password>\""));
+    }
+
+    #[test]
+    fn test_onepassword_item_fields() {
+        let metadata = StoreMetadata {
+            username: Some("bob".to_string()),
+            url: None,
+        };
+        let item: serde_json::Value =
+            serde_json::from_str(&onepassword_item("Example", "secret", &metadata)).unwrap();
+        assert_eq!(item["title"], "Example");
+        assert_eq!(item["fields"][0]["value"], "secret");
+        assert_eq!(item["fields"][1]["value"], "bob");
+        assert_eq!(item["urls"].as_array().unwrap().len(), 0);
+    }
+}
@@ -4,21 +4,37 @@
 // Author: Volker Schwaberow <volker@schwaberow.de>
 // Copyright (c) 2022 Volker Schwaberow
 
+use crate::i18n::trf;
 use std::collections::HashSet;
 
-pub fn evaluate_password_strength(password: &str) -> f64 {
+pub fn estimate_entropy_bits(password: &str) -> f64 {
     let length = password.len() as f64;
     let char_set_size = get_char_set_size(password) as f64;
-    let entropy = length * char_set_size.log2();
+    length * char_set_size.log2()
+}
+
+pub fn evaluate_password_strength(password: &str) -> f64 {
+    score(password, true)
+}
+
+/// The score of a password npwg generated itself. A run such as `abc` in
+/// random output is an accident that costs no entropy, so unlike
+/// [`evaluate_password_strength`] it is not penalized; `--avoid-sequential`
+/// keeps such runs out instead.
+pub fn evaluate_generated_strength(password: &str) -> f64 {
+    score(password, false)
+}
+
+fn score(password: &str, penalize_sequences: bool) -> f64 {
+    let length = password.len() as f64;
+    let entropy = estimate_entropy_bits(password);
 
     let normalized_score = (entropy / 256.0).min(1.0);
 
-    let penalized_score = apply_penalties(password, normalized_score);
+    let penalized_score = apply_penalties(password, normalized_score, penalize_sequences);
 
     let length_bonus = (length / 32.0).min(1.0);
-    let final_score = (penalized_score + length_bonus) / 2.0;
-
-    final_score
+    (penalized_score + length_bonus) / 2.0
 }
 
 fn get_char_set_size(password: &str) -> usize {
@@ -45,10 +61,10 @@ fn get_char_set_size(password: &str) -> usize {
     }
 }
 
-fn apply_penalties(password: &str, score: f64) -> f64 {
+fn apply_penalties(password: &str, score: f64, penalize_sequences: bool) -> f64 {
     let mut penalized_score = score;
 
-    if has_sequential_chars(password) {
+    if penalize_sequences && has_sequential_chars(password) {
         penalized_score *= 0.95;
     }
 
@@ -63,6 +79,9 @@ fn apply_penalties(password: &str, score: f64) -> f64 {
     penalized_score
 }
 
+/// An ascending run of three, such as `abc` or `123`. Scores have always
+/// counted only these; descending runs matter to `--avoid-sequential` alone,
+/// through [`find_sequential_run`].
 fn has_sequential_chars(password: &str) -> bool {
     let chars: Vec<char> = password.chars().collect();
     for window in chars.windows(3) {
@@ -73,6 +92,23 @@ fn has_sequential_chars(password: &str) -> bool {
     false
 }
 
+pub const SEQUENTIAL_RUN_LENGTH: usize = 3;
+
+/// The first run of at least `min_len` characters whose code points go up or
+/// down by one at each step, such as `abc`, `987` or `FED`.
+pub fn find_sequential_run(password: &str, min_len: usize) -> Option<String> {
+    let chars: Vec<char> = password.chars().collect();
+    if min_len < 2 || chars.len() < min_len {
+        return None;
+    }
+    let step = |a: char, b: char| b as i64 - a as i64;
+    chars.windows(min_len).find_map(|window| {
+        let first = step(window[0], window[1]);
+        ((first == 1 || first == -1) && window.windows(2).all(|pair| step(pair[0], pair[1]) == first))
+            .then(|| window.iter().collect())
+    })
+}
+
 fn has_repeated_chars(password: &str) -> bool {
     let chars: Vec<char> = password.chars().collect();
     for window in chars.windows(3) {
@@ -93,6 +129,209 @@ fn contains_common_word(password: &str) -> bool {
     false
 }
 
+const KEYBOARD_ROWS: &[&str] = &[
+    "`1234567890-=",
+    "~!@#$%^&*()_+",
+    "qwertyuiop[]\\",
+    "asdfghjkl;'",
+    "zxcvbnm,./",
+    "qwertzuiopü+",
+    "yxcvbnm,.-",
+    "azertyuiop",
+    "qsdfghjklm",
+    "wxcvbn",
+];
+
+pub const KEYBOARD_RUN_LENGTH: usize = 4;
+
+/// The first run of at least `min_len` keys that sit next to each other on a
+/// QWERTY, QWERTZ or AZERTY row, in either direction and ignoring case.
+pub fn find_keyboard_sequence(password: &str, min_len: usize) -> Option<String> {
+    let chars: Vec<char> = password.to_lowercase().chars().collect();
+    if min_len == 0 || chars.len() < min_len {
+        return None;
+    }
+    chars.windows(min_len).find_map(|window| {
+        let run: String = window.iter().collect();
+        let reversed: String = window.iter().rev().collect();
+        KEYBOARD_ROWS
+            .iter()
+            .any(|row| row.contains(&run) || row.contains(&reversed))
+            .then_some(run)
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub code: &'static str,
+    pub message: &'static str,
+}
+
+const MIN_RECOMMENDED_LENGTH: usize = 12;
+
+/// Concrete ways a password could be improved. The code is stable so callers
+/// can translate or render the message themselves.
+pub fn get_improvement_suggestions(password: &str) -> Vec<Suggestion> {
+    let mut suggestions = Vec::new();
+    let mut suggest = |condition: bool, code, message| {
+        if condition {
+            suggestions.push(Suggestion { code, message });
+        }
+    };
+    suggest(
+        password.chars().count() < MIN_RECOMMENDED_LENGTH,
+        "too_short",
+        "Use at least 12 characters",
+    );
+    suggest(
+        get_char_set_size(password) < 62,
+        "few_character_classes",
+        "Mix upper and lower case letters, digits and symbols",
+    );
+    suggest(
+        has_sequential_chars(password),
+        "sequential_characters",
+        "Avoid runs of sequential characters such as 'abc' or '123'",
+    );
+    suggest(
+        has_repeated_chars(password),
+        "repeated_characters",
+        "Avoid repeating the same character three or more times",
+    );
+    suggest(
+        contains_common_word(password),
+        "common_word",
+        "Avoid common words and patterns such as 'password' or 'qwerty'",
+    );
+    suggestions
+}
+
+/// The inputs of [`evaluate_password_strength`] for one password, so two
+/// candidates can be shown side by side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrengthBreakdown {
+    pub length: usize,
+    pub pool_size: usize,
+    pub entropy_bits: f64,
+    pub penalty: f64,
+    pub score: f64,
+    pub issues: Vec<&'static str>,
+}
+
+pub fn strength_breakdown(password: &str) -> StrengthBreakdown {
+    StrengthBreakdown {
+        length: password.chars().count(),
+        pool_size: get_char_set_size(password),
+        entropy_bits: estimate_entropy_bits(password),
+        penalty: apply_penalties(password, 1.0, true),
+        score: evaluate_password_strength(password),
+        issues: get_improvement_suggestions(password)
+            .into_iter()
+            .map(|s| s.code)
+            .collect(),
+    }
+}
+
+/// Which of two breakdowns scores higher (`Less` when `a` is weaker) and the
+/// components in which the stronger one is ahead.
+pub fn compare_strength(
+    a: &StrengthBreakdown,
+    b: &StrengthBreakdown,
+) -> (std::cmp::Ordering, Vec<Suggestion>) {
+    let ordering = a.score.total_cmp(&b.score);
+    let (stronger, weaker) = match ordering {
+        std::cmp::Ordering::Less => (b, a),
+        _ => (a, b),
+    };
+    let mut reasons = Vec::new();
+    let mut reason = |condition: bool, code, message| {
+        if condition {
+            reasons.push(Suggestion { code, message });
+        }
+    };
+    reason(stronger.length > weaker.length, "longer", "it is longer");
+    reason(
+        stronger.pool_size > weaker.pool_size,
+        "larger_pool",
+        "it draws from more character classes",
+    );
+    reason(
+        stronger.entropy_bits > weaker.entropy_bits,
+        "more_entropy",
+        "it has more entropy",
+    );
+    reason(
+        stronger.penalty > weaker.penalty,
+        "fewer_penalties",
+        "it avoids sequences, repeats or common words",
+    );
+    reason(
+        stronger.issues.len() < weaker.issues.len(),
+        "fewer_issues",
+        "it has fewer detected issues",
+    );
+    (ordering, reasons)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ScoreScale {
+    #[default]
+    Npwg,
+    Pwquality,
+}
+
+impl std::fmt::Display for ScoreScale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScoreScale::Npwg => write!(f, "npwg"),
+            ScoreScale::Pwquality => write!(f, "pwquality"),
+        }
+    }
+}
+
+impl std::str::FromStr for ScoreScale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "npwg" => Ok(ScoreScale::Npwg),
+            "pwquality" => Ok(ScoreScale::Pwquality),
+            _ => Err(format!("Invalid score scale: {} (use npwg or pwquality)", s)),
+        }
+    }
+}
+
+const PWQUALITY_MIN_LENGTH: usize = 8;
+
+/// Scores a password the way `pwscore` reports it: an integer from 0 to 100,
+/// or the reason the password fails outright. Failures follow libpwquality's
+/// default settings and wording.
+pub fn pwquality_score(password: &str) -> Result<u8, &'static str> {
+    let chars: Vec<char> = password.chars().collect();
+    if chars.is_empty() {
+        return Err("No password supplied");
+    }
+    if chars.len() < PWQUALITY_MIN_LENGTH {
+        return Err("The password is shorter than 8 characters");
+    }
+    if chars.len() > 1 && chars.iter().eq(chars.iter().rev()) {
+        return Err("The password is a palindrome");
+    }
+    if contains_common_word(password) {
+        return Err("The password fails the dictionary check - it is based on a dictionary word");
+    }
+    Ok((evaluate_password_strength(password) * 100.0).round().min(100.0) as u8)
+}
+
+pub fn get_strength_code(score: f64) -> &'static str {
+    match score {
+        s if s < 0.3 => "weak",
+        s if s < 0.6 => "moderate",
+        s if s < 0.8 => "strong",
+        _ => "very_strong",
+    }
+}
+
 pub fn get_strength_feedback(score: f64) -> String {
     match score {
         s if s < 0.3 => "Weak".to_string(),
@@ -102,13 +341,113 @@ pub fn get_strength_feedback(score: f64) -> String {
     }
 }
 
-pub fn get_strength_bar(score: f64) -> String {
-    let bar_length = 20;
-    let filled_length = (score * bar_length as f64).round() as usize;
-    let empty_length = bar_length - filled_length;
+/// Spoken form of the strength bar for screen readers, e.g. "strength 7 of 10".
+pub fn describe_strength(score: f64) -> String {
+    trf(
+        "strength {} of 10",
+        &[&((score.clamp(0.0, 1.0) * 10.0).round() as u8)],
+    )
+}
+
+/// A bar of `width` cells, the share given by `score` drawn with `filled`.
+pub fn strength_bar(score: f64, width: usize, filled: char, empty: char) -> String {
+    let filled_length = (score.clamp(0.0, 1.0) * width as f64).round() as usize;
+    let empty_length = width - filled_length;
 
-    let filled = "█".repeat(filled_length);
-    let empty = "░".repeat(empty_length);
+    let filled = filled.to_string().repeat(filled_length);
+    let empty = empty.to_string().repeat(empty_length);
 
     format!("[{}{}]", filled, empty)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_keyboard_sequence() {
+        assert_eq!(find_keyboard_sequence("x9ASDFq", 4), Some("asdf".to_string()));
+        assert_eq!(find_keyboard_sequence("k0987z", 4), Some("0987".to_string()));
+        assert_eq!(find_keyboard_sequence("qwertz", 6), Some("qwertz".to_string()));
+        assert_eq!(find_keyboard_sequence("asdx7890", 5), None);
+        assert_eq!(find_keyboard_sequence("aqsw", 4), None);
+    }
+
+    #[test]
+    fn test_find_sequential_run() {
+        assert_eq!(find_sequential_run("x7abcQ", 3), Some("abc".to_string()));
+        assert_eq!(find_sequential_run("k987z", 3), Some("987".to_string()));
+        assert_eq!(find_sequential_run("aFEDCb", 4), Some("FEDC".to_string()));
+        assert_eq!(find_sequential_run("abab", 3), None);
+        assert_eq!(find_sequential_run("aceg", 3), None);
+        assert_eq!(find_sequential_run("abc", 1), None);
+
+        // Scores keep penalizing ascending runs only.
+        assert!(has_sequential_chars("x7abcQ"));
+        assert!(!has_sequential_chars("k987z"));
+    }
+
+    #[test]
+    fn test_generated_strength() {
+        assert!(evaluate_generated_strength("Qx7abcQ-w2") > evaluate_password_strength("Qx7abcQ-w2"));
+        assert_eq!(
+            evaluate_generated_strength("Qx7a_cQ-w2"),
+            evaluate_password_strength("Qx7a_cQ-w2")
+        );
+    }
+
+    #[test]
+    fn test_improvement_suggestions() {
+        let codes = |password| {
+            get_improvement_suggestions(password)
+                .into_iter()
+                .map(|s| s.code)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            codes("password123"),
+            [
+                "too_short",
+                "few_character_classes",
+                "sequential_characters",
+                "common_word"
+            ]
+        );
+        assert_eq!(codes("aaaaaaaaaaaaaaaa"), ["few_character_classes", "repeated_characters"]);
+        assert!(codes("k7#Vq!pX2m@Lr9&w").is_empty());
+        assert_eq!(get_strength_code(0.7), "strong");
+        assert_eq!(describe_strength(0.68), "strength 7 of 10");
+    }
+
+    #[test]
+    fn test_compare_strength() {
+        let weak = strength_breakdown("password1");
+        let strong = strength_breakdown("k7#Vq!pX2m@Lr9&w");
+        assert_eq!(weak.pool_size, 52);
+        assert_eq!(weak.penalty, 0.9);
+        let (ordering, reasons) = compare_strength(&weak, &strong);
+        assert_eq!(ordering, std::cmp::Ordering::Less);
+        let codes: Vec<_> = reasons.iter().map(|r| r.code).collect();
+        assert_eq!(
+            codes,
+            ["longer", "larger_pool", "more_entropy", "fewer_penalties", "fewer_issues"]
+        );
+        let (ordering, reasons) = compare_strength(&strong, &strong.clone());
+        assert_eq!(ordering, std::cmp::Ordering::Equal);
+        assert!(reasons.is_empty());
+    }
+
+    #[test]
+    fn test_pwquality_score() {
+        assert_eq!("PWQuality".parse::<ScoreScale>(), Ok(ScoreScale::Pwquality));
+        assert!("zxcvbn".parse::<ScoreScale>().is_err());
+        assert_eq!(
+            pwquality_score("abc"),
+            Err("The password is shorter than 8 characters")
+        );
+        assert_eq!(pwquality_score("abcddcba"), Err("The password is a palindrome"));
+        assert!(pwquality_score("Password!23").is_err());
+        let score = pwquality_score("k7#Vq!pX2m@Lr9&w").unwrap();
+        assert!(score > 0 && score <= 100);
+    }
+}
@@ -0,0 +1,274 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/theme.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::{PasswordGeneratorError, Result};
+use crate::settings::ThemeSettings;
+use colored::{Color, ColoredString, Colorize};
+use std::sync::OnceLock;
+
+/// What a piece of output is, so its look can be set in `[theme]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Role {
+    /// Generated passwords and passphrases.
+    Password,
+    /// Section headings such as "Statistics:".
+    Heading,
+    /// The banner of interactive mode.
+    Title,
+    /// Values next to a label.
+    Value,
+    Success,
+    Warning,
+    Error,
+    Weak,
+    Moderate,
+    Strong,
+    VeryStrong,
+}
+
+impl Role {
+    /// The role of a label from [`crate::strength::get_strength_feedback`].
+    pub fn for_strength(feedback: &str) -> Role {
+        match feedback {
+            "Moderate" => Role::Moderate,
+            "Strong" => Role::Strong,
+            "Very Strong" => Role::VeryStrong,
+            _ => Role::Weak,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Style {
+    color: Option<Color>,
+    bold: bool,
+    dimmed: bool,
+    italic: bool,
+    underline: bool,
+}
+
+impl Style {
+    fn new(color: Color, bold: bool) -> Self {
+        Style {
+            color: Some(color),
+            bold,
+            ..Style::default()
+        }
+    }
+
+    pub fn paint(&self, text: &str) -> ColoredString {
+        let mut painted = text.normal();
+        if let Some(color) = self.color {
+            painted = painted.color(color);
+        }
+        if self.bold {
+            painted = painted.bold();
+        }
+        if self.dimmed {
+            painted = painted.dimmed();
+        }
+        if self.italic {
+            painted = painted.italic();
+        }
+        if self.underline {
+            painted = painted.underline();
+        }
+        painted
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Color::TrueColor {
+        r: channel(0)?,
+        g: channel(2)?,
+        b: channel(4)?,
+    })
+}
+
+impl std::str::FromStr for Style {
+    type Err = String;
+
+    /// Words such as `bold bright-blue`: at most one color, given by name,
+    /// as `bright <name>` or as `#rrggbb`, and any of `bold`, `dimmed`,
+    /// `italic` and `underline`. `plain` alone means no styling.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut style = Style::default();
+        let mut words = s.split_whitespace();
+        while let Some(word) = words.next() {
+            let word = word.to_lowercase();
+            let color = match word.as_str() {
+                "plain" => continue,
+                "bold" => {
+                    style.bold = true;
+                    continue;
+                }
+                "dimmed" => {
+                    style.dimmed = true;
+                    continue;
+                }
+                "italic" => {
+                    style.italic = true;
+                    continue;
+                }
+                "underline" => {
+                    style.underline = true;
+                    continue;
+                }
+                "bright" => words
+                    .next()
+                    .and_then(|name| format!("bright {}", name).parse().ok()),
+                _ => match word.strip_prefix('#') {
+                    Some(hex) => parse_hex(hex),
+                    None => word.replace(['-', '_'], " ").parse().ok(),
+                },
+            };
+            match (color, style.color) {
+                (Some(color), None) => style.color = Some(color),
+                (Some(_), Some(_)) => return Err(format!("'{}' names more than one color", s.trim())),
+                (None, _) => return Err(format!("unknown color or style '{}'", word)),
+            }
+        }
+        Ok(style)
+    }
+}
+
+/// A style for every role.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    password: Style,
+    heading: Style,
+    title: Style,
+    value: Style,
+    success: Style,
+    warning: Style,
+    error: Style,
+    weak: Style,
+    moderate: Style,
+    strong: Style,
+    very_strong: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            password: Style::new(Color::Green, false),
+            heading: Style::new(Color::Blue, true),
+            title: Style::new(Color::Cyan, true),
+            value: Style::new(Color::Yellow, false),
+            success: Style::new(Color::Green, false),
+            warning: Style::new(Color::Yellow, false),
+            error: Style::new(Color::Red, false),
+            weak: Style::new(Color::Yellow, false),
+            moderate: Style::new(Color::Blue, false),
+            strong: Style::new(Color::Green, false),
+            very_strong: Style::new(Color::BrightGreen, false),
+        }
+    }
+}
+
+impl Theme {
+    /// The default theme with the styles given in `[theme]` put in place.
+    pub fn from_settings(settings: &ThemeSettings) -> Result<Self> {
+        let mut theme = Theme::default();
+        let overrides = [
+            ("password", &settings.password, &mut theme.password),
+            ("heading", &settings.heading, &mut theme.heading),
+            ("title", &settings.title, &mut theme.title),
+            ("value", &settings.value, &mut theme.value),
+            ("success", &settings.success, &mut theme.success),
+            ("warning", &settings.warning, &mut theme.warning),
+            ("error", &settings.error, &mut theme.error),
+            ("weak", &settings.weak, &mut theme.weak),
+            ("moderate", &settings.moderate, &mut theme.moderate),
+            ("strong", &settings.strong, &mut theme.strong),
+            ("very_strong", &settings.very_strong, &mut theme.very_strong),
+        ];
+        for (key, value, style) in overrides {
+            if let Some(value) = value {
+                *style = value.parse().map_err(|e| {
+                    PasswordGeneratorError::InvalidConfig(format!("Invalid config.toml: theme.{}: {}", key, e))
+                })?;
+            }
+        }
+        Ok(theme)
+    }
+
+    pub fn style(&self, role: Role) -> Style {
+        match role {
+            Role::Password => self.password,
+            Role::Heading => self.heading,
+            Role::Title => self.title,
+            Role::Value => self.value,
+            Role::Success => self.success,
+            Role::Warning => self.warning,
+            Role::Error => self.error,
+            Role::Weak => self.weak,
+            Role::Moderate => self.moderate,
+            Role::Strong => self.strong,
+            Role::VeryStrong => self.very_strong,
+        }
+    }
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Sets the theme for the rest of the run; only the first call counts.
+pub fn init(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+/// Styles text by its role in the current theme.
+pub trait Paint {
+    fn paint(&self, role: Role) -> ColoredString;
+}
+
+impl Paint for str {
+    fn paint(&self, role: Role) -> ColoredString {
+        THEME.get_or_init(Theme::default).style(role).paint(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_style() {
+        assert_eq!("bold blue".parse(), Ok(Style::new(Color::Blue, true)));
+        assert_eq!("bright green".parse(), Ok(Style::new(Color::BrightGreen, false)));
+        assert_eq!("Bright_Green".parse(), Ok(Style::new(Color::BrightGreen, false)));
+        assert_eq!(
+            "#1a2B3c underline".parse::<Style>().unwrap().color,
+            Some(Color::TrueColor { r: 0x1a, g: 0x2b, b: 0x3c })
+        );
+        assert_eq!("plain".parse(), Ok(Style::default()));
+        assert!("red blue".parse::<Style>().is_err());
+        assert!("blinking".parse::<Style>().is_err());
+        assert!("#12345".parse::<Style>().is_err());
+    }
+
+    #[test]
+    fn test_theme_from_settings() {
+        let settings = ThemeSettings {
+            value: Some("bold black".to_string()),
+            ..ThemeSettings::default()
+        };
+        let theme = Theme::from_settings(&settings).unwrap();
+        assert_eq!(theme.style(Role::Value), Style::new(Color::Black, true));
+        assert_eq!(theme.style(Role::Heading), Theme::default().heading);
+        assert_eq!(theme.style(Role::for_strength("Very Strong")), Style::new(Color::BrightGreen, false));
+
+        let settings = ThemeSettings {
+            warning: Some("orange".to_string()),
+            ..ThemeSettings::default()
+        };
+        assert!(Theme::from_settings(&settings).unwrap_err().to_string().contains("theme.warning"));
+    }
+}
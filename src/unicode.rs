@@ -0,0 +1,370 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/unicode.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+mod confusables;
+mod tables;
+
+use crate::error::{PasswordGeneratorError, Result};
+use confusables::CONFUSABLES;
+use icu_normalizer::{ComposingNormalizer, DecomposingNormalizer};
+use std::collections::HashMap;
+use tables::CATEGORIES;
+use zeroize::Zeroize;
+
+/// Unicode blocks that can be named in `unicode:<block>`.
+const BLOCKS: &[(&str, u32, u32)] = &[
+    ("Basic Latin", 0x0000, 0x007F),
+    ("Latin-1 Supplement", 0x0080, 0x00FF),
+    ("Latin Extended-A", 0x0100, 0x017F),
+    ("Latin Extended-B", 0x0180, 0x024F),
+    ("IPA Extensions", 0x0250, 0x02AF),
+    ("Greek and Coptic", 0x0370, 0x03FF),
+    ("Cyrillic", 0x0400, 0x04FF),
+    ("Armenian", 0x0530, 0x058F),
+    ("Hebrew", 0x0590, 0x05FF),
+    ("Arabic", 0x0600, 0x06FF),
+    ("Devanagari", 0x0900, 0x097F),
+    ("Bengali", 0x0980, 0x09FF),
+    ("Tamil", 0x0B80, 0x0BFF),
+    ("Thai", 0x0E00, 0x0E7F),
+    ("Georgian", 0x10A0, 0x10FF),
+    ("Latin Extended Additional", 0x1E00, 0x1EFF),
+    ("Greek Extended", 0x1F00, 0x1FFF),
+    ("General Punctuation", 0x2000, 0x206F),
+    ("Currency Symbols", 0x20A0, 0x20CF),
+    ("Letterlike Symbols", 0x2100, 0x214F),
+    ("Number Forms", 0x2150, 0x218F),
+    ("Arrows", 0x2190, 0x21FF),
+    ("Mathematical Operators", 0x2200, 0x22FF),
+    ("Box Drawing", 0x2500, 0x257F),
+    ("Geometric Shapes", 0x25A0, 0x25FF),
+    ("Miscellaneous Symbols", 0x2600, 0x26FF),
+    ("Dingbats", 0x2700, 0x27BF),
+    ("Hiragana", 0x3040, 0x309F),
+    ("Katakana", 0x30A0, 0x30FF),
+    ("CJK Unified Ideographs", 0x4E00, 0x9FFF),
+    ("Hangul Syllables", 0xAC00, 0xD7AF),
+    ("Emoticons", 0x1F600, 0x1F64F),
+];
+
+/// Shorter names for blocks whose official name is a mouthful.
+const ALIASES: &[(&str, &str)] = &[
+    ("Greek", "Greek and Coptic"),
+    ("Latin1", "Latin-1 Supplement"),
+    ("CJK", "CJK Unified Ideographs"),
+    ("Hangul", "Hangul Syllables"),
+    ("Emoji", "Emoticons"),
+];
+
+/// Block names compare without case, spaces, hyphens or underscores.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn in_ranges(ranges: &[(u32, u32)], code_point: u32) -> bool {
+    ranges
+        .binary_search_by(|&(start, end)| {
+            if end < code_point {
+                std::cmp::Ordering::Less
+            } else if start > code_point {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Whether `c` is a letter, number, punctuation mark or symbol, and so
+/// something a password can hold on its own: no combining marks, spaces,
+/// controls or unassigned code points.
+pub fn is_password_char(c: char) -> bool {
+    CATEGORIES
+        .iter()
+        .any(|(_, ranges)| in_ranges(ranges, c as u32))
+}
+
+/// The character standing for everything that looks like `c`; two
+/// characters are confusable when their prototypes are equal.
+pub fn prototype(c: char) -> char {
+    CONFUSABLES
+        .binary_search_by_key(&(c as u32), |&(code_point, _)| code_point)
+        .ok()
+        .and_then(|index| char::from_u32(CONFUSABLES[index].1))
+        .unwrap_or(c)
+}
+
+/// `chars` without every character that looks like another one in it, so
+/// `0` and `O` both go while a lone `0` stays.
+pub fn without_confusables(chars: &[char]) -> Vec<char> {
+    let mut looks: HashMap<char, std::collections::HashSet<char>> = HashMap::new();
+    for &c in chars {
+        looks.entry(prototype(c)).or_default().insert(c);
+    }
+    chars
+        .iter()
+        .copied()
+        .filter(|&c| looks[&prototype(c)].len() == 1)
+        .collect()
+}
+
+/// The Unicode normalization form applied to generated output, so a
+/// password reads the same to a site whichever form it normalizes to.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Normalization {
+    #[default]
+    None,
+    Nfc,
+    Nfd,
+}
+
+impl std::fmt::Display for Normalization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Normalization::None => write!(f, "none"),
+            Normalization::Nfc => write!(f, "nfc"),
+            Normalization::Nfd => write!(f, "nfd"),
+        }
+    }
+}
+
+impl std::str::FromStr for Normalization {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Normalization::None),
+            "nfc" => Ok(Normalization::Nfc),
+            "nfd" => Ok(Normalization::Nfd),
+            _ => Err(format!("Invalid normalization: {} (use nfc, nfd or none)", s)),
+        }
+    }
+}
+
+impl Normalization {
+    /// `text` in this form; the original is wiped when it changes.
+    pub fn apply(self, mut text: String) -> String {
+        let normalized = match self {
+            Normalization::None => return text,
+            Normalization::Nfc => ComposingNormalizer::new_nfc().normalize(&text),
+            Normalization::Nfd => DecomposingNormalizer::new_nfd().normalize(&text),
+        };
+        text.zeroize();
+        normalized
+    }
+
+    /// Whether `text` already is in this form, so applying it changes nothing.
+    pub fn is_normalized(self, text: &str) -> bool {
+        match self {
+            Normalization::None => true,
+            Normalization::Nfc => ComposingNormalizer::new_nfc().is_normalized(text),
+            Normalization::Nfd => DecomposingNormalizer::new_nfd().is_normalized(text),
+        }
+    }
+
+    /// `c` in this form, or `None` when the form splits it into several
+    /// characters.
+    pub fn single_char(self, c: char) -> Option<char> {
+        let normalized = self.apply(c.to_string());
+        let mut chars = normalized.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    }
+}
+
+fn invalid(message: String) -> PasswordGeneratorError {
+    PasswordGeneratorError::InvalidConfig(message)
+}
+
+fn parse_code_point(text: &str) -> Option<u32> {
+    let hex = text
+        .strip_prefix("U+")
+        .or_else(|| text.strip_prefix("u+"))
+        .unwrap_or(text);
+    u32::from_str_radix(hex, 16)
+        .ok()
+        .filter(|&code_point| code_point <= 0x10FFFF)
+}
+
+/// `U+0370-03FF` or a single `U+20AC`.
+fn parse_range(spec: &str) -> Result<(u32, u32)> {
+    let bad = || {
+        invalid(format!(
+            "Invalid code point range '{}': use U+XXXX or U+XXXX-YYYY in hex",
+            spec
+        ))
+    };
+    let (start, end) = spec.split_once('-').unwrap_or((spec, spec));
+    let (start, end) = (
+        parse_code_point(start.trim()).ok_or_else(bad)?,
+        parse_code_point(end.trim()).ok_or_else(bad)?,
+    );
+    if start > end {
+        return Err(bad());
+    }
+    Ok((start, end))
+}
+
+fn find_block(name: &str) -> Result<(u32, u32)> {
+    let wanted = normalize(name);
+    let official = ALIASES
+        .iter()
+        .find(|(alias, _)| normalize(alias) == wanted)
+        .map_or(wanted, |(_, official)| normalize(official));
+    BLOCKS
+        .iter()
+        .find(|(block, _, _)| normalize(block) == official)
+        .map(|&(_, start, end)| (start, end))
+        .ok_or_else(|| {
+            invalid(format!(
+                "Unknown Unicode block '{}'. Use a range such as unicode:U+0370-03FF or one of: {}",
+                name,
+                ALIASES
+                    .iter()
+                    .map(|(alias, _)| *alias)
+                    .chain(BLOCKS.iter().map(|(block, _, _)| *block))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })
+}
+
+fn category_chars(name: &str) -> Result<Vec<char>> {
+    let matching: Vec<&[(u32, u32)]> = CATEGORIES
+        .iter()
+        .filter(|(category, _)| *category == name || (name.len() == 1 && category.starts_with(name)))
+        .map(|(_, ranges)| *ranges)
+        .collect();
+    if matching.is_empty() {
+        let hint = if name.starts_with(['M', 'Z', 'C']) {
+            "marks, separators and control or unassigned code points cannot be used in passwords"
+        } else {
+            "use a general category such as Lu, Ll, Nd, Po or Sm, or a major class L, N, P or S"
+        };
+        return Err(invalid(format!(
+            "Unsupported Unicode category '{}': {}",
+            name, hint
+        )));
+    }
+    let mut chars: Vec<char> = matching
+        .into_iter()
+        .flatten()
+        .flat_map(|&(start, end)| (start..=end).filter_map(char::from_u32))
+        .collect();
+    chars.sort_unstable();
+    Ok(chars)
+}
+
+/// Whether `name` is a Unicode charset specification for [`expand`].
+pub fn is_spec(name: &str) -> bool {
+    name.starts_with("unicode:") || name.starts_with("category:")
+}
+
+/// The characters of `unicode:<block>`, `unicode:U+XXXX-YYYY` or
+/// `category:<Xx>`, sorted and without duplicates. Ranges and blocks keep
+/// only the characters [`is_password_char`] accepts.
+pub fn expand(spec: &str) -> Result<Vec<char>> {
+    if let Some(category) = spec.strip_prefix("category:") {
+        return category_chars(category.trim());
+    }
+    let Some(selection) = spec.strip_prefix("unicode:").map(str::trim) else {
+        return Err(invalid(format!("Unknown characterset '{}'", spec)));
+    };
+    let (start, end) = if selection.starts_with("U+") || selection.starts_with("u+") {
+        parse_range(selection)?
+    } else {
+        find_block(selection)?
+    };
+    let chars: Vec<char> = (start..=end)
+        .filter_map(char::from_u32)
+        .filter(|&c| is_password_char(c))
+        .collect();
+    if chars.is_empty() {
+        return Err(invalid(format!(
+            "'{}' contains no characters usable in a password",
+            spec
+        )));
+    }
+    Ok(chars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks_and_ranges() {
+        let greek = expand("unicode:Greek").unwrap();
+        assert_eq!(greek, expand("unicode:U+0370-03FF").unwrap());
+        assert_eq!(greek, expand("unicode:greek and coptic").unwrap());
+        assert!(greek.contains(&'λ') && greek.contains(&'Ω'));
+        // Unassigned code points and combining marks are left out.
+        assert!(!greek.contains(&'\u{0378}'));
+        assert!(expand("unicode:U+0300-036F").is_err());
+        assert_eq!(expand("unicode:U+20AC").unwrap(), ['€']);
+        assert_eq!(expand("unicode:U+0020-0030").unwrap().first(), Some(&'!'));
+        assert!(expand("unicode:U+03FF-0370").is_err());
+        assert!(expand("unicode:U+110000").is_err());
+        assert!(expand("unicode:Klingon").is_err());
+    }
+
+    #[test]
+    fn test_categories() {
+        let upper = expand("category:Lu").unwrap();
+        assert!(upper.contains(&'A') && upper.contains(&'Ä') && upper.contains(&'Σ'));
+        assert!(!upper.contains(&'a'));
+        let digits = expand("category:Nd").unwrap();
+        assert_eq!(&digits[..10], &['0', '1', '2', '3', '4', '5', '6', '7', '8', '9']);
+        assert!(digits.len().is_multiple_of(10));
+        let letters = expand("category:L").unwrap();
+        assert!(letters.len() > upper.len() + 1000);
+        assert!(expand("category:Mn").is_err());
+        assert!(expand("category:Xx").is_err());
+    }
+
+    #[test]
+    fn test_confusables() {
+        assert_eq!(prototype('l'), prototype('1'));
+        assert_eq!(prototype('I'), prototype('|'));
+        assert_eq!(prototype('o'), prototype('ο'));
+        assert_eq!(prototype('O'), prototype('0'));
+        assert_ne!(prototype('o'), prototype('O'));
+        assert_eq!(prototype('x'), 'x');
+
+        let pool: Vec<char> = "01lIOoabcxοх".chars().collect();
+        assert_eq!(without_confusables(&pool), ['a', 'b', 'c']);
+        assert_eq!(without_confusables(&['0', '1', '2']), ['0', '1', '2']);
+    }
+
+    #[test]
+    fn test_normalization() {
+        let composed = "caf\u{e9}\u{2126}".to_string();
+        let decomposed = "cafe\u{301}\u{3a9}".to_string();
+        assert_eq!(Normalization::Nfd.apply(composed.clone()), decomposed);
+        assert_eq!(Normalization::Nfc.apply(decomposed.clone()), "caf\u{e9}\u{3a9}");
+        assert_eq!(Normalization::None.apply(decomposed.clone()), decomposed);
+        assert!(Normalization::Nfd.is_normalized(&decomposed));
+        assert!(!Normalization::Nfc.is_normalized(&decomposed));
+        assert_eq!(Normalization::Nfc.single_char('\u{2126}'), Some('\u{3a9}'));
+        assert_eq!(Normalization::Nfd.single_char('\u{e9}'), None);
+        assert_eq!("NFC".parse::<Normalization>(), Ok(Normalization::Nfc));
+        assert!("nfkc".parse::<Normalization>().is_err());
+    }
+
+    #[test]
+    fn test_is_password_char() {
+        assert!(is_password_char('a'));
+        assert!(is_password_char('€'));
+        assert!(!is_password_char(' '));
+        assert!(!is_password_char('\u{0301}'));
+        assert!(!is_password_char('\u{e000}'));
+    }
+}
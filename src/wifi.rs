@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: MIT
+// Project: npwg
+// File: src/wifi.rs
+// Author: Volker Schwaberow <volker@schwaberow.de>
+// Copyright (c) 2022 Volker Schwaberow
+
+use crate::error::{PasswordGeneratorError, Result};
+
+/// WPA2 and WPA3 personal both take 8 to 63 printable ASCII characters.
+pub const MIN_PASSPHRASE: usize = 8;
+pub const MAX_PASSPHRASE: usize = 63;
+/// SSIDs are at most 32 bytes.
+pub const MAX_SSID: usize = 32;
+
+pub fn validate(ssid: &str, passphrase_length: usize) -> Result<()> {
+    if ssid.is_empty() || ssid.len() > MAX_SSID {
+        return Err(PasswordGeneratorError::InvalidConfig(format!(
+            "SSID must be 1 to {} bytes long",
+            MAX_SSID
+        )));
+    }
+    if !(MIN_PASSPHRASE..=MAX_PASSPHRASE).contains(&passphrase_length) {
+        return Err(PasswordGeneratorError::InvalidConfig(format!(
+            "WPA passphrases must be {} to {} characters long",
+            MIN_PASSPHRASE, MAX_PASSPHRASE
+        )));
+    }
+    Ok(())
+}
+
+/// Backslash-escapes the characters the `WIFI:` format uses as syntax.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The payload phone cameras understand for joining a network. `T:WPA`
+/// covers WPA2 and WPA3 personal.
+pub fn payload(ssid: &str, passphrase: &str, hidden: bool) -> String {
+    format!(
+        "WIFI:T:WPA;S:{};P:{};{};",
+        escape(ssid),
+        escape(passphrase),
+        if hidden { "H:true;" } else { "" }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_escapes_syntax() {
+        assert_eq!(
+            payload("Cafe;Guest", "a:b\\c", false),
+            "WIFI:T:WPA;S:Cafe\\;Guest;P:a\\:b\\\\c;;"
+        );
+        assert_eq!(payload("Lab", "secret12", true), "WIFI:T:WPA;S:Lab;P:secret12;H:true;;");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("MyNet", 20).is_ok());
+        assert!(validate("MyNet", 7).is_err());
+        assert!(validate("MyNet", 64).is_err());
+        assert!(validate("", 20).is_err());
+        assert!(validate(&"x".repeat(33), 20).is_err());
+    }
+}